@@ -0,0 +1,75 @@
+//! compares the vectorized cpu loops of `web_splats::simd` with plain scalar loops
+//! run with `cargo bench --bench simd`
+
+use cgmath::{Deg, Matrix4, Point3, Quaternion, Rotation3, Vector3};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use web_splats::{simd, Aabb};
+
+const NUM_POINTS: usize = 1_000_000;
+
+/// deterministic pseudo random points in [-10,10]^3
+fn points() -> Vec<Point3<f32>> {
+    let mut state = 0x2545_f491u32;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as f32 / u32::MAX as f32 * 20. - 10.
+    };
+    (0..NUM_POINTS)
+        .map(|_| Point3::new(next(), next(), next()))
+        .collect()
+}
+
+fn bounding_box_scalar(points: &[Point3<f32>]) -> Option<Aabb<f32>> {
+    let mut aabb = Aabb::new(*points.first()?, *points.first()?);
+    for p in points {
+        aabb.grow(p);
+    }
+    Some(aabb)
+}
+
+fn depth_sort_scalar(points: &[Point3<f32>], view: &Matrix4<f32>) -> Vec<usize> {
+    let depths: Vec<f32> = points
+        .iter()
+        .map(|p| (view * p.to_homogeneous()).z)
+        .collect();
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|a, b| depths[*a].total_cmp(&depths[*b]));
+    order
+}
+
+fn depth_sort_simd(points: &[Point3<f32>], view: &Matrix4<f32>) -> Vec<usize> {
+    let keys = simd::depth_keys(points, view);
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_unstable_by_key(|i| keys[*i]);
+    order
+}
+
+fn bench(c: &mut Criterion) {
+    let points = points();
+    let view = Matrix4::from(Quaternion::from_axis_angle(Vector3::unit_y(), Deg(30.)))
+        * Matrix4::from_translation(Vector3::new(0., 0., 20.));
+
+    let mut group = c.benchmark_group("bounding_box");
+    group.bench_function("scalar", |b| {
+        b.iter(|| bounding_box_scalar(black_box(&points)))
+    });
+    group.bench_function("simd", |b| {
+        b.iter(|| simd::bounding_box(black_box(&points)))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("depth_sort");
+    group.sample_size(10);
+    group.bench_function("scalar", |b| {
+        b.iter(|| depth_sort_scalar(black_box(&points), &view))
+    });
+    group.bench_function("simd", |b| {
+        b.iter(|| depth_sort_simd(black_box(&points), &view))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -3,7 +3,7 @@ cargo build \
     --no-default-features \
     --target wasm32-unknown-unknown \
     --lib \
-    --features npz \
+    --features ui,openxr,formats-extra \
     --profile web-release \
 && wasm-bindgen \
     --out-dir public \
@@ -0,0 +1,15 @@
+#!/bin/sh
+# installs the viewer for the current user and makes it the default application for .ply, .splat and .wsc files
+# usage: install.sh [path to viewer binary]
+set -e
+BIN=${1:-target/release/viewer}
+DATA=${XDG_DATA_HOME:-$HOME/.local/share}
+DIR=$(dirname "$0")
+
+install -Dm755 "$BIN" "$HOME/.local/bin/viewer"
+install -Dm644 "$DIR/web-splat.desktop" "$DATA/applications/web-splat.desktop"
+install -Dm644 "$DIR/web-splat-mime.xml" "$DATA/mime/packages/web-splat.xml"
+
+update-mime-database "$DATA/mime"
+update-desktop-database "$DATA/applications" 2>/dev/null || true
+xdg-mime default web-splat.desktop model/x-ply application/x-gaussian-splat application/x-web-splat-container
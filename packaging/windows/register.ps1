@@ -0,0 +1,24 @@
+# registers the viewer as application for .ply, .splat and .wsc files for the current user (no admin rights needed)
+# usage: powershell -ExecutionPolicy Bypass -File register.ps1 [path to viewer.exe]
+param([string]$Viewer = "$PSScriptRoot\viewer.exe")
+
+$Viewer = (Resolve-Path $Viewer).Path
+$ProgId = "WebSplat.PointCloud"
+$Classes = "HKCU:\Software\Classes"
+
+New-Item -Force "$Classes\$ProgId" -Value "Gaussian Splatting Point Cloud" | Out-Null
+New-Item -Force "$Classes\$ProgId\DefaultIcon" -Value "`"$Viewer`",0" | Out-Null
+New-Item -Force "$Classes\$ProgId\shell\open\command" -Value "`"$Viewer`" `"%1`"" | Out-Null
+
+# .splat and .wsc files are only opened by the viewer, .ply files get an "open with" entry
+foreach ($ext in ".splat", ".wsc") {
+    New-Item -Force "$Classes\$ext" -Value $ProgId | Out-Null
+}
+foreach ($ext in ".ply", ".splat", ".wsc") {
+    if (-not (Test-Path "$Classes\$ext\OpenWithProgids")) {
+        New-Item -Force "$Classes\$ext\OpenWithProgids" | Out-Null
+    }
+    New-ItemProperty -Force -Path "$Classes\$ext\OpenWithProgids" -Name $ProgId -Value "" | Out-Null
+}
+
+Write-Host "registered $Viewer for .ply, .splat and .wsc files"
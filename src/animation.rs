@@ -1,12 +1,8 @@
-#[cfg(target_arch = "wasm32")]
-use instant::Duration;
 use splines::{Interpolate, Key};
-#[cfg(not(target_arch = "wasm32"))]
-use std::time::Duration;
 
-use cgmath::{EuclideanSpace, InnerSpace, Point3, Quaternion, Rad, VectorSpace};
+use cgmath::{EuclideanSpace, InnerSpace, Point3, Quaternion, Rad, Vector2, VectorSpace};
 
-use crate::{camera::PerspectiveCamera, PerspectiveProjection};
+use crate::{camera::PerspectiveCamera, time::Duration, PerspectiveProjection};
 
 pub trait Lerp {
     fn lerp(&self, other: &Self, amount: f32) -> Self;
@@ -40,6 +36,74 @@ impl<T: Lerp + Clone> Sampler for Transition<T> {
     }
 }
 
+/// number of samples of the arc length table of [ConstantSpeed]
+const ARC_LENGTH_SAMPLES: usize = 1024;
+
+/// reparameterizes a camera animation by the arc length of the camera positions
+/// so the camera moves with constant speed, e.g. for smooth video exports
+pub struct ConstantSpeed<S> {
+    sampler: S,
+    /// normalized arc length at evenly spaced parameters of the sampler
+    lengths: Vec<f32>,
+}
+
+impl<S: Sampler<Sample = PerspectiveCamera>> ConstantSpeed<S> {
+    pub fn new(sampler: S) -> Self {
+        let mut lengths = Vec::with_capacity(ARC_LENGTH_SAMPLES + 1);
+        let mut length = 0.;
+        let mut last = sampler.sample(0.).position;
+        for i in 0..=ARC_LENGTH_SAMPLES {
+            let p = sampler
+                .sample(i as f32 / ARC_LENGTH_SAMPLES as f32)
+                .position;
+            length += (p - last).magnitude();
+            lengths.push(length);
+            last = p;
+        }
+        if length > 0. {
+            lengths.iter_mut().for_each(|l| *l /= length);
+        } else {
+            // the camera only rotates, keep the original timing
+            lengths.clear();
+        }
+        Self { sampler, lengths }
+    }
+
+    /// parameter of the sampler at which the fraction `v` of the path length is reached
+    fn parameter(&self, v: f32) -> f32 {
+        if self.lengths.is_empty() {
+            return v;
+        }
+        let v = v.clamp(0., 1.);
+        let i = self
+            .lengths
+            .partition_point(|l| *l < v)
+            .clamp(1, ARC_LENGTH_SAMPLES);
+        let (l0, l1) = (self.lengths[i - 1], self.lengths[i]);
+        let amount = if l1 > l0 { (v - l0) / (l1 - l0) } else { 0. };
+        (i as f32 - 1. + amount) / ARC_LENGTH_SAMPLES as f32
+    }
+
+    /// fraction of the path length at parameter `u` of the sampler (inverse of the reparameterization)
+    /// e.g. to find the time at which the camera passes a keyframe
+    pub fn arc_length(&self, u: f32) -> f32 {
+        if self.lengths.is_empty() {
+            return u;
+        }
+        let x = u.clamp(0., 1.) * ARC_LENGTH_SAMPLES as f32;
+        let i = (x.floor() as usize).min(ARC_LENGTH_SAMPLES - 1);
+        let (l0, l1) = (self.lengths[i], self.lengths[i + 1]);
+        l0 + (l1 - l0) * (x - i as f32)
+    }
+}
+
+impl<S: Sampler<Sample = PerspectiveCamera>> Sampler for ConstantSpeed<S> {
+    type Sample = PerspectiveCamera;
+    fn sample(&self, v: f32) -> Self::Sample {
+        self.sampler.sample(self.parameter(v))
+    }
+}
+
 pub struct TrackingShot {
     spline: splines::Spline<f32, PerspectiveCamera>,
 }
@@ -212,6 +276,22 @@ impl Interpolate<f32> for PerspectiveProjection {
                 (b.0, b.1.fov2view_ratio),
                 (y.0, y.1.fov2view_ratio),
             ),
+            principal_offset: Vector2::new(
+                Interpolate::cubic_hermite(
+                    t,
+                    (x.0, x.1.principal_offset.x),
+                    (a.0, a.1.principal_offset.x),
+                    (b.0, b.1.principal_offset.x),
+                    (y.0, y.1.principal_offset.x),
+                ),
+                Interpolate::cubic_hermite(
+                    t,
+                    (x.0, x.1.principal_offset.y),
+                    (a.0, a.1.principal_offset.y),
+                    (b.0, b.1.principal_offset.y),
+                    (y.0, y.1.principal_offset.y),
+                ),
+            ),
         }
     }
 
@@ -0,0 +1,429 @@
+use cgmath::Vector2;
+use clap::Parser;
+use image::{DynamicImage, RgbImage};
+use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
+use serde::Deserialize;
+use std::{
+    fs::File,
+    io::{BufReader, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use web_splats::{
+    download_texture,
+    io::GenericGaussianPointCloud,
+    task::{CancellableReader, CancellationToken},
+    ColorSpace, GaussianRenderer, PerspectiveCamera, PointCloud, RenderMode, RenderSettings, Scene,
+    SceneCamera, Split, Transparency, WGPUContext,
+};
+
+#[derive(Debug, Parser)]
+#[command(author, version)]
+#[command(about = "Evaluates multiple scenes against their ground truth images and writes the results to a CSV file", long_about = None)]
+struct Opt {
+    /// manifest json file with a list of {"name", "model", "scene", "images"} entries
+    /// relative paths are resolved against the directory of the manifest
+    manifest: PathBuf,
+
+    /// csv output file
+    #[arg(long, default_value = "eval.csv")]
+    output: PathBuf,
+
+    /// camera split that is evaluated (train or test)
+    #[arg(long, default_value = "test")]
+    split: String,
+
+    /// render with a white instead of a black background
+    #[arg(long)]
+    white_background: bool,
+
+    /// render on a software rasterizer (e.g. lavapipe) for machines without a gpu
+    #[arg(long)]
+    software: bool,
+
+    /// fail if a scene cannot be evaluated or its PSNR is below this value
+    /// used as an end-to-end check of the whole pipeline in ci
+    #[arg(long)]
+    min_psnr: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestEntry {
+    /// name used in the csv file, defaults to the model file name
+    name: Option<String>,
+    /// point cloud file
+    model: PathBuf,
+    /// scene json file
+    scene: PathBuf,
+    /// directory with the ground truth images
+    images: PathBuf,
+}
+
+#[derive(Debug)]
+struct Metrics {
+    name: String,
+    num_points: u32,
+    num_views: usize,
+    psnr: f32,
+    ssim: f32,
+    render_time: Duration,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[pollster::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let opt = Opt::parse();
+
+    let split = match opt.split.as_str() {
+        "train" => Split::Train,
+        "test" => Split::Test,
+        s => return Err(anyhow::anyhow!("unknown split '{}'", s)),
+    };
+    let background = if opt.white_background {
+        wgpu::Color::WHITE
+    } else {
+        wgpu::Color::BLACK
+    };
+
+    let entries: Vec<ManifestEntry> = serde_json::from_reader(File::open(&opt.manifest)?)?;
+    let base_dir = opt.manifest.parent().unwrap_or(Path::new("."));
+
+    let wgpu_context = if opt.software {
+        WGPUContext::new_software().await?
+    } else {
+        WGPUContext::new_instance().await?
+    };
+
+    // ctrl-c stops the evaluation after the current view, the finished scenes are still saved
+    let token = CancellationToken::new();
+    let handler_token = token.clone();
+    ctrlc::set_handler(move || handler_token.cancel())?;
+
+    let num_entries = entries.len();
+    let mut results = Vec::with_capacity(num_entries);
+    for entry in entries {
+        let name = entry.name.clone().unwrap_or_else(|| {
+            entry
+                .model
+                .file_stem()
+                .map_or("unknown".to_string(), |s| s.to_string_lossy().to_string())
+        });
+        // a failing scene should not abort the whole batch
+        let result = evaluate(
+            &wgpu_context,
+            base_dir,
+            &entry,
+            &name,
+            split,
+            background,
+            &token,
+        )
+        .await;
+        match result {
+            Ok(metrics) => {
+                println!(
+                    "{}: PSNR {:.3}, SSIM {:.4}, {:.2} ms/frame",
+                    metrics.name,
+                    metrics.psnr,
+                    metrics.ssim,
+                    metrics.render_time.as_secs_f32() * 1000.
+                );
+                results.push(metrics);
+            }
+            Err(_) if token.is_cancelled() => {
+                log::warn!("evaluation was cancelled");
+                break;
+            }
+            Err(err) => log::error!("failed to evaluate {}: {:?}", name, err),
+        }
+    }
+
+    let mut out = File::create(&opt.output)?;
+    writeln!(out, "name,num_points,num_views,psnr,ssim,render_time_ms")?;
+    for m in &results {
+        writeln!(
+            out,
+            "{},{},{},{:.4},{:.5},{:.4}",
+            m.name,
+            m.num_points,
+            m.num_views,
+            m.psnr,
+            m.ssim,
+            m.render_time.as_secs_f32() * 1000.
+        )?;
+    }
+    if !results.is_empty() {
+        let n = results.len() as f32;
+        writeln!(
+            out,
+            "mean,{},{},{:.4},{:.5},{:.4}",
+            results.iter().map(|m| m.num_points as u64).sum::<u64>() / results.len() as u64,
+            results.iter().map(|m| m.num_views).sum::<usize>() / results.len(),
+            results.iter().map(|m| m.psnr).sum::<f32>() / n,
+            results.iter().map(|m| m.ssim).sum::<f32>() / n,
+            results
+                .iter()
+                .map(|m| m.render_time.as_secs_f32() * 1000.)
+                .sum::<f32>()
+                / n
+        )?;
+    }
+    println!("saved results to '{}'", opt.output.to_string_lossy());
+    token.check()?;
+
+    if let Some(min_psnr) = opt.min_psnr {
+        if results.len() < num_entries {
+            anyhow::bail!("{} scenes failed", num_entries - results.len());
+        }
+        let failed: Vec<&str> = results
+            .iter()
+            .filter(|m| m.psnr.is_nan() || m.psnr < min_psnr)
+            .map(|m| m.name.as_str())
+            .collect();
+        if !failed.is_empty() {
+            anyhow::bail!("PSNR below {} for {}", min_psnr, failed.join(", "));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    todo!("not implemented")
+}
+
+async fn evaluate(
+    wgpu_context: &WGPUContext,
+    base_dir: &Path,
+    entry: &ManifestEntry,
+    name: &str,
+    split: Split,
+    background: wgpu::Color,
+    token: &CancellationToken,
+) -> anyhow::Result<Metrics> {
+    let (device, queue) = (&wgpu_context.device, &wgpu_context.queue);
+    let scene = Scene::open(&base_dir.join(&entry.scene))?;
+    let file = File::open(base_dir.join(&entry.model))?;
+    let pc_raw = GenericGaussianPointCloud::load(BufReader::new(CancellableReader::new(
+        file,
+        token.clone(),
+    )))?;
+    let pc = PointCloud::new(device, pc_raw)?;
+    let mut renderer = GaussianRenderer::new(
+        device,
+        queue,
+        wgpu::TextureFormat::Rgba16Float,
+        pc.sh_deg(),
+        pc.compressed(),
+        pc.sh_storage(),
+    )
+    .await;
+
+    let cameras = scene.cameras(Some(split));
+    if cameras.is_empty() {
+        return Err(anyhow::anyhow!(
+            "scene has no {} cameras",
+            split.to_string()
+        ));
+    }
+    let images = base_dir.join(&entry.images);
+
+    let pb = ProgressBar::new(cameras.len() as u64);
+    pb.set_style(
+        ProgressStyle::with_template(
+            "{msg} {spinner:.green} [{bar:.cyan/blue}] {pos}/{len} [{elapsed}/{duration}]",
+        )
+        .unwrap()
+        .progress_chars("#>-"),
+    );
+    pb.set_message(format!("evaluating {name}"));
+
+    let (mut psnr, mut ssim) = (0., 0.);
+    let mut render_time = Duration::ZERO;
+    for camera in cameras.iter().progress_with(pb) {
+        token.check()?;
+        let gt = load_ground_truth(&images, camera)?;
+        let resolution = Vector2::new(gt.width(), gt.height());
+        let start = Instant::now();
+        let img = render_view(
+            device,
+            queue,
+            &mut renderer,
+            &pc,
+            camera,
+            resolution,
+            background,
+        )
+        .await?;
+        render_time += start.elapsed();
+        psnr += compute_psnr(&img, &gt);
+        ssim += compute_ssim(&img, &gt);
+    }
+    let n = cameras.len() as f32;
+    Ok(Metrics {
+        name: name.to_string(),
+        num_points: pc.num_points(),
+        num_views: cameras.len(),
+        psnr: psnr / n,
+        ssim: ssim / n,
+        render_time: render_time / cameras.len() as u32,
+    })
+}
+
+/// looks for the image of the camera in `dir`, the extension in the scene file is optional
+fn load_ground_truth(dir: &Path, camera: &SceneCamera) -> anyhow::Result<RgbImage> {
+    let path = ["", "png", "jpg", "JPG", "jpeg"]
+        .iter()
+        .map(|ext| {
+            if ext.is_empty() {
+                dir.join(&camera.img_name)
+            } else {
+                dir.join(format!("{}.{}", camera.img_name, ext))
+            }
+        })
+        .find(|p| p.is_file())
+        .ok_or(anyhow::anyhow!(
+            "no ground truth image for '{}' in '{}'",
+            camera.img_name,
+            dir.to_string_lossy()
+        ))?;
+    Ok(image::open(path)?.to_rgb8())
+}
+
+async fn render_view(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    renderer: &mut GaussianRenderer,
+    pc: &PointCloud,
+    scene_camera: &SceneCamera,
+    resolution: Vector2<u32>,
+    background: wgpu::Color,
+) -> anyhow::Result<RgbImage> {
+    let target = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("render texture"),
+        size: wgpu::Extent3d {
+            width: resolution.x,
+            height: resolution.y,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: renderer.color_format(),
+        usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let target_view = target.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("render encoder"),
+    });
+    let mut camera: PerspectiveCamera = scene_camera.clone().into();
+    camera.fit_near_far(pc.bbox());
+    renderer.prepare(
+        &mut encoder,
+        device,
+        queue,
+        pc,
+        RenderSettings {
+            camera,
+            viewport: resolution,
+            gaussian_scaling: 1.,
+            max_sh_deg: pc.sh_deg(),
+            show_env_map: false,
+            mip_splatting: None,
+            kernel_size: None,
+            opacity_threshold: 0.,
+            color_space: ColorSpace::Srgb,
+            render_mode: RenderMode::Splats,
+            transparency: Transparency::Sorted,
+            min_scale: 0.,
+            fog: None,
+            glare_filter: None,
+            exposure: 1.,
+            clipping_box: None,
+            clip_planes: Default::default(),
+            crop_box: None,
+            walltime: Duration::from_secs(100),
+            scene_center: None,
+            scene_extend: None,
+            background_color: background,
+            resolution,
+        },
+        &mut None,
+    );
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(background),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        renderer.render(&mut render_pass, pc);
+    }
+    queue.submit(std::iter::once(encoder.finish()));
+    let img = download_texture(&target, device, queue).await?;
+    Ok(DynamicImage::ImageRgba8(img).into_rgb8())
+}
+
+/// peak signal to noise ratio in dB
+fn compute_psnr(img: &RgbImage, gt: &RgbImage) -> f32 {
+    let mse = img
+        .as_raw()
+        .iter()
+        .zip(gt.as_raw())
+        .map(|(a, b)| (*a as f32 / 255. - *b as f32 / 255.).powi(2))
+        .sum::<f32>()
+        / img.as_raw().len() as f32;
+    -10. * mse.max(1e-10).log10()
+}
+
+/// mean structural similarity with a 7x7 box window, averaged over the color channels
+fn compute_ssim(img: &RgbImage, gt: &RgbImage) -> f32 {
+    const WIN: u32 = 7;
+    const C1: f32 = 0.01 * 0.01;
+    const C2: f32 = 0.03 * 0.03;
+    let (w, h) = img.dimensions();
+    if w < WIN || h < WIN {
+        return 0.;
+    }
+    let n = (WIN * WIN) as f32;
+    let mut total = 0.;
+    let mut count = 0;
+    for c in 0..3 {
+        for y in 0..=(h - WIN) {
+            for x in 0..=(w - WIN) {
+                let (mut sa, mut sb, mut saa, mut sbb, mut sab) = (0., 0., 0., 0., 0.);
+                for dy in 0..WIN {
+                    for dx in 0..WIN {
+                        let a = img.get_pixel(x + dx, y + dy)[c] as f32 / 255.;
+                        let b = gt.get_pixel(x + dx, y + dy)[c] as f32 / 255.;
+                        sa += a;
+                        sb += b;
+                        saa += a * a;
+                        sbb += b * b;
+                        sab += a * b;
+                    }
+                }
+                let (ma, mb) = (sa / n, sb / n);
+                // unbiased (co)variances like skimage
+                let va = (saa - sa * ma) / (n - 1.);
+                let vb = (sbb - sb * mb) / (n - 1.);
+                let cov = (sab - sa * mb) / (n - 1.);
+                total += ((2. * ma * mb + C1) * (2. * cov + C2))
+                    / ((ma * ma + mb * mb + C1) * (va + vb + C2));
+                count += 1;
+            }
+        }
+    }
+    total / count as f32
+}
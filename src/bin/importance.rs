@@ -0,0 +1,71 @@
+use cgmath::Vector2;
+use clap::Parser;
+use std::{fs::File, io::BufWriter, path::PathBuf};
+use web_splats::{io::GenericGaussianPointCloud, PerspectiveCamera, Scene};
+
+#[derive(Debug, Parser)]
+#[command(author, version)]
+#[command(about = "Computes the contribution of every splat to the scene camera images and exports it as ply file", long_about = None)]
+struct Opt {
+    /// input file
+    input: PathBuf,
+
+    /// scene json file
+    scene: PathBuf,
+
+    /// output ply file with the splat positions and an `importance` property
+    output: PathBuf,
+
+    /// resolution of the longer image side used for the approximate rendering
+    #[arg(long, default_value_t = 256)]
+    resolution: u32,
+
+    /// normalize the importance to [0, 1]
+    #[arg(long, default_value_t = false)]
+    normalize: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let opt = Opt::parse();
+
+    let scene = Scene::open(&opt.scene)?;
+    let pc = GenericGaussianPointCloud::load(File::open(&opt.input)?)?;
+
+    let cameras = scene.cameras(None);
+    let first = cameras
+        .first()
+        .ok_or(anyhow::anyhow!("scene contains no cameras"))?;
+    let factor = opt.resolution as f32 / first.width.max(first.height) as f32;
+    let viewport = Vector2::new(
+        ((first.width as f32 * factor) as u32).max(1),
+        ((first.height as f32 * factor) as u32).max(1),
+    );
+    let cameras: Vec<PerspectiveCamera> = cameras.into_iter().map(|c| c.into()).collect();
+
+    println!(
+        "rendering {} splats into {} views ({}x{})",
+        pc.num_points,
+        cameras.len(),
+        viewport.x,
+        viewport.y
+    );
+    let mut importance = pc.importance(&cameras, viewport)?;
+    let unused = importance.iter().filter(|v| **v == 0.).count();
+    println!("{} splats do not contribute to any view", unused);
+
+    if opt.normalize {
+        let max = importance.iter().copied().fold(0., f32::max);
+        if max > 0. {
+            importance.iter_mut().for_each(|v| *v /= max);
+        }
+    }
+
+    pc.write_channel(
+        BufWriter::new(File::create(&opt.output)?),
+        "importance",
+        &importance,
+    )?;
+    println!("saved importance to '{}'", opt.output.to_string_lossy());
+    Ok(())
+}
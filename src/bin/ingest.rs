@@ -0,0 +1,193 @@
+use cgmath::Vector2;
+use clap::Parser;
+#[allow(unused_imports)]
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+#[allow(unused_imports)]
+use web_splats::{
+    download_texture,
+    io::{
+        metadata::{save_png, Metadata},
+        GenericGaussianPointCloud,
+    },
+    unpremultiply, ColorSpace, FolderWatcher, PointCloud, RenderMode, RenderSettings,
+    RenderTexture, Transparency, WGPUContext,
+};
+
+#[derive(Debug, Parser)]
+#[command(author, version)]
+#[command(about = "Watches a directory and converts new point clouds into quantized containers with a thumbnail", long_about = None)]
+struct Opt {
+    /// directory that is watched for new ply files
+    input: PathBuf,
+
+    /// directory where the containers (.wsc) and thumbnails (.png) are written to
+    output: PathBuf,
+
+    /// chunk size in MiB
+    #[arg(long, default_value_t = 16)]
+    chunk_size: usize,
+
+    /// width and height of the thumbnails in pixels
+    #[arg(long, default_value_t = 256)]
+    thumbnail_size: u32,
+
+    /// convert the files that are already in the directory and exit
+    #[arg(long)]
+    once: bool,
+
+    /// render the thumbnails on a software rasterizer (e.g. lavapipe) for machines without a gpu
+    #[arg(long)]
+    software: bool,
+}
+
+/// time between two checks of the watched directory
+#[allow(unused)]
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[allow(unused)]
+fn output_paths(input: &Path, output: &Path) -> (PathBuf, PathBuf) {
+    let name = input.file_stem().unwrap_or_default().to_string_lossy();
+    (
+        output.join(format!("{name}.wsc")),
+        output.join(format!("{name}.png")),
+    )
+}
+
+/// true if the container is missing or older than the input file
+#[allow(unused)]
+fn needs_update(input: &Path, output: &Path) -> bool {
+    let modified = |p: &Path| p.metadata().and_then(|m| m.modified()).ok();
+    let (container, _) = output_paths(input, output);
+    match (modified(input), modified(&container)) {
+        (Some(input), Some(container)) => input > container,
+        _ => true,
+    }
+}
+
+#[allow(unused)]
+async fn convert(wgpu_context: &WGPUContext, input: &Path, opt: &Opt) -> anyhow::Result<()> {
+    let (container, thumbnail) = output_paths(input, &opt.output);
+    log::info!("converting {:?}", input);
+
+    let mut pc_raw = GenericGaussianPointCloud::load(BufReader::new(File::open(input)?))?;
+    pc_raw.metadata = match (pc_raw.metadata.take(), Metadata::load_sidecar(input)) {
+        (Some(embedded), Some(sidecar)) => Some(embedded.merge(sidecar)),
+        (embedded, sidecar) => embedded.or(sidecar),
+    };
+
+    // written to a temporary file first so consumers never see a partial container
+    let tmp = container.with_extension("wsc.tmp");
+    {
+        let mut out = BufWriter::new(File::create(&tmp)?);
+        for frame in pc_raw.to_packed_frames(opt.chunk_size << 20)? {
+            frame.write(&mut out, true)?;
+        }
+    }
+    std::fs::rename(&tmp, &container)?;
+
+    let metadata = pc_raw.metadata.clone();
+    let Some(view) = pc_raw.suggest_views(1)?.into_iter().next() else {
+        log::warn!("{:?} is empty, no thumbnail is rendered", input);
+        return Ok(());
+    };
+    let device = &wgpu_context.device;
+    let queue = &wgpu_context.queue;
+    let pc = PointCloud::new(device, pc_raw)?;
+    let size = opt.thumbnail_size.max(1);
+    let mut target = RenderTexture::new(device, queue, &pc, size, size).await;
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("thumbnail encoder"),
+    });
+    let viewport = Vector2::new(size, size);
+    target.render(
+        &mut encoder,
+        device,
+        queue,
+        &pc,
+        RenderSettings {
+            camera: view.camera,
+            viewport,
+            gaussian_scaling: 1.,
+            max_sh_deg: pc.sh_deg(),
+            show_env_map: false,
+            mip_splatting: None,
+            kernel_size: None,
+            opacity_threshold: 0.,
+            color_space: ColorSpace::Srgb,
+            render_mode: RenderMode::Splats,
+            transparency: Transparency::Sorted,
+            min_scale: 0.,
+            fog: None,
+            glare_filter: None,
+            exposure: 1.,
+            clipping_box: None,
+            clip_planes: Default::default(),
+            crop_box: None,
+            walltime: Duration::from_secs(100),
+            scene_center: None,
+            scene_extend: None,
+            background_color: wgpu::Color::TRANSPARENT,
+            resolution: viewport,
+        },
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+    let mut img = download_texture(target.texture(), device, queue).await?;
+    unpremultiply(&mut img);
+    save_png(&img, &thumbnail, metadata.as_ref())?;
+
+    log::info!("wrote {:?} and {:?}", container, thumbnail);
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[pollster::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let opt = Opt::parse();
+    std::fs::create_dir_all(&opt.output)?;
+
+    let wgpu_context = if opt.software {
+        WGPUContext::new_software().await?
+    } else {
+        WGPUContext::new_instance().await?
+    };
+
+    let is_ply = |p: &Path| {
+        p.extension()
+            .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("ply"))
+    };
+    let mut existing: Vec<PathBuf> = std::fs::read_dir(&opt.input)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && is_ply(p))
+        .filter(|p| needs_update(p, &opt.output))
+        .collect();
+    existing.sort();
+    for path in existing {
+        if let Err(err) = convert(&wgpu_context, &path, &opt).await {
+            log::error!("failed to convert {:?}: {:?}", path, err);
+        }
+    }
+    if opt.once {
+        return Ok(());
+    }
+
+    let mut watcher = FolderWatcher::new(&opt.input, &["ply"])?;
+    loop {
+        for path in watcher.poll() {
+            if let Err(err) = convert(&wgpu_context, &path, &opt).await {
+                log::error!("failed to convert {:?}: {:?}", path, err);
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    todo!("not implemented")
+}
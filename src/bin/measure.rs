@@ -1,15 +1,13 @@
 use cgmath::Vector2;
 use clap::Parser;
 #[allow(unused_imports)]
-use std::{
-    fs::File,
-    path::PathBuf,
-    time::{Duration, Instant},
-};
+use std::{fs::File, path::PathBuf};
 #[allow(unused_imports)]
 use web_splats::{
-    io, GaussianRenderer, PerspectiveCamera, PointCloud, Scene, SceneCamera, SplattingArgs, Split,
-    WGPUContext,
+    io,
+    time::{Duration, Instant},
+    ColorSpace, GaussianRenderer, PerspectiveCamera, PointCloud, RenderMode, RenderSettings, Scene,
+    SceneCamera, Split, Transparency, WGPUContext,
 };
 
 #[derive(Debug, Parser)]
@@ -61,7 +59,7 @@ async fn render_views(
         device,
         queue,
         &pc,
-        SplattingArgs {
+        RenderSettings {
             camera: camera,
             viewport: resolution,
             gaussian_scaling: 1.,
@@ -69,10 +67,22 @@ async fn render_views(
             show_env_map: false,
             mip_splatting: None,
             kernel_size: None,
+            opacity_threshold: 0.,
+            color_space: ColorSpace::Srgb,
+            render_mode: RenderMode::Splats,
+            transparency: Transparency::Sorted,
+            min_scale: 0.,
+            fog: None,
+            glare_filter: None,
+            exposure: 1.,
             clipping_box: None,
+            clip_planes: Default::default(),
+            crop_box: None,
             walltime: Duration::from_secs(100),
             scene_center: None,
             scene_extend: None,
+            background_color: wgpu::Color::BLACK,
+            resolution,
         },
         &mut None,
     );
@@ -109,7 +119,7 @@ async fn render_views(
                 device,
                 queue,
                 &pc,
-                SplattingArgs {
+                RenderSettings {
                     camera: camera,
                     viewport: resolution,
                     gaussian_scaling: 1.,
@@ -117,10 +127,22 @@ async fn render_views(
                     show_env_map: false,
                     mip_splatting: None,
                     kernel_size: None,
+                    opacity_threshold: 0.,
+                    color_space: ColorSpace::Srgb,
+                    render_mode: RenderMode::Splats,
+                    transparency: Transparency::Sorted,
+                    min_scale: 0.,
+                    fog: None,
+                    glare_filter: None,
+                    exposure: 1.,
                     clipping_box: None,
+                    clip_planes: Default::default(),
+                    crop_box: None,
                     walltime: Duration::from_secs(100),
                     scene_center: None,
                     scene_extend: None,
+                    background_color: wgpu::Color::BLACK,
+                    resolution,
                 },
                 &mut None,
             );
@@ -162,11 +184,9 @@ async fn main() {
 
     println!("reading scene file '{}'", opt.scene.to_string_lossy());
 
-    let scene_file = File::open(opt.scene).unwrap();
-
-    let scene = Scene::from_json(scene_file).unwrap();
+    let scene = Scene::open(&opt.scene).unwrap();
 
-    let wgpu_context = WGPUContext::new_instance().await;
+    let wgpu_context = WGPUContext::new_instance().await.unwrap();
     let device = &wgpu_context.device;
     let queue = &wgpu_context.queue;
 
@@ -184,6 +204,7 @@ async fn main() {
         wgpu::TextureFormat::Rgba8Unorm,
         pc.sh_deg(),
         pc.compressed(),
+        pc.sh_storage(),
     )
     .await;
 
@@ -0,0 +1,73 @@
+use clap::Parser;
+use std::{
+    fs::File,
+    io::{BufWriter, Read},
+    path::PathBuf,
+};
+use web_splats::io::{
+    codec::{Frame, FrameKind},
+    encrypted::write_encrypted,
+    EncryptionKey,
+};
+
+#[derive(Debug, Parser)]
+#[command(author, version)]
+#[command(about = "Packs a point cloud (and scene) file into a chunked container, optionally encrypted with AES-GCM", long_about = None)]
+struct Opt {
+    /// input point cloud file
+    input: PathBuf,
+
+    /// output container file (.wsc)
+    output: PathBuf,
+
+    /// scene json file that is stored in the container
+    #[arg(long)]
+    scene: Option<PathBuf>,
+
+    /// chunk size in MiB
+    #[arg(long, default_value_t = 16)]
+    chunk_size: usize,
+
+    /// encrypt the container
+    #[arg(long)]
+    encrypt: bool,
+
+    /// key (64 hex characters), a new key is generated and printed if not set
+    #[arg(long)]
+    key: Option<EncryptionKey>,
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let opt = Opt::parse();
+
+    let mut data = Vec::new();
+    File::open(&opt.input)?.read_to_end(&mut data)?;
+    let mut frames: Vec<Frame> = data
+        .chunks((opt.chunk_size << 20).max(1))
+        .map(|c| Frame::new(FrameKind::Chunk, c.to_vec()))
+        .collect();
+    if let Some(scene) = &opt.scene {
+        frames.push(Frame::new(FrameKind::Scene, std::fs::read(scene)?));
+    }
+
+    let mut out = BufWriter::new(File::create(&opt.output)?);
+    if opt.encrypt || opt.key.is_some() {
+        let key = opt.key.unwrap_or_else(|| {
+            let key = EncryptionKey::generate();
+            println!("generated key: {}", key.to_hex());
+            key
+        });
+        write_encrypted(&mut out, &frames, &key)?;
+    } else {
+        for frame in &frames {
+            frame.write(&mut out, true)?;
+        }
+    }
+    println!(
+        "packed {} frames into '{}'",
+        frames.len(),
+        opt.output.to_string_lossy()
+    );
+    Ok(())
+}
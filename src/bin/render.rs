@@ -7,8 +7,12 @@ use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
 use std::{fs::File, path::PathBuf, time::Duration};
 #[allow(unused_imports)]
 use web_splats::{
-    io::GenericGaussianPointCloud, GaussianRenderer, PerspectiveCamera, PointCloud, Scene,
-    SceneCamera, SplattingArgs, Split, WGPUContext,
+    io::{
+        metadata::{save_png, Metadata},
+        GenericGaussianPointCloud,
+    },
+    ColorSpace, GaussianRenderer, PerspectiveCamera, PointCloud, RenderMode, RenderSettings, Scene,
+    SceneCamera, Split, Transparency, WGPUContext, Watermark, WatermarkPosition,
 };
 
 #[derive(Debug, Parser)]
@@ -27,6 +31,27 @@ struct Opt {
     /// maximum allowed Spherical Harmonics (SH) degree
     #[arg(long, default_value_t = 3)]
     max_sh_deg: u32,
+
+    /// logo image that is composited onto every frame
+    #[arg(long)]
+    watermark: Option<PathBuf>,
+
+    /// position of the watermark (top-left, top-right, bottom-left, bottom-right, center)
+    #[arg(long, default_value = "bottom-right")]
+    watermark_position: WatermarkPosition,
+
+    /// opacity of the watermark
+    #[arg(long, default_value_t = 0.8)]
+    watermark_opacity: f32,
+
+    /// export RGBA images with a transparent background and straight alpha
+    /// the alpha channel holds the accumulated opacity of the splats
+    #[arg(long)]
+    matte: bool,
+
+    /// render on a software rasterizer (e.g. lavapipe) for machines without a gpu
+    #[arg(long)]
+    software: bool,
 }
 
 #[allow(unused)]
@@ -38,6 +63,9 @@ async fn render_views(
     cameras: Vec<SceneCamera>,
     img_out: &PathBuf,
     split: &str,
+    metadata: Option<&Metadata>,
+    watermark: Option<&Watermark>,
+    matte: bool,
 ) {
     let img_out = img_out.join(&split);
     println!("saving images to '{}'", img_out.to_string_lossy());
@@ -89,7 +117,7 @@ async fn render_views(
             device,
             queue,
             &pc,
-            SplattingArgs {
+            RenderSettings {
                 camera: camera,
                 viewport: resolution,
                 gaussian_scaling: 1.,
@@ -97,10 +125,22 @@ async fn render_views(
                 show_env_map: false,
                 mip_splatting: None,
                 kernel_size: None,
+                opacity_threshold: 0.,
+                color_space: ColorSpace::Srgb,
+                render_mode: RenderMode::Splats,
+                transparency: Transparency::Sorted,
+                min_scale: 0.,
+                fog: None,
+                glare_filter: None,
+                exposure: 1.,
                 clipping_box: None,
+                clip_planes: Default::default(),
+                crop_box: None,
                 walltime: Duration::from_secs(100),
                 scene_center: None,
                 scene_extend: None,
+                background_color: wgpu::Color::TRANSPARENT,
+                resolution,
             },
             &mut None,
         );
@@ -122,8 +162,14 @@ async fn render_views(
             renderer.render(&mut render_pass, &pc);
         }
         queue.submit(std::iter::once(encoder.finish()));
-        let img = download_texture(&target, device, queue).await;
-        img.save(img_out.join(format!("{i:0>5}.png"))).unwrap();
+        let mut img = download_texture(&target, device, queue).await;
+        if matte {
+            web_splats::unpremultiply(&mut img);
+        }
+        if let Some(watermark) = watermark {
+            watermark.apply(&mut img);
+        }
+        save_png(&img, img_out.join(format!("{i:0>5}.png")), metadata).unwrap();
     }
 }
 
@@ -138,23 +184,40 @@ async fn main() {
 
     // TODO this is suboptimal as it is never closed
     let ply_file = File::open(&opt.input).unwrap();
-    let scene_file = File::open(opt.scene).unwrap();
-
-    let scene = Scene::from_json(scene_file).unwrap();
+    let scene = Scene::open(&opt.scene).unwrap();
 
-    let wgpu_context = WGPUContext::new_instance().await;
+    let wgpu_context = if opt.software {
+        WGPUContext::new_software().await.unwrap()
+    } else {
+        WGPUContext::new_instance().await.unwrap()
+    };
     let device = &wgpu_context.device;
     let queue = &wgpu_context.queue;
 
     println!("reading point cloud file '{}'", opt.input.to_string_lossy());
 
     let pc_raw = GenericGaussianPointCloud::load(ply_file).unwrap();
+    let metadata = match (pc_raw.metadata.clone(), Metadata::load_sidecar(&opt.input)) {
+        (Some(embedded), Some(sidecar)) => Some(embedded.merge(sidecar)),
+        (embedded, sidecar) => embedded.or(sidecar),
+    };
     let mut pc = PointCloud::new(&device, pc_raw).unwrap();
+    let watermark = opt
+        .watermark
+        .as_ref()
+        .map(|path| Watermark::load(path, opt.watermark_position, opt.watermark_opacity).unwrap());
 
     let render_format = wgpu::TextureFormat::Rgba16Float;
 
-    let mut renderer =
-        GaussianRenderer::new(&device, &queue, render_format, pc.sh_deg(), pc.compressed()).await;
+    let mut renderer = GaussianRenderer::new(
+        &device,
+        &queue,
+        render_format,
+        pc.sh_deg(),
+        pc.compressed(),
+        pc.sh_storage(),
+    )
+    .await;
 
     render_views(
         device,
@@ -164,6 +227,9 @@ async fn main() {
         scene.cameras(Some(Split::Test)),
         &opt.img_out,
         "test",
+        metadata.as_ref(),
+        watermark.as_ref(),
+        opt.matte,
     )
     .await;
     render_views(
@@ -174,6 +240,9 @@ async fn main() {
         scene.cameras(Some(Split::Train)),
         &opt.img_out,
         "train",
+        metadata.as_ref(),
+        watermark.as_ref(),
+        opt.matte,
     )
     .await;
 
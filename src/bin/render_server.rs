@@ -0,0 +1,504 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{mpsc, Arc},
+    time::{Duration, Instant},
+};
+use std::{path::PathBuf, str::FromStr};
+
+#[cfg(not(target_arch = "wasm32"))]
+use cgmath::{Deg, Quaternion, Vector2, Vector3};
+use clap::Parser;
+#[cfg(not(target_arch = "wasm32"))]
+use image::{codecs::jpeg::JpegEncoder, DynamicImage, RgbImage};
+#[cfg(not(target_arch = "wasm32"))]
+use num_traits::One;
+#[cfg(not(target_arch = "wasm32"))]
+use serde::Deserialize;
+#[cfg(not(target_arch = "wasm32"))]
+use tungstenite::Message;
+#[cfg(not(target_arch = "wasm32"))]
+use web_splats::{
+    download_texture, io::GenericGaussianPointCloud, CameraController, ColorSpace,
+    GaussianRenderer, KeyMap, PerspectiveCamera, PerspectiveProjection, PointCloud, RenderMode,
+    RenderSettings, Scene, Transparency, WGPUContext,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use winit::{event::TouchPhase, keyboard::KeyCode};
+
+#[derive(Debug, Parser)]
+#[command(author, version)]
+#[command(about = "Renders frames on the server for thin clients without WebGPU (see public/thin.html)", long_about = None)]
+struct Opt {
+    /// input point cloud file
+    input: PathBuf,
+
+    /// scene json file, the first camera is used as start view
+    #[arg(long)]
+    scene: Option<PathBuf>,
+
+    /// address the server listens on
+    #[arg(long, default_value = "0.0.0.0:9002")]
+    address: String,
+
+    /// encoding of the frames sent to the clients (jpeg, h264)
+    /// h264 requires ffmpeg and a browser with WebCodecs, but needs much less bandwidth
+    #[arg(long, default_value = "jpeg")]
+    codec: Codec,
+
+    /// jpeg quality (1-100)
+    #[arg(long, default_value_t = 80)]
+    quality: u8,
+
+    /// maximum width and height of the rendered frames
+    #[arg(long, default_value_t = 1920)]
+    max_size: u32,
+
+    /// maximum frame rate per client
+    #[arg(long, default_value_t = 30.)]
+    fps: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    /// every frame is sent as a jpeg image
+    Jpeg,
+    /// h264 frames (length prefixed nal units), see [H264Encoder]
+    H264,
+}
+
+impl FromStr for Codec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jpeg" => Ok(Self::Jpeg),
+            "h264" => Ok(Self::H264),
+            _ => Err(anyhow::anyhow!(
+                "invalid codec '{}', expected jpeg or h264",
+                s
+            )),
+        }
+    }
+}
+
+/// input events sent by the client
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    /// size of the client viewport in pixels
+    Resize {
+        width: u32,
+        height: u32,
+    },
+    /// mouse movement while a button is pressed
+    Mouse {
+        dx: f32,
+        dy: f32,
+        right: bool,
+    },
+    Scroll {
+        dy: f32,
+    },
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        x: f32,
+        y: f32,
+    },
+    /// javascript key code, e.g. `KeyW`
+    Key {
+        code: KeyCode,
+        pressed: bool,
+    },
+}
+
+/// data shared by all client threads
+#[cfg(not(target_arch = "wasm32"))]
+struct Shared {
+    wgpu_context: WGPUContext,
+    pc: PointCloud,
+    start_camera: Option<PerspectiveCamera>,
+    codec: Codec,
+    quality: u8,
+    max_size: u32,
+    frame_time: Duration,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let opt = Opt::parse();
+
+    let wgpu_context = pollster::block_on(WGPUContext::new_instance())?;
+    let pc_raw =
+        GenericGaussianPointCloud::load(std::io::BufReader::new(std::fs::File::open(&opt.input)?))?;
+    let pc = PointCloud::new(&wgpu_context.device, pc_raw)?;
+    println!("loaded point cloud with {} points", pc.num_points());
+    let start_camera: Option<PerspectiveCamera> = match &opt.scene {
+        Some(path) => Scene::open(path)?.camera(0).map(|c| c.into()),
+        None => None,
+    };
+
+    let shared = Arc::new(Shared {
+        wgpu_context,
+        pc,
+        start_camera,
+        codec: opt.codec,
+        quality: opt.quality.clamp(1, 100),
+        max_size: opt.max_size,
+        frame_time: Duration::from_secs_f32(1. / opt.fps),
+    });
+
+    let listener = TcpListener::bind(&opt.address)?;
+    println!("listening on ws://{}", opt.address);
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let shared = shared.clone();
+                std::thread::spawn(move || {
+                    if let Err(err) = pollster::block_on(handle_client(stream, &shared)) {
+                        log::warn!("client disconnected: {}", err);
+                    }
+                });
+            }
+            Err(err) => log::error!("connection failed: {}", err),
+        }
+    }
+    Ok(())
+}
+
+/// renders a new frame whenever the camera of the client changed
+#[cfg(not(target_arch = "wasm32"))]
+async fn handle_client(stream: TcpStream, shared: &Shared) -> anyhow::Result<()> {
+    let peer = stream.peer_addr()?;
+    let mut socket = tungstenite::accept(stream)?;
+    // reads time out so that the camera keeps moving (e.g. while a key is pressed)
+    socket
+        .get_mut()
+        .set_read_timeout(Some(Duration::from_millis(5)))?;
+    println!("client {} connected", peer);
+    let codec = match shared.codec {
+        Codec::Jpeg => "jpeg",
+        Codec::H264 => "h264",
+    };
+    socket.send(Message::Text(format!(
+        r#"{{"type":"codec","codec":"{codec}"}}"#
+    )))?;
+
+    let device = &shared.wgpu_context.device;
+    let queue = &shared.wgpu_context.queue;
+    let pc = &shared.pc;
+    let mut renderer = GaussianRenderer::new(
+        device,
+        queue,
+        wgpu::TextureFormat::Rgba16Float,
+        pc.sh_deg(),
+        pc.compressed(),
+        pc.sh_storage(),
+    )
+    .await;
+
+    let mut resolution = Vector2::new(800, 600);
+    let mut camera = shared.start_camera.unwrap_or_else(|| {
+        let aabb = pc.bbox();
+        PerspectiveCamera::new(
+            aabb.center() - Vector3::new(1., 1., 1.) * aabb.radius() * 0.5,
+            Quaternion::one(),
+            PerspectiveProjection::new(
+                resolution,
+                Vector2::new(Deg(45.), Deg(45. / 800. * 600.)),
+                0.01,
+                1000.,
+            ),
+        )
+    });
+    let mut controller = CameraController::new(0.1, 0.05);
+    let keys = KeyMap::default();
+    controller.center = pc.center();
+    controller.reset_to_camera(camera);
+
+    let mut last_update = Instant::now();
+    let mut last_frame: Option<(PerspectiveCamera, Vector2<u32>)> = None;
+    let mut h264: Option<H264Encoder> = None;
+    loop {
+        if let Some(encoder) = &h264 {
+            for data in encoder.output() {
+                socket.send(Message::Binary(data))?;
+            }
+        }
+        match socket.read() {
+            Ok(Message::Text(msg)) => match serde_json::from_str(&msg) {
+                Ok(ClientMessage::Resize { width, height }) => {
+                    let scale = (shared.max_size as f32 / width.max(height) as f32).min(1.);
+                    resolution = Vector2::new(
+                        ((width as f32 * scale) as u32).max(1),
+                        ((height as f32 * scale) as u32).max(1),
+                    );
+                    if shared.codec == Codec::H264 {
+                        // yuv420p needs an even number of rows and columns
+                        resolution = resolution.map(|v| (v & !1).max(2));
+                    }
+                    camera.projection.resize(resolution.x, resolution.y);
+                }
+                Ok(ClientMessage::Mouse { dx, dy, right }) => {
+                    controller.left_mouse_pressed = !right;
+                    controller.right_mouse_pressed = right;
+                    controller.process_mouse(dx, dy);
+                }
+                Ok(ClientMessage::Scroll { dy }) => controller.process_scroll(dy),
+                Ok(ClientMessage::Touch { id, phase, x, y }) => {
+                    controller.process_touch(id, phase, Vector2::new(x, y))
+                }
+                Ok(ClientMessage::Key { code, pressed }) => {
+                    if let Some(action) = keys.navigation(code, false, false) {
+                        controller.process_keyboard(action, pressed);
+                    }
+                }
+                Err(err) => log::warn!("invalid message from {}: {}", peer, err),
+            },
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(err))
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let dt = last_update.elapsed();
+        if dt < shared.frame_time {
+            continue;
+        }
+        last_update = Instant::now();
+        controller.update_camera(&mut camera, dt);
+        camera.fit_near_far(pc.bbox());
+
+        if last_frame == Some((camera, resolution)) {
+            continue;
+        }
+        last_frame = Some((camera, resolution));
+        let img = render_frame(device, queue, &mut renderer, pc, camera, resolution).await?;
+        match shared.codec {
+            Codec::Jpeg => {
+                let mut jpeg = Vec::new();
+                JpegEncoder::new_with_quality(&mut jpeg, shared.quality).encode_image(&img)?;
+                socket.send(Message::Binary(jpeg))?;
+            }
+            Codec::H264 => {
+                // a new stream (starting with a key frame) is encoded when the resolution changes
+                if h264.as_ref().map(|e| e.resolution) != Some(resolution) {
+                    h264 = Some(H264Encoder::new(resolution, shared.frame_time)?);
+                }
+                if let Some(encoder) = &mut h264 {
+                    encoder.encode(&img)?;
+                }
+            }
+        }
+    }
+    println!("client {} disconnected", peer);
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn render_frame(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    renderer: &mut GaussianRenderer,
+    pc: &PointCloud,
+    camera: PerspectiveCamera,
+    resolution: Vector2<u32>,
+) -> anyhow::Result<RgbImage> {
+    let target = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("render texture"),
+        size: wgpu::Extent3d {
+            width: resolution.x,
+            height: resolution.y,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: renderer.color_format(),
+        usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let target_view = target.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("render encoder"),
+    });
+    renderer.prepare(
+        &mut encoder,
+        device,
+        queue,
+        pc,
+        RenderSettings {
+            camera,
+            viewport: resolution,
+            gaussian_scaling: 1.,
+            max_sh_deg: pc.sh_deg(),
+            show_env_map: false,
+            mip_splatting: None,
+            kernel_size: None,
+            opacity_threshold: 0.,
+            color_space: ColorSpace::Srgb,
+            render_mode: RenderMode::Splats,
+            transparency: Transparency::Sorted,
+            min_scale: 0.,
+            fog: None,
+            glare_filter: None,
+            exposure: 1.,
+            clipping_box: None,
+            clip_planes: Default::default(),
+            crop_box: None,
+            walltime: Duration::from_secs(100),
+            scene_center: None,
+            scene_extend: None,
+            background_color: wgpu::Color::BLACK,
+            resolution,
+        },
+        &mut None,
+    );
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        renderer.render(&mut render_pass, pc);
+    }
+    queue.submit(std::iter::once(encoder.finish()));
+    let img = download_texture(&target, device, queue).await?;
+    Ok(DynamicImage::ImageRgba8(img).into_rgb8())
+}
+
+/// low latency h264 encoder running in an ffmpeg process
+///
+/// ffmpeg writes an flv stream, which splits the h264 stream into frames
+/// every message starts with the kind of the frame followed by its data:
+/// - 0: decoder configuration (AVCDecoderConfigurationRecord), sent before the first frame
+/// - 1: key frame
+/// - 2: delta frame
+#[cfg(not(target_arch = "wasm32"))]
+struct H264Encoder {
+    ffmpeg: Child,
+    stdin: ChildStdin,
+    output: mpsc::Receiver<Vec<u8>>,
+    resolution: Vector2<u32>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl H264Encoder {
+    fn new(resolution: Vector2<u32>, frame_time: Duration) -> anyhow::Result<Self> {
+        let fps = (1. / frame_time.as_secs_f32()).round().max(1.);
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args(["-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgb24"])
+            .args(["-s", &format!("{}x{}", resolution.x, resolution.y)])
+            .args(["-r", &fps.to_string(), "-i", "-"])
+            // no b-frames, so every frame is sent as soon as it is encoded
+            .args([
+                "-c:v",
+                "libx264",
+                "-preset",
+                "ultrafast",
+                "-tune",
+                "zerolatency",
+            ])
+            .args(["-profile:v", "baseline", "-pix_fmt", "yuv420p"])
+            .args(["-flush_packets", "1", "-flvflags", "no_duration_filesize"])
+            .args(["-f", "flv", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("failed to run ffmpeg (is it installed?): {}", e))?;
+        let stdin = ffmpeg
+            .stdin
+            .take()
+            .ok_or(anyhow::anyhow!("no ffmpeg stdin"))?;
+        let stdout = ffmpeg
+            .stdout
+            .take()
+            .ok_or(anyhow::anyhow!("no ffmpeg stdout"))?;
+        let (tx, output) = mpsc::channel();
+        std::thread::spawn(move || {
+            if let Err(err) = read_flv_frames(stdout, tx) {
+                log::warn!("h264 encoder stopped: {}", err);
+            }
+        });
+        Ok(Self {
+            ffmpeg,
+            stdin,
+            output,
+            resolution,
+        })
+    }
+
+    fn encode(&mut self, img: &RgbImage) -> anyhow::Result<()> {
+        self.stdin.write_all(img.as_raw())?;
+        Ok(())
+    }
+
+    /// encoded frames that are ready to be sent
+    fn output(&self) -> Vec<Vec<u8>> {
+        self.output.try_iter().collect()
+    }
+}
+
+/// reads the avc video tags of an flv stream and sends them as encoder messages (see [H264Encoder])
+#[cfg(not(target_arch = "wasm32"))]
+fn read_flv_frames<R: Read>(mut r: R, tx: mpsc::Sender<Vec<u8>>) -> std::io::Result<()> {
+    // file header and the size of the (non existent) previous tag
+    let mut header = [0u8; 13];
+    r.read_exact(&mut header)?;
+    loop {
+        let mut tag = [0u8; 11];
+        r.read_exact(&mut tag)?;
+        let size = u32::from_be_bytes([0, tag[1], tag[2], tag[3]]) as usize;
+        // tag data followed by the size of the tag
+        let mut data = vec![0u8; size + 4];
+        r.read_exact(&mut data)?;
+        // video tags start with the frame type and codec, the avc packet type and the composition time
+        if tag[0] != 9 || size < 5 {
+            continue;
+        }
+        let kind = match (data[0] >> 4, data[1]) {
+            (_, 0) => 0,
+            (1, 1) => 1,
+            (_, 1) => 2,
+            _ => continue,
+        };
+        let mut msg = vec![kind];
+        msg.extend_from_slice(&data[5..size]);
+        if tx.send(msg).is_err() {
+            // the client disconnected
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for H264Encoder {
+    fn drop(&mut self) {
+        self.ffmpeg.kill().ok();
+        self.ffmpeg.wait().ok();
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    todo!("not implemented")
+}
@@ -0,0 +1,83 @@
+use cgmath::Vector2;
+use clap::Parser;
+use std::{fs::File, path::PathBuf};
+use web_splats::{
+    io::{GenericGaussianPointCloud, SplatSizeStats},
+    PerspectiveCamera, Scene,
+};
+
+#[derive(Debug, Parser)]
+#[command(author, version)]
+#[command(about = "Prints the distribution of the projected splat sizes and suggests a decimation threshold", long_about = None)]
+struct Opt {
+    /// input file
+    input: PathBuf,
+
+    /// scene json file with the representative cameras
+    scene: PathBuf,
+
+    /// resolution of the longer image side, defaults to the resolution of the scene cameras
+    #[arg(long)]
+    resolution: Option<u32>,
+
+    /// maximum fraction of visible splats that the suggested threshold may remove
+    #[arg(long, default_value_t = 0.2)]
+    max_fraction: f32,
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let opt = Opt::parse();
+
+    let scene = Scene::open(&opt.scene)?;
+    let pc = GenericGaussianPointCloud::load(File::open(&opt.input)?)?;
+
+    let cameras = scene.cameras(None);
+    let first = cameras
+        .first()
+        .ok_or(anyhow::anyhow!("scene contains no cameras"))?;
+    let viewport = match opt.resolution {
+        Some(res) => {
+            let factor = res as f32 / first.width.max(first.height) as f32;
+            Vector2::new(
+                (first.width as f32 * factor) as u32,
+                (first.height as f32 * factor) as u32,
+            )
+        }
+        None => Vector2::new(first.width, first.height),
+    };
+    let cameras: Vec<PerspectiveCamera> = cameras.into_iter().map(|c| c.into()).collect();
+
+    println!(
+        "projecting {} splats into {} views ({}x{})",
+        pc.num_points,
+        cameras.len(),
+        viewport.x,
+        viewport.y
+    );
+    let radii = pc.projected_radii(&cameras, viewport)?;
+    let stats = SplatSizeStats::new(&radii);
+
+    println!(
+        "visible: {}, never visible: {}",
+        stats.num_visible(),
+        stats.num_invisible
+    );
+    println!("largest projected radius per splat (pixels):");
+    for p in [0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99] {
+        println!("  {:>3}%: {:.2}", (p * 100.) as u32, stats.percentile(p));
+    }
+    println!("histogram:");
+    for (min, count) in stats.histogram(10) {
+        println!("  >= {:>4} px: {}", min, count);
+    }
+
+    let threshold = stats.suggest_threshold(opt.max_fraction);
+    println!(
+        "suggested threshold: {:.2} px (removes {:.1}% of the visible splats and all {} invisible splats)",
+        threshold,
+        stats.fraction_below(threshold) * 100.,
+        stats.num_invisible
+    );
+    Ok(())
+}
@@ -0,0 +1,124 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    collections::HashMap,
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, Arc, Mutex},
+};
+
+use clap::Parser;
+#[cfg(not(target_arch = "wasm32"))]
+use tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    Message,
+};
+
+#[derive(Debug, Parser)]
+#[command(author, version)]
+#[command(about = "Relays camera updates between viewers in the same room (websocket url path)", long_about = None)]
+struct Opt {
+    /// address the server listens on
+    #[arg(long, default_value = "0.0.0.0:9001")]
+    address: String,
+}
+
+/// clients per room with their id and outgoing message queue
+#[cfg(not(target_arch = "wasm32"))]
+type Rooms = Arc<Mutex<HashMap<String, Vec<(usize, mpsc::Sender<String>)>>>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let opt = Opt::parse();
+    let listener = TcpListener::bind(&opt.address)?;
+    println!("listening on ws://{}/<room>", opt.address);
+
+    let rooms: Rooms = Default::default();
+    for (id, stream) in listener.incoming().enumerate() {
+        match stream {
+            Ok(stream) => {
+                let rooms = rooms.clone();
+                std::thread::spawn(move || {
+                    if let Err(err) = handle_client(id, stream, rooms) {
+                        log::warn!("client {} disconnected: {}", id, err);
+                    }
+                });
+            }
+            Err(err) => log::error!("connection failed: {}", err),
+        }
+    }
+    Ok(())
+}
+
+/// forwards all messages of the client to the other clients in its room
+#[cfg(not(target_arch = "wasm32"))]
+fn handle_client(id: usize, stream: TcpStream, rooms: Rooms) -> anyhow::Result<()> {
+    // the callback has to be 'static, so the room is passed out through a channel
+    let (room_tx, room_rx) = mpsc::channel();
+    let mut socket = tungstenite::accept_hdr(stream, move |req: &Request, resp: Response| {
+        room_tx.send(req.uri().path().to_string()).ok();
+        Ok::<_, ErrorResponse>(resp)
+    })?;
+    let room = room_rx.try_recv().unwrap_or_default();
+    // reads time out so that messages from other clients can be sent in between
+    socket
+        .get_mut()
+        .set_read_timeout(Some(std::time::Duration::from_millis(10)))?;
+
+    let (tx, rx) = mpsc::channel();
+    rooms
+        .lock()
+        .unwrap()
+        .entry(room.clone())
+        .or_default()
+        .push((id, tx));
+    println!("client {} joined room {}", id, room);
+
+    let result = relay(id, &mut socket, &rx, &rooms, &room);
+
+    let mut rooms = rooms.lock().unwrap();
+    if let Some(clients) = rooms.get_mut(&room) {
+        clients.retain(|(other, _)| *other != id);
+        if clients.is_empty() {
+            rooms.remove(&room);
+        }
+    }
+    println!("client {} left room {}", id, room);
+    Ok(result?)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn relay(
+    id: usize,
+    socket: &mut tungstenite::WebSocket<TcpStream>,
+    rx: &mpsc::Receiver<String>,
+    rooms: &Rooms,
+    room: &str,
+) -> Result<(), tungstenite::Error> {
+    loop {
+        for msg in rx.try_iter() {
+            socket.send(Message::Text(msg))?;
+        }
+        match socket.read() {
+            Ok(Message::Text(msg)) => {
+                for (other, tx) in rooms.lock().unwrap().get(room).into_iter().flatten() {
+                    if *other != id {
+                        tx.send(msg.clone()).ok();
+                    }
+                }
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(err))
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    todo!("not implemented")
+}
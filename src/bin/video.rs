@@ -1,16 +1,18 @@
 use cgmath::Vector2;
 use clap::Parser;
 
-use image::{ImageBuffer, Rgb};
+use image::{DynamicImage, ImageBuffer, Rgba};
 use indicatif::{ProgressIterator, ProgressStyle};
 use std::{
     fs::{create_dir_all, File},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    process::Command,
     time::Duration,
 };
 use web_splats::{
-    io, smoothstep, Animation, GaussianRenderer, PointCloud, Scene, SceneCamera, SplattingArgs,
-    TrackingShot, WGPUContext,
+    io, smoothstep, write_srt, Animation, CameraPath, Caption, ColorSpace, ConstantSpeed,
+    GaussianRenderer, PathInterpolation, PerspectiveCamera, PointCloud, RenderMode, RenderSettings,
+    Sampler, Scene, TrackingShot, Transparency, WGPUContext, Watermark, WatermarkPosition,
 };
 
 #[derive(Debug, Parser)]
@@ -37,6 +39,44 @@ struct Opt {
 
     #[arg(long, default_value_t = 30)]
     fps: u32,
+
+    /// camera path file that is rendered instead of a tracking shot through the scene cameras
+    #[arg(long)]
+    camera_path: Option<PathBuf>,
+
+    /// interpolation between the keyframes of the camera path (linear, catmull-rom)
+    #[arg(long, default_value = "catmull-rom")]
+    interpolation: PathInterpolation,
+
+    /// keep the keyframe times of the camera path instead of moving the camera with constant speed
+    #[arg(long)]
+    keep_timing: bool,
+
+    /// encode the frames into a video file with ffmpeg (e.g. video.mp4)
+    #[arg(long)]
+    encode: Option<PathBuf>,
+
+    /// audio file that is muxed into the encoded video
+    /// the audio is cut to the duration of the camera animation
+    #[arg(long, requires = "encode")]
+    audio: Option<PathBuf>,
+
+    /// logo image that is composited onto every frame
+    #[arg(long)]
+    watermark: Option<PathBuf>,
+
+    /// position of the watermark (top-left, top-right, bottom-left, bottom-right, center)
+    #[arg(long, default_value = "bottom-right")]
+    watermark_position: WatermarkPosition,
+
+    /// opacity of the watermark
+    #[arg(long, default_value_t = 0.8)]
+    watermark_opacity: f32,
+
+    /// export RGBA images with a transparent background and straight alpha
+    /// the alpha channel holds the accumulated opacity of the splats
+    #[arg(long)]
+    matte: bool,
 }
 
 async fn render_tracking_shot(
@@ -44,10 +84,13 @@ async fn render_tracking_shot(
     queue: &wgpu::Queue,
     renderer: &mut GaussianRenderer,
     pc: PointCloud,
-    cameras: Vec<SceneCamera>,
+    mut animation: Animation<PerspectiveCamera>,
     video_out: &PathBuf,
-    duration: Option<Duration>,
+    video_duration: Duration,
+    ease: bool,
     fps: u32,
+    watermark: Option<&Watermark>,
+    matte: bool,
 ) {
     println!("saving video to '{}'", video_out.to_string_lossy());
 
@@ -68,20 +111,13 @@ async fn render_tracking_shot(
         view_formats: &[],
     });
 
-    let trackshot_duration = duration.unwrap_or(Duration::from_secs_f32(cameras.len() as f32 * 3.));
-
-    let mut animation = Animation::new(
-        trackshot_duration,
-        true,
-        Box::new(TrackingShot::from_cameras(cameras)),
-    );
-
-    let init_duration = Duration::from_secs_f32(4.);
-    let video_duration = trackshot_duration + init_duration;
-
     println!("video duration: {:?}", video_duration);
 
-    let bg = wgpu::Color::BLACK;
+    let bg = if matte {
+        wgpu::Color::TRANSPARENT
+    } else {
+        wgpu::Color::BLACK
+    };
 
     let target_view = target.create_view(&wgpu::TextureViewDescriptor::default());
     let num_frames = (video_duration.as_secs_f32() * fps as f32).ceil() as u32;
@@ -102,9 +138,8 @@ async fn render_tracking_shot(
         //     Duration::ZERO
         //     // dt.mul_f32()
         // });
-        animation.set_progress(smoothstep(
-            state_time.as_secs_f32() / video_duration.as_secs_f32(),
-        ));
+        let progress = (state_time.as_secs_f32() / video_duration.as_secs_f32()).min(1.);
+        animation.set_progress(if ease { smoothstep(progress) } else { progress });
         let mut cam = animation.update(Duration::ZERO);
 
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -117,7 +152,7 @@ async fn render_tracking_shot(
             device,
             queue,
             &pc,
-            SplattingArgs {
+            RenderSettings {
                 camera: cam,
                 viewport: resolution,
                 gaussian_scaling: 1.,
@@ -125,10 +160,22 @@ async fn render_tracking_shot(
                 show_env_map: false,
                 mip_splatting: None,
                 kernel_size: None,
+                opacity_threshold: 0.,
+                color_space: ColorSpace::Srgb,
+                render_mode: RenderMode::Splats,
+                transparency: Transparency::Sorted,
+                min_scale: 0.,
+                fog: None,
+                glare_filter: None,
+                exposure: 1.,
                 clipping_box: None,
+                clip_planes: Default::default(),
+                crop_box: None,
                 walltime: state_time,
                 scene_center: None,
                 scene_extend: None,
+                background_color: bg,
+                resolution,
             },
             &mut None,
         );
@@ -151,13 +198,61 @@ async fn render_tracking_shot(
         }
         queue.submit(std::iter::once(encoder.finish()));
 
-        let img = download_texture(&target, device, queue).await;
+        let mut img = download_texture(&target, device, queue).await;
+        if matte {
+            web_splats::unpremultiply(&mut img);
+        }
+        if let Some(watermark) = watermark {
+            watermark.apply(&mut img);
+        }
 
-        img.save(&video_out.join(format!("frame_{:04}.png", i)))
-            .unwrap();
+        let frame_file = video_out.join(format!("frame_{:04}.png", i));
+        if matte {
+            img.save(&frame_file).unwrap();
+        } else {
+            DynamicImage::ImageRgba8(img)
+                .to_rgb8()
+                .save(&frame_file)
+                .unwrap();
+        }
     }
 }
 
+/// moves the camera with constant speed unless `keep_timing` is set
+fn with_timing<S: Sampler<Sample = PerspectiveCamera> + 'static>(
+    sampler: S,
+    keep_timing: bool,
+) -> Box<dyn Sampler<Sample = PerspectiveCamera>> {
+    if keep_timing {
+        Box::new(sampler)
+    } else {
+        Box::new(ConstantSpeed::new(sampler))
+    }
+}
+
+/// captions of the camera path with the times at which the video shows the keyframes
+/// the path is stretched to `duration` and moves with constant speed unless `keep_timing` is set
+fn video_captions(path: &CameraPath, duration: Duration, keep_timing: bool) -> Vec<Caption> {
+    let path_duration = path.duration().as_secs_f32();
+    if path_duration <= 0. {
+        return Vec::new();
+    }
+    let constant_speed = (!keep_timing).then(|| ConstantSpeed::new(path.clone()));
+    let video_time = |t: f32| {
+        let u = t / path_duration;
+        let v = constant_speed.as_ref().map_or(u, |c| c.arc_length(u));
+        v * duration.as_secs_f32()
+    };
+    path.captions()
+        .iter()
+        .map(|c| Caption {
+            start: video_time(c.start),
+            end: video_time(c.end),
+            text: c.text.clone(),
+        })
+        .collect()
+}
+
 #[pollster::main]
 async fn main() {
     #[cfg(not(target_arch = "wasm32"))]
@@ -168,17 +263,19 @@ async fn main() {
 
     // TODO this is suboptimal as it is never closed
     let mut ply_file = File::open(&opt.input).unwrap();
-    let scene_file = File::open(opt.scene).unwrap();
+    let scene = Scene::open(&opt.scene).unwrap();
 
-    let scene = Scene::from_json(scene_file).unwrap();
-
-    let wgpu_context = WGPUContext::new_instance().await;
+    let wgpu_context = WGPUContext::new_instance().await.unwrap();
     let device = &wgpu_context.device;
     let queue = &wgpu_context.queue;
 
     println!("reading point cloud file '{}'", opt.input.to_string_lossy());
     let pc_raw = io::GenericGaussianPointCloud::load(&mut ply_file).unwrap();
     let pc = PointCloud::new(device, pc_raw).unwrap();
+    let watermark = opt
+        .watermark
+        .as_ref()
+        .map(|path| Watermark::load(path, opt.watermark_position, opt.watermark_opacity).unwrap());
 
     let mut renderer = GaussianRenderer::new(
         device,
@@ -186,29 +283,121 @@ async fn main() {
         wgpu::TextureFormat::Rgba32Float,
         pc.sh_deg(),
         pc.compressed(),
+        pc.sh_storage(),
     )
     .await;
 
+    // the tracking shot is eased in and out, camera paths keep their timing
+    let mut captions = Vec::new();
+    let (sampler, duration, ease) = match &opt.camera_path {
+        Some(path) => {
+            let mut path = CameraPath::from_json(File::open(path).unwrap()).unwrap();
+            path.set_interpolation(opt.interpolation);
+            let duration = opt
+                .duration
+                .map_or(path.duration(), Duration::from_secs_f32);
+            captions = video_captions(&path, duration, opt.keep_timing);
+            (with_timing(path, opt.keep_timing), duration, false)
+        }
+        None => {
+            let cameras = scene.cameras(None);
+            let duration = opt
+                .duration
+                .map(Duration::from_secs_f32)
+                .unwrap_or(Duration::from_secs_f32(cameras.len() as f32 * 3.));
+            let shot = TrackingShot::from_cameras(cameras);
+            // additional time for easing in and out
+            let duration = duration + Duration::from_secs_f32(4.);
+            (with_timing(shot, opt.keep_timing), duration, true)
+        }
+    };
+
     render_tracking_shot(
         device,
         queue,
         &mut renderer,
         pc,
-        scene.cameras(None),
+        Animation::new(duration, true, sampler),
         &opt.video_out,
-        opt.duration.map(Duration::from_secs_f32),
+        duration,
+        ease,
         opt.fps,
+        watermark.as_ref(),
+        opt.matte,
     )
     .await;
 
+    if !captions.is_empty() {
+        let srt = opt.video_out.join("captions.srt");
+        write_srt(&captions, File::create(&srt).unwrap()).unwrap();
+        println!("saved captions to '{}'", srt.to_string_lossy());
+    }
+
+    if let Some(output) = &opt.encode {
+        if !captions.is_empty() {
+            // players pick up subtitles with the same name as the video
+            write_srt(
+                &captions,
+                File::create(output.with_extension("srt")).unwrap(),
+            )
+            .unwrap();
+        }
+        if opt.matte {
+            log::warn!("the alpha channel of the frames is not encoded");
+        }
+        encode_video(
+            &opt.video_out,
+            output,
+            opt.fps,
+            duration,
+            opt.audio.as_deref(),
+        )
+        .unwrap();
+        println!("encoded video '{}'", output.to_string_lossy());
+    }
+
     println!("done!");
 }
 
+/// encodes the rendered frames with ffmpeg (h264) and muxes the optional audio track (aac)
+/// the video length is given by the number of frames, longer audio tracks are cut
+fn encode_video(
+    frames: &Path,
+    output: &Path,
+    fps: u32,
+    duration: Duration,
+    audio: Option<&Path>,
+) -> anyhow::Result<()> {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-y")
+        .args(["-framerate", &fps.to_string()])
+        .arg("-i")
+        .arg(frames.join("frame_%04d.png"));
+    if let Some(audio) = audio {
+        cmd.args(["-t", &duration.as_secs_f32().to_string()])
+            .arg("-i")
+            .arg(audio)
+            .args(["-c:a", "aac"]);
+    }
+    // frames of previous exports with more frames are ignored
+    let num_frames = (duration.as_secs_f32() * fps as f32).ceil() as u32;
+    cmd.args(["-frames:v", &num_frames.to_string()])
+        .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+        .arg(output);
+    let status = cmd
+        .status()
+        .map_err(|e| anyhow::anyhow!("failed to run ffmpeg (is it installed?): {}", e))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("ffmpeg failed with {}", status));
+    }
+    Ok(())
+}
+
 pub async fn download_texture(
     texture: &wgpu::Texture,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
-) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
     let texture_format = texture.format();
 
     let texel_size: u32 = texture_format.block_copy_size(None).unwrap();
@@ -254,11 +443,16 @@ pub async fn download_texture(
                 let r = f32::from_le_bytes(c[0..4].try_into().unwrap()).clamp(0., 1.);
                 let g = f32::from_le_bytes(c[4..8].try_into().unwrap()).clamp(0., 1.);
                 let b = f32::from_le_bytes(c[8..12].try_into().unwrap()).clamp(0., 1.);
-                let _a = f32::from_le_bytes(c[12..16].try_into().unwrap()).clamp(0., 1.);
-                [(r * 255.) as u8, (g * 255.) as u8, (b * 255.) as u8]
+                let a = f32::from_le_bytes(c[12..16].try_into().unwrap()).clamp(0., 1.);
+                [
+                    (r * 255.) as u8,
+                    (g * 255.) as u8,
+                    (b * 255.) as u8,
+                    (a * 255.) as u8,
+                ]
             })
             .collect();
-        ImageBuffer::<Rgb<u8>, _>::from_raw(fb_size.width, fb_size.height, buf).unwrap()
+        ImageBuffer::<Rgba<u8>, _>::from_raw(fb_size.width, fb_size.height, buf).unwrap()
     };
 
     return image::imageops::crop(&mut image, 0, 0, fb_size.width, fb_size.height).to_image();
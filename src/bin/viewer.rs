@@ -1,36 +1,363 @@
-use clap::Parser;
+#[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+use anyhow::Context;
+use cgmath::Vector2;
+use clap::{error::ErrorKind, CommandFactory, Parser};
 #[allow(unused_imports)]
-use std::{fmt::Debug, fs::File, path::PathBuf};
+use std::{fmt::Debug, fs::File, path::PathBuf, time::Duration};
+#[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+use web_splats::io::remote::RemoteLoader;
 #[allow(unused_imports)]
-use web_splats::{open_window, RenderConfig};
+use web_splats::{
+    io::{CropRegion, EncryptionKey},
+    AutoExposure, Bloom, ColorGrading, ColorSpace, DepthOfField, DynamicResolution, FeedConfig,
+    FeedMode, Fog, FullscreenMode, Gallery, GlareFilter, PointCloudSequence, PresentMode,
+    RenderConfig, RenderMode, ShStorage, Stereo, StereoMode, SyncConfig, Tonemapping, Transparency,
+    UserConfig, Viewer,
+};
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
 struct Opt {
-    /// Input file
-    input: PathBuf,
+    /// Input file, directory with per-frame point clouds or http(s) url
+    /// opens the most recently viewed scene and the gallery if omitted
+    input: Option<PathBuf>,
 
-    /// Scene json file
+    /// Scene json file, colmap sparse reconstruction directory or http(s) url
     scene: Option<PathBuf>,
 
+    /// Disable v-sync and render as fast as possible
     #[arg(long, default_value_t = false)]
     no_vsync: bool,
 
+    /// Surface present mode (vsync, no-vsync, fifo, mailbox or immediate), overrides the config file
+    #[arg(long)]
+    present_mode: Option<PresentMode>,
+
+    /// Cap the frame rate (e.g. 30 to save battery), overrides the config file
+    #[arg(long)]
+    max_fps: Option<f32>,
+
+    /// Initial window size (e.g. 1920x1080), derived from the first scene camera if not set
+    #[arg(long, value_parser = parse_size)]
+    window_size: Option<Vector2<u32>>,
+
+    /// Open the viewer in fullscreen mode (borderless or exclusive), toggle with F11
+    #[arg(long, num_args = 0..=1, default_missing_value = "borderless")]
+    fullscreen: Option<FullscreenMode>,
+
+    /// Background color as hex code (e.g. #ffffff) or comma separated floats (e.g. 1,1,1)
+    #[arg(long, value_parser = parse_color)]
+    background: Option<wgpu::Color>,
+
+    /// Transparent background (alpha zero) so the window can be composited over other content
+    #[arg(long, default_value_t = false)]
+    transparent: bool,
+
+    /// Start with hidden user interface (toggle with U)
+    #[arg(long, default_value_t = false)]
+    no_gui: bool,
+
     /// Support HDR rendering
     #[arg(long, default_value_t = false)]
     hdr: bool,
 
-    /// Sky box image
+    /// Adapt the exposure to the brightness of the rendered image, requires --hdr or --tonemapping
+    #[arg(long, default_value_t = false)]
+    auto_exposure: bool,
+
+    /// Adaption speed of the auto exposure, higher values adapt faster
+    #[arg(long, default_value_t = AutoExposure::default().speed)]
+    exposure_speed: f32,
+
+    /// Lower exposure limit of the auto exposure in EV
+    #[arg(long, default_value_t = AutoExposure::default().min_ev, allow_negative_numbers = true)]
+    exposure_min_ev: f32,
+
+    /// Upper exposure limit of the auto exposure in EV
+    #[arg(long, default_value_t = AutoExposure::default().max_ev, allow_negative_numbers = true)]
+    exposure_max_ev: f32,
+
+    /// Tone mapping operator (none, reinhard or aces), renders into a 16 bit float target
+    #[arg(long, default_value = "none")]
+    tonemapping: Tonemapping,
+
+    /// Color space in which the splats are blended (srgb or linear), linear renders into a 16 bit float target
+    #[arg(long, default_value = "srgb")]
+    color_space: ColorSpace,
+
+    /// Transfer function of the presented colors (srgb or linear)
+    #[arg(long, default_value = "srgb")]
+    output_color_space: ColorSpace,
+
+    /// Debug view of the splats (splats, wireframe, ellipsoids, points, depth or overdraw)
+    #[arg(long, default_value = "splats")]
+    render_mode: RenderMode,
+
+    /// Blending of overlapping splats (sorted, weighted-blended or stochastic)
+    /// the unsorted modes are faster but only approximate the transparency
+    #[arg(long, default_value = "sorted")]
+    transparency: Transparency,
+
+    /// Samples per pixel for anti-aliasing of the splat edges (1, 2, 4 or 8)
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=8))]
+    msaa: u32,
+
+    /// Resolution of the splat rendering relative to the window (0.5 to 2)
+    #[arg(long, default_value_t = 1.)]
+    render_scale: f32,
+
+    /// Lower the render scale by this factor while the camera moves fast (default 0.5)
+    #[arg(long, num_args = 0..=1, default_missing_value = "0.5")]
+    dynamic_resolution: Option<f32>,
+
+    /// Suppress small clusters of very bright splats, e.g. floaters in front of light sources
+    #[arg(long, default_value_t = false)]
+    glare_filter: bool,
+
+    /// Blur the parts of the scene that are not at the given distance from the camera (depth of field)
+    #[arg(long)]
+    dof_focus: Option<f32>,
+
+    /// Blur radius in pixels of distant objects when the depth of field is enabled
+    #[arg(long, default_value_t = 8.)]
+    dof_aperture: f32,
+
+    /// Glow around bright regions, the strength of the glow (e.g. 0.2)
+    #[arg(long)]
+    bloom: Option<f32>,
+
+    /// Darken the image towards the corners, 0 is off and 1 darkens the corners to black
+    #[arg(long, default_value_t = 0.)]
+    vignette: f32,
+
+    /// Contrast adaptive sharpening of the rendered image, 0 is off and 1 is the strongest
+    #[arg(long, default_value_t = 0.)]
+    sharpening: f32,
+
+    /// Gamma correction of the presented colors, values above 1 brighten the midtones
+    #[arg(long, default_value_t = 1.)]
+    gamma: f32,
+
+    /// Color saturation, 0 is grayscale and 1 leaves the colors unchanged
+    #[arg(long, default_value_t = 1.)]
+    saturation: f32,
+
+    /// White balance temperature from -1 (cooler) to 1 (warmer)
+    #[arg(long, default_value_t = 0., allow_negative_numbers = true)]
+    temperature: f32,
+
+    /// White balance tint from -1 (green) to 1 (magenta)
+    #[arg(long, default_value_t = 0., allow_negative_numbers = true)]
+    tint: f32,
+
+    /// Distance from the camera at which the distance fog reaches full strength (linear fog)
+    #[arg(long)]
+    fog_end: Option<f32>,
+
+    /// Distance from the camera at which the linear fog starts
+    #[arg(long, default_value_t = 0.)]
+    fog_start: f32,
+
+    /// Density of an exponential distance fog, replaces the linear fog
+    #[arg(long)]
+    fog_density: Option<f32>,
+
+    /// Fog color as hex code or comma separated floats, an alpha of zero fades the splats out, defaults to the background color
+    #[arg(long, value_parser = parse_color)]
+    fog_color: Option<wgpu::Color>,
+
+    /// Render a left and a right eye view (sbs, half-sbs for 3d tvs or anaglyph for red-cyan glasses)
+    #[arg(long)]
+    stereo: Option<StereoMode>,
+
+    /// Distance between the eyes of the stereo view in scene units
+    #[arg(long, default_value_t = 0.065)]
+    ipd: f32,
+
+    /// Comma separated indices of up to three scene cameras that are shown next to the current view in a grid
+    #[arg(long, value_delimiter = ',')]
+    grid: Vec<usize>,
+
+    /// Sky box, equirectangular image (e.g. hdr or exr) or directory with the six cubemap faces (px, nx, py, ny, pz, nz)
     #[arg(long)]
     skybox: Option<PathBuf>,
+
+    /// Store the spherical harmonics coefficients in a texture instead of a storage buffer
+    #[arg(long, default_value_t = false)]
+    sh_texture: bool,
+
+    /// Reload the point cloud when the file changes on disk (e.g. new training checkpoints)
+    #[arg(long, default_value_t = false)]
+    watch: bool,
+
+    /// Camera path json file (web-splat keyframes or nerfstudio camera path)
+    #[arg(long)]
+    camera_path: Option<PathBuf>,
+
+    /// Quantize the point cloud at load time to reduce GPU memory usage
+    #[arg(long, default_value_t = false)]
+    low_memory: bool,
+
+    /// Build an octree level of detail hierarchy, distant parts of large scenes are drawn with fewer splats
+    /// All splats stay in GPU memory (plus about 2% proxy splats), combine with --low-memory to reduce it
+    #[arg(long, default_value_t = false)]
+    lod: bool,
+
+    /// Store the covariances and SH coefficients as f16 instead of f32 on the GPU, halves their memory
+    #[arg(long, default_value_t = false)]
+    half_precision: bool,
+
+    /// Pack the splat rotations and scales into 10 bits per component on the GPU, saves 4 bytes per splat
+    #[arg(long, default_value_t = false)]
+    packed_covariance: bool,
+
+    /// Apply the Mip-Splatting 3D smoothing filter with the given size (default 0.2), requires a scene file
+    #[arg(long, num_args = 0..=1, default_missing_value = "0.2")]
+    filter_3d: Option<f32>,
+
+    /// Upload the splats in chunks after loading (large and opaque splats first) so that a coarse scene is shown while uploading
+    #[arg(long, default_value_t = false)]
+    chunked_upload: bool,
+
+    /// Seed for random features (e.g. the random camera key) to make demos and benchmarks reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Reveal the point cloud at startup by growing a clipping box from its center over the given number of seconds
+    #[arg(long)]
+    reveal: Option<f32>,
+
+    /// Index of the scene camera that is shown at startup
+    #[arg(long)]
+    camera: Option<usize>,
+
+    /// Maximum Spherical Harmonics (SH) degree used for rendering
+    #[arg(long)]
+    max_sh_deg: Option<u32>,
+
+    /// Crop file (axis aligned or oriented box json), splats outside of the box are removed when loading
+    /// defaults to `<input>.crop.json` or `crop.json` next to the input file
+    #[arg(long)]
+    crop: Option<PathBuf>,
+
+    /// Camera image that is composited with the splats (AR preview)
+    #[arg(long)]
+    feed: Option<PathBuf>,
+
+    /// Camera json file with the pose and intrinsics of the feed image (same format as the scene cameras)
+    #[arg(long)]
+    feed_camera: Option<PathBuf>,
+
+    /// Draw the feed behind or in front of the splats, or composite it with its depth image ("depth")
+    #[arg(long, default_value = "behind")]
+    feed_mode: FeedMode,
+
+    #[arg(long, default_value_t = 1.)]
+    feed_opacity: f32,
+
+    /// 16 bit depth image of the feed, 0 marks pixels without depth
+    #[arg(long)]
+    feed_depth: Option<PathBuf>,
+
+    /// View space depth per unit of the feed depth image (0.001 for depth images in millimeters)
+    #[arg(long, default_value_t = 0.001)]
+    feed_depth_scale: f32,
+
+    /// Directory in which a json lines log of every session is written (camera poses, settings changes, time spent per scene camera)
+    #[arg(long)]
+    event_log: Option<PathBuf>,
+
+    /// Websocket url of a sync room (e.g. ws://localhost:9001/review), see the sync_server binary
+    #[arg(long)]
+    sync: Option<String>,
+
+    /// Drive the camera of all other viewers in the sync room
+    #[arg(long, default_value_t = false, requires = "sync")]
+    present: bool,
+
+    /// Additional http header for remote files (e.g. "Authorization: Basic ..."), can be repeated
+    #[cfg(feature = "remote")]
+    #[arg(long = "header", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+
+    /// Bearer token for remote files, read from the WEB_SPLAT_AUTH_TOKEN environment variable if not set
+    #[cfg(feature = "remote")]
+    #[arg(long)]
+    auth_token: Option<String>,
+
+    /// Key (64 hex characters) for encrypted containers, read from the WEB_SPLAT_KEY environment variable if not set
+    #[arg(long)]
+    key: Option<EncryptionKey>,
+
+    /// Config file with persistent settings (controller, window size, present mode, background color and keys)
+    /// defaults to `~/.config/web-splat/config.toml`
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Playback rate when the input is a directory with per-frame point clouds
+    #[arg(long, default_value_t = 30.)]
+    fps: f32,
+}
+
+/// parses `<width>x<height>`
+fn parse_size(s: &str) -> Result<Vector2<u32>, String> {
+    let (w, h) = s
+        .split_once('x')
+        .ok_or(format!("invalid size '{s}', expected <width>x<height>"))?;
+    let parse = |v: &str| v.trim().parse::<u32>().map_err(|e| e.to_string());
+    Ok(Vector2::new(parse(w)?, parse(h)?))
+}
+
+/// parses `Name: value`
+#[cfg(feature = "remote")]
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or(format!("invalid header '{s}', expected 'Name: value'"))?;
+    let name = name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(format!("invalid header name '{name}'"));
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// parses `#rrggbb`, `#rrggbbaa` or `r,g,b[,a]` with values in [0,1]
+fn parse_color(s: &str) -> Result<wgpu::Color, String> {
+    let values: Vec<f64> = if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() != 6 && hex.len() != 8 {
+            return Err(format!("invalid hex color '{s}'"));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                u8::from_str_radix(&hex[i..i + 2], 16)
+                    .map(|v| v as f64 / 255.)
+                    .map_err(|e| e.to_string())
+            })
+            .collect::<Result<_, _>>()?
+    } else {
+        s.split(',')
+            .map(|v| v.trim().parse::<f64>().map_err(|e| e.to_string()))
+            .collect::<Result<_, _>>()?
+    };
+    match values[..] {
+        [r, g, b] => Ok(wgpu::Color { r, g, b, a: 1. }),
+        [r, g, b, a] => Ok(wgpu::Color { r, g, b, a }),
+        _ => Err(format!("invalid color '{s}'")),
+    }
 }
 
 /// check if there is a scene file in the same directory or parent directory as the input file
+/// cameras.json is preferred over nerfstudio's transforms.json and colmap reconstructions
 #[allow(unused)]
 fn try_find_scene_file(input: &PathBuf, depth: u32) -> Option<PathBuf> {
     if let Some(parent) = input.parent() {
-        let scene = parent.join("cameras.json");
-        if scene.exists() {
+        let candidates = [
+            parent.join("cameras.json"),
+            parent.join("transforms.json"),
+            parent.join("sparse").join("0").join("cameras.bin"),
+        ];
+        if let Some(scene) = candidates.into_iter().find(|p| p.exists()) {
             return Some(scene);
         }
         if depth == 0 {
@@ -41,35 +368,228 @@ fn try_find_scene_file(input: &PathBuf, depth: u32) -> Option<PathBuf> {
     return None;
 }
 
+/// creates the loader for remote files with the authorization headers from the command line
+#[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+fn remote_loader(headers: &[(String, String)], auth_token: Option<String>) -> RemoteLoader {
+    let mut loader = RemoteLoader::new(RemoteLoader::default_cache_dir());
+    for (name, value) in headers {
+        loader.add_header(name, value);
+    }
+    if let Some(token) = auth_token.or_else(|| std::env::var("WEB_SPLAT_AUTH_TOKEN").ok()) {
+        loader.set_auth_token(&token);
+    }
+    loader
+}
+
+/// downloads urls into the local cache and replaces them with the path of the cached file
+#[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+fn resolve_remote(path: PathBuf, loader: &RemoteLoader) -> anyhow::Result<PathBuf> {
+    match path.to_str() {
+        Some(url) if RemoteLoader::is_url(url) => {
+            loader
+                .fetch(url)
+                .with_context(|| format!("failed to download {url}"))?;
+            Ok(loader.cache_path(url))
+        }
+        _ => Ok(path),
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[pollster::main]
 async fn main() {
     let mut opt = Opt::parse();
 
+    let user_config_path = opt.config.clone().unwrap_or_else(UserConfig::default_path);
+    let mut user_config = UserConfig::load_or_create(&user_config_path);
+    if let Some(present_mode) = opt.present_mode {
+        user_config.present_mode = present_mode;
+    }
+    user_config.max_fps = opt.max_fps.or(user_config.max_fps);
+
+    let encryption_key = match (opt.key.take(), std::env::var("WEB_SPLAT_KEY")) {
+        (Some(key), _) => Some(key),
+        (None, Ok(key)) => match key.parse() {
+            Ok(key) => Some(key),
+            Err(err) => Opt::command()
+                .error(
+                    ErrorKind::ValueValidation,
+                    format!("invalid key in WEB_SPLAT_KEY: {err}"),
+                )
+                .exit(),
+        },
+        (None, Err(_)) => None,
+    };
+
+    #[cfg(feature = "remote")]
+    let loader = remote_loader(&opt.headers, opt.auth_token.take());
+    #[cfg(feature = "remote")]
+    let resolve = |path| match resolve_remote(path, &loader) {
+        Ok(path) => path,
+        Err(err) => Opt::command()
+            .error(ErrorKind::Io, format!("{err:#}"))
+            .exit(),
+    };
+    // without the remote feature only local files can be opened
+    #[cfg(not(feature = "remote"))]
+    let resolve = |path: PathBuf| path;
+
+    let show_gallery = opt.input.is_none();
+    let source = match opt.input {
+        Some(input) => input,
+        None => {
+            // on the first launch there is nothing to show yet
+            let gallery = Gallery::load(Gallery::default_dir());
+            let Some(recent) = gallery.most_recent() else {
+                Opt::command()
+                    .error(
+                        ErrorKind::MissingRequiredArgument,
+                        "no input file specified and no recently opened scenes in the gallery",
+                    )
+                    .exit();
+            };
+            opt.scene = recent.scene.clone();
+            recent.pointcloud.clone()
+        }
+    };
+    let input = resolve(source.clone());
+    // point clouds opened by url keep the url as path, so reloads go through the remote loader
+    let is_remote = input != source;
+    opt.scene = opt.scene.map(resolve);
+
+    // a directory is played back as a sequence of frames
+    let sequence = if input.is_dir() {
+        PointCloudSequence::list_files(&input).unwrap()
+    } else {
+        Vec::new()
+    };
+    let input = sequence.first().cloned().unwrap_or(input);
+
+    let crop = opt.crop.or_else(|| {
+        CropRegion::sidecar_paths(&input)
+            .into_iter()
+            .find(|p| p.exists())
+    });
+
     if opt.scene.is_none() {
-        opt.scene = try_find_scene_file(&opt.input, 2);
+        opt.scene = try_find_scene_file(&input, 2);
         log::warn!("No scene file specified, using {:?}", opt.scene);
     }
-    let data_file = File::open(&opt.input).unwrap();
-
-    let scene_file = opt.scene.as_ref().map(|p| File::open(p).unwrap());
+    let data_file = File::open(&input).unwrap();
 
     if opt.no_vsync {
         log::info!("V-sync disabled");
     }
 
-    open_window(
-        data_file,
-        scene_file,
-        RenderConfig {
-            no_vsync: opt.no_vsync,
-            skybox: opt.skybox,
-            hdr: opt.hdr,
+    let background = opt.background.or(user_config.background_color());
+    let fog_color = opt.fog_color.or(background).unwrap_or(wgpu::Color::BLACK);
+
+    if Viewer::new(RenderConfig {
+        no_vsync: opt.no_vsync,
+        skybox: opt.skybox,
+        hdr: opt.hdr,
+        sh_storage: if opt.sh_texture {
+            ShStorage::Texture
+        } else {
+            ShStorage::Buffer
         },
-        Some(opt.input),
+        camera_path: opt.camera_path,
+        show_gallery,
+        watch: opt.watch,
+        sequence,
+        sequence_fps: opt.fps,
+        low_memory: opt.low_memory,
+        lod: opt.lod,
+        half_precision: opt.half_precision,
+        packed_covariance: opt.packed_covariance,
+        filter_3d: opt.filter_3d,
+        chunked_upload: opt.chunked_upload,
+        seed: opt.seed,
+        reveal: opt.reveal.map(Duration::from_secs_f32),
+        camera: opt.camera,
+        max_sh_deg: opt.max_sh_deg,
+        crop,
+        feed: opt.feed.map(|image| FeedConfig {
+            image,
+            camera: opt.feed_camera,
+            mode: opt.feed_mode,
+            opacity: opt.feed_opacity,
+            depth: opt.feed_depth,
+            depth_scale: opt.feed_depth_scale,
+        }),
+        window_size: opt
+            .window_size
+            .or(user_config.window_size.map(|[w, h]| Vector2::new(w, h))),
+        fullscreen: opt.fullscreen,
+        background_color: background,
+        transparent: opt.transparent,
+        no_gui: opt.no_gui,
+        event_log: opt.event_log,
+        auto_exposure: opt.auto_exposure.then(|| AutoExposure {
+            speed: opt.exposure_speed,
+            min_ev: opt.exposure_min_ev,
+            max_ev: opt.exposure_max_ev,
+            ..Default::default()
+        }),
+        glare_filter: opt.glare_filter.then(GlareFilter::default),
+        depth_of_field: opt.dof_focus.map(|focus_distance| DepthOfField {
+            focus_distance,
+            aperture: opt.dof_aperture,
+        }),
+        bloom: opt.bloom.map(|intensity| Bloom {
+            intensity,
+            ..Default::default()
+        }),
+        vignette: opt.vignette,
+        sharpening: opt.sharpening,
+        color_grading: ColorGrading {
+            gamma: opt.gamma,
+            saturation: opt.saturation,
+            temperature: opt.temperature,
+            tint: opt.tint,
+        },
+        fog: match (opt.fog_density, opt.fog_end) {
+            (Some(density), _) => Some(Fog::exponential(fog_color, density)),
+            (None, Some(end)) => Some(Fog::linear(fog_color, opt.fog_start, end)),
+            (None, None) => None,
+        },
+        stereo: opt.stereo.map(|mode| Stereo { mode, ipd: opt.ipd }),
+        grid: opt.grid,
+        tonemapping: opt.tonemapping,
+        color_space: opt.color_space,
+        output_color_space: opt.output_color_space,
+        render_mode: opt.render_mode,
+        transparency: opt.transparency,
+        msaa: opt.msaa,
+        render_scale: opt.render_scale,
+        dynamic_resolution: opt
+            .dynamic_resolution
+            .map(|motion_scale| DynamicResolution {
+                motion_scale,
+                ..Default::default()
+            }),
+        sync: opt.sync.map(|url| SyncConfig {
+            url,
+            presenter: opt.present,
+        }),
+        user_config,
+        user_config_path: Some(user_config_path),
+        encryption_key,
+        #[cfg(feature = "remote")]
+        remote_loader: Some(loader.clone()),
+    })
+    .open(
+        data_file,
+        None,
+        Some(if is_remote { source } else { input }),
         opt.scene,
     )
-    .await;
+    .await
+    .is_err()
+    {
+        // the error was already shown in a message dialog
+        std::process::exit(1);
+    }
 }
 #[cfg(target_arch = "wasm32")]
 fn main() {
@@ -0,0 +1,298 @@
+use wgpu::{include_wgsl, Extent3d};
+
+use crate::{renderer::ColorSpace, uniform::UniformBuffer};
+
+/// maximum number of levels of the blur chain, the first level has half the render resolution
+const MAX_LEVELS: u32 = 6;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// glow around bright regions of the image
+/// only colors above the threshold glow, so it is most useful with hdr rendering
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bloom {
+    /// strength of the glow added to the image
+    pub intensity: f32,
+    /// linear colors with a lower maximum component do not glow
+    pub threshold: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self {
+            intensity: 0.2,
+            threshold: 0.8,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct BloomUniform {
+    threshold: f32,
+    color_space: u32,
+    _pad: [u32; 2],
+}
+
+impl Default for BloomUniform {
+    fn default() -> Self {
+        Self {
+            threshold: Bloom::default().threshold,
+            color_space: ColorSpace::Srgb as u32,
+            _pad: [0; 2],
+        }
+    }
+}
+
+/// blurs the bright parts of the rendered image with a chain of downsampled targets
+/// the bright pass is downsampled level by level and then upsampled and added back up to the first level
+pub(crate) struct BloomChain {
+    prefilter: wgpu::RenderPipeline,
+    downsample: wgpu::RenderPipeline,
+    upsample: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    uniform: UniformBuffer<BloomUniform>,
+    /// one view per level
+    levels: Vec<wgpu::TextureView>,
+    /// bind groups for sampling the levels
+    level_bgs: Vec<wgpu::BindGroup>,
+}
+
+impl BloomChain {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("bloom bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: UniformBuffer::<BloomUniform>::binding_type(),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("bloom pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(include_wgsl!("shaders/bloom.wgsl"));
+        let create_pipeline = |entry_point: &str, blend: Option<wgpu::BlendState>| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("bloom pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: FORMAT,
+                        blend,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                multiview: None,
+            })
+        };
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let prefilter = create_pipeline("fs_prefilter", None);
+        let downsample = create_pipeline("fs_downsample", None);
+        let upsample = create_pipeline(
+            "fs_upsample",
+            Some(wgpu::BlendState {
+                color: additive,
+                alpha: additive,
+            }),
+        );
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("bloom sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let uniform = UniformBuffer::new_default(device, Some("bloom settings"));
+        let mut chain = Self {
+            prefilter,
+            downsample,
+            upsample,
+            bind_group_layout,
+            sampler,
+            uniform,
+            levels: Vec::new(),
+            level_bgs: Vec::new(),
+        };
+        chain.resize(device, width, height);
+        chain
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        let width = (width / 2).max(1);
+        let height = (height / 2).max(1);
+        let num_levels = (width.min(height).ilog2() + 1).min(MAX_LEVELS);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("bloom texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: num_levels,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        self.levels = (0..num_levels)
+            .map(|level| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("bloom level view"),
+                    base_mip_level: level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        self.level_bgs = self
+            .levels
+            .iter()
+            .map(|view| self.create_bind_group(device, view))
+            .collect();
+    }
+
+    /// blurred bright pass of the last recorded image at half resolution
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.levels[0]
+    }
+
+    pub fn threshold(&self) -> f32 {
+        self.uniform.data().threshold
+    }
+
+    pub fn set_threshold(&mut self, queue: &wgpu::Queue, threshold: f32) {
+        self.uniform.as_mut().threshold = threshold.max(0.);
+        self.uniform.sync(queue);
+    }
+
+    /// records the blur chain of `source`, the splats in `source` were blended in `color_space`
+    pub fn record(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        color_space: ColorSpace,
+    ) {
+        if self.uniform.data().color_space != color_space as u32 {
+            self.uniform.as_mut().color_space = color_space as u32;
+            self.uniform.sync(queue);
+        }
+        let source_bg = self.create_bind_group(device, source);
+        Self::pass(
+            encoder,
+            &self.prefilter,
+            &source_bg,
+            &self.levels[0],
+            wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+        );
+        for i in 1..self.levels.len() {
+            Self::pass(
+                encoder,
+                &self.downsample,
+                &self.level_bgs[i - 1],
+                &self.levels[i],
+                wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            );
+        }
+        for i in (1..self.levels.len()).rev() {
+            Self::pass(
+                encoder,
+                &self.upsample,
+                &self.level_bgs[i],
+                &self.levels[i - 1],
+                wgpu::LoadOp::Load,
+            );
+        }
+    }
+
+    fn pass(
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::RenderPipeline,
+        bind_group: &wgpu::BindGroup,
+        target: &wgpu::TextureView,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("bloom pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..4, 0..1);
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        view: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("bloom bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.uniform.buffer().as_entire_binding(),
+                },
+            ],
+        })
+    }
+}
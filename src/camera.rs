@@ -1,7 +1,7 @@
 use cgmath::*;
 use std::hash::{Hash, Hasher};
 
-use crate::{animation::Lerp, pointcloud::Aabb};
+use crate::{animation::Lerp, pointcloud::Aabb, utils::math::Frustum};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct PerspectiveCamera {
@@ -67,6 +67,7 @@ impl Default for PerspectiveCamera {
                 znear: 0.1,
                 zfar: 100.,
                 fov2view_ratio: 1.,
+                principal_offset: Vector2::zero(),
             },
         }
     }
@@ -91,6 +92,9 @@ pub struct PerspectiveProjection {
     /// fov ratio to viewport ratio
     /// needed for camera viewport resize
     pub(crate) fov2view_ratio: f32,
+    /// offset of the principal point from the image center in normalized device coordinates
+    /// e.g. `(2 * cx / width - 1, 2 * cy / height - 1)` for the pixel coordinates `cx`, `cy`
+    pub principal_offset: Vector2<f32>,
 }
 
 impl Hash for PerspectiveProjection {
@@ -100,6 +104,8 @@ impl Hash for PerspectiveProjection {
         self.znear.to_bits().hash(state);
         self.zfar.to_bits().hash(state);
         self.fov2view_ratio.to_bits().hash(state);
+        self.principal_offset.x.to_bits().hash(state);
+        self.principal_offset.y.to_bits().hash(state);
     }
 }
 
@@ -127,11 +133,29 @@ impl PerspectiveProjection {
             znear,
             zfar,
             fov2view_ratio: vr / fr,
+            principal_offset: Vector2::zero(),
         }
     }
 
+    /// projection with the principal point at the pixel coordinates `principal_point`
+    pub fn with_principal_point(
+        mut self,
+        viewport: Vector2<u32>,
+        principal_point: Vector2<f32>,
+    ) -> Self {
+        let size: Vector2<f32> = viewport.cast().unwrap();
+        self.principal_offset = Vector2::new(
+            2. * principal_point.x / size.x - 1.,
+            2. * principal_point.y / size.y - 1.,
+        );
+        self
+    }
+
     pub fn projection_matrix(&self) -> Matrix4<f32> {
-        build_proj(self.znear, self.zfar, self.fovx, self.fovy)
+        let mut proj = build_proj(self.znear, self.zfar, self.fovx, self.fovy);
+        proj[2][0] += self.principal_offset.x;
+        proj[2][1] += self.principal_offset.y;
+        proj
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
@@ -158,6 +182,7 @@ impl PerspectiveProjection {
             znear: self.znear * (1. - amount) + other.znear * amount,
             zfar: self.zfar * (1. - amount) + other.zfar * amount,
             fov2view_ratio: self.fov2view_ratio * (1. - amount) + other.fov2view_ratio * amount,
+            principal_offset: self.principal_offset.lerp(other.principal_offset, amount),
         }
     }
 }
@@ -180,19 +205,7 @@ pub trait Camera {
     }
 
     fn frustum_planes(&self) -> FrustumPlanes {
-        let p = self.proj_matrix();
-        let v = self.view_matrix();
-        let pv = p * v;
-        let mut planes = [Vector4::zero(); 6];
-        planes[0] = pv.row(3) + pv.row(0);
-        planes[1] = pv.row(3) - pv.row(0);
-        planes[2] = pv.row(3) + pv.row(1);
-        planes[3] = pv.row(3) - pv.row(1);
-        planes[4] = pv.row(3) + pv.row(2);
-        planes[5] = pv.row(3) - pv.row(2);
-        for i in 0..6 {
-            planes[i] = planes[i].normalize();
-        }
+        let planes = Frustum::from_matrix(self.proj_matrix() * self.view_matrix()).planes;
         return FrustumPlanes {
             near: planes[4],
             far: planes[5],
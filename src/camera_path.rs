@@ -0,0 +1,336 @@
+use std::{
+    io::{self, BufReader, Read},
+    str::FromStr,
+};
+
+use cgmath::{Deg, Rad};
+use serde::{Deserialize, Serialize};
+use splines::{Interpolation, Key, Spline};
+
+use crate::{
+    animation::{Lerp, Sampler},
+    camera::{fov2focal, PerspectiveCamera},
+    scene::{opengl_to_scene_pose, SceneCamera, Split},
+    time::Duration,
+};
+
+/// a single pose of a camera path
+/// uses the same camera convention as the scene json files
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CameraPathKeyframe {
+    /// time in seconds
+    pub time: f32,
+    pub width: u32,
+    pub height: u32,
+    pub position: [f32; 3],
+    pub rotation: [[f32; 3]; 3],
+    pub fx: f32,
+    pub fy: f32,
+    /// caption that is shown from this keyframe until the next one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+}
+
+impl CameraPathKeyframe {
+    fn camera(&self) -> PerspectiveCamera {
+        SceneCamera {
+            id: 0,
+            img_name: String::new(),
+            width: self.width,
+            height: self.height,
+            position: self.position,
+            rotation: self.rotation,
+            fx: self.fx,
+            fy: self.fy,
+            cx: None,
+            cy: None,
+            split: Split::default(),
+        }
+        .into()
+    }
+}
+
+/// timed text that is shown while a camera path is played (e.g. in guided tours)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Caption {
+    /// start and end time in seconds
+    pub start: f32,
+    pub end: f32,
+    pub text: String,
+}
+
+/// writes the captions as SubRip (srt) subtitles
+pub fn write_srt<W: io::Write>(captions: &[Caption], mut w: W) -> io::Result<()> {
+    let timestamp = |t: f32| {
+        let ms = (t.max(0.) * 1000.).round() as u64;
+        format!(
+            "{:02}:{:02}:{:02},{:03}",
+            ms / 3_600_000,
+            ms / 60_000 % 60,
+            ms / 1000 % 60,
+            ms % 1000
+        )
+    };
+    for (i, c) in captions.iter().enumerate() {
+        writeln!(w, "{}", i + 1)?;
+        writeln!(w, "{} --> {}", timestamp(c.start), timestamp(c.end))?;
+        writeln!(w, "{}\n", c.text)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct CameraPathFile {
+    keyframes: Vec<CameraPathKeyframe>,
+    /// captions with explicit times, in addition to the keyframe captions
+    #[serde(default)]
+    captions: Vec<Caption>,
+}
+
+impl CameraPathFile {
+    /// keyframe captions last until the next keyframe or the end of the path
+    fn keyframe_captions(&self) -> impl Iterator<Item = Caption> + '_ {
+        let mut keyframes: Vec<&CameraPathKeyframe> = self.keyframes.iter().collect();
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        let end = keyframes.last().map_or(0., |k| k.time);
+        (0..keyframes.len()).filter_map(move |i| {
+            let text = keyframes[i].caption.clone()?;
+            Some(Caption {
+                start: keyframes[i].time,
+                end: keyframes.get(i + 1).map_or(end, |k| k.time),
+                text,
+            })
+        })
+    }
+}
+
+/// camera path as exported by nerfstudio's viewer
+#[derive(Debug, Deserialize)]
+struct NerfstudioCameraPath {
+    render_width: Option<u32>,
+    render_height: Option<u32>,
+    fps: Option<f32>,
+    seconds: Option<f32>,
+    camera_path: Vec<NerfstudioPose>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NerfstudioPose {
+    /// row major 4x4 matrix in OpenGL convention
+    camera_to_world: [f32; 16],
+    /// vertical field of view in degrees
+    fov: f32,
+    aspect: f32,
+}
+
+/// interpolation of the poses between the keyframes of a camera path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathInterpolation {
+    #[default]
+    Linear,
+    /// Catmull-Rom spline through the keyframes, smooth at the keyframes
+    CatmullRom,
+}
+
+impl FromStr for PathInterpolation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Self::Linear),
+            "catmull-rom" => Ok(Self::CatmullRom),
+            _ => Err(anyhow::anyhow!(
+                "invalid interpolation '{}', expected linear or catmull-rom",
+                s
+            )),
+        }
+    }
+}
+
+/// timed sequence of camera poses
+/// poses are linearly interpolated between keyframes unless a spline is set with [CameraPath::set_interpolation]
+#[derive(Debug, Clone)]
+pub struct CameraPath {
+    keyframes: Vec<(f32, PerspectiveCamera)>,
+    duration: Duration,
+    spline: Option<Spline<f32, PerspectiveCamera>>,
+    /// sorted by start time, relative to the first keyframe
+    captions: Vec<Caption>,
+}
+
+impl CameraPath {
+    pub fn new(mut keyframes: Vec<(f32, PerspectiveCamera)>) -> anyhow::Result<Self> {
+        if keyframes.is_empty() {
+            return Err(anyhow::anyhow!("camera path has no keyframes"));
+        }
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let start = keyframes[0].0;
+        for k in keyframes.iter_mut() {
+            k.0 -= start;
+        }
+        let duration = Duration::from_secs_f32(keyframes.last().unwrap().0);
+        Ok(Self {
+            keyframes,
+            duration,
+            spline: None,
+            captions: Vec::new(),
+        })
+    }
+
+    /// `captions` use the times of the keyframes passed to [CameraPath::new]
+    pub fn set_captions(&mut self, start: f32, mut captions: Vec<Caption>) {
+        for c in captions.iter_mut() {
+            c.start -= start;
+            c.end -= start;
+        }
+        captions.sort_by(|a, b| a.start.total_cmp(&b.start));
+        self.captions = captions;
+    }
+
+    pub fn captions(&self) -> &[Caption] {
+        &self.captions
+    }
+
+    /// caption shown at `time` seconds after the start of the path
+    pub fn caption_at(&self, time: f32) -> Option<&str> {
+        self.captions
+            .iter()
+            .rev()
+            .find(|c| c.start <= time && time < c.end)
+            .map(|c| c.text.as_str())
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: PathInterpolation) {
+        self.spline = match interpolation {
+            PathInterpolation::CatmullRom if self.keyframes.len() > 1 => {
+                // the first and last keyframe are repeated so the spline passes through all keyframes
+                let n = self.keyframes.len();
+                let (t0, c0) = self.keyframes[0];
+                let (t1, _) = self.keyframes[1];
+                let (tn, cn) = self.keyframes[n - 1];
+                let (tm, _) = self.keyframes[n - 2];
+                let keys = std::iter::once((2. * t0 - t1, c0))
+                    .chain(self.keyframes.iter().copied())
+                    .chain(std::iter::once((2. * tn - tm, cn)))
+                    .map(|(t, c)| Key::new(t, c, Interpolation::CatmullRom));
+                Some(Spline::from_iter(keys))
+            }
+            _ => None,
+        };
+    }
+
+    /// loads a camera path file
+    /// supports the web-splat keyframe format and nerfstudio camera paths
+    pub fn from_json<R: io::Read>(file: R) -> anyhow::Result<Self> {
+        let mut data = Vec::new();
+        BufReader::new(file).read_to_end(&mut data)?;
+
+        let path = if let Ok(file) = serde_json::from_slice::<CameraPathFile>(&data) {
+            let mut path = Self::new(
+                file.keyframes
+                    .iter()
+                    .map(|k| (k.time, k.camera()))
+                    .collect(),
+            )?;
+            let start = file
+                .keyframes
+                .iter()
+                .map(|k| k.time)
+                .fold(f32::INFINITY, f32::min);
+            let mut captions: Vec<Caption> = file.keyframe_captions().collect();
+            captions.extend(file.captions);
+            path.set_captions(start, captions);
+            path
+        } else {
+            let path: NerfstudioCameraPath = serde_json::from_slice(&data)?;
+            Self::from_nerfstudio(path)?
+        };
+        log::info!(
+            "loaded camera path with {} keyframes ({:.2}s)",
+            path.keyframes.len(),
+            path.duration.as_secs_f32()
+        );
+        Ok(path)
+    }
+
+    fn from_nerfstudio(path: NerfstudioCameraPath) -> anyhow::Result<Self> {
+        let num_poses = path.camera_path.len();
+        let fps = match (path.fps, path.seconds) {
+            (Some(fps), _) => fps,
+            (None, Some(seconds)) if seconds > 0. => (num_poses.max(2) - 1) as f32 / seconds,
+            _ => 30.,
+        };
+        let keyframes = path
+            .camera_path
+            .iter()
+            .enumerate()
+            .map(|(i, pose)| {
+                let height = path.render_height.unwrap_or(1080);
+                let width = path
+                    .render_width
+                    .unwrap_or((height as f32 * pose.aspect) as u32);
+                (i as f32 / fps, nerfstudio_camera(pose, width, height))
+            })
+            .collect();
+        Self::new(keyframes)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn num_keyframes(&self) -> usize {
+        self.keyframes.len()
+    }
+}
+
+impl Sampler for CameraPath {
+    type Sample = PerspectiveCamera;
+
+    fn sample(&self, v: f32) -> Self::Sample {
+        let t = v.clamp(0., 1.) * self.duration.as_secs_f32();
+        if let Some(camera) = self.spline.as_ref().and_then(|s| s.sample(t)) {
+            return camera;
+        }
+        let next = self.keyframes.partition_point(|(time, _)| *time <= t);
+        if next == 0 {
+            return self.keyframes[0].1;
+        }
+        if next == self.keyframes.len() {
+            return self.keyframes[next - 1].1;
+        }
+        let (t0, c0) = &self.keyframes[next - 1];
+        let (t1, c1) = &self.keyframes[next];
+        let amount = if t1 > t0 { (t - t0) / (t1 - t0) } else { 0. };
+        c0.lerp(c1, amount)
+    }
+}
+
+/// converts a nerfstudio pose (OpenGL convention) to the colmap convention used by the viewer
+fn nerfstudio_camera(pose: &NerfstudioPose, width: u32, height: u32) -> PerspectiveCamera {
+    let m = &pose.camera_to_world;
+    let (position, rotation) = opengl_to_scene_pose([
+        [m[0], m[1], m[2], m[3]],
+        [m[4], m[5], m[6], m[7]],
+        [m[8], m[9], m[10], m[11]],
+        [m[12], m[13], m[14], m[15]],
+    ]);
+    let fovy: Rad<f32> = Deg(pose.fov).into();
+    let fy = fov2focal(fovy, height as f32);
+    // square pixels
+    let fx = fy;
+    SceneCamera {
+        id: 0,
+        img_name: String::new(),
+        width,
+        height,
+        position,
+        rotation,
+        fx,
+        fy,
+        cx: None,
+        cy: None,
+        split: Split::default(),
+    }
+    .into()
+}
@@ -0,0 +1,372 @@
+use std::{path::PathBuf, str::FromStr};
+
+use wgpu::util::DeviceExt;
+
+use crate::uniform::UniformBuffer;
+
+/// where the external image is drawn relative to the splats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FeedMode {
+    /// the image is the background and splats are drawn on top
+    #[default]
+    Behind,
+    /// the image is blended over the splats
+    Front,
+    /// the image is blended over the splats where it is closer than the splats
+    /// and drawn behind them everywhere else, requires a depth image of the feed
+    Depth,
+}
+
+impl FromStr for FeedMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "behind" | "back" => Ok(Self::Behind),
+            "front" => Ok(Self::Front),
+            "depth" => Ok(Self::Depth),
+            _ => Err(anyhow::anyhow!("unknown feed mode '{}'", s)),
+        }
+    }
+}
+
+/// external image that is composited with the splats
+pub struct FeedConfig {
+    pub image: PathBuf,
+    /// camera json file (same format as the cameras in the scene file) with the pose and intrinsics of the image
+    pub camera: Option<PathBuf>,
+    pub mode: FeedMode,
+    pub opacity: f32,
+    /// 16 bit depth image of the feed (e.g. from a depth sensor), 0 marks pixels without depth
+    pub depth: Option<PathBuf>,
+    /// view space depth per unit of the depth image
+    pub depth_scale: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct FeedUniform {
+    opacity: f32,
+    _pad: [f32; 3],
+}
+
+impl Default for FeedUniform {
+    fn default() -> Self {
+        Self {
+            opacity: 1.,
+            _pad: [0.; 3],
+        }
+    }
+}
+
+/// composites an external camera image (image file, video frame or a texture provided by the host) with the splats
+/// the image covers the whole viewport, so the view camera should use the pose and intrinsics of the image
+pub struct FeedCompositor {
+    pipeline: wgpu::RenderPipeline,
+    /// draws the image where it is closer than the splats, see [FeedMode::Depth]
+    depth_pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    texture: Option<wgpu::Texture>,
+    bind_group: Option<wgpu::BindGroup>,
+    depth: Option<wgpu::TextureView>,
+    /// feed depth and expected depth of the splats, see [FeedCompositor::set_splat_depth]
+    depth_bind_group: Option<wgpu::BindGroup>,
+    settings: UniformBuffer<FeedUniform>,
+    pub mode: FeedMode,
+}
+
+impl FeedCompositor {
+    pub fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/composite.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("feed composite pipeline layout"),
+            bind_group_layouts: &[
+                &Self::bind_group_layout(device),
+                &UniformBuffer::<FeedUniform>::bind_group_layout(device),
+            ],
+            push_constant_ranges: &[],
+        });
+        let depth_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("feed depth composite pipeline layout"),
+                bind_group_layouts: &[
+                    &Self::bind_group_layout(device),
+                    &UniformBuffer::<FeedUniform>::bind_group_layout(device),
+                    &Self::depth_bind_group_layout(device),
+                ],
+                push_constant_ranges: &[],
+            });
+        let create_pipeline = |label, layout, entry_point| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                // drawn in the same render pass as the splats
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    ..Default::default()
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: color_format,
+                        blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                multiview: None,
+            })
+        };
+        let pipeline = create_pipeline("feed composite pipeline", &pipeline_layout, "fs_main");
+        let depth_pipeline = create_pipeline(
+            "feed depth composite pipeline",
+            &depth_pipeline_layout,
+            "fs_depth",
+        );
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("feed sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        Self {
+            pipeline,
+            depth_pipeline,
+            sampler,
+            texture: None,
+            bind_group: None,
+            depth: None,
+            depth_bind_group: None,
+            settings: UniformBuffer::new_default(device, Some("feed settings uniform buffer")),
+            mode: FeedMode::default(),
+        }
+    }
+
+    fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("feed bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    /// feed depth (binding 0) and expected depth of the splats (binding 1)
+    /// both are read with `textureLoad`, so they do not need to be filterable
+    fn depth_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        let entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("feed depth bind group layout"),
+            entries: &[entry(0), entry(1)],
+        })
+    }
+
+    /// uses a texture provided by the host (e.g. the current frame of a webcam)
+    /// the texture must contain gamma encoded colors like the splats
+    pub fn set_texture_view(&mut self, device: &wgpu::Device, view: &wgpu::TextureView) {
+        self.texture = None;
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("feed bind group"),
+            layout: &Self::bind_group_layout(device),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        }));
+    }
+
+    /// uploads an image, the texture is reused if the image size did not change
+    /// can be called every frame to stream video frames
+    pub fn set_image(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        img: &image::RgbaImage,
+    ) {
+        let size = wgpu::Extent3d {
+            width: img.width(),
+            height: img.height(),
+            depth_or_array_layers: 1,
+        };
+        match &self.texture {
+            Some(texture) if texture.size() == size => {
+                queue.write_texture(
+                    texture.as_image_copy(),
+                    img.as_raw(),
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(4 * img.width()),
+                        rows_per_image: None,
+                    },
+                    size,
+                );
+            }
+            _ => {
+                let texture = device.create_texture_with_data(
+                    queue,
+                    &wgpu::TextureDescriptor {
+                        label: Some("feed texture"),
+                        size,
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format: wgpu::TextureFormat::Rgba8Unorm,
+                        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                        view_formats: &[],
+                    },
+                    wgpu::util::TextureDataOrder::LayerMajor,
+                    img.as_raw(),
+                );
+                self.set_texture_view(device, &texture.create_view(&Default::default()));
+                self.texture = Some(texture);
+            }
+        }
+    }
+
+    /// uploads the depth image of the feed, the values are multiplied with `scale`
+    pub fn set_depth_image(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        img: &image::ImageBuffer<image::Luma<u16>, Vec<u16>>,
+        scale: f32,
+    ) {
+        let depth: Vec<f32> = img.pixels().map(|p| p[0] as f32 * scale).collect();
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("feed depth texture"),
+                size: wgpu::Extent3d {
+                    width: img.width(),
+                    height: img.height(),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R32Float,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(&depth),
+        );
+        self.depth = Some(texture.create_view(&Default::default()));
+        self.depth_bind_group = None;
+    }
+
+    /// true if the splats are composited with the feed depth
+    /// the expected depth of the splats must then be passed to [FeedCompositor::set_splat_depth] every frame
+    pub fn needs_splat_depth(&self) -> bool {
+        self.mode == FeedMode::Depth && self.depth.is_some()
+    }
+
+    /// sets the expected depth of the splats that the feed depth is compared to
+    /// (see [crate::GaussianRenderer::render_expected_depth])
+    pub fn set_splat_depth(&mut self, device: &wgpu::Device, expected_depth: &wgpu::TextureView) {
+        let Some(depth) = &self.depth else {
+            return;
+        };
+        self.depth_bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("feed depth bind group"),
+            layout: &Self::depth_bind_group_layout(device),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(depth),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(expected_depth),
+                },
+            ],
+        }));
+    }
+
+    pub fn opacity(&self) -> f32 {
+        self.settings.data().opacity
+    }
+
+    pub fn set_opacity(&mut self, queue: &wgpu::Queue, opacity: f32) {
+        self.settings.as_mut().opacity = opacity.clamp(0., 1.);
+        self.settings.sync(queue);
+    }
+
+    /// draws the image if the compositor has an image
+    /// `mode` is the place in the render pass ([FeedMode::Behind] before and [FeedMode::Front] after the splats)
+    /// in [FeedMode::Depth] the image is drawn behind the splats and again in front of them where it is closer
+    pub fn render<'rpass>(
+        &'rpass self,
+        render_pass: &mut wgpu::RenderPass<'rpass>,
+        mode: FeedMode,
+    ) {
+        let Some(bind_group) = &self.bind_group else {
+            return;
+        };
+        let depth_bind_group = match (self.mode, mode) {
+            (FeedMode::Behind | FeedMode::Depth, FeedMode::Behind)
+            | (FeedMode::Front, FeedMode::Front) => None,
+            (FeedMode::Depth, FeedMode::Front) => match &self.depth_bind_group {
+                Some(depth_bind_group) => Some(depth_bind_group),
+                None => return,
+            },
+            _ => return,
+        };
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.set_bind_group(1, self.settings.bind_group(), &[]);
+        match depth_bind_group {
+            Some(depth_bind_group) => {
+                render_pass.set_pipeline(&self.depth_pipeline);
+                render_pass.set_bind_group(2, depth_bind_group, &[]);
+            }
+            None => render_pass.set_pipeline(&self.pipeline),
+        }
+        render_pass.draw(0..4, 0..1);
+    }
+}
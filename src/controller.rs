@@ -1,14 +1,13 @@
 use cgmath::*;
-#[cfg(target_arch = "wasm32")]
-use instant::Duration;
 use num_traits::Float;
-use std::f32::consts::PI;
-#[cfg(not(target_arch = "wasm32"))]
-use std::time::Duration;
+use std::{collections::HashMap, f32::consts::PI};
 
-use winit::keyboard::KeyCode;
+use winit::event::TouchPhase;
 
 use crate::camera::PerspectiveCamera;
+use crate::io::DensityGrid;
+use crate::keymap::Action;
+use crate::time::Duration;
 
 #[derive(Debug)]
 pub struct CameraController {
@@ -24,7 +23,13 @@ pub struct CameraController {
     pub left_mouse_pressed: bool,
     pub right_mouse_pressed: bool,
     pub alt_pressed: bool,
+    pub ctrl_pressed: bool,
     pub user_inptut: bool,
+    /// stop zooming in before the camera passes through dense regions of the point cloud
+    pub zoom_collision: bool,
+
+    /// current positions of the active touch points
+    touches: HashMap<u64, Vector2<f32>>,
 }
 
 impl CameraController {
@@ -41,42 +46,46 @@ impl CameraController {
             left_mouse_pressed: false,
             right_mouse_pressed: false,
             alt_pressed: false,
+            ctrl_pressed: false,
             user_inptut: false,
+            zoom_collision: true,
+            touches: HashMap::new(),
         }
     }
 
-    pub fn process_keyboard(&mut self, key: KeyCode, pressed: bool) -> bool {
+    /// handles navigation actions, see [Action::is_navigation]
+    pub fn process_keyboard(&mut self, action: Action, pressed: bool) -> bool {
         let amount = if pressed { 1.0 } else { 0.0 };
-        let processed = match key {
-            KeyCode::KeyW | KeyCode::ArrowUp => {
+        let processed = match action {
+            Action::MoveForward => {
                 self.amount.z += amount;
                 true
             }
-            KeyCode::KeyS | KeyCode::ArrowDown => {
+            Action::MoveBackward => {
                 self.amount.z += -amount;
                 true
             }
-            KeyCode::KeyA | KeyCode::ArrowLeft => {
+            Action::MoveLeft => {
                 self.amount.x += -amount;
                 true
             }
-            KeyCode::KeyD | KeyCode::ArrowRight => {
+            Action::MoveRight => {
                 self.amount.x += amount;
                 true
             }
-            KeyCode::KeyQ => {
+            Action::RollLeft => {
                 self.rotation.z += amount / self.sensitivity;
                 true
             }
-            KeyCode::KeyE => {
+            Action::RollRight => {
                 self.rotation.z += -amount / self.sensitivity;
                 true
             }
-            KeyCode::Space => {
+            Action::MoveUp => {
                 self.amount.y += amount;
                 true
             }
-            KeyCode::ShiftLeft => {
+            Action::MoveDown => {
                 self.amount.y += -amount;
                 true
             }
@@ -104,6 +113,46 @@ impl CameraController {
         self.user_inptut = true;
     }
 
+    /// one finger orbits, two fingers pan and pinch to zoom
+    pub fn process_touch(&mut self, id: u64, phase: TouchPhase, position: Vector2<f32>) {
+        match phase {
+            TouchPhase::Started => {
+                self.touches.insert(id, position);
+            }
+            TouchPhase::Moved => {
+                let Some(last) = self.touches.insert(id, position) else {
+                    return;
+                };
+                match self.touches.len() {
+                    1 => {
+                        let delta = position - last;
+                        self.rotation.x += delta.x;
+                        self.rotation.y += delta.y;
+                        self.user_inptut = true;
+                    }
+                    2 => {
+                        let other = *self.touches.iter().find(|(i, _)| **i != id).unwrap().1;
+                        // the other finger did not move, so the centroid moves by half the delta
+                        let delta = (position - last) / 2.;
+                        self.shift.y += -delta.x;
+                        self.shift.x += delta.y;
+
+                        let dist_old = (last - other).magnitude();
+                        let dist_new = (position - other).magnitude();
+                        if dist_old > 0. && dist_new > 0. {
+                            self.process_scroll((dist_new / dist_old).ln() * 10.);
+                        }
+                        self.user_inptut = true;
+                    }
+                    _ => {}
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.remove(&id);
+            }
+        }
+    }
+
     /// moves the controller center to the closest point on a line defined by the camera position and rotation
     /// ajusts the controller up vector by projecting the current up vector onto the plane defined by the camera right vector
     pub fn reset_to_camera(&mut self, camera: PerspectiveCamera) {
@@ -121,11 +170,31 @@ impl CameraController {
     }
 
     pub fn update_camera(&mut self, camera: &mut PerspectiveCamera, dt: Duration) {
+        self.update_camera_with_collision(camera, dt, None);
+    }
+
+    /// same as [CameraController::update_camera], zooming in stops in front of solid cells of the density grid
+    pub fn update_camera_with_collision(
+        &mut self,
+        camera: &mut PerspectiveCamera,
+        dt: Duration,
+        density: Option<&DensityGrid>,
+    ) {
         let dt: f32 = dt.as_secs_f32();
         let mut dir = camera.position - self.center;
         let distance = dir.magnitude();
 
-        dir = dir.normalize_to((distance.ln() + self.scroll * dt * 10. * self.speed).exp());
+        let mut new_distance = (distance.ln() + self.scroll * dt * 10. * self.speed).exp();
+        if let Some(density) = density.filter(|_| self.zoom_collision) {
+            if new_distance < distance {
+                let free = density.free_distance(camera.position, -dir, distance - new_distance);
+                new_distance = distance - free;
+                if free == 0. {
+                    self.scroll = 0.;
+                }
+            }
+        }
+        dir = dir.normalize_to(new_distance);
 
         let view_t: Matrix3<f32> = camera.rotation.invert().into();
 
@@ -0,0 +1,233 @@
+//! cpu reference of the culling done in the preprocess shaders
+//! used to cull groups of splats (e.g. octree nodes or chunks) before they are uploaded or drawn
+//! a group must never be culled if one of its splats passes the per splat test of the shader
+
+use cgmath::{Matrix4, Point3, Vector4};
+
+use crate::{camera::Camera, math::Frustum, pointcloud::Aabb};
+
+/// splats are kept if their center lies within 1.2 times the viewport (see preprocess.wgsl)
+pub const GUARD_BAND: f32 = 1.2;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Culler {
+    view_proj: Matrix4<f32>,
+    /// frustum widened by the guard band
+    frustum: Frustum,
+    clipping_box: Option<Aabb<f32>>,
+}
+
+impl Culler {
+    pub fn new<C: Camera>(camera: &C, clipping_box: Option<Aabb<f32>>) -> Self {
+        let view_proj = camera.proj_matrix() * camera.view_matrix();
+        let guard_band = Matrix4::from_nonuniform_scale(1. / GUARD_BAND, 1. / GUARD_BAND, 1.);
+        Self {
+            view_proj,
+            frustum: Frustum::from_matrix(guard_band * view_proj),
+            clipping_box,
+        }
+    }
+
+    /// same test as the preprocess shader
+    pub fn point_visible(&self, p: Point3<f32>) -> bool {
+        if let Some(clip) = &self.clipping_box {
+            if p.x < clip.min.x
+                || p.y < clip.min.y
+                || p.z < clip.min.z
+                || p.x > clip.max.x
+                || p.y > clip.max.y
+                || p.z > clip.max.z
+            {
+                return false;
+            }
+        }
+        let pos2d: Vector4<f32> = self.view_proj * p.to_homogeneous();
+        let bounds = GUARD_BAND * pos2d.w;
+        let z = pos2d.z / pos2d.w;
+        !(z <= 0.
+            || z >= 1.
+            || pos2d.x < -bounds
+            || pos2d.x > bounds
+            || pos2d.y < -bounds
+            || pos2d.y > bounds)
+    }
+
+    /// conservative test for a group of splats with the given bounding box
+    /// returns false only if no point of the box passes [Culler::point_visible]
+    pub fn aabb_visible(&self, aabb: &Aabb<f32>) -> bool {
+        if let Some(clip) = &self.clipping_box {
+            if aabb.max.x < clip.min.x
+                || aabb.max.y < clip.min.y
+                || aabb.max.z < clip.min.z
+                || aabb.min.x > clip.max.x
+                || aabb.min.y > clip.max.y
+                || aabb.min.z > clip.max.z
+            {
+                return false;
+            }
+        }
+        self.frustum.intersects_aabb(aabb)
+    }
+
+    /// conservative test for a group of splats within the given sphere
+    pub fn sphere_visible(&self, center: Point3<f32>, radius: f32) -> bool {
+        self.frustum.intersects_sphere(center, radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, InnerSpace, Quaternion, Transform, Vector2, Vector3};
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::camera::{PerspectiveCamera, PerspectiveProjection};
+
+    /// points closer than this (relative to the view depth) to a frustum plane are not compared
+    const EPS: f32 = 1e-3;
+
+    prop_compose! {
+        fn camera()(
+            position in prop::array::uniform3(-10f32..10.),
+            rotation in prop::array::uniform4(-1f32..1.)
+                .prop_filter("rotation must not be zero", |q| q.iter().map(|v| v * v).sum::<f32>() > 0.01),
+            fov in (20f32..120., 20f32..120.),
+            znear in 0.01f32..1.,
+            depth_range in 10f32..1000.,
+        ) -> PerspectiveCamera {
+            let [w, x, y, z] = rotation;
+            PerspectiveCamera::new(
+                Point3::from(position),
+                Quaternion::new(w, x, y, z).normalize(),
+                PerspectiveProjection::new(
+                    Vector2::new(800, 600),
+                    Vector2::new(Deg(fov.0), Deg(fov.1)),
+                    znear,
+                    znear * depth_range,
+                ),
+            )
+        }
+    }
+
+    prop_compose! {
+        fn aabb()(
+            min in prop::array::uniform3(-20f32..20.),
+            size in prop::array::uniform3(0f32..10.),
+        ) -> Aabb<f32> {
+            let min = Point3::from(min);
+            Aabb::new(min, min + Vector3::from(size))
+        }
+    }
+
+    /// visibility computed from the view space position and the field of view
+    /// returns `None` if the point is too close to the border of the (guard band) frustum
+    fn reference_visible(camera: &PerspectiveCamera, p: Point3<f32>) -> Option<bool> {
+        let v = camera.view_matrix().transform_point(p);
+        let projection = camera.projection;
+        let half_width = GUARD_BAND * (projection.fovx.0 / 2.).tan() * v.z;
+        let half_height = GUARD_BAND * (projection.fovy.0 / 2.).tan() * v.z;
+        let margins = [
+            v.z - projection.znear,
+            projection.zfar - v.z,
+            half_width - v.x.abs(),
+            half_height - v.y.abs(),
+        ];
+        if margins.iter().any(|m| m.abs() < EPS * v.z.abs().max(1.)) {
+            return None;
+        }
+        Some(margins.iter().all(|m| *m > 0.))
+    }
+
+    /// corners, center and some interior points of the box
+    fn sample_points(aabb: &Aabb<f32>, t: &[[f32; 3]]) -> Vec<Point3<f32>> {
+        let size = aabb.size();
+        aabb.corners()
+            .into_iter()
+            .chain([aabb.center()])
+            .chain(
+                t.iter()
+                    .map(|t| aabb.min + Vector3::new(t[0] * size.x, t[1] * size.y, t[2] * size.z)),
+            )
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn point_visible_matches_reference(
+            camera in camera(),
+            p in prop::array::uniform3(-30f32..30.),
+        ) {
+            let p = Point3::from(p);
+            let reference = reference_visible(&camera, p);
+            prop_assume!(reference.is_some());
+            prop_assert_eq!(Culler::new(&camera, None).point_visible(p), reference.unwrap());
+        }
+
+        #[test]
+        fn aabb_with_visible_point_is_not_culled(
+            camera in camera(),
+            aabb in aabb(),
+            clipping_box in prop::option::of(aabb()),
+            t in prop::collection::vec(prop::array::uniform3(0f32..1.), 16),
+        ) {
+            let culler = Culler::new(&camera, clipping_box);
+            if sample_points(&aabb, &t).into_iter().any(|p| culler.point_visible(p)) {
+                prop_assert!(culler.aabb_visible(&aabb));
+            }
+        }
+
+        #[test]
+        fn sphere_with_visible_point_is_not_culled(
+            camera in camera(),
+            center in prop::array::uniform3(-20f32..20.),
+            radius in 0f32..10.,
+            directions in prop::collection::vec(prop::array::uniform3(-1f32..1.), 16),
+        ) {
+            let culler = Culler::new(&camera, None);
+            let center = Point3::from(center);
+            let visible = directions.into_iter().map(Vector3::from).any(|d| {
+                let d = if d.magnitude2() > 1. { d.normalize() } else { d };
+                culler.point_visible(center + d * radius)
+            });
+            if visible || culler.point_visible(center) {
+                prop_assert!(culler.sphere_visible(center, radius));
+            }
+        }
+
+        #[test]
+        fn clipping_box_culls_outside_points(
+            camera in camera(),
+            clipping_box in aabb(),
+            p in prop::array::uniform3(-30f32..30.),
+        ) {
+            let p = Point3::from(p);
+            let culler = Culler::new(&camera, Some(clipping_box));
+            if culler.point_visible(p) {
+                prop_assert!(crate::utils::math::aabb_contains_point(&clipping_box, p));
+            }
+        }
+    }
+
+    #[test]
+    fn point_in_front_of_camera_is_visible() {
+        let camera = PerspectiveCamera::new(
+            Point3::new(0., 0., 0.),
+            Quaternion::new(1., 0., 0., 0.),
+            PerspectiveProjection::new(
+                Vector2::new(800, 600),
+                Vector2::new(Deg(90.), Deg(70.)),
+                0.1,
+                100.,
+            ),
+        );
+        let culler = Culler::new(&camera, None);
+        let ahead = camera
+            .view_matrix()
+            .inverse_transform()
+            .unwrap()
+            .transform_point(Point3::new(0., 0., 10.));
+        assert!(culler.point_visible(ahead));
+        assert!(culler.sphere_visible(ahead, 0.));
+        assert!(culler.aabb_visible(&Aabb::new(ahead, ahead)));
+    }
+}
@@ -0,0 +1,74 @@
+use cgmath::InnerSpace;
+
+use crate::{camera::PerspectiveCamera, time::Duration};
+
+/// time the lowered scale is kept after the camera slowed down, bridges short pauses
+const HOLD_TIME: f32 = 0.15;
+/// time in which the full resolution is restored after the hold time
+const BLEND_TIME: f32 = 0.3;
+/// the scale changes in steps, so the render targets are not recreated every frame of the blend
+const SCALE_STEP: f32 = 0.05;
+
+/// lowers the render scale while the camera moves fast and restores it when the camera settles
+/// keeps the interaction smooth on slow gpus, the still image is rendered at full resolution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DynamicResolution {
+    /// render scale relative to the configured render scale while the camera moves
+    pub motion_scale: f32,
+    /// camera speed above which the scale is lowered
+    /// sum of the translation in scene radii and the rotation in radians per second
+    pub speed_threshold: f32,
+}
+
+impl Default for DynamicResolution {
+    fn default() -> Self {
+        Self {
+            motion_scale: 0.5,
+            speed_threshold: 0.2,
+        }
+    }
+}
+
+/// tracks the camera motion and computes the factor for the render scale
+pub(crate) struct MotionScaler {
+    pub settings: DynamicResolution,
+    /// seconds since the camera moved faster than the threshold
+    since_motion: f32,
+    last_camera: Option<PerspectiveCamera>,
+}
+
+impl MotionScaler {
+    pub fn new(settings: DynamicResolution) -> Self {
+        Self {
+            settings,
+            since_motion: f32::INFINITY,
+            last_camera: None,
+        }
+    }
+
+    /// updates the motion with the camera of the current frame and returns the scale factor
+    pub fn update(&mut self, camera: &PerspectiveCamera, scene_radius: f32, dt: Duration) -> f32 {
+        let dt = dt.as_secs_f32();
+        let distance = self.last_camera.map_or(0., |last| {
+            let translation =
+                (camera.position - last.position).magnitude() / scene_radius.max(1e-6);
+            let rotation = 2. * last.rotation.dot(camera.rotation).abs().min(1.).acos();
+            translation + rotation
+        });
+        self.last_camera = Some(*camera);
+        if dt > 0. && distance / dt > self.settings.speed_threshold {
+            self.since_motion = 0.;
+        } else {
+            self.since_motion += dt;
+        }
+        self.scale()
+    }
+
+    /// factor for the render scale, the motion scale while moving and 1 when settled
+    pub fn scale(&self) -> f32 {
+        let blend = ((self.since_motion - HOLD_TIME) / BLEND_TIME).clamp(0., 1.);
+        let scale = self.settings.motion_scale.clamp(0.1, 1.);
+        let scale = scale + (1. - scale) * blend;
+        ((scale / SCALE_STEP).round() * SCALE_STEP).min(1.)
+    }
+}
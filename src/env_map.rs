@@ -0,0 +1,116 @@
+use std::{f32::consts::PI, path::Path};
+
+use cgmath::Vector3;
+use image::{imageops::sample_bilinear, DynamicImage, Rgba, Rgba32FImage};
+
+/// file names of the cubemap faces in the order +x, -x, +y, -y, +z, -z
+/// both the short and the descriptive naming of common skybox packs are supported
+const CUBE_FACE_NAMES: [[&str; 2]; 6] = [
+    ["px", "right"],
+    ["nx", "left"],
+    ["py", "top"],
+    ["ny", "bottom"],
+    ["pz", "front"],
+    ["nz", "back"],
+];
+
+/// the equirectangular image (4x the face size wide) must not exceed the default texture size limit
+const MAX_FACE_SIZE: u32 = 2048;
+
+/// loads an environment map as equirectangular image
+/// `path` is either an equirectangular image (e.g. hdr or exr) or a directory with the six faces of a cubemap
+pub(crate) fn load_env_map(path: &Path) -> anyhow::Result<Rgba32FImage> {
+    if path.is_dir() {
+        let faces = CUBE_FACE_NAMES
+            .iter()
+            .map(|names| load_cube_face(path, names))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(cubemap_to_equirect(&faces))
+    } else {
+        Ok(to_linear(image::open(path)?))
+    }
+}
+
+/// converts the image to linear colors
+/// float images (e.g. hdr or exr) are linear already, all other formats store srgb encoded colors
+fn to_linear(img: DynamicImage) -> Rgba32FImage {
+    let is_linear = matches!(
+        img,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+    );
+    let mut img = img.to_rgba32f();
+    if !is_linear {
+        for p in img.pixels_mut() {
+            for c in &mut p.0[..3] {
+                *c = if *c <= 0.04045 {
+                    *c / 12.92
+                } else {
+                    ((*c + 0.055) / 1.055).powf(2.4)
+                };
+            }
+        }
+    }
+    img
+}
+
+fn load_cube_face(dir: &Path, names: &[&str]) -> anyhow::Result<Rgba32FImage> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if names.contains(&stem.as_str()) {
+            return Ok(to_linear(image::open(&path)?));
+        }
+    }
+    Err(anyhow::anyhow!(
+        "cubemap face {} not found in {:?}",
+        names.join("/"),
+        dir
+    ))
+}
+
+/// resamples the faces of a cubemap (OpenGL convention, order +x, -x, +y, -y, +z, -z)
+/// with the equirectangular mapping used by the display shader
+fn cubemap_to_equirect(faces: &[Rgba32FImage]) -> Rgba32FImage {
+    let face_size = faces
+        .iter()
+        .map(|f| f.width())
+        .max()
+        .unwrap_or(1)
+        .min(MAX_FACE_SIZE);
+    let (width, height) = (face_size * 4, face_size * 2);
+    Rgba32FImage::from_fn(width, height, |x, y| {
+        let u = (x as f32 + 0.5) / width as f32;
+        let v = (y as f32 + 0.5) / height as f32;
+        let theta = (u - 0.5) * 2. * PI;
+        let phi = (v - 0.5) * PI;
+        let dir = Vector3::new(phi.cos() * theta.cos(), -phi.sin(), phi.cos() * theta.sin());
+        let (face, s, t) = cube_face_coords(dir);
+        sample_bilinear(&faces[face], s, t).unwrap_or(Rgba([0., 0., 0., 1.]))
+    })
+}
+
+/// face index and texture coordinates of a direction in a cubemap
+fn cube_face_coords(d: Vector3<f32>) -> (usize, f32, f32) {
+    let (ax, ay, az) = (d.x.abs(), d.y.abs(), d.z.abs());
+    let (face, sc, tc, ma) = if ax >= ay && ax >= az {
+        if d.x > 0. {
+            (0, -d.z, -d.y, ax)
+        } else {
+            (1, d.z, -d.y, ax)
+        }
+    } else if ay >= az {
+        if d.y > 0. {
+            (2, d.x, d.z, ay)
+        } else {
+            (3, d.x, -d.z, ay)
+        }
+    } else if d.z > 0. {
+        (4, d.x, -d.y, az)
+    } else {
+        (5, -d.x, -d.y, az)
+    };
+    (face, (sc / ma + 1.) / 2., (tc / ma + 1.) / 2.)
+}
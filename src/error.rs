@@ -0,0 +1,26 @@
+/// errors returned by the public loading and setup functions
+#[derive(Debug, thiserror::Error)]
+pub enum WebSplatError {
+    #[error("no suitable graphics adapter found")]
+    NoAdapter,
+    #[error("cannot create graphics device: {0}")]
+    RequestDevice(#[from] wgpu::RequestDeviceError),
+    #[error("cannot create surface: {0}")]
+    CreateSurface(#[from] wgpu::CreateSurfaceError),
+    #[cfg(feature = "ui")]
+    #[error("cannot create window: {0}")]
+    Window(#[from] winit::error::OsError),
+    #[cfg(feature = "ui")]
+    #[error("event loop error: {0}")]
+    EventLoop(#[from] winit::error::EventLoopError),
+    #[error("unknown point cloud file format")]
+    UnknownFormat,
+    #[error("cannot load point cloud: {0:#}")]
+    PointCloud(#[source] anyhow::Error),
+    #[error("cannot load scene: {0:#}")]
+    Scene(#[source] anyhow::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
@@ -0,0 +1,192 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use cgmath::{EuclideanSpace, Point3, Quaternion};
+use serde::Serialize;
+
+use crate::{
+    math::{quaternion_to_array, QuaternionOrder},
+    renderer::RenderSettings,
+    time::{Duration, Instant},
+    Scene,
+};
+
+/// minimum time between two logged camera poses
+const CAMERA_INTERVAL: Duration = Duration::from_millis(500);
+
+/// render settings that are logged when changed by the user
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Settings {
+    gaussian_scaling: f32,
+    max_sh_deg: u32,
+    show_env_map: bool,
+    mip_splatting: Option<bool>,
+    kernel_size: Option<f32>,
+    /// min and max corner
+    clipping_box: Option<[[f32; 3]; 2]>,
+    background_color: [f64; 4],
+}
+
+impl From<&RenderSettings> for Settings {
+    fn from(args: &RenderSettings) -> Self {
+        let bg = args.background_color;
+        Self {
+            gaussian_scaling: args.gaussian_scaling,
+            max_sh_deg: args.max_sh_deg,
+            show_env_map: args.show_env_map,
+            mip_splatting: args.mip_splatting,
+            kernel_size: args.kernel_size,
+            clipping_box: args.clipping_box.map(|b| [b.min.into(), b.max.into()]),
+            background_color: [bg.r, bg.g, bg.b, bg.a],
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    SessionStart {
+        /// unix timestamp in seconds
+        unix_time: u64,
+    },
+    SceneOpened {
+        file: &'a Path,
+    },
+    Camera {
+        position: [f32; 3],
+        /// scalar last
+        rotation: [f32; 4],
+    },
+    Settings(Settings),
+    /// time spent close to a scene camera
+    Region {
+        camera: usize,
+        name: String,
+        /// duration in seconds
+        duration: f32,
+    },
+    SessionEnd,
+}
+
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    /// seconds since the start of the session
+    time: f32,
+    #[serde(flatten)]
+    event: Event<'a>,
+}
+
+/// writes user interactions of a viewer session as json lines
+/// (camera poses, settings changes and the time spent close to each scene camera)
+pub struct EventLog {
+    writer: BufWriter<File>,
+    start: Instant,
+    pointcloud_file: Option<PathBuf>,
+    last_pose: Option<(Instant, Point3<f32>, Quaternion<f32>)>,
+    settings: Option<Settings>,
+    /// nearest scene camera and when the camera got close to it
+    region: Option<(usize, String, Instant)>,
+}
+
+impl EventLog {
+    /// creates a new log file `session-<unix time>.jsonl` in `dir`
+    pub fn new(dir: &Path) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let unix_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let path = dir.join(format!("session-{unix_time}.jsonl"));
+        log::info!("writing event log to {:?}", path);
+        let mut event_log = Self {
+            writer: BufWriter::new(File::create(path)?),
+            start: Instant::now(),
+            pointcloud_file: None,
+            last_pose: None,
+            settings: None,
+            region: None,
+        };
+        event_log.write(Event::SessionStart { unix_time });
+        Ok(event_log)
+    }
+
+    fn write(&mut self, event: Event) {
+        let record = Record {
+            time: self.start.elapsed().as_secs_f32(),
+            event,
+        };
+        // the log is flushed after every event so that nothing is lost if the viewer is killed (e.g. on kiosk systems)
+        let result = serde_json::to_writer(&mut self.writer, &record)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(writeln!(self.writer)?))
+            .and_then(|_| Ok(self.writer.flush()?));
+        if let Err(err) = result {
+            log::error!("failed to write event log: {:?}", err);
+        }
+    }
+
+    /// logs changes since the last call, called once per frame
+    pub fn update(
+        &mut self,
+        args: &RenderSettings,
+        scene: Option<&Scene>,
+        pointcloud_file: Option<&Path>,
+    ) {
+        if pointcloud_file != self.pointcloud_file.as_deref() {
+            self.end_region();
+            self.pointcloud_file = pointcloud_file.map(|p| p.to_path_buf());
+            if let Some(file) = pointcloud_file {
+                self.write(Event::SceneOpened { file });
+            }
+        }
+
+        let settings = Settings::from(args);
+        if self.settings.as_ref() != Some(&settings) {
+            self.settings = Some(settings.clone());
+            self.write(Event::Settings(settings));
+        }
+
+        let camera = &args.camera;
+        let moved = match self.last_pose {
+            Some((time, position, rotation)) => {
+                time.elapsed() >= CAMERA_INTERVAL
+                    && (position != camera.position || rotation != camera.rotation)
+            }
+            None => true,
+        };
+        if moved {
+            self.last_pose = Some((Instant::now(), camera.position, camera.rotation));
+            self.write(Event::Camera {
+                position: camera.position.to_vec().into(),
+                rotation: quaternion_to_array(camera.rotation, QuaternionOrder::Xyzw),
+            });
+        }
+
+        let nearest = scene.and_then(|s| {
+            s.nearest_camera(camera.position, None)
+                .and_then(|i| s.camera(i).map(|c| (i, c.img_name)))
+        });
+        if nearest.as_ref().map(|n| n.0) != self.region.as_ref().map(|r| r.0) {
+            self.end_region();
+            self.region = nearest.map(|(i, name)| (i, name, Instant::now()));
+        }
+    }
+
+    fn end_region(&mut self) {
+        if let Some((camera, name, since)) = self.region.take() {
+            self.write(Event::Region {
+                camera,
+                name,
+                duration: since.elapsed().as_secs_f32(),
+            });
+        }
+    }
+}
+
+impl Drop for EventLog {
+    fn drop(&mut self) {
+        self.end_region();
+        self.write(Event::SessionEnd);
+    }
+}
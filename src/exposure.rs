@@ -0,0 +1,276 @@
+use std::sync::mpsc;
+
+use wgpu::include_wgsl;
+
+use crate::{time::Duration, uniform::UniformBuffer};
+
+const NUM_BINS: usize = 256;
+/// luminance range covered by the histogram (log2)
+const MIN_LOG_LUM: f32 = -10.;
+const MAX_LOG_LUM: f32 = 6.;
+/// average luminance the exposure maps to (middle gray)
+const KEY_VALUE: f32 = 0.18;
+/// only pixels between these percentiles are averaged, so that a dark background
+/// or a few very bright splats do not dominate the exposure
+const LOW_PERCENTILE: f32 = 0.5;
+const HIGH_PERCENTILE: f32 = 0.95;
+
+/// automatic exposure from the luminance histogram of the rendered image
+/// only useful with hdr rendering, 8 bit render targets are clamped to [0, 1]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoExposure {
+    /// adaption speed, higher values adapt faster
+    pub speed: f32,
+    /// exposure limits in EV (log2 of the exposure factor)
+    pub min_ev: f32,
+    pub max_ev: f32,
+    /// added to the measured exposure (in EV)
+    pub compensation: f32,
+}
+
+impl Default for AutoExposure {
+    fn default() -> Self {
+        Self {
+            speed: 1.5,
+            min_ev: -4.,
+            max_ev: 4.,
+            compensation: 0.,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct HistogramUniform {
+    min_log_lum: f32,
+    inv_log_lum_range: f32,
+    _pad: [f32; 2],
+}
+
+/// computes the luminance histogram on the gpu and reads it back asynchronously
+/// the exposure is adapted on the cpu with one or more frames of latency
+pub(crate) struct ExposureController {
+    pub settings: AutoExposure,
+    /// current exposure in EV
+    ev: f32,
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform: UniformBuffer<HistogramUniform>,
+    histogram: wgpu::Buffer,
+    staging: wgpu::Buffer,
+    /// a copy into the staging buffer was recorded and waits for submission
+    copied: bool,
+    /// staging buffer is being mapped
+    mapping: Option<mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>>,
+    /// last histogram that was read back
+    bins: Option<[u32; NUM_BINS]>,
+}
+
+impl ExposureController {
+    pub fn new(device: &wgpu::Device, settings: AutoExposure) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("luminance histogram bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: UniformBuffer::<HistogramUniform>::binding_type(),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("luminance histogram pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(include_wgsl!("shaders/luminance_histogram.wgsl"));
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("luminance histogram pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+            compilation_options: Default::default(),
+        });
+        let size = (NUM_BINS * std::mem::size_of::<u32>()) as u64;
+        let histogram = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("luminance histogram buffer"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("luminance histogram staging buffer"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniform = UniformBuffer::new(
+            device,
+            HistogramUniform {
+                min_log_lum: MIN_LOG_LUM,
+                inv_log_lum_range: 1. / (MAX_LOG_LUM - MIN_LOG_LUM),
+                _pad: [0.; 2],
+            },
+            Some("luminance histogram settings"),
+        );
+        Self {
+            settings,
+            ev: 0.,
+            pipeline,
+            bind_group_layout,
+            uniform,
+            histogram,
+            staging,
+            copied: false,
+            mapping: None,
+            bins: None,
+        }
+    }
+
+    /// linear exposure factor
+    pub fn exposure(&self) -> f32 {
+        self.ev.exp2()
+    }
+
+    /// records the histogram computation of `source`
+    /// skipped while the previous histogram is still read back
+    pub fn record(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        size: (u32, u32),
+    ) {
+        if self.copied || self.mapping.is_some() {
+            return;
+        }
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("luminance histogram bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.histogram.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.uniform.buffer().as_entire_binding(),
+                },
+            ],
+        });
+        encoder.clear_buffer(&self.histogram, 0, None);
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("luminance histogram pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(size.0.div_ceil(16), size.1.div_ceil(16), 1);
+        }
+        encoder.copy_buffer_to_buffer(&self.histogram, 0, &self.staging, 0, self.staging.size());
+        self.copied = true;
+    }
+
+    /// starts reading back the histogram, must be called after the commands of [Self::record] were submitted
+    pub fn after_submit(&mut self) {
+        if !self.copied {
+            return;
+        }
+        self.copied = false;
+        let (tx, rx) = mpsc::channel();
+        self.staging
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                tx.send(result).ok();
+            });
+        self.mapping = Some(rx);
+    }
+
+    /// adapts the exposure towards the exposure measured with the latest histogram
+    /// returns true if the exposure changed noticeably
+    pub fn update(&mut self, device: &wgpu::Device, dt: Duration) -> bool {
+        if let Some(rx) = &self.mapping {
+            device.poll(wgpu::Maintain::Poll);
+            match rx.try_recv() {
+                Ok(result) => {
+                    if let Err(err) = result {
+                        log::error!("failed to read luminance histogram: {:?}", err);
+                    } else {
+                        let data = self.staging.slice(..).get_mapped_range();
+                        let mut bins = [0u32; NUM_BINS];
+                        bins.copy_from_slice(bytemuck::cast_slice(&data));
+                        self.bins = Some(bins);
+                    }
+                    self.staging.unmap();
+                    self.mapping = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.mapping = None,
+            }
+        }
+
+        let Some(avg_log_lum) = self.bins.as_ref().and_then(average_log_luminance) else {
+            return false;
+        };
+        let target = ((KEY_VALUE.log2() - avg_log_lum) + self.settings.compensation)
+            .clamp(self.settings.min_ev, self.settings.max_ev);
+        let old_ev = self.ev;
+        self.ev += (target - self.ev) * (1. - (-dt.as_secs_f32() * self.settings.speed).exp());
+        (self.ev - old_ev).abs() > 1e-3
+    }
+}
+
+/// mean log2 luminance of the histogram without black pixels and the darkest and brightest pixels
+fn average_log_luminance(bins: &[u32; NUM_BINS]) -> Option<f32> {
+    let total: u32 = bins[1..].iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let low = (total as f32 * LOW_PERCENTILE) as u32;
+    let high = (total as f32 * HIGH_PERCENTILE) as u32;
+    let bin_width = (MAX_LOG_LUM - MIN_LOG_LUM) / (NUM_BINS - 2) as f32;
+
+    let mut count = 0;
+    let mut sum = 0.;
+    let mut weight = 0;
+    for (i, n) in bins.iter().enumerate().skip(1) {
+        // part of the bin between the low and high percentile
+        let start = count.max(low);
+        let end = (count + n).min(high);
+        count += n;
+        if end > start {
+            let log_lum = MIN_LOG_LUM + (i as f32 - 0.5) * bin_width;
+            sum += log_lum * (end - start) as f32;
+            weight += end - start;
+        }
+    }
+    (weight > 0).then(|| sum / weight as f32)
+}
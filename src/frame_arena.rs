@@ -0,0 +1,207 @@
+//! bump allocator for the transient cpu data of a frame
+//! the level of detail traversal (culling stack and selected ranges), the staging data of
+//! per-splat bit masks and the frame time plots are allocated here instead of on the heap
+//! (the sort keys are sorted on the gpu and never touch the cpu)
+
+use std::{
+    cell::{Cell, RefCell},
+    mem::{align_of, size_of},
+    ops::{Deref, DerefMut},
+};
+
+/// size of the first chunk in bytes
+const INITIAL_CAPACITY: usize = 64 * 1024;
+
+/// usage of the arena during the last frame
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameArenaStats {
+    /// bytes handed out
+    pub used: usize,
+    /// bytes reserved by the arena
+    pub capacity: usize,
+    /// most bytes handed out in a single frame
+    pub peak: usize,
+    /// chunks that had to be allocated because the arena was full
+    pub overflows: usize,
+}
+
+/// bump allocator for transient cpu data that only lives for one frame
+/// everything is released at once with [FrameArena::reset], afterwards the chunks are merged
+/// so that after a few frames the whole frame fits into one chunk and nothing is allocated on the heap
+pub struct FrameArena {
+    /// the heap memory of a chunk never moves, so handed out slices stay valid until the next reset
+    chunks: RefCell<Vec<Vec<u64>>>,
+    /// start of the free part of the last chunk
+    next: Cell<*mut u64>,
+    /// free words in the last chunk
+    remaining: Cell<usize>,
+    used: Cell<usize>,
+    overflows: Cell<usize>,
+    stats: FrameArenaStats,
+}
+
+impl FrameArenaStats {
+    /// combined usage of two arenas
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            used: self.used + other.used,
+            capacity: self.capacity + other.capacity,
+            peak: self.peak + other.peak,
+            overflows: self.overflows + other.overflows,
+        }
+    }
+}
+
+impl Default for FrameArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameArena {
+    pub fn new() -> Self {
+        let arena = Self {
+            chunks: RefCell::new(Vec::new()),
+            next: Cell::new(std::ptr::null_mut()),
+            remaining: Cell::new(0),
+            used: Cell::new(0),
+            overflows: Cell::new(0),
+            stats: FrameArenaStats::default(),
+        };
+        arena.add_chunk(INITIAL_CAPACITY / size_of::<u64>());
+        arena
+    }
+
+    fn add_chunk(&self, words: usize) {
+        let mut chunk = vec![0u64; words];
+        self.next.set(chunk.as_mut_ptr());
+        self.remaining.set(words);
+        self.chunks.borrow_mut().push(chunk);
+    }
+
+    /// zero initialized slice that is valid until the next [FrameArena::reset]
+    // every call hands out a different part of the chunk, so the mutable slices never alias
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice<T: bytemuck::Pod>(&self, len: usize) -> &mut [T] {
+        assert!(
+            align_of::<T>() <= align_of::<u64>(),
+            "frame arena only supports types aligned to at most 8 bytes"
+        );
+        let bytes = len
+            .checked_mul(size_of::<T>())
+            .expect("frame arena allocation too large");
+        let words = bytes.div_ceil(size_of::<u64>());
+        if words > self.remaining.get() {
+            let last_len = self.chunks.borrow().last().map_or(0, |c| c.len());
+            self.add_chunk(words.max(last_len * 2));
+            self.overflows.set(self.overflows.get() + 1);
+        }
+        let ptr = self.next.get();
+        // SAFETY: the words between ptr and ptr + words belong to the last chunk and are
+        // handed out only once until the next reset, which requires a mutable borrow of the arena.
+        // all zero bytes are a valid T because T is Pod
+        unsafe {
+            self.next.set(ptr.add(words));
+            self.remaining.set(self.remaining.get() - words);
+            self.used.set(self.used.get() + bytes);
+            let ptr = ptr as *mut T;
+            std::ptr::write_bytes(ptr, 0, len);
+            std::slice::from_raw_parts_mut(ptr, len)
+        }
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_from_iter<T, I>(&self, iter: I) -> &mut [T]
+    where
+        T: bytemuck::Pod,
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let iter = iter.into_iter();
+        let slice = self.alloc_slice(iter.len());
+        for (s, v) in slice.iter_mut().zip(iter) {
+            *s = v;
+        }
+        slice
+    }
+
+    /// empty vector that can hold up to `capacity` items
+    pub fn alloc_vec<T: bytemuck::Pod>(&self, capacity: usize) -> ArenaVec<'_, T> {
+        ArenaVec {
+            data: self.alloc_slice(capacity),
+            len: 0,
+        }
+    }
+
+    /// releases all allocations of the frame
+    pub fn reset(&mut self) {
+        let chunks = self.chunks.get_mut();
+        let capacity: usize = chunks.iter().map(|c| c.len()).sum();
+        let used = self.used.replace(0);
+        self.stats = FrameArenaStats {
+            used,
+            capacity: capacity * size_of::<u64>(),
+            peak: self.stats.peak.max(used),
+            overflows: self.overflows.replace(0),
+        };
+        if chunks.len() > 1 {
+            // one chunk that fits the whole frame
+            chunks.clear();
+            self.add_chunk(capacity);
+        } else if let Some(chunk) = chunks.first_mut() {
+            self.next.set(chunk.as_mut_ptr());
+            self.remaining.set(chunk.len());
+        }
+    }
+
+    pub fn stats(&self) -> FrameArenaStats {
+        self.stats
+    }
+}
+
+/// vector with a fixed capacity that lives in a [FrameArena]
+pub struct ArenaVec<'a, T> {
+    data: &'a mut [T],
+    len: usize,
+}
+
+impl<'a, T: Copy> ArenaVec<'a, T> {
+    /// panics if the capacity is exceeded
+    pub fn push(&mut self, value: T) {
+        assert!(self.len < self.data.len(), "arena vector is full");
+        self.data[self.len] = value;
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.len = self.len.checked_sub(1)?;
+        Some(self.data[self.len])
+    }
+
+    /// the items, valid until the arena is reset
+    pub fn into_slice(self) -> &'a [T] {
+        &self.data[..self.len]
+    }
+}
+
+impl<T: Copy> Extend<T> for ArenaVec<'_, T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T> Deref for ArenaVec<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.data[..self.len]
+    }
+}
+
+impl<T> DerefMut for ArenaVec<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.data[..self.len]
+    }
+}
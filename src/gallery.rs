@@ -0,0 +1,212 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// maximum number of entries kept in the recent scenes list
+const MAX_ENTRIES: usize = 32;
+
+/// width of the thumbnails in pixels
+pub const THUMBNAIL_WIDTH: u32 = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GalleryEntry {
+    pub pointcloud: PathBuf,
+    pub scene: Option<PathBuf>,
+    pub thumbnail: Option<PathBuf>,
+    /// unix timestamp in seconds
+    pub last_opened: u64,
+}
+
+impl GalleryEntry {
+    pub fn name(&self) -> String {
+        // point clouds are usually stored as <name>/point_cloud/iteration_<n>/point_cloud.ply
+        self.pointcloud
+            .ancestors()
+            .filter_map(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .find(|n| !n.starts_with("point_cloud") && !n.starts_with("iteration_"))
+            .unwrap_or_else(|| self.pointcloud.to_string_lossy().to_string())
+    }
+}
+
+/// list of recently opened scenes with their thumbnails
+/// the list is stored in the cache directory (in the local storage of the browser on the web)
+pub struct Gallery {
+    dir: PathBuf,
+    entries: Vec<GalleryEntry>,
+    textures: HashMap<PathBuf, egui::TextureHandle>,
+}
+
+impl Gallery {
+    pub fn load(dir: PathBuf) -> Self {
+        let entries = Self::read_entries(&dir.join("recent.json")).unwrap_or_else(|err| {
+            log::debug!("no recent scenes loaded: {err}");
+            Vec::new()
+        });
+        Self {
+            dir,
+            entries,
+            textures: HashMap::new(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn default_dir() -> PathBuf {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))
+            .unwrap_or_else(std::env::temp_dir)
+            .join("web-splat")
+    }
+
+    /// on the web the directory is only used as key prefix for the local storage
+    #[cfg(target_arch = "wasm32")]
+    pub fn default_dir() -> PathBuf {
+        PathBuf::from("web-splat")
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_entries(path: &Path) -> anyhow::Result<Vec<GalleryEntry>> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn read_entries(path: &Path) -> anyhow::Result<Vec<GalleryEntry>> {
+        let json = local_storage()?
+            .get_item(&path.to_string_lossy())
+            .map_err(|err| anyhow::anyhow!("{err:?}"))?
+            .ok_or(anyhow::anyhow!("no recent scenes stored"))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&self) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let file = File::create(self.dir.join("recent.json"))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), &self.entries)?;
+        Ok(())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save(&self) -> anyhow::Result<()> {
+        let json = serde_json::to_string(&self.entries)?;
+        local_storage()?
+            .set_item(&self.dir.join("recent.json").to_string_lossy(), &json)
+            .map_err(|err| anyhow::anyhow!("{err:?}"))
+    }
+
+    /// entries sorted by the time they were last opened (most recent first)
+    pub fn entries(&self) -> &[GalleryEntry] {
+        &self.entries
+    }
+
+    pub fn most_recent(&self) -> Option<&GalleryEntry> {
+        self.entries.first()
+    }
+
+    /// moves the point cloud to the front of the list
+    pub fn add_recent(&mut self, pointcloud: &Path, scene: Option<&Path>) {
+        let pointcloud = pointcloud
+            .canonicalize()
+            .unwrap_or_else(|_| pointcloud.to_path_buf());
+        let thumbnail = self
+            .entries
+            .iter()
+            .position(|e| e.pointcloud == pointcloud)
+            .and_then(|i| self.entries.remove(i).thumbnail);
+        self.entries.insert(
+            0,
+            GalleryEntry {
+                scene: scene.map(|s| s.canonicalize().unwrap_or_else(|_| s.to_path_buf())),
+                pointcloud,
+                thumbnail,
+                last_opened: now_secs(),
+            },
+        );
+        self.entries.truncate(MAX_ENTRIES);
+        if let Err(err) = self.save() {
+            log::warn!("failed to save recent scenes: {err}");
+        }
+    }
+
+    /// returns true if the most recent entry has no thumbnail yet
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn needs_thumbnail(&self) -> bool {
+        self.entries.first().map_or(false, |e| {
+            e.thumbnail.as_ref().map_or(true, |t| !t.exists())
+        })
+    }
+
+    /// stores the thumbnail for the most recent entry
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_thumbnail(&mut self, image: &image::RgbaImage) -> anyhow::Result<()> {
+        let entry = self
+            .entries
+            .first_mut()
+            .ok_or(anyhow::anyhow!("gallery is empty"))?;
+        let thumb_dir = self.dir.join("thumbnails");
+        std::fs::create_dir_all(&thumb_dir)?;
+        let path = thumb_dir.join(format!(
+            "{:08x}.png",
+            crc32fast::hash(entry.pointcloud.to_string_lossy().as_bytes())
+        ));
+        image.save(&path)?;
+        self.textures.remove(&path);
+        entry.thumbnail = Some(path);
+        self.save()
+    }
+
+    /// loads the thumbnail texture of an entry the first time it is requested
+    /// thumbnails are not rendered on the web, so entries there have no preview
+    pub fn thumbnail_texture(
+        &mut self,
+        ctx: &egui::Context,
+        entry: &GalleryEntry,
+    ) -> Option<egui::TextureHandle> {
+        let path = entry.thumbnail.as_ref()?;
+        if let Some(texture) = self.textures.get(path) {
+            return Some(texture.clone());
+        }
+        let img = image::open(path).ok()?.to_rgba8();
+        let texture = ctx.load_texture(
+            path.to_string_lossy(),
+            egui::ColorImage::from_rgba_unmultiplied(
+                [img.width() as usize, img.height() as usize],
+                img.as_raw(),
+            ),
+            Default::default(),
+        );
+        self.textures.insert(path.clone(), texture.clone());
+        Some(texture)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+// the system time is not available on wasm32-unknown-unknown
+#[cfg(target_arch = "wasm32")]
+fn now_secs() -> u64 {
+    (js_sys::Date::now() / 1000.) as u64
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> anyhow::Result<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|win| win.local_storage().ok().flatten())
+        .ok_or(anyhow::anyhow!("local storage is not available"))
+}
@@ -48,6 +48,7 @@ pub struct IndirectDispatch {
     dispatch_z: u32,
 }
 
+#[allow(dead_code)]
 pub struct GeneralInfo {
     pub keys_size: u32,
     pub padded_size: u32,
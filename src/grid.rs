@@ -0,0 +1,214 @@
+use cgmath::Vector2;
+
+/// maximum number of views of the grid, including the current view
+pub const MAX_GRID_VIEWS: usize = 4;
+
+/// splits the window into tiles that show the point cloud from different cameras
+/// the first tile shows the current camera, the other tiles show scene cameras
+pub(crate) struct ViewportGrid {
+    /// scene cameras of the views after the first one
+    cameras: Vec<usize>,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    format: wgpu::TextureFormat,
+    window_size: Vector2<u32>,
+    /// displayed image of every view and the bind group to draw it into its tile
+    tiles: Vec<(wgpu::TextureView, wgpu::BindGroup)>,
+}
+
+impl ViewportGrid {
+    pub fn new(
+        device: &wgpu::Device,
+        cameras: Vec<usize>,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("grid bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("grid pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/grid.wgsl"));
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("grid pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            multiview: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("grid sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let mut grid = Self {
+            cameras,
+            pipeline,
+            bind_group_layout,
+            sampler,
+            format,
+            window_size: Vector2::new(width, height),
+            tiles: Vec::new(),
+        };
+        grid.create_tiles(device);
+        grid
+    }
+
+    fn create_tiles(&mut self, device: &wgpu::Device) {
+        let size = self.tile_size();
+        self.tiles = (0..self.num_views())
+            .map(|_| {
+                let view = device
+                    .create_texture(&wgpu::TextureDescriptor {
+                        label: Some("grid tile texture"),
+                        size: wgpu::Extent3d {
+                            width: size.x,
+                            height: size.y,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format: self.format,
+                        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                            | wgpu::TextureUsages::TEXTURE_BINDING,
+                        view_formats: &[],
+                    })
+                    .create_view(&Default::default());
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("grid tile bind group"),
+                    layout: &self.bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&self.sampler),
+                        },
+                    ],
+                });
+                (view, bind_group)
+            })
+            .collect();
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.window_size = Vector2::new(width, height);
+        self.create_tiles(device);
+    }
+
+    pub fn cameras(&self) -> &[usize] {
+        &self.cameras
+    }
+
+    pub fn set_cameras(&mut self, device: &wgpu::Device, cameras: Vec<usize>) {
+        let num_changed = cameras.len() != self.cameras.len();
+        self.cameras = cameras;
+        if num_changed {
+            self.create_tiles(device);
+        }
+    }
+
+    /// number of views including the current view
+    pub fn num_views(&self) -> usize {
+        1 + self.cameras.len()
+    }
+
+    /// columns and rows of the grid, two views are shown side by side
+    fn layout(&self) -> Vector2<u32> {
+        let num_views = self.num_views() as u32;
+        let columns = num_views.min(2);
+        Vector2::new(columns, num_views.div_ceil(columns))
+    }
+
+    /// size of a tile in pixels
+    pub fn tile_size(&self) -> Vector2<u32> {
+        let layout = self.layout();
+        Vector2::new(
+            (self.window_size.x / layout.x).max(1),
+            (self.window_size.y / layout.y).max(1),
+        )
+    }
+
+    /// target for the displayed image of one view
+    pub fn tile_view(&self, view: usize) -> &wgpu::TextureView {
+        &self.tiles[view].0
+    }
+
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("grid render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        let columns = self.layout().x;
+        let size = self.tile_size();
+        for (i, (_, bind_group)) in self.tiles.iter().enumerate() {
+            let (column, row) = (i as u32 % columns, i as u32 / columns);
+            render_pass.set_viewport(
+                (column * size.x) as f32,
+                (row * size.y) as f32,
+                size.x as f32,
+                size.y as f32,
+                0.,
+                1.,
+            );
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw(0..4, 0..1);
+        }
+    }
+}
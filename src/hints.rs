@@ -0,0 +1,88 @@
+/// rough performance class of the gpu, used to warn about point clouds that are too large
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GpuClass {
+    Discrete,
+    Integrated,
+    Software,
+}
+
+impl GpuClass {
+    pub fn detect(info: &wgpu::AdapterInfo) -> Self {
+        match info.device_type {
+            wgpu::DeviceType::DiscreteGpu => Self::Discrete,
+            wgpu::DeviceType::Cpu => Self::Software,
+            // browsers and virtual machines do not report the gpu type, assume the slower class
+            _ => Self::Integrated,
+        }
+    }
+
+    /// number of splats that can be rendered with sh degree 3 at interactive frame rates (full hd)
+    fn splat_budget(self) -> usize {
+        match self {
+            Self::Discrete => 6_000_000,
+            Self::Integrated => 1_500_000,
+            Self::Software => 200_000,
+        }
+    }
+}
+
+/// setting that speeds up rendering of a large point cloud
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PerformanceHint {
+    /// level of detail hierarchy, distant regions are rendered with fewer splats
+    Lod,
+    /// quantize the point cloud when loading
+    LowMemory,
+    /// render with a lower sh degree
+    ShDegree(u32),
+}
+
+impl PerformanceHint {
+    pub fn description(&self) -> String {
+        match self {
+            PerformanceHint::Lod => {
+                "Level of detail: render distant regions with fewer splats (--lod)".to_string()
+            }
+            PerformanceHint::LowMemory => {
+                "Quantization: reduce the gpu memory usage of the splats (--low-memory)".to_string()
+            }
+            PerformanceHint::ShDegree(deg) => {
+                format!("Reduce the SH degree to {deg} (--max-sh-deg {deg})")
+            }
+        }
+    }
+
+    /// true if the point cloud has to be reloaded to apply the hint
+    pub fn needs_reload(&self) -> bool {
+        !matches!(self, PerformanceHint::ShDegree(_))
+    }
+}
+
+/// suggests settings for point clouds with more splats than the gpu can render smoothly
+/// `num_splats` is the number of splats drawn per frame, which the level of detail hierarchy reduces
+/// `sh_deg` is the rendered sh degree and `lod` is true if a level of detail hierarchy is in use
+pub(crate) fn performance_hints(
+    gpu: GpuClass,
+    num_splats: usize,
+    sh_deg: u32,
+    compressed: bool,
+    lod: bool,
+) -> Vec<PerformanceHint> {
+    let budget = gpu.splat_budget();
+    if num_splats <= budget {
+        return Vec::new();
+    }
+    let mut hints = Vec::new();
+    if !lod {
+        hints.push(PerformanceHint::Lod);
+    }
+    // compressed point clouds are already quantized
+    if !compressed {
+        hints.push(PerformanceHint::LowMemory);
+    }
+    let max_sh_deg = if num_splats > 2 * budget { 0 } else { 1 };
+    if sh_deg > max_sh_deg {
+        hints.push(PerformanceHint::ShDegree(max_sh_deg));
+    }
+    hints
+}
@@ -0,0 +1,140 @@
+use cgmath::{Angle, Deg, InnerSpace, Point3, Quaternion, Rotation, Vector2, Vector3};
+
+use super::{importance::blend_splat, splat_size::SplatProjector, GenericGaussianPointCloud};
+use crate::{
+    camera::{Camera, PerspectiveCamera, PerspectiveProjection},
+    pointcloud::Aabb,
+    renderer::DEFAULT_KERNEL_SIZE,
+    task::CancellationToken,
+    utils::simd,
+};
+
+/// number of viewing directions that are evaluated
+const NUM_CANDIDATES: usize = 48;
+/// at most this many splats are rendered per candidate
+const MAX_SPLATS: usize = 100_000;
+/// resolution of the cpu rendered candidate images
+const VIEWPORT: u32 = 64;
+/// field of view of the suggested cameras
+const FOV: Deg<f32> = Deg(45.);
+/// suggested views must look at the model from directions that differ by at least this angle
+const MIN_ANGLE: Deg<f32> = Deg(40.);
+
+#[derive(Debug, Clone, Copy)]
+pub struct SuggestedView {
+    pub camera: PerspectiveCamera,
+    /// fraction of the image covered by splats
+    pub coverage: f32,
+}
+
+/// subset of the splats of a point cloud that is rendered to rank the candidate views
+/// it owns its data, so the views can be computed in a background task
+pub struct ViewSuggester {
+    splats: Vec<(Point3<f32>, [f32; 6])>,
+    opacities: Vec<f32>,
+    kernel_size: f32,
+    aabb: Aabb<f32>,
+    up: Vector3<f32>,
+}
+
+impl GenericGaussianPointCloud {
+    /// proposes up to `num_views` viewpoints, see [ViewSuggester::suggest]
+    pub fn suggest_views(&self, num_views: usize) -> anyhow::Result<Vec<SuggestedView>> {
+        self.view_suggester()?
+            .suggest(num_views, &CancellationToken::new())
+    }
+
+    /// samples at most [MAX_SPLATS] splats for [ViewSuggester::suggest]
+    pub fn view_suggester(&self) -> anyhow::Result<ViewSuggester> {
+        let step = self.num_points.div_ceil(MAX_SPLATS).max(1);
+        Ok(ViewSuggester {
+            splats: self.covariances(step)?,
+            opacities: self.opacities(step)?,
+            kernel_size: self.kernel_size.unwrap_or(DEFAULT_KERNEL_SIZE),
+            aabb: self.aabb,
+            up: self.up.unwrap_or(Vector3::unit_y()).normalize(),
+        })
+    }
+}
+
+impl ViewSuggester {
+    /// proposes up to `num_views` viewpoints outside of the model that look at its center
+    /// candidates on a sphere around the model are rendered on the cpu with the sampled splats
+    /// and ranked by their coverage, similar directions are skipped so the views show different sides
+    pub fn suggest(
+        &self,
+        num_views: usize,
+        token: &CancellationToken,
+    ) -> anyhow::Result<Vec<SuggestedView>> {
+        if self.splats.is_empty() {
+            return Ok(Vec::new());
+        }
+        let viewport = Vector2::new(VIEWPORT, VIEWPORT);
+        let positions: Vec<_> = self.splats.iter().map(|(xyz, _)| *xyz).collect();
+
+        let center = self.aabb.center();
+        // far enough away that the bounding sphere fits into the image
+        let distance = self.aabb.radius() / (FOV / 2.).sin();
+        let y_axis = self.up;
+
+        let mut candidates: Vec<(Vector3<f32>, SuggestedView)> = fibonacci_sphere(NUM_CANDIDATES)
+            .into_iter()
+            // look_at is undefined when looking along the up axis
+            .filter(|dir| dir.dot(y_axis).abs() < 0.95)
+            .map(|dir| {
+                token.check()?;
+                let camera = PerspectiveCamera::new(
+                    center + dir * distance,
+                    Quaternion::look_at(-dir, y_axis),
+                    PerspectiveProjection::new(viewport, Vector2::new(FOV, FOV), 0.01, 1000.),
+                );
+                let projector =
+                    SplatProjector::new(&camera, viewport, &self.aabb, self.kernel_size);
+                let mut projected: Vec<_> = self
+                    .splats
+                    .iter()
+                    .zip(&self.opacities)
+                    .enumerate()
+                    .filter_map(|(i, ((xyz, cov), o))| {
+                        projector.project(*xyz, cov).map(|s| (i, s, *o))
+                    })
+                    .collect();
+                // front to back
+                let keys = simd::depth_keys(&positions, &camera.view_matrix());
+                projected.sort_unstable_by_key(|(i, _, _)| keys[*i]);
+                let mut transmittance = vec![1f32; (VIEWPORT * VIEWPORT) as usize];
+                for (_, splat, opacity) in &projected {
+                    blend_splat(splat, *opacity, &mut transmittance, VIEWPORT as usize);
+                }
+                let coverage = 1. - transmittance.iter().sum::<f32>() / transmittance.len() as f32;
+                Ok((dir, SuggestedView { camera, coverage }))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        candidates.sort_by(|(_, a), (_, b)| b.coverage.total_cmp(&a.coverage));
+
+        let min_cos = MIN_ANGLE.cos();
+        let mut selected: Vec<(Vector3<f32>, SuggestedView)> = Vec::with_capacity(num_views);
+        for (dir, view) in candidates {
+            if selected.len() == num_views {
+                break;
+            }
+            if selected.iter().all(|(other, _)| other.dot(dir) < min_cos) {
+                selected.push((dir, view));
+            }
+        }
+        Ok(selected.into_iter().map(|(_, view)| view).collect())
+    }
+}
+
+/// evenly distributed unit vectors
+fn fibonacci_sphere(n: usize) -> Vec<Vector3<f32>> {
+    let golden_angle = std::f32::consts::PI * (3. - 5f32.sqrt());
+    (0..n)
+        .map(|i| {
+            let y = 1. - 2. * (i as f32 + 0.5) / n as f32;
+            let r = (1. - y * y).sqrt();
+            let theta = golden_angle * i as f32;
+            Vector3::new(r * theta.cos(), y, r * theta.sin())
+        })
+        .collect()
+}
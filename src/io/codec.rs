@@ -0,0 +1,190 @@
+use std::io::{Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+/// framed wire format shared by the streaming loader and the live training feed
+///
+/// every frame starts with a 16 byte header
+/// | magic (4) | kind (1) | flags (1) | reserved (2) | payload length (4) | crc32 of the decoded payload (4) |
+/// followed by the (optionally zstd compressed) payload
+const FRAME_MAGIC: &[u8; 4] = b"WSFR";
+const FLAG_ZSTD: u8 = 1;
+
+/// payloads smaller than this are not worth compressing
+const MIN_COMPRESS_SIZE: usize = 256;
+
+/// upper bound for a single frame to guard against corrupt headers
+pub const MAX_FRAME_SIZE: usize = 1 << 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    /// full point cloud file (ply or npz)
+    PointCloud,
+    /// part of a point cloud that is streamed in chunks
+    Chunk,
+    /// snapshot delta, see [super::delta::SnapshotDelta]
+    Delta,
+    /// scene json file
+    Scene,
+    /// aes-gcm encrypted frame, see [super::encrypted]
+    Encrypted,
+    /// marks the end of a stream of chunks
+    End,
+    /// header of a quantized point cloud whose data follows in chunks, see [super::packed]
+    Quantized,
+    Other(u8),
+}
+
+impl From<u8> for FrameKind {
+    fn from(v: u8) -> Self {
+        match v {
+            0 => FrameKind::PointCloud,
+            1 => FrameKind::Chunk,
+            2 => FrameKind::Delta,
+            3 => FrameKind::Scene,
+            4 => FrameKind::Encrypted,
+            5 => FrameKind::End,
+            6 => FrameKind::Quantized,
+            v => FrameKind::Other(v),
+        }
+    }
+}
+
+impl From<FrameKind> for u8 {
+    fn from(k: FrameKind) -> Self {
+        match k {
+            FrameKind::PointCloud => 0,
+            FrameKind::Chunk => 1,
+            FrameKind::Delta => 2,
+            FrameKind::Scene => 3,
+            FrameKind::Encrypted => 4,
+            FrameKind::End => 5,
+            FrameKind::Quantized => 6,
+            FrameKind::Other(v) => v,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub kind: FrameKind,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    pub fn new(kind: FrameKind, payload: Vec<u8>) -> Self {
+        Self { kind, payload }
+    }
+
+    /// writes the frame and compresses the payload if `compress` is set
+    /// fails for payloads larger than [MAX_FRAME_SIZE]
+    pub fn write<W: Write>(&self, w: &mut W, compress: bool) -> anyhow::Result<()> {
+        if self.payload.len() > MAX_FRAME_SIZE {
+            return Err(anyhow::anyhow!(
+                "frame too large ({} bytes)",
+                self.payload.len()
+            ));
+        }
+        let checksum = crc32fast::hash(&self.payload);
+        let compressed = (compress && self.payload.len() >= MIN_COMPRESS_SIZE)
+            .then(|| {
+                ruzstd::encoding::compress_to_vec(
+                    self.payload.as_slice(),
+                    ruzstd::encoding::CompressionLevel::Fastest,
+                )
+            })
+            // only use compressed data if it actually is smaller
+            .filter(|c| c.len() < self.payload.len());
+
+        let (flags, data) = match &compressed {
+            Some(c) => (FLAG_ZSTD, c.as_slice()),
+            None => (0, self.payload.as_slice()),
+        };
+        w.write_all(FRAME_MAGIC)?;
+        w.write_u8(self.kind.into())?;
+        w.write_u8(flags)?;
+        w.write_u16::<LittleEndian>(0)?;
+        w.write_u32::<LittleEndian>(data.len() as u32)?;
+        w.write_u32::<LittleEndian>(checksum)?;
+        w.write_all(data)?;
+        Ok(())
+    }
+
+    /// reads a frame and verifies its checksum
+    pub fn read<R: Read>(r: &mut R) -> anyhow::Result<Self> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != FRAME_MAGIC {
+            return Err(anyhow::anyhow!("invalid frame header"));
+        }
+        let kind = FrameKind::from(r.read_u8()?);
+        let flags = r.read_u8()?;
+        let _reserved = r.read_u16::<LittleEndian>()?;
+        let len = r.read_u32::<LittleEndian>()? as usize;
+        let checksum = r.read_u32::<LittleEndian>()?;
+        if len > MAX_FRAME_SIZE {
+            return Err(anyhow::anyhow!("frame too large ({} bytes)", len));
+        }
+
+        // the buffer grows with the received data instead of trusting the length in the header
+        let mut data = Vec::new();
+        r.take(len as u64).read_to_end(&mut data)?;
+        if data.len() != len {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let payload = if flags & FLAG_ZSTD != 0 {
+            let decoder = ruzstd::decoding::StreamingDecoder::new(data.as_slice())
+                .map_err(|e| anyhow::anyhow!("invalid zstd frame: {}", e))?;
+            let mut payload = Vec::new();
+            // a small compressed frame can expand to an arbitrary size
+            decoder
+                .take(MAX_FRAME_SIZE as u64 + 1)
+                .read_to_end(&mut payload)?;
+            if payload.len() > MAX_FRAME_SIZE {
+                return Err(anyhow::anyhow!("decompressed frame too large"));
+            }
+            payload
+        } else {
+            data
+        };
+
+        if crc32fast::hash(&payload) != checksum {
+            return Err(anyhow::anyhow!("frame checksum mismatch"));
+        }
+        Ok(Self { kind, payload })
+    }
+
+    /// parses a frame from a byte slice
+    /// returns the frame and the number of bytes consumed
+    pub fn from_bytes(data: &[u8]) -> anyhow::Result<(Self, usize)> {
+        let mut cursor = std::io::Cursor::new(data);
+        let frame = Self::read(&mut cursor)?;
+        Ok((frame, cursor.position() as usize))
+    }
+
+    pub fn to_bytes(&self, compress: bool) -> anyhow::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.write(&mut data, compress)?;
+        Ok(data)
+    }
+}
+
+/// checks if the data starts with a frame header
+pub fn is_framed(data: &[u8]) -> bool {
+    data.starts_with(FRAME_MAGIC)
+}
+
+/// reads all frames from a reader until it is exhausted
+pub fn read_frames<R: Read>(r: &mut R) -> anyhow::Result<Vec<Frame>> {
+    let mut data = Vec::new();
+    r.read_to_end(&mut data)?;
+    let mut frames = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let (frame, n) = Frame::from_bytes(&data[offset..])?;
+        frames.push(frame);
+        offset += n;
+    }
+    Ok(frames)
+}
@@ -0,0 +1,245 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use cgmath::{EuclideanSpace, Euler, Matrix, Matrix3, Matrix4, Point3, Rad, Vector3};
+use half::f16;
+use serde::Deserialize;
+
+use super::{plane_from_points, GenericGaussianPointCloud};
+use crate::{
+    pointcloud::{Aabb, Gaussian, GaussianCompressed},
+    utils::math::{matrix_from_rows, quaternion_from_array, QuaternionOrder},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ObbRotation {
+    /// rows of the local to world rotation matrix
+    Matrix([[f32; 3]; 3]),
+    /// local to world rotation as quaternion (w, x, y, z)
+    Quaternion([f32; 4]),
+}
+
+#[derive(Debug, Deserialize)]
+struct NerfstudioCrop {
+    crop_center: [f32; 3],
+    crop_scale: [f32; 3],
+    /// roll, pitch, yaw in radians
+    #[serde(default)]
+    crop_rot: [f32; 3],
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CropFile {
+    Aabb {
+        min: [f32; 3],
+        max: [f32; 3],
+    },
+    Obb {
+        center: [f32; 3],
+        /// edge lengths of the box
+        #[serde(alias = "scale", alias = "extent")]
+        size: [f32; 3],
+        rotation: Option<ObbRotation>,
+    },
+    Nerfstudio {
+        crop: NerfstudioCrop,
+    },
+}
+
+/// oriented box that defines the region of interest of a point cloud
+/// splats outside of the box are removed when loading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropRegion {
+    pub center: Point3<f32>,
+    pub half_size: Vector3<f32>,
+    pub world_to_local: Matrix3<f32>,
+}
+
+impl CropRegion {
+    pub fn from_aabb(min: Point3<f32>, max: Point3<f32>) -> Self {
+        Self {
+            center: Point3::new(
+                (min.x + max.x) / 2.,
+                (min.y + max.y) / 2.,
+                (min.z + max.z) / 2.,
+            ),
+            half_size: (max - min) / 2.,
+            world_to_local: Matrix3::from_scale(1.),
+        }
+    }
+
+    /// reads an axis aligned box (`min`, `max`), an oriented box (`center`, `size`, `rotation`)
+    /// or the crop settings of a nerfstudio camera path
+    pub fn from_json<R: std::io::Read>(reader: R) -> anyhow::Result<Self> {
+        let file: CropFile = serde_json::from_reader(reader)?;
+        Ok(match file {
+            CropFile::Aabb { min, max } => Self::from_aabb(min.into(), max.into()),
+            CropFile::Obb {
+                center,
+                size,
+                rotation,
+            } => Self {
+                center: center.into(),
+                half_size: Vector3::from(size) / 2.,
+                world_to_local: match rotation {
+                    Some(ObbRotation::Matrix(rows)) => matrix_from_rows(rows).transpose(),
+                    Some(ObbRotation::Quaternion(q)) => {
+                        Matrix3::from(quaternion_from_array(q, QuaternionOrder::Wxyz)).transpose()
+                    }
+                    None => Matrix3::from_scale(1.),
+                },
+            },
+            CropFile::Nerfstudio { crop } => {
+                let [roll, pitch, yaw] = crop.crop_rot;
+                let rotation = Matrix3::from(Euler::new(Rad(roll), Rad(pitch), Rad(yaw)));
+                Self {
+                    center: crop.crop_center.into(),
+                    half_size: Vector3::from(crop.crop_scale) / 2.,
+                    world_to_local: rotation.transpose(),
+                }
+            }
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        Self::from_json(BufReader::new(File::open(path)?))
+    }
+
+    /// candidate companion files for a point cloud file
+    /// `<file>.crop.json` or `crop.json` in the same directory
+    pub fn sidecar_paths(pc_file: &Path) -> Vec<PathBuf> {
+        let mut paths = vec![PathBuf::from(format!("{}.crop.json", pc_file.display()))];
+        if let Some(dir) = pc_file.parent() {
+            paths.push(dir.join("crop.json"));
+        }
+        paths
+    }
+
+    pub fn load_sidecar(pc_file: &Path) -> Option<Self> {
+        let path = Self::sidecar_paths(pc_file)
+            .into_iter()
+            .find(|p| p.exists())?;
+        match Self::load(&path) {
+            Ok(crop) => {
+                log::info!("using crop region from {:?}", path);
+                Some(crop)
+            }
+            Err(err) => {
+                log::warn!("failed to read crop file {:?}: {}", path, err);
+                None
+            }
+        }
+    }
+
+    pub fn contains(&self, p: Point3<f32>) -> bool {
+        let local = self.world_to_local * (p - self.center);
+        local.x.abs() <= self.half_size.x
+            && local.y.abs() <= self.half_size.y
+            && local.z.abs() <= self.half_size.z
+    }
+
+    /// maps the region to the cube [-1, 1]³
+    pub fn world_to_unit(&self) -> Matrix4<f32> {
+        Matrix4::from_nonuniform_scale(
+            1. / self.half_size.x,
+            1. / self.half_size.y,
+            1. / self.half_size.z,
+        ) * Matrix4::from(self.world_to_local)
+            * Matrix4::from_translation(-self.center.to_vec())
+    }
+}
+
+impl GenericGaussianPointCloud {
+    /// removes all splats outside of the region
+    pub fn crop(&mut self, region: &CropRegion) -> anyhow::Result<()> {
+        let keep: Vec<bool> = self
+            .positions()
+            .into_iter()
+            .map(|p| region.contains(p))
+            .collect();
+        let num_points = self.num_points;
+        self.retain(&keep)
+            .map_err(|_| anyhow::anyhow!("crop region contains no splats"))?;
+        log::info!(
+            "cropped point cloud from {} to {} points",
+            num_points,
+            self.num_points
+        );
+        Ok(())
+    }
+
+    /// keeps the splats whose entry in `keep` is true and updates the bounding box and center
+    /// for compressed point clouds only the splats are removed, shared sh coefficients and covariances are kept
+    pub fn retain(&mut self, keep: &[bool]) -> anyhow::Result<()> {
+        if keep.len() != self.num_points {
+            return Err(anyhow::anyhow!(
+                "expected {} entries, got {}",
+                self.num_points,
+                keep.len()
+            ));
+        }
+        let positions: Vec<Point3<f32>> = self
+            .positions()
+            .into_iter()
+            .zip(keep)
+            .filter(|(_, k)| **k)
+            .map(|(p, _)| p)
+            .collect();
+        if positions.is_empty() {
+            return Err(anyhow::anyhow!("no splats left"));
+        }
+
+        if self.compressed {
+            let gaussians: Vec<GaussianCompressed> = self
+                .gaussians_compressed()?
+                .iter()
+                .zip(keep)
+                .filter(|(_, k)| **k)
+                .map(|(g, _)| *g)
+                .collect();
+            self.gaussians = bytemuck::cast_slice(&gaussians).to_vec();
+        } else {
+            let sh_coefs: &[[[f16; 3]; 16]] = bytemuck::cast_slice(&self.sh_coefs);
+            let (gaussians, sh_coefs): (Vec<Gaussian>, Vec<[[f16; 3]; 16]>) = self
+                .gaussians()?
+                .iter()
+                .zip(sh_coefs)
+                .zip(keep)
+                .filter(|(_, k)| **k)
+                .map(|((g, sh), _)| (*g, *sh))
+                .unzip();
+            self.gaussians = bytemuck::cast_slice(&gaussians).to_vec();
+            self.sh_coefs = bytemuck::cast_slice(&sh_coefs).to_vec();
+        }
+
+        self.num_points = positions.len();
+        let mut aabb = Aabb::new(positions[0], positions[0]);
+        for p in &positions {
+            aabb.grow(p);
+        }
+        let (center, up) = plane_from_points(&positions);
+        self.aabb = aabb;
+        self.center = center;
+        self.up = up.filter(|_| aabb.radius() >= 10.);
+        Ok(())
+    }
+
+    pub(crate) fn positions(&self) -> Vec<Point3<f32>> {
+        if self.compressed {
+            bytemuck::cast_slice::<_, GaussianCompressed>(&self.gaussians)
+                .iter()
+                .map(|g| g.xyz.map(|v| v.to_f32()))
+                .collect()
+        } else {
+            bytemuck::cast_slice::<_, Gaussian>(&self.gaussians)
+                .iter()
+                .map(|g| g.xyz)
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    io::{Read, Write},
+    mem::size_of,
+};
+
+use bytemuck::Zeroable;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use half::f16;
+
+use crate::{
+    pointcloud::{Aabb, Gaussian},
+    utils::simd,
+};
+
+use super::{
+    codec::{Frame, FrameKind},
+    GenericGaussianPointCloud,
+};
+
+const DELTA_MAGIC: &[u8; 4] = b"SPDT";
+
+/// bytes of an entry in [SnapshotDelta::updated] (index, splat and sh coefficients)
+const UPDATE_SIZE: usize = 4 + size_of::<Gaussian>() + size_of::<[[f16; 3]; 16]>();
+
+/// incremental update between two snapshots of a point cloud
+/// used to stream training progress without sending the whole point cloud every time
+///
+/// the delta is applied in two steps:
+/// 1. all `removed` indices (refering to the old snapshot) are removed, the remaining splats keep their order
+/// 2. every entry in `updated` either overwrites an existing splat or (if the index equals the current number of splats) appends a new one
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotDelta {
+    pub removed: Vec<u32>,
+    pub updated: Vec<(u32, Gaussian, [[f16; 3]; 16])>,
+}
+
+impl SnapshotDelta {
+    /// computes the delta that turns `old` into `new`
+    /// splats are matched by index
+    pub fn diff(
+        old: &GenericGaussianPointCloud,
+        new: &GenericGaussianPointCloud,
+    ) -> anyhow::Result<Self> {
+        let old_gaussians = old.gaussians()?;
+        let new_gaussians = new.gaussians()?;
+        let old_sh: &[[[f16; 3]; 16]] = bytemuck::cast_slice(old.sh_coefs_buffer());
+        let new_sh: &[[[f16; 3]; 16]] = bytemuck::cast_slice(new.sh_coefs_buffer());
+
+        let removed = (new_gaussians.len()..old_gaussians.len())
+            .map(|i| i as u32)
+            .collect();
+        let updated = new_gaussians
+            .iter()
+            .zip(new_sh)
+            .enumerate()
+            .filter(|(i, (g, sh))| {
+                *i >= old_gaussians.len()
+                    || bytemuck::bytes_of(*g) != bytemuck::bytes_of(&old_gaussians[*i])
+                    || bytemuck::cast_slice::<_, u8>(sh.as_slice())
+                        != bytemuck::cast_slice::<_, u8>(old_sh[*i].as_slice())
+            })
+            .map(|(i, (g, sh))| (i as u32, *g, *sh))
+            .collect();
+        Ok(Self { removed, updated })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.updated.is_empty()
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W) -> anyhow::Result<()> {
+        w.write_all(DELTA_MAGIC)?;
+        w.write_u32::<LittleEndian>(self.removed.len() as u32)?;
+        w.write_u32::<LittleEndian>(self.updated.len() as u32)?;
+        for i in &self.removed {
+            w.write_u32::<LittleEndian>(*i)?;
+        }
+        for (i, g, sh) in &self.updated {
+            w.write_u32::<LittleEndian>(*i)?;
+            w.write_all(bytemuck::bytes_of(g))?;
+            w.write_all(bytemuck::cast_slice(sh.as_slice()))?;
+        }
+        Ok(())
+    }
+
+    pub fn read(data: &[u8]) -> anyhow::Result<Self> {
+        let r = &mut &data[..];
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != DELTA_MAGIC {
+            return Err(anyhow::anyhow!("not a snapshot delta"));
+        }
+        let num_removed = r.read_u32::<LittleEndian>()? as usize;
+        let num_updated = r.read_u32::<LittleEndian>()? as usize;
+        // the counts are checked against the payload before anything is allocated
+        let size = num_removed
+            .checked_mul(4)
+            .zip(num_updated.checked_mul(UPDATE_SIZE))
+            .and_then(|(removed, updated)| removed.checked_add(updated));
+        if size.filter(|size| *size <= r.len()).is_none() {
+            return Err(anyhow::anyhow!(
+                "delta with {} removed and {} updated splats does not fit into {} bytes",
+                num_removed,
+                num_updated,
+                r.len()
+            ));
+        }
+        let mut removed = vec![0u32; num_removed];
+        r.read_u32_into::<LittleEndian>(&mut removed)?;
+
+        let mut updated = Vec::with_capacity(num_updated);
+        for _ in 0..num_updated {
+            let i = r.read_u32::<LittleEndian>()?;
+            let mut g = Gaussian::zeroed();
+            r.read_exact(bytemuck::bytes_of_mut(&mut g))?;
+            let mut sh = [[f16::ZERO; 3]; 16];
+            r.read_exact(bytemuck::cast_slice_mut(sh.as_mut_slice()))?;
+            updated.push((i, g, sh));
+        }
+        Ok(Self { removed, updated })
+    }
+
+    pub fn to_frame(&self) -> anyhow::Result<Frame> {
+        let mut payload = Vec::new();
+        self.write(&mut payload)?;
+        Ok(Frame::new(FrameKind::Delta, payload))
+    }
+
+    pub fn from_frame(frame: &Frame) -> anyhow::Result<Self> {
+        if frame.kind != FrameKind::Delta {
+            return Err(anyhow::anyhow!(
+                "expected delta frame, got {:?}",
+                frame.kind
+            ));
+        }
+        Self::read(&frame.payload)
+    }
+}
+
+impl GenericGaussianPointCloud {
+    /// appends the changes since `previous` as a delta frame to a container
+    /// a trainer writes the first snapshot as a [FrameKind::PointCloud] frame and every later one with this,
+    /// so viewers that watch or stream the container only receive the changed splats
+    /// returns false if nothing changed and no frame was written
+    pub fn write_delta<W: Write>(&self, w: &mut W, previous: &Self) -> anyhow::Result<bool> {
+        let delta = SnapshotDelta::diff(previous, self)?;
+        if delta.is_empty() {
+            return Ok(false);
+        }
+        delta.to_frame()?.write(w, true)?;
+        Ok(true)
+    }
+
+    /// applies a delta update to an uncompressed point cloud
+    pub fn apply_delta(&mut self, delta: &SnapshotDelta) -> anyhow::Result<()> {
+        if self.compressed {
+            return Err(anyhow::anyhow!(
+                "delta updates are not supported for compressed point clouds"
+            ));
+        }
+        let mut gaussians: Vec<Gaussian> = bytemuck::cast_slice(&self.gaussians).to_vec();
+        let mut sh_coefs: Vec<[[f16; 3]; 16]> = bytemuck::cast_slice(&self.sh_coefs).to_vec();
+
+        if !delta.removed.is_empty() {
+            let mut keep = vec![true; gaussians.len()];
+            for i in &delta.removed {
+                *keep
+                    .get_mut(*i as usize)
+                    .ok_or(anyhow::anyhow!("removed index {} out of bounds", i))? = false;
+            }
+            let mut k = keep.iter();
+            gaussians.retain(|_| *k.next().unwrap());
+            let mut k = keep.iter();
+            sh_coefs.retain(|_| *k.next().unwrap());
+        }
+
+        for (i, g, sh) in &delta.updated {
+            let i = *i as usize;
+            if i < gaussians.len() {
+                gaussians[i] = *g;
+                sh_coefs[i] = *sh;
+            } else if i == gaussians.len() {
+                gaussians.push(*g);
+                sh_coefs.push(*sh);
+            } else {
+                return Err(anyhow::anyhow!(
+                    "updated index {} out of bounds (size {})",
+                    i,
+                    gaussians.len()
+                ));
+            }
+        }
+
+        let positions: Vec<_> = gaussians.iter().map(|g| g.xyz).collect();
+        let bbox = simd::bounding_box(&positions).unwrap_or_else(Aabb::zeroed);
+        self.aabb = bbox;
+        self.center = bbox.center();
+        self.num_points = gaussians.len();
+        self.gaussians = bytemuck::cast_slice(&gaussians).to_vec();
+        self.sh_coefs = bytemuck::cast_slice(&sh_coefs).to_vec();
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+//! coarse density grid of the splats
+//! used by the camera controller to stop zooming in before the camera passes through a surface
+
+use cgmath::{InnerSpace, Point3, Vector3};
+
+use super::GenericGaussianPointCloud;
+use crate::pointcloud::Aabb;
+
+/// number of cells along the longest axis of the bounding box
+const RESOLUTION: usize = 64;
+/// at most this many splats are inserted into the grid
+const MAX_SPLATS: usize = 1_000_000;
+/// cells with more than this times the mean opacity of the occupied cells are solid
+const SOLID_FACTOR: f32 = 0.5;
+
+#[derive(Debug, Clone)]
+pub struct DensityGrid {
+    aabb: Aabb<f32>,
+    cell_size: f32,
+    pub(super) resolution: [usize; 3],
+    /// the summed opacity of the splats in the cell exceeds the solid threshold
+    pub(super) solid: Vec<bool>,
+}
+
+impl GenericGaussianPointCloud {
+    /// sums the opacity of the splat centers in a grid with cubic cells covering the bounding box
+    pub fn density_grid(&self) -> anyhow::Result<DensityGrid> {
+        let step = self.num_points.div_ceil(MAX_SPLATS).max(1);
+        let positions = self.positions();
+        let opacities = self.opacities(step)?;
+
+        let size = self.aabb.size();
+        let cell_size = (size.x.max(size.y).max(size.z) / RESOLUTION as f32).max(f32::EPSILON);
+        let resolution = [size.x, size.y, size.z].map(|s| ((s / cell_size).ceil() as usize).max(1));
+        let mut density = vec![0f32; resolution[0] * resolution[1] * resolution[2]];
+        let grid = DensityGrid {
+            aabb: self.aabb,
+            cell_size,
+            resolution,
+            solid: Vec::new(),
+        };
+        for (p, o) in positions.iter().step_by(step).zip(opacities) {
+            if let Some(i) = grid.cell(*p) {
+                density[i] += o.clamp(0., 1.);
+            }
+        }
+
+        let occupied = density.iter().filter(|d| **d > 0.).count();
+        let threshold = if occupied > 0 {
+            SOLID_FACTOR * density.iter().sum::<f32>() / occupied as f32
+        } else {
+            f32::INFINITY
+        };
+        Ok(DensityGrid {
+            solid: density.iter().map(|d| *d > threshold).collect(),
+            ..grid
+        })
+    }
+}
+
+impl DensityGrid {
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub(super) fn cell(&self, p: Point3<f32>) -> Option<usize> {
+        let rel = (p - self.aabb.min) / self.cell_size;
+        let mut idx = [0; 3];
+        for (i, v) in [rel.x, rel.y, rel.z].into_iter().enumerate() {
+            if !(0. ..=self.resolution[i] as f32).contains(&v) {
+                return None;
+            }
+            // points on the max face of the bounding box belong to the last cell
+            idx[i] = (v as usize).min(self.resolution[i] - 1);
+        }
+        Some((idx[2] * self.resolution[1] + idx[1]) * self.resolution[0] + idx[0])
+    }
+
+    pub fn is_solid(&self, p: Point3<f32>) -> bool {
+        self.cell(p).is_some_and(|i| self.solid[i])
+    }
+
+    /// distance along the ray to the first solid cell
+    /// solid cells around the origin are skipped, so a camera that is already inside a surface can move out of it
+    pub fn raycast(
+        &self,
+        origin: Point3<f32>,
+        dir: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<f32> {
+        let dir = dir.normalize();
+        let (t_min, t_max) = self.intersect(origin, dir)?;
+        let step = self.cell_size * 0.5;
+        let mut t = t_min.max(0.);
+        let mut left_origin = t > 0. || !self.is_solid(origin);
+        while t <= t_max.min(max_distance) {
+            let solid = self.is_solid(origin + dir * t);
+            if solid && left_origin {
+                return Some(t);
+            }
+            left_origin |= !solid;
+            t += step;
+        }
+        None
+    }
+
+    /// how far the camera can move along `dir` (at most `max_distance`) while keeping one cell away from solid cells
+    pub fn free_distance(&self, origin: Point3<f32>, dir: Vector3<f32>, max_distance: f32) -> f32 {
+        match self.raycast(origin, dir, max_distance + self.cell_size) {
+            Some(t) => (t - self.cell_size).clamp(0., max_distance),
+            None => max_distance,
+        }
+    }
+
+    /// entry and exit distance of the ray through the bounding box (slab test)
+    fn intersect(&self, origin: Point3<f32>, dir: Vector3<f32>) -> Option<(f32, f32)> {
+        let mut t_min = f32::NEG_INFINITY;
+        let mut t_max = f32::INFINITY;
+        for i in 0..3 {
+            let inv = 1. / dir[i];
+            let t0 = (self.aabb.min[i] - origin[i]) * inv;
+            let t1 = (self.aabb.max[i] - origin[i]) * inv;
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+        }
+        (t_max >= t_min.max(0.)).then_some((t_min, t_max))
+    }
+}
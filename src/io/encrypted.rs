@@ -0,0 +1,155 @@
+use std::{
+    io::{Read, Write},
+    str::FromStr,
+};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use rand::RngCore;
+
+use super::codec::{read_frames, Frame, FrameKind};
+
+const NONCE_SIZE: usize = 12;
+
+/// 256 bit key for encrypted containers
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self(key)
+    }
+
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.0))
+    }
+}
+
+// the key is never printed
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// parses a key from 64 hex characters
+impl FromStr for EncryptionKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.len() != 64 || !s.is_ascii() {
+            return Err(anyhow::anyhow!("key must consist of 64 hex characters"));
+        }
+        let mut key = [0u8; 32];
+        for (i, b) in key.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&s[2 * i..2 * i + 2], 16)?;
+        }
+        Ok(Self(key))
+    }
+}
+
+/// encrypted container
+///
+/// the container is a sequence of [FrameKind::Encrypted] frames (see [super::codec])
+/// every payload holds a random nonce (12 bytes) followed by the aes-256-gcm encrypted bytes of the inner frame
+/// the index of the frame and whether it is the last one are used as associated data,
+/// so frames cannot be reordered, dropped or cut off at the end unnoticed
+pub fn write_encrypted<W: Write>(
+    w: &mut W,
+    frames: &[Frame],
+    key: &EncryptionKey,
+) -> anyhow::Result<()> {
+    let cipher = key.cipher();
+    for (i, frame) in frames.iter().enumerate() {
+        // compress before encrypting, encrypted data does not compress
+        let plain = frame.to_bytes(true)?;
+        let mut nonce = [0u8; NONCE_SIZE];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let aad = associated_data(i, i + 1 == frames.len());
+        let encrypted = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &plain,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("failed to encrypt frame {}", i))?;
+        let mut payload = nonce.to_vec();
+        payload.extend(encrypted);
+        Frame::new(FrameKind::Encrypted, payload).write(w, false)?;
+    }
+    Ok(())
+}
+
+/// reads all frames of a container and decrypts them
+/// unencrypted frames are returned as they are if no key is given
+pub fn read_container<R: Read>(
+    r: &mut R,
+    key: Option<&EncryptionKey>,
+) -> anyhow::Result<Vec<Frame>> {
+    let frames = read_frames(r)?;
+    let num_frames = frames.len();
+    let cipher = key.map(|k| k.cipher());
+    frames
+        .into_iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            if frame.kind != FrameKind::Encrypted {
+                return Ok(frame);
+            }
+            let cipher = cipher
+                .as_ref()
+                .ok_or(anyhow::anyhow!("file is encrypted, but no key was given"))?;
+            if frame.payload.len() < NONCE_SIZE {
+                return Err(anyhow::anyhow!("encrypted frame {} is too short", i));
+            }
+            let (nonce, data) = frame.payload.split_at(NONCE_SIZE);
+            let decrypt = |last: bool| {
+                cipher.decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: data,
+                        aad: &associated_data(i, last),
+                    },
+                )
+            };
+            let last = i + 1 == num_frames;
+            let plain = match decrypt(last) {
+                Ok(plain) => plain,
+                // the last frame was not written as the last one
+                Err(_) if last && decrypt(false).is_ok() => {
+                    return Err(anyhow::anyhow!("encrypted container is truncated"))
+                }
+                Err(_) => {
+                    return Err(anyhow::anyhow!(
+                        "failed to decrypt frame {} (wrong key?)",
+                        i
+                    ))
+                }
+            };
+            Ok(Frame::from_bytes(&plain)?.0)
+        })
+        .collect()
+}
+
+/// index of the frame followed by 1 for the last frame of the container and 0 otherwise
+fn associated_data(index: usize, last: bool) -> [u8; 9] {
+    let mut aad = [0u8; 9];
+    aad[..8].copy_from_slice(&(index as u64).to_le_bytes());
+    aad[8] = last as u8;
+    aad
+}
@@ -0,0 +1,134 @@
+//! 3D smoothing filter from Mip-Splatting (Yu et al. 2023)
+//! every splat is convolved with a gaussian low pass filter whose size depends on the highest
+//! sampling rate of the splat in the training cameras, so no splat is thinner than a training pixel
+//! this removes needle artifacts in zoomed in views, the 2D mip filter of the preprocess shader handles zoomed out views
+
+use cgmath::{Matrix4, Point3, Transform, Vector2};
+use half::f16;
+
+use super::GenericGaussianPointCloud;
+use crate::{
+    camera::{Camera, PerspectiveCamera},
+    pointcloud::Gaussian,
+    scene::SceneCamera,
+    utils::simd,
+};
+
+/// variance of the low pass filter in pixels as used by Mip-Splatting
+pub const DEFAULT_FILTER_3D_SIZE: f32 = 0.2;
+
+/// splats closer to a camera are ignored for its sampling rate
+const MIN_DEPTH: f32 = 0.2;
+/// splats slightly outside of the image are still counted as seen by the camera
+const SCREEN_MARGIN: f32 = 0.15;
+
+struct FilterCamera {
+    view: Matrix4<f32>,
+    focal: Vector2<f32>,
+    size: Vector2<f32>,
+}
+
+impl GenericGaussianPointCloud {
+    /// bakes the 3D filter into the covariances and opacities of the splats
+    /// `size` is the variance of the filter in pixels of the training cameras
+    /// the 2D mip filter is enabled as well, it is needed to render the filtered splats correctly
+    pub fn apply_3d_filter(&mut self, cameras: &[SceneCamera], size: f32) -> anyhow::Result<()> {
+        let _span = tracing::info_span!("apply_3d_filter", num_cameras = cameras.len()).entered();
+        if self.mip_splatting == Some(true) {
+            log::warn!(
+                "point cloud was trained with mip splatting, 3D filter is not applied again"
+            );
+            return Ok(());
+        }
+        if self.compressed {
+            return Err(anyhow::anyhow!(
+                "3D filter is not supported for compressed point clouds"
+            ));
+        }
+        if cameras.is_empty() {
+            return Err(anyhow::anyhow!("3D filter requires the training cameras"));
+        }
+        let cameras: Vec<FilterCamera> = cameras
+            .iter()
+            .map(|c| {
+                let camera: PerspectiveCamera = c.clone().into();
+                FilterCamera {
+                    view: camera.view_matrix(),
+                    focal: Vector2::new(c.fx, c.fy),
+                    size: Vector2::new(c.width as f32, c.height as f32),
+                }
+            })
+            .collect();
+
+        let gaussians: &mut [Gaussian] = bytemuck::cast_slice_mut(&mut self.gaussians);
+        let positions: Vec<Point3<f32>> = gaussians.iter().map(|g| g.xyz).collect();
+
+        // smallest distance of a pixel footprint in world space (inverse of the sampling rate)
+        let footprints: Vec<Option<f32>> = positions
+            .iter()
+            .map(|p| {
+                cameras
+                    .iter()
+                    .filter_map(|c| pixel_footprint(c, *p))
+                    .min_by(f32::total_cmp)
+            })
+            .collect();
+        // splats that are not seen by any camera get the largest filter
+        let max_footprint = footprints
+            .iter()
+            .flatten()
+            .copied()
+            .max_by(f32::total_cmp)
+            .unwrap_or(0.);
+
+        for (g, footprint) in gaussians.iter_mut().zip(footprints) {
+            let variance = size * footprint.unwrap_or(max_footprint).powi(2);
+            let cov = g.cov.map(f16::to_f32);
+            let filtered = [
+                cov[0] + variance,
+                cov[1],
+                cov[2],
+                cov[3] + variance,
+                cov[4],
+                cov[5] + variance,
+            ];
+            // keep the integral of the splat, so thin splats become more transparent
+            let coef = (det(&cov).max(0.) / det(&filtered).max(f32::EPSILON)).sqrt();
+            g.cov = simd::f32_to_f16(&filtered);
+            g.opacity *= coef;
+        }
+        self.mip_splatting = Some(true);
+        Ok(())
+    }
+}
+
+/// world space size of a pixel of the camera at the position of the splat
+/// None if the splat is not in the image of the camera
+fn pixel_footprint(camera: &FilterCamera, p: Point3<f32>) -> Option<f32> {
+    let p_cam = camera.view.transform_point(p);
+    let depth = p_cam.z;
+    if depth <= MIN_DEPTH {
+        return None;
+    }
+    let x = p_cam.x / depth * camera.focal.x + camera.size.x / 2.;
+    let y = p_cam.y / depth * camera.focal.y + camera.size.y / 2.;
+    let in_screen = x >= -SCREEN_MARGIN * camera.size.x
+        && x <= (1. + SCREEN_MARGIN) * camera.size.x
+        && y >= -SCREEN_MARGIN * camera.size.y
+        && y <= (1. + SCREEN_MARGIN) * camera.size.y;
+    in_screen.then(|| depth / camera.focal.x)
+}
+
+/// determinant of a symmetric 3x3 matrix stored as [xx, xy, xz, yy, yz, zz]
+fn det(c: &[f32; 6]) -> f32 {
+    c[0] * (c[3] * c[5] - c[4] * c[4]) - c[1] * (c[1] * c[5] - c[4] * c[2])
+        + c[2] * (c[1] * c[4] - c[3] * c[2])
+}
+
+/// 3D filter that is applied when loading a point cloud, see [GenericGaussianPointCloud::apply_3d_filter]
+#[derive(Debug, Clone)]
+pub struct Filter3D {
+    pub size: f32,
+    /// training cameras of the point cloud, the filter is skipped if empty
+    pub cameras: Vec<SceneCamera>,
+}
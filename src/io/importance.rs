@@ -0,0 +1,148 @@
+use std::io::Write;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use cgmath::Vector2;
+
+use super::{
+    splat_size::{ProjectedSplat, SplatProjector},
+    GenericGaussianPointCloud,
+};
+use crate::{
+    camera::{Camera, PerspectiveCamera},
+    pointcloud::{Gaussian, GaussianCompressed},
+    renderer::DEFAULT_KERNEL_SIZE,
+    utils::simd,
+};
+
+/// same cutoff as in the fragment shader
+const MIN_ALPHA: f32 = 1. / 255.;
+/// pixels with a lower transmittance are considered opaque
+const MIN_TRANSMITTANCE: f32 = 1e-4;
+
+impl GenericGaussianPointCloud {
+    /// accumulated contribution (alpha times transmittance) of every splat to the pixels of all camera images
+    /// the images are rendered on the cpu with `viewport` resolution, so a small resolution gives a fast approximation
+    pub fn importance(
+        &self,
+        cameras: &[PerspectiveCamera],
+        viewport: Vector2<u32>,
+    ) -> anyhow::Result<Vec<f32>> {
+        let splats = self.covariances(1)?;
+        let opacities = self.opacities(1)?;
+        let positions: Vec<_> = splats.iter().map(|(xyz, _)| *xyz).collect();
+        let kernel_size = self.kernel_size.unwrap_or(DEFAULT_KERNEL_SIZE);
+        let width = viewport.x as usize;
+
+        let mut importance = vec![0f32; splats.len()];
+        for camera in cameras {
+            let projector = SplatProjector::new(camera, viewport, &self.aabb, kernel_size);
+            let mut projected: Vec<_> = splats
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (xyz, cov))| projector.project(*xyz, cov).map(|s| (i, s)))
+                .collect();
+            // front to back
+            let keys = simd::depth_keys(&positions, &camera.view_matrix());
+            projected.sort_unstable_by_key(|(i, _)| keys[*i]);
+
+            let mut transmittance = vec![1f32; width * viewport.y as usize];
+            for (i, splat) in projected {
+                importance[i] += blend_splat(&splat, opacities[i], &mut transmittance, width);
+            }
+        }
+        Ok(importance)
+    }
+
+    /// opacities of every `step`-th splat
+    pub(super) fn opacities(&self, step: usize) -> anyhow::Result<Vec<f32>> {
+        if self.compressed {
+            let quantization = self.quantization.unwrap_or_default().opacity;
+            Ok(self
+                .gaussians_compressed()?
+                .iter()
+                .step_by(step)
+                .map(|g: &GaussianCompressed| {
+                    (g.opacity as i32 - quantization.zero_point) as f32 * quantization.scale
+                })
+                .collect())
+        } else {
+            Ok(self
+                .gaussians()?
+                .iter()
+                .step_by(step)
+                .map(|g: &Gaussian| g.opacity)
+                .collect())
+        }
+    }
+
+    /// writes the splat positions and a per splat value (e.g. the importance) as binary ply file
+    /// the vertices have the same order as in the loaded file, so the value can be matched by index
+    pub fn write_channel<W: Write>(
+        &self,
+        mut writer: W,
+        name: &str,
+        values: &[f32],
+    ) -> anyhow::Result<()> {
+        if values.len() != self.num_points {
+            return Err(anyhow::anyhow!(
+                "expected {} values, got {}",
+                self.num_points,
+                values.len()
+            ));
+        }
+        write!(
+            writer,
+            "ply\nformat binary_little_endian 1.0\nelement vertex {}\nproperty float x\nproperty float y\nproperty float z\nproperty float {}\nend_header\n",
+            self.num_points, name
+        )?;
+        for (p, v) in self.positions().iter().zip(values) {
+            writer.write_f32::<LittleEndian>(p.x)?;
+            writer.write_f32::<LittleEndian>(p.y)?;
+            writer.write_f32::<LittleEndian>(p.z)?;
+            writer.write_f32::<LittleEndian>(*v)?;
+        }
+        Ok(())
+    }
+}
+
+/// alpha blends the splat front to back into the `transmittance` image (row major with `width` columns)
+/// returns the contribution (alpha times transmittance) of the splat summed over all pixels
+pub(super) fn blend_splat(
+    splat: &ProjectedSplat,
+    opacity: f32,
+    transmittance: &mut [f32],
+    width: usize,
+) -> f32 {
+    let height = transmittance.len() / width;
+    let [a, b, c] = splat.cov;
+    let det = a * c - b * b;
+    if det <= 0. {
+        return 0.;
+    }
+    // inverse of the 2D covariance
+    let conic = [c / det, -b / det, a / det];
+    let radius = splat.radius();
+    let x_min = (splat.center.x - radius).floor().max(0.) as usize;
+    let y_min = (splat.center.y - radius).floor().max(0.) as usize;
+    let x_max = ((splat.center.x + radius).ceil() as usize).min(width);
+    let y_max = ((splat.center.y + radius).ceil() as usize).min(height);
+    let mut contribution = 0.;
+    for y in y_min..y_max {
+        for x in x_min..x_max {
+            let t = &mut transmittance[y * width + x];
+            if *t < MIN_TRANSMITTANCE {
+                continue;
+            }
+            let d = Vector2::new(x as f32 + 0.5, y as f32 + 0.5) - splat.center;
+            let power =
+                -0.5 * (conic[0] * d.x * d.x + 2. * conic[1] * d.x * d.y + conic[2] * d.y * d.y);
+            let alpha = (opacity * power.exp()).min(0.99);
+            if alpha < MIN_ALPHA {
+                continue;
+            }
+            contribution += alpha * *t;
+            *t *= 1. - alpha;
+        }
+    }
+    contribution
+}
@@ -0,0 +1,549 @@
+//! level of detail hierarchy for very large point clouds
+//! the splats are sorted into an octree and every node stores proxy splats that approximate its content,
+//! nodes that are small on screen are drawn with their proxies instead of their children
+
+use cgmath::{MetricSpace, Point3, Vector2};
+use half::f16;
+
+use super::{quantize, GenericGaussianPointCloud};
+use crate::{
+    camera::PerspectiveCamera,
+    culling::Culler,
+    frame_arena::{ArenaVec, FrameArena},
+    pointcloud::{Aabb, Covariance3D, Gaussian, GaussianCompressed, GaussianQuantization},
+    utils::{sh_num_coefficients, simd},
+};
+
+/// nodes with fewer splats are not split further
+const LEAF_SIZE: usize = 4096;
+const MAX_DEPTH: u32 = 16;
+/// the proxies of a node are aggregated on a grid with this resolution per axis
+const PROXY_GRID: usize = 4;
+/// largest value stored in the f16 covariances of the proxies
+const MAX_COV: f32 = 65000.;
+
+/// nodes that are smaller than this on screen (in pixels) are drawn with their proxies
+pub const DEFAULT_LOD_THRESHOLD: f32 = 64.;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    /// bounding box of the splat centers
+    aabb: Aabb<f32>,
+    /// children are stored consecutively, leaves have no children
+    first_child: u32,
+    num_children: u32,
+    /// splats of a leaf (start, count)
+    splats: (u32, u32),
+    /// proxy splats of the node (start, count)
+    proxies: (u32, u32),
+}
+
+impl Node {
+    fn is_leaf(&self) -> bool {
+        self.num_children == 0
+    }
+}
+
+/// consecutive splats in the buffer that are processed by the preprocess shader
+/// `offset` is the number of selected splats before this range, the last entry only holds the total
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LodRange {
+    pub offset: u32,
+    pub start: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Octree {
+    /// root first, children always come after their parents
+    nodes: Vec<Node>,
+    /// number of original splats, the proxies are stored behind them
+    num_splats: u32,
+}
+
+/// splat with decoded values, used to aggregate proxies
+#[derive(Clone, Copy)]
+struct Splat {
+    pos: Point3<f32>,
+    cov: [f32; 6],
+    opacity: f32,
+    sh: [[f32; 3]; 16],
+}
+
+impl Splat {
+    /// opacity times the area of the splat, splats with more weight dominate the proxy
+    fn weight(&self) -> f32 {
+        let c = &self.cov;
+        let det = c[0] * (c[3] * c[5] - c[4] * c[4]) - c[1] * (c[1] * c[5] - c[4] * c[2])
+            + c[2] * (c[1] * c[4] - c[3] * c[2]);
+        self.opacity * det.max(0.).cbrt()
+    }
+}
+
+/// weighted moments of the splats in one cell of the proxy grid
+#[derive(Clone, Copy)]
+struct Moments {
+    /// sum of the opacity weighted areas
+    weight: f32,
+    pos: [f32; 3],
+    /// E[cov + x xᵀ]
+    second: [f32; 6],
+    sh: [[f32; 3]; 16],
+}
+
+impl Moments {
+    const ZERO: Self = Self {
+        weight: 0.,
+        pos: [0.; 3],
+        second: [0.; 6],
+        sh: [[0.; 3]; 16],
+    };
+
+    fn add(&mut self, s: &Splat) {
+        let w = s.weight();
+        if w <= 0. {
+            return;
+        }
+        let p = [s.pos.x, s.pos.y, s.pos.z];
+        self.weight += w;
+        for (m, p) in self.pos.iter_mut().zip(p) {
+            *m += w * p;
+        }
+        let outer = [
+            p[0] * p[0],
+            p[0] * p[1],
+            p[0] * p[2],
+            p[1] * p[1],
+            p[1] * p[2],
+            p[2] * p[2],
+        ];
+        for ((m, c), o) in self.second.iter_mut().zip(s.cov).zip(outer) {
+            *m += w * (c + o);
+        }
+        for (m, c) in self.sh.iter_mut().flatten().zip(s.sh.iter().flatten()) {
+            *m += w * c;
+        }
+    }
+
+    /// gaussian with the same mean and covariance as the weighted splats
+    fn proxy(&self) -> Option<Splat> {
+        if self.weight <= 0. {
+            return None;
+        }
+        let inv = 1. / self.weight;
+        let mean = self.pos.map(|p| p * inv);
+        let outer = [
+            mean[0] * mean[0],
+            mean[0] * mean[1],
+            mean[0] * mean[2],
+            mean[1] * mean[1],
+            mean[1] * mean[2],
+            mean[2] * mean[2],
+        ];
+        let mut cov = [0.; 6];
+        for ((c, m), o) in cov.iter_mut().zip(self.second).zip(outer) {
+            *c = m * inv - o;
+        }
+        let proxy = Splat {
+            pos: Point3::from(mean),
+            cov,
+            opacity: 1.,
+            sh: self.sh.map(|c| c.map(|v| v * inv)),
+        };
+        // the proxy covers about the same opacity weighted area as the splats
+        let area = proxy.weight();
+        let opacity = if area > 0. {
+            (self.weight / area).min(1.)
+        } else {
+            1.
+        };
+        Some(Splat { opacity, ..proxy })
+    }
+}
+
+impl GenericGaussianPointCloud {
+    /// sorts the splats into an octree and appends proxy splats for every node
+    /// the splats are reordered in place and the proxies are stored behind them,
+    /// for compressed point clouds every proxy gets its own covariance and sh coefficient entry
+    /// the proxies add about 2% splats, see [Octree::num_proxies]
+    pub fn build_lod(&mut self) -> anyhow::Result<()> {
+        let _span = tracing::info_span!("build_lod", num_points = self.num_points).entered();
+        let splats = self.covariances(1)?;
+        let opacities = self.opacities(1)?;
+        let positions: Vec<Point3<f32>> = splats.iter().map(|(xyz, _)| *xyz).collect();
+        let (mut nodes, order) = build_nodes(&positions, self.aabb);
+        drop(positions);
+
+        let sh = ShDecoder::new(self);
+        let decode = |idx: usize| Splat {
+            pos: splats[idx].0,
+            cov: splats[idx].1,
+            opacity: opacities[idx],
+            sh: sh.decode(idx),
+        };
+
+        // aggregate proxies bottom up, children are processed before their parents
+        let mut node_proxies: Vec<Vec<Splat>> = vec![Vec::new(); nodes.len()];
+        for i in (0..nodes.len()).rev() {
+            let node = nodes[i];
+            let mut grid = vec![Moments::ZERO; PROXY_GRID * PROXY_GRID * PROXY_GRID];
+            let size = node.aabb.size();
+            let mut add = |s: &Splat| {
+                let cell = |v: f32, min: f32, size: f32| {
+                    (((v - min) / size.max(f32::EPSILON)) * PROXY_GRID as f32)
+                        .clamp(0., PROXY_GRID as f32 - 1.) as usize
+                };
+                let x = cell(s.pos.x, node.aabb.min.x, size.x);
+                let y = cell(s.pos.y, node.aabb.min.y, size.y);
+                let z = cell(s.pos.z, node.aabb.min.z, size.z);
+                grid[(z * PROXY_GRID + y) * PROXY_GRID + x].add(s);
+            };
+            if node.is_leaf() {
+                let (start, count) = node.splats;
+                for idx in &order[start as usize..(start + count) as usize] {
+                    add(&decode(*idx as usize));
+                }
+            } else {
+                let children =
+                    node.first_child as usize..(node.first_child + node.num_children) as usize;
+                for child in children {
+                    for s in &node_proxies[child] {
+                        add(s);
+                    }
+                }
+            }
+            node_proxies[i] = grid.iter().filter_map(Moments::proxy).collect();
+        }
+        drop(splats);
+        drop(opacities);
+
+        // proxies are stored node by node in the same order as the nodes
+        let mut proxies: Vec<Splat> = Vec::new();
+        for (node, node_proxies) in nodes.iter_mut().zip(&node_proxies) {
+            node.proxies = (
+                (self.num_points + proxies.len()) as u32,
+                node_proxies.len() as u32,
+            );
+            proxies.extend(node_proxies);
+        }
+
+        if self.compressed {
+            permute(
+                bytemuck::cast_slice_mut::<_, GaussianCompressed>(&mut self.gaussians),
+                &order,
+            );
+            self.append_compressed_proxies(&proxies)?;
+        } else {
+            permute(
+                bytemuck::cast_slice_mut::<_, Gaussian>(&mut self.gaussians),
+                &order,
+            );
+            permute(
+                bytemuck::cast_slice_mut::<_, [[f16; 3]; 16]>(&mut self.sh_coefs),
+                &order,
+            );
+            for p in &proxies {
+                let gaussian = Gaussian {
+                    xyz: p.pos,
+                    opacity: p.opacity,
+                    cov: simd::f32_to_f16(&p.cov.map(|c| c.clamp(-MAX_COV, MAX_COV))),
+                };
+                let sh = p.sh.map(|c| simd::f32_to_f16(&c));
+                self.gaussians
+                    .extend_from_slice(bytemuck::bytes_of(&gaussian));
+                self.sh_coefs.extend_from_slice(bytemuck::bytes_of(&sh));
+            }
+        }
+        log::info!(
+            "built lod hierarchy with {} nodes and {} proxy splats ({:.1}% of the splats)",
+            nodes.len(),
+            proxies.len(),
+            100. * proxies.len() as f32 / self.num_points.max(1) as f32
+        );
+
+        self.lod = Some(Octree {
+            nodes,
+            num_splats: self.num_points as u32,
+        });
+        self.num_points += proxies.len();
+        Ok(())
+    }
+
+    /// quantizes the proxies with the quantization of the point cloud
+    /// the covariance and sh coefficients of every proxy are appended to the codebooks
+    fn append_compressed_proxies(&mut self, proxies: &[Splat]) -> anyhow::Result<()> {
+        let q = self
+            .quantization
+            .ok_or_else(|| anyhow::anyhow!("compressed point cloud without quantization"))?;
+        let n = sh_num_coefficients(self.sh_deg) as usize;
+        let row_size = n * 3;
+        // the codebook may be followed by padding, so the rows behind the last used one are replaced
+        let num_rows = self
+            .gaussians_compressed()?
+            .iter()
+            .map(|g| g.sh_idx as usize + 1)
+            .max()
+            .unwrap_or(0);
+        self.sh_coefs.truncate(num_rows * row_size);
+        let covars = self.covars.get_or_insert_with(Vec::new);
+
+        let mut compressed = Vec::with_capacity(proxies.len());
+        for (i, p) in proxies.iter().enumerate() {
+            let scale_factor = quantize::quantize(quantize::log_scale(p.cov), &q.scaling_factor);
+            let s = quantize::dequantize(scale_factor, &q.scaling_factor).exp();
+            compressed.push(GaussianCompressed {
+                xyz: p.pos.map(f16::from_f32),
+                opacity: quantize::quantize(p.opacity, &q.opacity),
+                scale_factor,
+                geometry_idx: covars.len() as u32,
+                sh_idx: (num_rows + i) as u32,
+            });
+            covars.push(Covariance3D(simd::f32_to_f16(
+                &p.cov.map(|c| (c / (s * s)).clamp(-MAX_COV, MAX_COV)),
+            )));
+            for (j, c) in p.sh[..n].iter().enumerate() {
+                let q = if j == 0 { &q.color_dc } else { &q.color_rest };
+                self.sh_coefs
+                    .extend(c.map(|v| quantize::quantize(v, q) as u8));
+            }
+        }
+        quantize::pad_sh_coefs(&mut self.sh_coefs);
+        self.gaussians
+            .extend_from_slice(bytemuck::cast_slice(&compressed));
+        Ok(())
+    }
+}
+
+/// sorts the splats into an octree, returns the nodes and the splat indices ordered by node
+fn build_nodes(positions: &[Point3<f32>], aabb: Aabb<f32>) -> (Vec<Node>, Vec<u32>) {
+    let num_points = positions.len() as u32;
+    let mut order: Vec<u32> = (0..num_points).collect();
+    let mut nodes = vec![Node {
+        aabb: simd::bounding_box(positions).unwrap_or(aabb),
+        first_child: 0,
+        num_children: 0,
+        splats: (0, num_points),
+        proxies: (0, 0),
+    }];
+    let mut depths = vec![0];
+
+    // split nodes breadth first, so the children of a node are stored consecutively
+    let mut i = 0;
+    while i < nodes.len() {
+        let node = nodes[i];
+        let (start, count) = node.splats;
+        if (count as usize) <= LEAF_SIZE || depths[i] >= MAX_DEPTH {
+            i += 1;
+            continue;
+        }
+        let center = node.aabb.center();
+        let octant = |idx: u32| {
+            let p = positions[idx as usize];
+            (p.x > center.x) as usize
+                | ((p.y > center.y) as usize) << 1
+                | ((p.z > center.z) as usize) << 2
+        };
+        let range = &mut order[start as usize..(start + count) as usize];
+        range.sort_unstable_by_key(|idx| octant(*idx));
+
+        nodes[i].first_child = nodes.len() as u32;
+        let mut child_start = start;
+        for o in 0..8 {
+            let child_count = range.iter().filter(|idx| octant(**idx) == o).count() as u32;
+            if child_count == 0 {
+                continue;
+            }
+            let child_positions: Vec<Point3<f32>> = range
+                [(child_start - start) as usize..(child_start - start + child_count) as usize]
+                .iter()
+                .map(|idx| positions[*idx as usize])
+                .collect();
+            nodes.push(Node {
+                aabb: simd::bounding_box(&child_positions).unwrap(),
+                first_child: 0,
+                num_children: 0,
+                splats: (child_start, child_count),
+                proxies: (0, 0),
+            });
+            depths.push(depths[i] + 1);
+            nodes[i].num_children += 1;
+            child_start += child_count;
+        }
+        i += 1;
+    }
+    (nodes, order)
+}
+
+/// reorders the items so that `data[i]` is the previous `data[order[i]]`
+/// the cycles of the permutation are followed, so no second copy of the data is needed
+fn permute<T: Copy>(data: &mut [T], order: &[u32]) {
+    let mut done = vec![false; data.len()];
+    for start in 0..data.len() {
+        if done[start] {
+            continue;
+        }
+        let first = data[start];
+        let mut i = start;
+        loop {
+            done[i] = true;
+            let next = order[i] as usize;
+            if next == start {
+                data[i] = first;
+                break;
+            }
+            data[i] = data[next];
+            i = next;
+        }
+    }
+}
+
+/// decodes the sh coefficients of single splats
+enum ShDecoder<'a> {
+    Uncompressed(&'a [[[f16; 3]; 16]]),
+    Compressed {
+        gaussians: &'a [GaussianCompressed],
+        coefs: &'a [u8],
+        num_coefs: usize,
+        quantization: GaussianQuantization,
+    },
+}
+
+impl<'a> ShDecoder<'a> {
+    fn new(pc: &'a GenericGaussianPointCloud) -> Self {
+        match pc.gaussians_compressed() {
+            Ok(gaussians) => Self::Compressed {
+                gaussians,
+                coefs: pc.sh_coefs_buffer(),
+                num_coefs: sh_num_coefficients(pc.sh_deg) as usize,
+                quantization: pc.quantization.unwrap_or_default(),
+            },
+            Err(_) => Self::Uncompressed(bytemuck::cast_slice(pc.sh_coefs_buffer())),
+        }
+    }
+
+    fn decode(&self, idx: usize) -> [[f32; 3]; 16] {
+        match self {
+            Self::Uncompressed(coefs) => coefs[idx].map(|c| c.map(f16::to_f32)),
+            Self::Compressed {
+                gaussians,
+                coefs,
+                num_coefs,
+                quantization,
+            } => {
+                let start = gaussians[idx].sh_idx as usize * num_coefs * 3;
+                let mut sh = [[0.; 3]; 16];
+                for (j, (c, bytes)) in sh
+                    .iter_mut()
+                    .zip(coefs[start..start + num_coefs * 3].chunks_exact(3))
+                    .enumerate()
+                {
+                    let q = if j == 0 {
+                        &quantization.color_dc
+                    } else {
+                        &quantization.color_rest
+                    };
+                    *c = std::array::from_fn(|k| quantize::dequantize(bytes[k] as i8, q));
+                }
+                sh
+            }
+        }
+    }
+}
+
+impl Octree {
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// number of splats without the proxies
+    pub fn num_splats(&self) -> u32 {
+        self.num_splats
+    }
+
+    /// number of proxy splats stored behind the original splats
+    pub fn num_proxies(&self) -> u32 {
+        self.nodes.iter().map(|n| n.proxies.1).sum()
+    }
+
+    /// selects the nodes to draw, nodes smaller than `threshold` pixels on screen are drawn with their proxies
+    /// returns the ranges of the selected splats (allocated in `arena`) and the total number of selected splats
+    pub fn select<'a>(
+        &self,
+        camera: &PerspectiveCamera,
+        viewport: Vector2<u32>,
+        clipping_box: Option<Aabb<f32>>,
+        threshold: f32,
+        arena: &'a FrameArena,
+    ) -> (&'a [LodRange], u32) {
+        let culler = Culler::new(camera, clipping_box);
+        let focal = camera.projection.focal(viewport).y;
+        let camera_pos = camera.position;
+        let mut ranges = arena.alloc_vec(self.max_ranges());
+        let mut total = 0;
+        let mut push = |(start, count): (u32, u32), ranges: &mut ArenaVec<LodRange>| {
+            if count == 0 {
+                return;
+            }
+            match ranges.last() {
+                // extend the previous range if the splats are adjacent
+                Some(last) if last.start + (total - last.offset) == start => {}
+                _ => ranges.push(LodRange {
+                    offset: total,
+                    start,
+                }),
+            }
+            total += count;
+        };
+
+        // every node is pushed at most once
+        let mut stack = arena.alloc_vec::<u32>(self.nodes.len().max(1));
+        stack.push(0);
+        while let Some(i) = stack.pop() {
+            let node: &Node = &self.nodes[i as usize];
+            if !culler.aabb_visible(&node.aabb) {
+                continue;
+            }
+            let radius = node.aabb.radius();
+            let distance = node.aabb.center().distance(camera_pos);
+            let projected_size = if distance > radius {
+                2. * radius * focal / distance
+            } else {
+                f32::INFINITY
+            };
+            if projected_size < threshold {
+                push(node.proxies, &mut ranges);
+            } else if node.is_leaf() {
+                push(node.splats, &mut ranges);
+            } else {
+                // reversed so that the children are visited in order and their ranges can be merged
+                let children = node.first_child..node.first_child + node.num_children;
+                stack.extend(children.rev());
+            }
+        }
+        ranges.push(LodRange {
+            offset: total,
+            start: 0,
+        });
+        (ranges.into_slice(), total)
+    }
+
+    /// all original splats without proxies
+    pub fn select_all<'a>(&self, arena: &'a FrameArena) -> (&'a [LodRange], u32) {
+        let ranges = arena.alloc_from_iter([
+            LodRange {
+                offset: 0,
+                start: 0,
+            },
+            LodRange {
+                offset: self.num_splats,
+                start: 0,
+            },
+        ]);
+        (ranges, self.num_splats)
+    }
+
+    /// most ranges [Octree::select] can return (including the total)
+    pub fn max_ranges(&self) -> usize {
+        self.nodes.len() + 1
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// capture metadata used for attribution
+/// read from a sidecar json file or from `comment <key>=<value>` lines in the ply header
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub license: Option<String>,
+    pub description: Option<String>,
+    pub source: Option<String>,
+}
+
+impl Metadata {
+    pub fn from_ply_comments(comments: &[String]) -> Option<Self> {
+        let value = |key: &str| {
+            comments.iter().find_map(|c| {
+                let (k, v) = c.split_once('=')?;
+                (k.trim() == key).then(|| v.trim().to_string())
+            })
+        };
+        let meta = Self {
+            title: value("title"),
+            author: value("author"),
+            license: value("license"),
+            description: value("description"),
+            source: value("source"),
+        };
+        (!meta.is_empty()).then_some(meta)
+    }
+
+    /// candidate sidecar files for a point cloud file
+    /// `<file>.meta.json` or `metadata.json` in the same directory
+    pub fn sidecar_paths(pc_file: &Path) -> Vec<PathBuf> {
+        let mut paths = vec![PathBuf::from(format!("{}.meta.json", pc_file.display()))];
+        if let Some(dir) = pc_file.parent() {
+            paths.push(dir.join("metadata.json"));
+        }
+        paths
+    }
+
+    pub fn load_sidecar(pc_file: &Path) -> Option<Self> {
+        let path = Self::sidecar_paths(pc_file)
+            .into_iter()
+            .find(|p| p.exists())?;
+        match File::open(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|f| Ok(serde_json::from_reader::<_, Self>(BufReader::new(f))?))
+        {
+            Ok(meta) => Some(meta),
+            Err(err) => {
+                log::warn!("failed to read metadata file {:?}: {}", path, err);
+                None
+            }
+        }
+    }
+
+    /// fields set in `other` override the fields of self
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            title: other.title.or(self.title),
+            author: other.author.or(self.author),
+            license: other.license.or(self.license),
+            description: other.description.or(self.description),
+            source: other.source.or(self.source),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// key value pairs using the keywords defined in the png specification
+    pub fn png_text_chunks(&self) -> Vec<(&'static str, String)> {
+        [
+            ("Title", &self.title),
+            ("Author", &self.author),
+            ("Copyright", &self.license),
+            ("Description", &self.description),
+            ("Source", &self.source),
+        ]
+        .into_iter()
+        .filter_map(|(k, v)| v.clone().map(|v| (k, v)))
+        .collect()
+    }
+}
+
+/// saves an image as png and embeds the metadata as text chunks
+pub fn save_png<P: AsRef<Path>>(
+    img: &image::RgbaImage,
+    path: P,
+    metadata: Option<&Metadata>,
+) -> anyhow::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, img.width(), img.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    if let Some(metadata) = metadata {
+        for (key, value) in metadata.png_text_chunks() {
+            encoder.add_itxt_chunk(key.to_string(), value)?;
+        }
+    }
+    encoder.add_text_chunk("Software".to_string(), "web-splat".to_string())?;
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(img.as_raw())?;
+    writer.finish()?;
+    Ok(())
+}
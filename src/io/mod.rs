@@ -1,21 +1,63 @@
+use std::borrow::Cow;
 #[cfg(feature = "npz")]
 use std::io::BufReader;
-use std::io::{Read, Seek};
+use std::io::{Cursor, Read, Seek};
+use std::mem;
+use std::ops::Range;
 
 use bytemuck::Zeroable;
 use cgmath::{Array, EuclideanSpace, InnerSpace, Point3, Vector3};
-use half::f16;
-
-use crate::pointcloud::{Aabb, Covariance3D, Gaussian, GaussianCompressed, GaussianQuantization};
+use half::{f16, slice::HalfFloatSliceExt};
+
+use crate::{
+    pointcloud::{
+        Aabb, Covariance3D, Gaussian, GaussianCompressed, GaussianLayout, GaussianQuantization,
+    },
+    task::CancellationToken,
+    time::Instant,
+    utils::simd,
+    WebSplatError,
+};
+
+pub use self::best_view::{SuggestedView, ViewSuggester};
+pub use self::crop::CropRegion;
+pub use self::density::DensityGrid;
+pub use self::encrypted::EncryptionKey;
+pub use self::filter_3d::{Filter3D, DEFAULT_FILTER_3D_SIZE};
+pub use self::lod::{LodRange, Octree, DEFAULT_LOD_THRESHOLD};
+pub use self::metadata::Metadata;
+pub use self::partition::{Partition, Partitions, MAX_PARTITIONS};
+pub use self::splat_size::SplatSizeStats;
+pub use self::stats::{PointCloudStats, MAX_LARGEST_SCALES};
 
 #[cfg(feature = "npz")]
 use self::npz::NpzReader;
 
 use self::ply::PlyReader;
-
+use self::splat::SplatReader;
+
+mod best_view;
+pub mod codec;
+pub mod crop;
+pub mod delta;
+mod density;
+pub mod encrypted;
+mod filter_3d;
+mod importance;
+mod lod;
+pub mod metadata;
 #[cfg(feature = "npz")]
 pub mod npz;
+mod packed;
+mod partition;
 pub mod ply;
+mod progressive;
+mod quantize;
+#[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+pub mod remote;
+pub mod splat;
+mod splat_size;
+mod stats;
 
 pub trait PointCloudReader {
     fn read(&mut self) -> Result<GenericGaussianPointCloud, anyhow::Error>;
@@ -24,6 +66,28 @@ pub trait PointCloudReader {
     fn file_ending() -> &'static str;
 }
 
+/// processing that is applied to point clouds after loading
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// region of interest, splats outside of it are removed
+    pub crop: Option<CropRegion>,
+    /// quantize uncompressed point clouds to reduce GPU memory usage
+    pub low_memory: bool,
+    /// key for encrypted containers
+    pub key: Option<EncryptionKey>,
+    /// build a level of detail hierarchy, the proxy splats are quantized too in low memory mode
+    pub lod: bool,
+    /// Mip-Splatting 3D filter (uncompressed point clouds only)
+    pub filter_3d: Option<Filter3D>,
+    /// store the covariances and sh coefficients as f16 on the gpu instead of f32, the positions are always f32
+    /// (uncompressed point clouds only, the shaders unpack the values with `unpack2x16float`, so no device feature is needed)
+    pub half_precision: bool,
+    /// pack the covariances into a rotation and scale on the gpu (uncompressed point clouds only)
+    pub packed_covariance: bool,
+    /// stops the processing after loading (filtering, level of detail, quantization)
+    pub cancellation: CancellationToken,
+}
+
 pub struct GenericGaussianPointCloud {
     gaussians: Vec<u8>,
     sh_coefs: Vec<u8>,
@@ -35,29 +99,129 @@ pub struct GenericGaussianPointCloud {
     pub kernel_size: Option<f32>,
     pub mip_splatting: Option<bool>,
     pub background_color: Option<[f32; 3]>,
+    pub metadata: Option<Metadata>,
 
     pub up: Option<Vector3<f32>>,
     pub center: Point3<f32>,
     pub aabb: Aabb<f32>,
+    /// level of detail hierarchy, see [GenericGaussianPointCloud::build_lod]
+    pub lod: Option<Octree>,
+    /// upload the covariances and sh coefficients as f16, see [LoadOptions::half_precision]
+    pub half_precision: bool,
+    /// upload the covariances as packed rotation and scale, see [GaussianLayout]
+    pub packed_covariance: bool,
 }
 
 impl GenericGaussianPointCloud {
-    pub fn load<'a, R: Read + Seek>(f: R) -> Result<Self, anyhow::Error> {
+    pub fn load<'a, R: Read + Seek>(f: R) -> Result<Self, WebSplatError> {
         let mut signature: [u8; 4] = [0; 4];
         let mut f = f;
         f.read_exact(&mut signature)?;
         f.rewind()?;
         if signature.starts_with(PlyReader::<R>::magic_bytes()) {
-            let mut ply_reader = PlyReader::new(f)?;
-            return ply_reader.read();
+            return PlyReader::new(f)
+                .and_then(|mut ply_reader| ply_reader.read())
+                .map_err(WebSplatError::PointCloud);
         }
         #[cfg(feature = "npz")]
         if signature.starts_with(NpzReader::<R>::magic_bytes()) {
             let mut reader = BufReader::new(f);
-            let mut npz_reader = NpzReader::new(&mut reader)?;
-            return npz_reader.read();
+            return NpzReader::new(&mut reader)
+                .and_then(|mut npz_reader| npz_reader.read())
+                .map_err(WebSplatError::PointCloud);
+        }
+        // .splat files have no header, any file with a multiple of the splat size is read as one
+        let size = f.seek(std::io::SeekFrom::End(0))?;
+        f.rewind()?;
+        if size > 0 && size % splat::SPLAT_SIZE == 0 {
+            return SplatReader::new(f)
+                .and_then(|mut splat_reader| splat_reader.read())
+                .map_err(WebSplatError::PointCloud);
+        }
+        return Err(WebSplatError::UnknownFormat);
+    }
+
+    /// loads a point cloud file or a (possibly encrypted) container
+    pub fn load_with_options<R: Read + Seek>(
+        mut f: R,
+        options: &LoadOptions,
+    ) -> Result<Self, WebSplatError> {
+        let _span = tracing::info_span!("load_point_cloud").entered();
+        let start = Instant::now();
+        let mut signature: [u8; 4] = [0; 4];
+        f.read_exact(&mut signature)?;
+        f.rewind()?;
+        let mut pc = if codec::is_framed(&signature) {
+            Self::load_container(f, options.key.as_ref())?
+        } else {
+            Self::load(f)?
+        };
+        let check_cancelled = || options.cancellation.check().map_err(anyhow::Error::from);
+        if let Some(crop) = &options.crop {
+            pc.crop(crop).map_err(WebSplatError::PointCloud)?;
+        }
+        if let Some(filter) = options.filter_3d.as_ref().filter(|f| !f.cameras.is_empty()) {
+            check_cancelled()?;
+            if let Err(err) = pc.apply_3d_filter(&filter.cameras, filter.size) {
+                log::warn!("failed to apply 3D filter: {:?}", err);
+            }
+        }
+        check_cancelled()?;
+        if options.lod {
+            pc.build_lod().map_err(WebSplatError::PointCloud)?;
+        }
+        check_cancelled()?;
+        if options.low_memory && !pc.compressed() {
+            pc = pc.quantize().map_err(WebSplatError::PointCloud)?;
+        }
+        pc.half_precision = options.half_precision;
+        pc.packed_covariance = options.packed_covariance;
+        tracing::info!(
+            num_points = pc.num_points,
+            compressed = pc.compressed(),
+            duration_ms = start.elapsed().as_secs_f32() * 1000.,
+            "loaded point cloud"
+        );
+        Ok(pc)
+    }
+
+    /// loads the point cloud from a framed container
+    /// the file is either stored in a single frame or split into chunks
+    /// delta frames (see [GenericGaussianPointCloud::write_delta]) are applied in order after loading
+    fn load_container<R: Read>(
+        mut f: R,
+        key: Option<&EncryptionKey>,
+    ) -> Result<Self, WebSplatError> {
+        let mut data = Vec::new();
+        let mut packed_header = None;
+        let mut deltas = Vec::new();
+        for frame in encrypted::read_container(&mut f, key).map_err(WebSplatError::PointCloud)? {
+            match frame.kind {
+                codec::FrameKind::PointCloud => data = frame.payload,
+                codec::FrameKind::Chunk => data.extend(frame.payload),
+                codec::FrameKind::Quantized => packed_header = Some(frame.payload),
+                codec::FrameKind::Delta => deltas.push(
+                    delta::SnapshotDelta::from_frame(&frame).map_err(WebSplatError::PointCloud)?,
+                ),
+                kind => log::debug!("skipping {:?} frame in container", kind),
+            }
+        }
+        if data.is_empty() {
+            return Err(WebSplatError::PointCloud(anyhow::anyhow!(
+                "container does not contain a point cloud"
+            )));
+        }
+        let mut pc = match packed_header {
+            Some(header) => Self::from_packed(&header, &data).map_err(WebSplatError::PointCloud)?,
+            None => Self::load(Cursor::new(data))?,
+        };
+        for delta in &deltas {
+            pc.apply_delta(delta).map_err(WebSplatError::PointCloud)?;
+        }
+        if !deltas.is_empty() {
+            log::info!("applied {} snapshot deltas", deltas.len());
         }
-        return Err(anyhow::anyhow!("Unknown file format"));
+        Ok(pc)
     }
 
     fn new(
@@ -71,18 +235,13 @@ impl GenericGaussianPointCloud {
         covars: Option<Vec<Covariance3D>>,
         quantization: Option<GaussianQuantization>,
     ) -> Self {
+        let positions: Vec<Point3<f32>> = gaussians.iter().map(|g| g.xyz).collect();
         let mut bbox: Aabb<f32> = Aabb::zeroed();
-        for v in &gaussians {
-            bbox.grow(&v.xyz.map(|x| x.to_f32()));
+        if let Some(points_bbox) = simd::bounding_box(&positions) {
+            bbox.grow_union(&points_bbox);
         }
 
-        let (center, mut up) = plane_from_points(
-            gaussians
-                .iter()
-                .map(|g| g.xyz.cast().unwrap())
-                .collect::<Vec<Point3<f32>>>()
-                .as_slice(),
-        );
+        let (center, mut up) = plane_from_points(&positions);
 
         if bbox.radius() < 10. {
             up = None;
@@ -95,16 +254,19 @@ impl GenericGaussianPointCloud {
             kernel_size,
             mip_splatting,
             background_color,
+            metadata: None,
             covars,
             quantization,
             up: up,
             center,
             aabb: bbox,
             compressed: false,
+            lod: None,
+            half_precision: false,
+            packed_covariance: false,
         }
     }
 
-    #[cfg(feature = "npz")]
     fn new_compressed(
         gaussians: Vec<GaussianCompressed>,
         sh_coefs: Vec<u8>,
@@ -116,18 +278,13 @@ impl GenericGaussianPointCloud {
         covars: Option<Vec<Covariance3D>>,
         quantization: Option<GaussianQuantization>,
     ) -> Self {
+        let positions = simd::points_to_f32(gaussians.iter().map(|g| g.xyz));
         let mut bbox: Aabb<f32> = Aabb::unit();
-        for v in &gaussians {
-            bbox.grow(&v.xyz.map(|x| x.to_f32()));
+        if let Some(points_bbox) = simd::bounding_box(&positions) {
+            bbox.grow_union(&points_bbox);
         }
 
-        let (center, mut up) = plane_from_points(
-            gaussians
-                .iter()
-                .map(|g| g.xyz.cast().unwrap())
-                .collect::<Vec<Point3<f32>>>()
-                .as_slice(),
-        );
+        let (center, mut up) = plane_from_points(&positions);
 
         if bbox.radius() < 10. {
             up = None;
@@ -140,12 +297,16 @@ impl GenericGaussianPointCloud {
             kernel_size,
             mip_splatting,
             background_color,
+            metadata: None,
             covars,
             quantization,
             up: up,
             center,
             aabb: bbox,
             compressed: true,
+            lod: None,
+            half_precision: false,
+            packed_covariance: false,
         }
     }
 
@@ -159,9 +320,9 @@ impl GenericGaussianPointCloud {
 
     pub fn gaussians_compressed(&self) -> anyhow::Result<&[GaussianCompressed]> {
         if self.compressed {
-            Err(anyhow::anyhow!("Gaussians are compressed"))
-        } else {
             Ok(bytemuck::cast_slice(&self.gaussians))
+        } else {
+            Err(anyhow::anyhow!("Gaussians are not compressed"))
         }
     }
 
@@ -173,6 +334,70 @@ impl GenericGaussianPointCloud {
         &self.gaussians
     }
 
+    /// size of one splat in the gpu buffer
+    /// `layout` is ignored for compressed point clouds
+    pub fn gpu_gaussian_size(&self, layout: GaussianLayout) -> usize {
+        if self.compressed {
+            mem::size_of::<GaussianCompressed>()
+        } else {
+            layout.size()
+        }
+    }
+
+    /// gpu buffer contents of the splats in `range`
+    pub fn gpu_gaussian_buffer(
+        &self,
+        range: Range<usize>,
+        layout: GaussianLayout,
+    ) -> Cow<'_, [u8]> {
+        match self.gaussians() {
+            Ok(gaussians) if layout.packed_covariance.is_some() || !layout.half_precision => {
+                let mut data = Vec::with_capacity(range.len() * layout.size());
+                for g in &gaussians[range] {
+                    let pos_opacity = [g.xyz.x, g.xyz.y, g.xyz.z, g.opacity];
+                    data.extend_from_slice(bytemuck::bytes_of(&pos_opacity));
+                    match layout.packed_covariance {
+                        Some(log_scale_range) => data.extend_from_slice(bytemuck::bytes_of(
+                            &quantize::pack_covariance(&g.cov, log_scale_range),
+                        )),
+                        None => data.extend_from_slice(bytemuck::bytes_of(&g.cov.map(f16::to_f32))),
+                    }
+                }
+                Cow::Owned(data)
+            }
+            _ => {
+                let size = self.gpu_gaussian_size(layout);
+                Cow::Borrowed(&self.gaussians[range.start * size..range.end * size])
+            }
+        }
+    }
+
+    /// size of the sh coefficients buffer on the gpu
+    /// `layout` is ignored for compressed point clouds
+    pub fn gpu_sh_buffer_size(&self, layout: GaussianLayout) -> usize {
+        if self.compressed {
+            self.sh_coefs.len()
+        } else {
+            self.num_points * layout.sh_size()
+        }
+    }
+
+    /// gpu buffer contents of the sh coefficients of the splats in `range`
+    /// compressed point clouds index their coefficients, so all of them are returned
+    pub fn gpu_sh_buffer(&self, range: Range<usize>, layout: GaussianLayout) -> Cow<'_, [u8]> {
+        if self.compressed {
+            return Cow::Borrowed(&self.sh_coefs);
+        }
+        let size = mem::size_of::<[[f16; 3]; 16]>();
+        let coefs = &self.sh_coefs[range.start * size..range.end * size];
+        if layout.half_precision {
+            Cow::Borrowed(coefs)
+        } else {
+            let coefs: Vec<f32> = bytemuck::cast_slice::<_, f16>(coefs).to_f32_vec();
+            Cow::Owned(bytemuck::cast_slice(&coefs).to_vec())
+        }
+    }
+
     pub fn compressed(&self) -> bool {
         self.compressed
     }
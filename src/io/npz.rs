@@ -6,13 +6,9 @@ use half::f16;
 use image::EncodableLayout;
 use npyz::npz::{self, NpzArchive};
 
-#[cfg(target_arch = "wasm32")]
-use instant::Instant;
-#[cfg(not(target_arch = "wasm32"))]
-use std::time::Instant;
-
 use crate::{
     pointcloud::{Covariance3D, GaussianCompressed, GaussianQuantization, Quantization},
+    time::Instant,
     utils::{build_cov, sh_deg_from_num_coefs, sh_num_coefficients},
 };
 
@@ -0,0 +1,109 @@
+use std::mem::size_of;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    codec::{Frame, FrameKind},
+    metadata::Metadata,
+    GenericGaussianPointCloud,
+};
+use crate::pointcloud::{Covariance3D, GaussianCompressed, GaussianQuantization};
+
+/// header of a quantized point cloud in a container
+/// the splat data follows in chunk frames:
+/// | quantization | gaussians | covariances | sh coefficients |
+#[derive(Debug, Serialize, Deserialize)]
+struct PackedHeader {
+    num_points: usize,
+    num_covars: usize,
+    sh_deg: u32,
+    kernel_size: Option<f32>,
+    mip_splatting: Option<bool>,
+    background_color: Option<[f32; 3]>,
+    metadata: Option<Metadata>,
+}
+
+impl GenericGaussianPointCloud {
+    /// converts the point cloud into container frames with the quantized splat data split into chunks
+    /// uncompressed point clouds are quantized first
+    /// `chunk_size` is the maximum payload size of a chunk frame in bytes
+    pub fn to_packed_frames(&self, chunk_size: usize) -> anyhow::Result<Vec<Frame>> {
+        if !self.compressed {
+            return self.quantize()?.to_packed_frames(chunk_size);
+        }
+        let (Some(quantization), Some(covars)) = (&self.quantization, &self.covars) else {
+            return Err(anyhow::anyhow!("compressed point cloud is not quantized"));
+        };
+        let header = PackedHeader {
+            num_points: self.num_points,
+            num_covars: covars.len(),
+            sh_deg: self.sh_deg,
+            kernel_size: self.kernel_size,
+            mip_splatting: self.mip_splatting,
+            background_color: self.background_color,
+            metadata: self.metadata.clone(),
+        };
+
+        let mut data = Vec::with_capacity(
+            size_of::<GaussianQuantization>()
+                + self.gaussians.len()
+                + covars.len() * size_of::<Covariance3D>()
+                + self.sh_coefs.len(),
+        );
+        data.extend_from_slice(bytemuck::bytes_of(quantization));
+        data.extend_from_slice(&self.gaussians);
+        data.extend_from_slice(bytemuck::cast_slice(covars));
+        data.extend_from_slice(&self.sh_coefs);
+
+        let mut frames = vec![Frame::new(
+            FrameKind::Quantized,
+            serde_json::to_vec(&header)?,
+        )];
+        frames.extend(
+            data.chunks(chunk_size.max(1))
+                .map(|c| Frame::new(FrameKind::Chunk, c.to_vec())),
+        );
+        Ok(frames)
+    }
+
+    /// reads a point cloud written by [GenericGaussianPointCloud::to_packed_frames]
+    pub(crate) fn from_packed(header: &[u8], data: &[u8]) -> anyhow::Result<Self> {
+        let header: PackedHeader = serde_json::from_slice(header)?;
+        let mut rest = data;
+        let mut take = |n: usize| {
+            if rest.len() < n {
+                return Err(anyhow::anyhow!("packed point cloud is truncated"));
+            }
+            let (a, b) = rest.split_at(n);
+            rest = b;
+            Ok(a)
+        };
+        let quantization: GaussianQuantization =
+            bytemuck::pod_read_unaligned(take(size_of::<GaussianQuantization>())?);
+        let gaussians: Vec<GaussianCompressed> =
+            read_unaligned(take(header.num_points * size_of::<GaussianCompressed>())?);
+        let covars: Vec<Covariance3D> =
+            read_unaligned(take(header.num_covars * size_of::<Covariance3D>())?);
+        let sh_coefs = rest.to_vec();
+
+        let mut pc = Self::new_compressed(
+            gaussians,
+            sh_coefs,
+            header.sh_deg,
+            header.num_points,
+            header.kernel_size,
+            header.mip_splatting,
+            header.background_color,
+            Some(covars),
+            Some(quantization),
+        );
+        pc.metadata = header.metadata;
+        Ok(pc)
+    }
+}
+
+fn read_unaligned<T: bytemuck::Pod>(data: &[u8]) -> Vec<T> {
+    data.chunks_exact(size_of::<T>())
+        .map(bytemuck::pod_read_unaligned)
+        .collect()
+}
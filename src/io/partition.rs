@@ -0,0 +1,147 @@
+//! partitioning of the splats into spatially separated parts, e.g. the rooms of a building scan
+//! the parts are the connected components of the solid cells of the density grid
+
+use std::collections::VecDeque;
+
+use super::{DensityGrid, GenericGaussianPointCloud};
+use crate::pointcloud::Aabb;
+
+/// at most this many partitions are kept, the largest ones
+pub const MAX_PARTITIONS: usize = 32;
+/// components with fewer splats than this fraction of all splats are not partitions
+const MIN_FRACTION: f32 = 0.01;
+/// the components grow this many cells into the non-solid cells around them
+/// so the sparse splats at their surface belong to them
+const GROW_CELLS: usize = 2;
+/// label of the splats that belong to no partition, they are never hidden
+const NO_PARTITION: u16 = u16::MAX;
+const NO_COMPONENT: u32 = u32::MAX;
+
+#[derive(Debug, Clone)]
+pub struct Partition {
+    pub num_splats: usize,
+    pub aabb: Aabb<f32>,
+}
+
+/// spatially separated parts of a point cloud
+/// parts that touch each other (e.g. rooms sharing a wall without a gap) form one partition
+#[derive(Debug, Clone)]
+pub struct Partitions {
+    /// sorted by the number of splats, largest first
+    pub parts: Vec<Partition>,
+    /// partition of every splat
+    labels: Vec<u16>,
+}
+
+impl Partitions {
+    /// hidden splats for [crate::PointCloud::set_hidden] with one visibility flag per partition
+    /// splats without a partition and partitions without a flag are visible
+    pub fn hidden_splats(&self, visible: &[bool]) -> Vec<bool> {
+        self.labels
+            .iter()
+            .map(|l| *l != NO_PARTITION && !visible.get(*l as usize).copied().unwrap_or(true))
+            .collect()
+    }
+}
+
+impl GenericGaussianPointCloud {
+    /// assigns the splats to the connected components of the solid cells of `grid`
+    pub fn partitions(&self, grid: &DensityGrid) -> Partitions {
+        let positions = self.positions();
+        let [nx, ny, nz] = grid.resolution;
+        let neighbors = |i: usize| {
+            let (x, y, z) = (i % nx, (i / nx) % ny, i / (nx * ny));
+            [
+                (x > 0).then(|| i - 1),
+                (x + 1 < nx).then(|| i + 1),
+                (y > 0).then(|| i - nx),
+                (y + 1 < ny).then(|| i + nx),
+                (z > 0).then(|| i - nx * ny),
+                (z + 1 < nz).then(|| i + nx * ny),
+            ]
+            .into_iter()
+            .flatten()
+        };
+
+        // flood fill of the solid cells, cells that share a face are connected
+        let mut components = vec![NO_COMPONENT; grid.solid.len()];
+        let mut num_components = 0;
+        let mut queue = VecDeque::new();
+        for start in 0..grid.solid.len() {
+            if !grid.solid[start] || components[start] != NO_COMPONENT {
+                continue;
+            }
+            components[start] = num_components;
+            queue.push_back(start);
+            while let Some(i) = queue.pop_front() {
+                for n in neighbors(i) {
+                    if grid.solid[n] && components[n] == NO_COMPONENT {
+                        components[n] = num_components;
+                        queue.push_back(n);
+                    }
+                }
+            }
+            num_components += 1;
+        }
+        let mut front: Vec<usize> = (0..components.len())
+            .filter(|i| components[*i] != NO_COMPONENT)
+            .collect();
+        for _ in 0..GROW_CELLS {
+            let mut next = Vec::new();
+            for i in front {
+                for n in neighbors(i) {
+                    if components[n] == NO_COMPONENT {
+                        components[n] = components[i];
+                        next.push(n);
+                    }
+                }
+            }
+            front = next;
+        }
+
+        let component_of = |i: usize| {
+            grid.cell(positions[i])
+                .map(|c| components[c])
+                .filter(|c| *c != NO_COMPONENT)
+        };
+        let mut counts = vec![0usize; num_components as usize];
+        for i in 0..positions.len() {
+            if let Some(c) = component_of(i) {
+                counts[c as usize] += 1;
+            }
+        }
+
+        // the largest components become the partitions
+        let min_splats = ((positions.len() as f32 * MIN_FRACTION) as usize).max(1);
+        let mut order: Vec<usize> = (0..counts.len())
+            .filter(|c| counts[*c] >= min_splats)
+            .collect();
+        order.sort_by_key(|c| std::cmp::Reverse(counts[*c]));
+        order.truncate(MAX_PARTITIONS);
+        let mut partition_of = vec![NO_PARTITION; counts.len()];
+        for (p, c) in order.iter().enumerate() {
+            partition_of[*c] = p as u16;
+        }
+
+        let mut bounds: Vec<Option<Aabb<f32>>> = vec![None; order.len()];
+        let labels = (0..positions.len())
+            .map(|i| {
+                let label = component_of(i).map_or(NO_PARTITION, |c| partition_of[c as usize]);
+                if let Some(aabb) = bounds.get_mut(label as usize) {
+                    aabb.get_or_insert(Aabb::new(positions[i], positions[i]))
+                        .grow(&positions[i]);
+                }
+                label
+            })
+            .collect();
+        let parts = order
+            .iter()
+            .zip(bounds)
+            .map(|(c, aabb)| Partition {
+                num_splats: counts[*c],
+                aabb: aabb.unwrap_or(self.aabb),
+            })
+            .collect();
+        Partitions { parts, labels }
+    }
+}
@@ -9,10 +9,10 @@ use cgmath::{InnerSpace, Point3, Quaternion, Vector3};
 
 use crate::{
     pointcloud::Gaussian,
-    utils::{build_cov, sh_deg_from_num_coefs, sigmoid},
+    utils::{build_cov, sh_deg_from_num_coefs, sigmoid, simd},
 };
 
-use super::{GenericGaussianPointCloud, PointCloudReader};
+use super::{GenericGaussianPointCloud, Metadata, PointCloudReader};
 
 pub struct PlyReader<R: Read + Seek> {
     header: ply_rs::ply::Header,
@@ -91,11 +91,11 @@ impl<R: io::Read + io::Seek> PlyReader<R> {
 
         return Ok((
             Gaussian {
-                xyz: Point3::from(pos).cast().unwrap(),
-                opacity: f16::from_f32(opacity),
-                cov: cov.map(|x| f16::from_f32(x)),
+                xyz: Point3::from(pos),
+                opacity,
+                cov: simd::f32_to_f16(&cov),
             },
-            sh.map(|x| x.map(|y| f16::from_f32(y))),
+            sh.map(|x| simd::f32_to_f16(&x)),
         ));
     }
 
@@ -182,7 +182,7 @@ impl<R: io::Read + io::Seek> PointCloudReader for PlyReader<R> {
                 }
             }
         };
-        return Ok(GenericGaussianPointCloud::new(
+        let mut pc = GenericGaussianPointCloud::new(
             gaussians,
             sh_coefs,
             self.sh_deg,
@@ -192,7 +192,9 @@ impl<R: io::Read + io::Seek> PointCloudReader for PlyReader<R> {
             self.background_color,
             None,
             None,
-        ));
+        );
+        pc.metadata = Metadata::from_ply_comments(&self.header.comments);
+        return Ok(pc);
     }
 
     fn magic_bytes() -> &'static [u8] {
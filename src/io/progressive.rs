@@ -0,0 +1,46 @@
+use half::f16;
+
+use super::GenericGaussianPointCloud;
+use crate::pointcloud::{Covariance3D, Gaussian, GaussianCompressed};
+
+/// determinant of a symmetric 3x3 matrix stored as (xx, xy, xz, yy, yz, zz)
+fn det(c: &[f16; 6]) -> f32 {
+    let [a, b, c, d, e, f] = c.map(|v| v.to_f32());
+    a * (d * f - e * e) - b * (b * f - e * c) + c * (b * e - d * c)
+}
+
+impl GenericGaussianPointCloud {
+    /// reorders the splats so that large and opaque splats come first
+    /// uploading the splats in this order shows a coarse version of the scene early
+    pub fn sort_coarse_to_fine(&mut self) -> anyhow::Result<()> {
+        if self.compressed {
+            let quantization = self.quantization.unwrap_or_default();
+            let covars = self.covars.as_deref().unwrap_or_default();
+            let importance = |g: &GaussianCompressed| {
+                let opacity = (g.opacity as i32 - quantization.opacity.zero_point) as f32
+                    * quantization.opacity.scale;
+                let scale = (g.scale_factor as i32 - quantization.scaling_factor.zero_point) as f32
+                    * quantization.scaling_factor.scale;
+                let volume = covars
+                    .get(g.geometry_idx as usize)
+                    .map_or(0., |c: &Covariance3D| det(&c.0).max(0.).sqrt());
+                opacity * (3. * scale).exp() * volume
+            };
+            let mut gaussians = self.gaussians_compressed()?.to_vec();
+            gaussians.sort_by(|a, b| importance(b).total_cmp(&importance(a)));
+            self.gaussians = bytemuck::cast_slice(&gaussians).to_vec();
+        } else {
+            let importance = |g: &Gaussian| g.opacity * det(&g.cov).max(0.).sqrt();
+            let gaussians = self.gaussians()?;
+            let mut order: Vec<usize> = (0..gaussians.len()).collect();
+            order.sort_by(|a, b| importance(&gaussians[*b]).total_cmp(&importance(&gaussians[*a])));
+
+            let sh_coefs: &[[[f16; 3]; 16]] = bytemuck::cast_slice(&self.sh_coefs);
+            let (gaussians, sh_coefs): (Vec<Gaussian>, Vec<[[f16; 3]; 16]>) =
+                order.iter().map(|i| (gaussians[*i], sh_coefs[*i])).unzip();
+            self.gaussians = bytemuck::cast_slice(&gaussians).to_vec();
+            self.sh_coefs = bytemuck::cast_slice(&sh_coefs).to_vec();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use std::f32::consts::SQRT_2;
+
+use half::f16;
+
+use super::GenericGaussianPointCloud;
+use crate::{
+    pointcloud::{Covariance3D, GaussianCompressed, GaussianQuantization, Quantization},
+    utils::{math::decompose_cov, sh_num_coefficients},
+};
+
+/// bits per component of the packed rotations and scales
+const PACKED_BITS: u32 = 10;
+const PACKED_MAX: u32 = (1 << PACKED_BITS) - 1;
+/// largest ratio between the log scales of the biggest and smallest splat (e^16)
+/// degenerated splats would otherwise waste most of the packed scale range
+const MAX_LOG_SCALE_RANGE: f32 = 16.;
+
+/// 8 bit quantization that maps [min,max] to [-127,127]
+fn quantization_for_range((min, max): (f32, f32)) -> Quantization {
+    let scale = ((max - min) / 254.).max(f32::EPSILON);
+    Quantization::new(-127 - (min / scale).round() as i32, scale)
+}
+
+pub(super) fn quantize(value: f32, q: &Quantization) -> i8 {
+    ((value / q.scale).round() as i32 + q.zero_point).clamp(-127, 127) as i8
+}
+
+pub(super) fn dequantize(value: i8, q: &Quantization) -> f32 {
+    (value as i32 - q.zero_point) as f32 * q.scale
+}
+
+/// the covariance is stored as cov / s² with s = exp(log_scale)
+pub(super) fn log_scale(cov: [f32; 6]) -> f32 {
+    let max = cov.iter().map(|c| c.abs()).fold(1e-12, f32::max);
+    0.5 * max.ln()
+}
+
+/// the shader reads the coefficients as u32 and always fetches two words
+pub(super) fn pad_sh_coefs(sh_coefs: &mut Vec<u8>) {
+    sh_coefs.resize(sh_coefs.len().next_multiple_of(4) + 4, 0);
+}
+
+fn value_range(values: impl Iterator<Item = f32>) -> (f32, f32) {
+    let (min, max) = values.fold((f32::MAX, f32::MIN), |(min, max), v| {
+        (min.min(v), max.max(v))
+    });
+    if min > max {
+        (0., 0.)
+    } else {
+        (min, max)
+    }
+}
+
+impl GenericGaussianPointCloud {
+    /// converts the point cloud into the quantized layout used by compressed point clouds
+    /// sh coefficients, opacity and covariance scale are stored as 8 bit values and dequantized in the shader,
+    /// the normalized covariances are kept as f16
+    pub fn quantize(&self) -> anyhow::Result<Self> {
+        let gaussians = self.gaussians()?;
+        let sh_coefs: &[[[f16; 3]; 16]] = bytemuck::cast_slice(self.sh_coefs_buffer());
+        let n = sh_num_coefficients(self.sh_deg) as usize;
+
+        let log_scales: Vec<f32> = gaussians
+            .iter()
+            .map(|g| log_scale(g.cov.map(f16::to_f32)))
+            .collect();
+
+        let quantization = GaussianQuantization {
+            color_dc: quantization_for_range(value_range(
+                sh_coefs.iter().flat_map(|c| c[0]).map(f16::to_f32),
+            )),
+            color_rest: quantization_for_range(value_range(
+                sh_coefs
+                    .iter()
+                    .flat_map(|c| c[1..n].iter().flatten())
+                    .map(|v| v.to_f32()),
+            )),
+            opacity: quantization_for_range((0., 1.)),
+            scaling_factor: quantization_for_range(value_range(log_scales.iter().copied())),
+        };
+
+        let mut covars = Vec::with_capacity(gaussians.len());
+        let quantized: Vec<GaussianCompressed> = gaussians
+            .iter()
+            .zip(&log_scales)
+            .enumerate()
+            .map(|(i, (g, log_scale))| {
+                let scale_factor = quantize(*log_scale, &quantization.scaling_factor);
+                let s = dequantize(scale_factor, &quantization.scaling_factor).exp();
+                covars.push(Covariance3D(
+                    g.cov.map(|c| f16::from_f32(c.to_f32() / (s * s))),
+                ));
+                GaussianCompressed {
+                    xyz: g.xyz.map(f16::from_f32),
+                    opacity: quantize(g.opacity, &quantization.opacity),
+                    scale_factor,
+                    geometry_idx: i as u32,
+                    sh_idx: i as u32,
+                }
+            })
+            .collect();
+
+        let mut sh_quantized: Vec<u8> = Vec::with_capacity(sh_coefs.len() * n * 3 + 8);
+        for coefs in sh_coefs {
+            for (j, c) in coefs[..n].iter().enumerate() {
+                let q = if j == 0 {
+                    &quantization.color_dc
+                } else {
+                    &quantization.color_rest
+                };
+                sh_quantized.extend(c.map(|v| quantize(v.to_f32(), q) as u8));
+            }
+        }
+        pad_sh_coefs(&mut sh_quantized);
+
+        let mut pc = Self::new_compressed(
+            quantized,
+            sh_quantized,
+            self.sh_deg,
+            self.num_points,
+            self.kernel_size,
+            self.mip_splatting,
+            self.background_color,
+            Some(covars),
+            Some(quantization),
+        );
+        pc.aabb = self.aabb;
+        pc.center = self.center;
+        pc.up = self.up;
+        pc.metadata = self.metadata.clone();
+        // the splats keep their order, so the level of detail hierarchy stays valid
+        pc.lod = self.lod.clone();
+        Ok(pc)
+    }
+}
+
+impl GenericGaussianPointCloud {
+    /// range of the log scales of all splats, see [pack_covariance]
+    pub(crate) fn log_scale_range(&self) -> anyhow::Result<(f32, f32)> {
+        let (min, max) = value_range(self.gaussians()?.iter().flat_map(|g| {
+            let (_, scale) = decompose_cov(g.cov.map(f16::to_f32));
+            <[f32; 3]>::from(scale).map(|s| s.max(f32::MIN_POSITIVE).ln())
+        }));
+        Ok((min.max(max - MAX_LOG_SCALE_RANGE), max))
+    }
+}
+
+/// packs a covariance into a rotation and log scale with [PACKED_BITS] per component
+/// the rotation stores the index of the largest quaternion component and the other three
+/// components (smallest three), the log scale is mapped to [min, max]
+/// decoded in preprocess.wgsl
+pub(crate) fn pack_covariance(cov: &[f16; 6], (min, max): (f32, f32)) -> [u32; 2] {
+    let (rotation, scale) = decompose_cov(cov.map(f16::to_f32));
+    let q = [rotation.v.x, rotation.v.y, rotation.v.z, rotation.s];
+    let largest = (0..4)
+        .max_by(|a, b| q[*a].abs().total_cmp(&q[*b].abs()))
+        .unwrap();
+    // q and -q are the same rotation, so the largest component is always positive
+    let sign = q[largest].signum();
+    let mut packed_rotation = (largest as u32) << (3 * PACKED_BITS);
+    let smallest = (0..4).filter(|i| *i != largest).map(|i| q[i] * sign);
+    for (i, v) in smallest.enumerate() {
+        // the smaller components are within [-1/sqrt(2), 1/sqrt(2)]
+        let unorm = (v * SQRT_2 * 0.5 + 0.5).clamp(0., 1.);
+        packed_rotation |=
+            ((unorm * PACKED_MAX as f32).round() as u32) << ((2 - i as u32) * PACKED_BITS);
+    }
+
+    let mut packed_scale = 0;
+    for (i, s) in <[f32; 3]>::from(scale).into_iter().enumerate() {
+        let unorm =
+            ((s.max(f32::MIN_POSITIVE).ln() - min) / (max - min).max(f32::EPSILON)).clamp(0., 1.);
+        packed_scale |= ((unorm * PACKED_MAX as f32).round() as u32) << (i as u32 * PACKED_BITS);
+    }
+    [packed_rotation, packed_scale]
+}
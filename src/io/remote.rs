@@ -0,0 +1,403 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::task::{CancellableReader, CancellationToken};
+
+use super::codec::{self, Frame};
+
+/// number of attempts per chunk before giving up
+const MAX_RETRIES: u32 = 3;
+
+/// largest chunk size accepted from a manifest, a chunk is held in memory while it is downloaded
+const MAX_CHUNK_SIZE: u64 = 256 * 1024 * 1024;
+
+/// optional manifest served next to a file (`<url>.chunks.json`)
+/// lists the crc32 checksum of every chunk of the file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkManifest {
+    pub chunk_size: u64,
+    pub size: u64,
+    pub checksums: Vec<u32>,
+}
+
+impl ChunkManifest {
+    /// computes the manifest for a local file
+    pub fn from_reader<R: Read>(mut reader: R, chunk_size: u64) -> anyhow::Result<Self> {
+        let mut checksums = Vec::new();
+        let mut buffer = vec![0u8; chunk_size as usize];
+        let mut size = 0;
+        loop {
+            let n = read_full(&mut reader, &mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            checksums.push(crc32fast::hash(&buffer[..n]));
+            size += n as u64;
+        }
+        Ok(Self {
+            chunk_size,
+            size,
+            checksums,
+        })
+    }
+
+    /// checks that the chunks cover the whole file
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.chunk_size == 0 || self.chunk_size > MAX_CHUNK_SIZE {
+            return Err(anyhow::anyhow!("invalid chunk size {}", self.chunk_size));
+        }
+        let num_chunks = self.size.div_ceil(self.chunk_size);
+        if self.checksums.len() as u64 != num_chunks {
+            return Err(anyhow::anyhow!(
+                "expected {} checksums for {} bytes, got {}",
+                num_chunks,
+                self.size,
+                self.checksums.len()
+            ));
+        }
+        Ok(())
+    }
+
+    fn chunk_range(&self, i: usize) -> (u64, u64) {
+        let start = i as u64 * self.chunk_size;
+        (start, (start + self.chunk_size).min(self.size))
+    }
+}
+
+/// identifies the version of a remote file (`ETag` and `Last-Modified` headers)
+/// stored next to the cached file to detect files that changed on the server
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct CacheValidator {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl CacheValidator {
+    fn from_response(resp: &ureq::Response) -> Self {
+        Self {
+            etag: resp.header("etag").map(str::to_string),
+            last_modified: resp.header("last-modified").map(str::to_string),
+        }
+    }
+
+    fn load(path: &Path) -> Option<Self> {
+        serde_json::from_slice(&fs::read(path).ok()?).ok()
+    }
+
+    fn save(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    /// the server did not send any validator, so changes cannot be detected
+    fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// compares the etags if both are known, otherwise the modification dates
+    fn matches(&self, other: &Self) -> bool {
+        match (&self.etag, &other.etag) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.last_modified.is_some() && self.last_modified == other.last_modified,
+        }
+    }
+}
+
+/// downloads files over http(s) into a local cache
+/// interrupted downloads are resumed with range requests
+#[derive(Debug, Clone)]
+pub struct RemoteLoader {
+    cache_dir: PathBuf,
+    /// headers sent with every request (e.g. authorization)
+    headers: Vec<(String, String)>,
+    /// stops running downloads, the partial file is kept and resumed by the next fetch
+    token: CancellationToken,
+}
+
+impl RemoteLoader {
+    pub fn new(cache_dir: PathBuf) -> Self {
+        Self {
+            cache_dir,
+            headers: Vec::new(),
+            token: CancellationToken::new(),
+        }
+    }
+
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.token = token;
+    }
+
+    pub fn add_header(&mut self, name: &str, value: &str) {
+        self.headers.push((name.to_string(), value.to_string()));
+    }
+
+    /// sends `Authorization: Bearer <token>` with every request
+    pub fn set_auth_token(&mut self, token: &str) {
+        self.headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case("authorization"));
+        self.add_header("Authorization", &format!("Bearer {token}"));
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        self.headers
+            .iter()
+            .fold(ureq::request(method, url), |req, (name, value)| {
+                req.set(name, value)
+            })
+    }
+
+    fn get(&self, url: &str) -> ureq::Request {
+        self.request("GET", url)
+    }
+
+    /// asks the server for the current version of the file
+    /// returns `None` if the server cannot be reached (e.g. offline)
+    fn fetch_validator(&self, url: &str) -> Option<CacheValidator> {
+        match self.request("HEAD", url).call() {
+            Ok(resp) => Some(CacheValidator::from_response(&resp)),
+            Err(err) => {
+                log::warn!("failed to check {url} for changes: {err}");
+                None
+            }
+        }
+    }
+
+    pub fn default_cache_dir() -> PathBuf {
+        std::env::temp_dir().join("web-splat-cache")
+    }
+
+    pub fn is_url(path: &str) -> bool {
+        path.starts_with("http://") || path.starts_with("https://")
+    }
+
+    /// path of the cached file for the given url
+    /// the query is ignored, so signed urls with a new signature still hit the cache
+    pub fn cache_path(&self, url: &str) -> PathBuf {
+        let url = url.split(['?', '#']).next().unwrap_or(url);
+        let name = url
+            .rsplit('/')
+            .next()
+            .filter(|n| !n.is_empty())
+            .unwrap_or("download");
+        self.cache_dir
+            .join(format!("{:08x}_{}", crc32fast::hash(url.as_bytes()), name))
+    }
+
+    /// downloads the file (or the missing part of it) and returns the opened cached file
+    /// the cached file is only used if it is still the version on the server
+    pub fn fetch(&self, url: &str) -> anyhow::Result<File> {
+        fs::create_dir_all(&self.cache_dir)?;
+        let path = self.cache_path(url);
+        let partial = path.with_extension("part");
+        let meta = path.with_extension("meta");
+
+        let validator = self.fetch_validator(url);
+        // a partial or cached file belongs to a different version of the file if the validators differ
+        let outdated = match &validator {
+            Some(validator) if !validator.is_empty() => {
+                !CacheValidator::load(&meta).is_some_and(|cached| cached.matches(validator))
+            }
+            // without a validator the cache is trusted
+            _ => false,
+        };
+
+        if path.exists() {
+            if !outdated {
+                log::info!("using cached file {:?}", path);
+                return Ok(File::open(path)?);
+            }
+            log::info!("{url} changed on the server, downloading it again");
+            fs::remove_file(&path)?;
+        }
+
+        let manifest = self.fetch_manifest(url);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&partial)?;
+        if outdated && file.metadata()?.len() > 0 {
+            log::info!("discarding partial download of an outdated version of {url}");
+            file.set_len(0)?;
+        }
+        if let Some(validator) = &validator {
+            validator.save(&meta)?;
+        }
+
+        match &manifest {
+            Some(manifest) => self.download_chunked(url, &mut file, manifest)?,
+            None => self.download_resume(url, &mut file)?,
+        }
+        drop(file);
+        fs::rename(&partial, &path)?;
+        Ok(File::open(path)?)
+    }
+
+    fn fetch_manifest(&self, url: &str) -> Option<ChunkManifest> {
+        // the manifest is expected next to the file, the query (e.g. a signature) is kept
+        let manifest_url = match url.split_once('?') {
+            Some((path, query)) => format!("{path}.chunks.json?{query}"),
+            None => format!("{url}.chunks.json"),
+        };
+        match self.get(&manifest_url).call() {
+            Ok(resp) => match resp
+                .into_json::<ChunkManifest>()
+                .map_err(anyhow::Error::from)
+                .and_then(|manifest| manifest.validate().map(|_| manifest))
+            {
+                Ok(manifest) => Some(manifest),
+                Err(err) => {
+                    log::warn!("invalid chunk manifest {manifest_url}: {err}");
+                    None
+                }
+            },
+            Err(_) => None,
+        }
+    }
+
+    /// continues a download at the end of the partial file
+    /// framed files (see [codec]) are verified frame by frame, so a download is resumed after the last intact frame
+    fn download_resume(&self, url: &str, file: &mut File) -> anyhow::Result<()> {
+        let mut offset = file.seek(SeekFrom::End(0))?;
+        if offset > 0 && is_framed_file(file)? {
+            // the last frame of an interrupted download is usually incomplete
+            offset = valid_frames_len(file)?;
+            file.set_len(offset)?;
+            file.seek(SeekFrom::Start(offset))?;
+        }
+        if offset > 0 {
+            log::info!("resuming download of {url} at {offset} bytes");
+        }
+        let resp = match self
+            .get(url)
+            .set("Range", &format!("bytes={offset}-"))
+            .call()
+        {
+            Ok(resp) => resp,
+            // range starts at the end of the file, so we already have everything
+            Err(ureq::Error::Status(416, _)) if offset > 0 => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        if resp.status() != 206 && offset > 0 {
+            // server does not support range requests, start over
+            log::warn!("server ignored range request, restarting download");
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+        }
+        std::io::copy(
+            &mut CancellableReader::new(resp.into_reader(), self.token.clone()),
+            file,
+        )?;
+
+        if is_framed_file(file)? {
+            let size = file.seek(SeekFrom::End(0))?;
+            let valid = valid_frames_len(file)?;
+            if valid != size {
+                // keep the intact frames, the next fetch downloads the rest again
+                file.set_len(valid)?;
+                return Err(anyhow::anyhow!(
+                    "corrupted frame at byte {} of {}",
+                    valid,
+                    url
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// downloads all chunks that are missing or corrupted in the partial file
+    fn download_chunked(
+        &self,
+        url: &str,
+        file: &mut File,
+        manifest: &ChunkManifest,
+    ) -> anyhow::Result<()> {
+        file.set_len(manifest.size)?;
+        let mut buffer = vec![0u8; manifest.chunk_size as usize];
+        for i in 0..manifest.checksums.len() {
+            self.token.check()?;
+            let (start, end) = manifest.chunk_range(i);
+            let chunk = &mut buffer[..(end - start) as usize];
+
+            // check if chunk is already present from a previous download
+            file.seek(SeekFrom::Start(start))?;
+            if read_full(file, chunk)? == chunk.len()
+                && crc32fast::hash(chunk) == manifest.checksums[i]
+            {
+                continue;
+            }
+
+            let mut attempt = 0;
+            loop {
+                attempt += 1;
+                let resp = self
+                    .get(url)
+                    .set("Range", &format!("bytes={}-{}", start, end - 1))
+                    .call()?;
+                if resp.status() != 206 {
+                    return Err(anyhow::anyhow!(
+                        "server does not support range requests (status {})",
+                        resp.status()
+                    ));
+                }
+                let n = read_full(
+                    &mut CancellableReader::new(resp.into_reader(), self.token.clone()),
+                    chunk,
+                )?;
+                if n == chunk.len() && crc32fast::hash(chunk) == manifest.checksums[i] {
+                    break;
+                }
+                if attempt >= MAX_RETRIES {
+                    return Err(anyhow::anyhow!(
+                        "checksum mismatch for chunk {} of {} after {} attempts",
+                        i,
+                        url,
+                        attempt
+                    ));
+                }
+                log::warn!("checksum mismatch for chunk {i}, retrying");
+            }
+            file.seek(SeekFrom::Start(start))?;
+            file.write_all(chunk)?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// checks if the file starts with a frame header
+fn is_framed_file(file: &mut File) -> std::io::Result<bool> {
+    let mut magic = [0u8; 4];
+    file.seek(SeekFrom::Start(0))?;
+    let n = read_full(file, &mut magic)?;
+    Ok(codec::is_framed(&magic[..n]))
+}
+
+/// length of the leading frames of the file that are complete and have a valid checksum
+fn valid_frames_len(file: &mut File) -> anyhow::Result<u64> {
+    let mut reader = BufReader::new(&mut *file);
+    reader.seek(SeekFrom::Start(0))?;
+    let mut len = 0;
+    while Frame::read(&mut reader).is_ok() {
+        len = reader.stream_position()?;
+    }
+    Ok(len)
+}
+
+/// reads until the buffer is full or the reader is exhausted
+fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut n = 0;
+    while n < buffer.len() {
+        match reader.read(&mut buffer[n..])? {
+            0 => break,
+            m => n += m,
+        }
+    }
+    Ok(n)
+}
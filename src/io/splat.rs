@@ -0,0 +1,104 @@
+//! reader for the `.splat` format of the antimatter15 web viewer
+//! every splat is stored in 32 bytes: position (3 x f32), scale (3 x f32), rgba color (4 x u8) and rotation (4 x u8, wxyz)
+//! the file has no header, so it is detected by its size (see [GenericGaussianPointCloud::load])
+
+use std::io::{self, BufReader, Read, Seek};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use cgmath::{InnerSpace, Point3, Quaternion, Vector3};
+use half::f16;
+
+use crate::{
+    pointcloud::Gaussian,
+    utils::{build_cov, simd},
+};
+
+use super::{GenericGaussianPointCloud, PointCloudReader};
+
+/// size of a splat in bytes
+pub const SPLAT_SIZE: u64 = 32;
+
+/// zeroth order sh coefficient
+const SH_C0: f32 = 0.28209479;
+
+pub struct SplatReader<R: Read + Seek> {
+    reader: BufReader<R>,
+    num_points: usize,
+}
+
+impl<R: Read + Seek> SplatReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, anyhow::Error> {
+        let size = reader.seek(io::SeekFrom::End(0))?;
+        reader.rewind()?;
+        if size == 0 || size % SPLAT_SIZE != 0 {
+            return Err(anyhow::anyhow!(
+                "file size {size} is not a multiple of the splat size {SPLAT_SIZE}"
+            ));
+        }
+        Ok(Self {
+            reader: BufReader::new(reader),
+            num_points: (size / SPLAT_SIZE) as usize,
+        })
+    }
+
+    fn read_splat(&mut self) -> anyhow::Result<(Gaussian, [[f16; 3]; 16])> {
+        let mut pos = [0.; 3];
+        self.reader.read_f32_into::<LittleEndian>(&mut pos)?;
+        let mut scale = [0.; 3];
+        self.reader.read_f32_into::<LittleEndian>(&mut scale)?;
+        let mut color = [0u8; 4];
+        self.reader.read_exact(&mut color)?;
+        let mut rot = [0u8; 4];
+        self.reader.read_exact(&mut rot)?;
+
+        // the rotation is quantized to [-1, 1]
+        let [w, x, y, z] = rot.map(|v| (v as f32 - 128.) / 128.);
+        let rot = Quaternion::new(w, x, y, z).normalize();
+        let cov = build_cov(rot, Vector3::from(scale));
+
+        // the colors are stored after the sh evaluation, the file only contains the view independent color
+        let mut sh = [[0.; 3]; 16];
+        sh[0] = [0, 1, 2].map(|i| (color[i] as f32 / 255. - 0.5) / SH_C0);
+
+        Ok((
+            Gaussian {
+                xyz: Point3::from(pos),
+                opacity: color[3] as f32 / 255.,
+                cov: simd::f32_to_f16(&cov),
+            },
+            sh.map(|x| simd::f32_to_f16(&x)),
+        ))
+    }
+}
+
+impl<R: Read + Seek> PointCloudReader for SplatReader<R> {
+    fn read(&mut self) -> Result<GenericGaussianPointCloud, anyhow::Error> {
+        let mut gaussians = Vec::with_capacity(self.num_points);
+        let mut sh_coefs = Vec::with_capacity(self.num_points);
+        for _ in 0..self.num_points {
+            let (g, s) = self.read_splat()?;
+            gaussians.push(g);
+            sh_coefs.push(s);
+        }
+        Ok(GenericGaussianPointCloud::new(
+            gaussians,
+            sh_coefs,
+            0,
+            self.num_points,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ))
+    }
+
+    /// the format has no header
+    fn magic_bytes() -> &'static [u8] {
+        &[]
+    }
+
+    fn file_ending() -> &'static str {
+        "splat"
+    }
+}
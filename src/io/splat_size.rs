@@ -0,0 +1,235 @@
+use cgmath::{Matrix, Matrix3, Matrix4, Point3, Vector2};
+
+use super::GenericGaussianPointCloud;
+use crate::{
+    camera::{Camera, PerspectiveCamera},
+    culling::Culler,
+    pointcloud::{Aabb, Gaussian, GaussianCompressed},
+    renderer::DEFAULT_KERNEL_SIZE,
+};
+
+/// distribution of the projected splat sizes
+#[derive(Debug, Clone)]
+pub struct SplatSizeStats {
+    /// sorted radii in pixels
+    radii: Vec<f32>,
+    /// number of splats that are not visible from any camera
+    pub num_invisible: usize,
+}
+
+impl SplatSizeStats {
+    /// `radii` contains the projected radius of every splat, 0 for splats that are never visible
+    pub fn new(radii: &[f32]) -> Self {
+        let mut sorted: Vec<f32> = radii.iter().copied().filter(|r| *r > 0.).collect();
+        sorted.sort_by(f32::total_cmp);
+        Self {
+            num_invisible: radii.len() - sorted.len(),
+            radii: sorted,
+        }
+    }
+
+    pub fn num_visible(&self) -> usize {
+        self.radii.len()
+    }
+
+    /// radius in pixels below which `p` (0 to 1) of the visible splats are
+    pub fn percentile(&self, p: f32) -> f32 {
+        if self.radii.is_empty() {
+            return 0.;
+        }
+        let i = ((self.radii.len() - 1) as f32 * p.clamp(0., 1.)).round() as usize;
+        self.radii[i]
+    }
+
+    /// fraction of the visible splats with a radius smaller than `radius`
+    pub fn fraction_below(&self, radius: f32) -> f32 {
+        if self.radii.is_empty() {
+            return 0.;
+        }
+        self.radii.partition_point(|r| *r < radius) as f32 / self.radii.len() as f32
+    }
+
+    /// number of splats per bin, bins are [0, 1), [1, 2), [2, 4), ... pixels
+    pub fn histogram(&self, num_bins: usize) -> Vec<(f32, usize)> {
+        let mut bins: Vec<(f32, usize)> = (0..num_bins)
+            .map(|i| (if i == 0 { 0. } else { (1 << (i - 1)) as f32 }, 0))
+            .collect();
+        for r in &self.radii {
+            let i = if *r < 1. {
+                0
+            } else {
+                (r.log2().floor() as usize + 1).min(num_bins - 1)
+            };
+            bins[i].1 += 1;
+        }
+        bins
+    }
+
+    /// suggests a decimation threshold
+    /// splats smaller than half a pixel in all views barely contribute to the image,
+    /// the threshold is lowered so that at most `max_fraction` of the visible splats are removed
+    pub fn suggest_threshold(&self, max_fraction: f32) -> f32 {
+        0.5f32.min(self.percentile(max_fraction))
+    }
+}
+
+/// splat projected into screen space, see preprocess.wgsl
+pub(super) struct ProjectedSplat {
+    /// center in pixels (y pointing down)
+    pub center: Vector2<f32>,
+    /// 2D covariance (xx, xy, yy) in pixels including the dilation kernel
+    pub cov: [f32; 3],
+}
+
+impl ProjectedSplat {
+    /// half extent of the rendered quad
+    pub fn radius(&self) -> f32 {
+        let [a, b, c] = self.cov;
+        let mid = 0.5 * (a + c);
+        let lambda1 = mid + (((a - c) / 2.).powi(2) + b * b).sqrt();
+        2. * (2. * lambda1).sqrt()
+    }
+}
+
+/// cpu version of the splat projection done in the preprocess shader
+pub(super) struct SplatProjector {
+    culler: Culler,
+    view_proj: Matrix4<f32>,
+    view: Matrix4<f32>,
+    w: Matrix3<f32>,
+    focal: Vector2<f32>,
+    viewport: Vector2<f32>,
+    kernel_size: f32,
+}
+
+impl SplatProjector {
+    pub fn new(
+        camera: &PerspectiveCamera,
+        viewport: Vector2<u32>,
+        aabb: &Aabb<f32>,
+        kernel_size: f32,
+    ) -> Self {
+        let mut camera = *camera;
+        camera.projection.resize(viewport.x, viewport.y);
+        camera.fit_near_far(aabb);
+        let view = camera.view_matrix();
+        Self {
+            culler: Culler::new(&camera, None),
+            view_proj: camera.proj_matrix() * view,
+            view,
+            w: Matrix3::from_cols(view.x.truncate(), view.y.truncate(), view.z.truncate())
+                .transpose(),
+            focal: camera.projection.focal(viewport),
+            viewport: viewport.cast().unwrap(),
+            kernel_size,
+        }
+    }
+
+    /// returns None if the splat is culled
+    pub fn project(&self, xyz: Point3<f32>, cov: &[f32; 6]) -> Option<ProjectedSplat> {
+        if !self.culler.point_visible(xyz) {
+            return None;
+        }
+        let c = (self.view * xyz.to_homogeneous()).truncate();
+        // jacobian of the perspective projection (columns)
+        // normalized device coordinates point up and pixel coordinates down, so y is negated like in the shader
+        let j = Matrix3::new(
+            self.focal.x / c.z,
+            0.,
+            -(self.focal.x * c.x) / (c.z * c.z),
+            0.,
+            -self.focal.y / c.z,
+            (self.focal.y * c.y) / (c.z * c.z),
+            0.,
+            0.,
+            0.,
+        );
+        let vrk = Matrix3::new(
+            cov[0], cov[1], cov[2], cov[1], cov[3], cov[4], cov[2], cov[4], cov[5],
+        );
+        let t = self.w * j;
+        let cov2d = t.transpose() * vrk * t;
+
+        let pos2d = self.view_proj * xyz.to_homogeneous();
+        let ndc = pos2d.truncate() / pos2d.w;
+        Some(ProjectedSplat {
+            center: Vector2::new(
+                (ndc.x * 0.5 + 0.5) * self.viewport.x,
+                (0.5 - ndc.y * 0.5) * self.viewport.y,
+            ),
+            cov: [
+                cov2d[0][0] + self.kernel_size,
+                cov2d[0][1],
+                cov2d[1][1] + self.kernel_size,
+            ],
+        })
+    }
+}
+
+impl GenericGaussianPointCloud {
+    /// largest screen space radius (in pixels) of every splat over all cameras
+    /// the radius is 0 for splats that are culled in all views
+    pub fn projected_radii(
+        &self,
+        cameras: &[PerspectiveCamera],
+        viewport: Vector2<u32>,
+    ) -> anyhow::Result<Vec<f32>> {
+        let splats = self.covariances(1)?;
+        let kernel_size = self.kernel_size.unwrap_or(DEFAULT_KERNEL_SIZE);
+        let mut radii = vec![0f32; splats.len()];
+        for camera in cameras {
+            let projector = SplatProjector::new(camera, viewport, &self.aabb, kernel_size);
+            for (radius, (xyz, cov)) in radii.iter_mut().zip(&splats) {
+                if let Some(splat) = projector.project(*xyz, cov) {
+                    *radius = radius.max(splat.radius());
+                }
+            }
+        }
+        Ok(radii)
+    }
+
+    /// removes all splats with a projected radius smaller than `min_radius` pixels
+    pub fn decimate(&mut self, radii: &[f32], min_radius: f32) -> anyhow::Result<()> {
+        let keep: Vec<bool> = radii.iter().map(|r| *r >= min_radius).collect();
+        let num_points = self.num_points;
+        self.retain(&keep)?;
+        log::info!(
+            "decimated point cloud from {} to {} points",
+            num_points,
+            self.num_points
+        );
+        Ok(())
+    }
+
+    /// positions and 3D covariances of every `step`-th splat
+    pub(super) fn covariances(&self, step: usize) -> anyhow::Result<Vec<(Point3<f32>, [f32; 6])>> {
+        if self.compressed {
+            let quantization = self.quantization.unwrap_or_default();
+            let covars = self.covars.as_deref().unwrap_or_default();
+            self.gaussians_compressed()?
+                .iter()
+                .step_by(step)
+                .map(|g: &GaussianCompressed| {
+                    let scale = ((g.scale_factor as i32 - quantization.scaling_factor.zero_point)
+                        as f32
+                        * quantization.scaling_factor.scale)
+                        .exp();
+                    let cov = covars
+                        .get(g.geometry_idx as usize)
+                        .ok_or(anyhow::anyhow!("invalid geometry index"))?;
+                    Ok((
+                        g.xyz.map(|v| v.to_f32()),
+                        cov.0.map(|v| v.to_f32() * scale * scale),
+                    ))
+                })
+                .collect()
+        } else {
+            Ok(self
+                .gaussians()?
+                .iter()
+                .step_by(step)
+                .map(|g: &Gaussian| (g.xyz, g.cov.map(|v| v.to_f32())))
+                .collect())
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use super::GenericGaussianPointCloud;
+use crate::pointcloud::Aabb;
+
+/// number of bins of the opacity histogram, the bins evenly divide [0, 1]
+const OPACITY_BINS: usize = 10;
+/// the scale histogram has one bin per power of ten between these exponents
+const MIN_SCALE_EXP: i32 = -4;
+const MAX_SCALE_EXP: i32 = 2;
+/// number of scales kept in [PointCloudStats::largest_scales]
+pub const MAX_LARGEST_SCALES: usize = 100_000;
+
+/// summary of a loaded point cloud, useful to spot bloated or degenerated training results
+#[derive(Debug, Clone)]
+pub struct PointCloudStats {
+    pub num_points: usize,
+    pub sh_deg: u32,
+    pub compressed: bool,
+    /// number of splats per opacity bin
+    pub opacity_histogram: [usize; OPACITY_BINS],
+    /// lower bound of the bin and number of splats per scale bin
+    /// the scale of a splat is the geometric mean of its standard deviations
+    pub scale_histogram: Vec<(f32, usize)>,
+    pub median_scale: f32,
+    /// scales of the largest splats in descending order, at most [MAX_LARGEST_SCALES]
+    pub largest_scales: Vec<f32>,
+    /// bytes of the decoded splats and sh coefficients
+    pub cpu_memory: usize,
+    pub aabb: Aabb<f32>,
+}
+
+impl GenericGaussianPointCloud {
+    pub fn stats(&self) -> anyhow::Result<PointCloudStats> {
+        let mut opacity_histogram = [0; OPACITY_BINS];
+        for o in self.opacities(1)? {
+            let i = (o.clamp(0., 1.) * OPACITY_BINS as f32) as usize;
+            opacity_histogram[i.min(OPACITY_BINS - 1)] += 1;
+        }
+
+        let mut scales: Vec<f32> = self
+            .covariances(1)?
+            .iter()
+            .map(|(_, c)| {
+                let det = c[0] * (c[3] * c[5] - c[4] * c[4]) - c[1] * (c[1] * c[5] - c[4] * c[2])
+                    + c[2] * (c[1] * c[4] - c[3] * c[2]);
+                det.max(0.).powf(1. / 6.)
+            })
+            .collect();
+        scales.sort_by(f32::total_cmp);
+        let mut scale_histogram: Vec<(f32, usize)> = (MIN_SCALE_EXP..MAX_SCALE_EXP)
+            .map(|e| (10f32.powi(e), 0))
+            .collect();
+        for s in &scales {
+            let i =
+                (s.log10().floor() as i32).clamp(MIN_SCALE_EXP, MAX_SCALE_EXP - 1) - MIN_SCALE_EXP;
+            scale_histogram[i as usize].1 += 1;
+        }
+
+        Ok(PointCloudStats {
+            num_points: self.num_points,
+            sh_deg: self.sh_deg,
+            compressed: self.compressed,
+            opacity_histogram,
+            scale_histogram,
+            median_scale: scales.get(scales.len() / 2).copied().unwrap_or(0.),
+            largest_scales: scales
+                .iter()
+                .rev()
+                .take(MAX_LARGEST_SCALES)
+                .copied()
+                .collect(),
+            cpu_memory: self.gaussians.len()
+                + self.sh_coefs.len()
+                + self
+                    .covars
+                    .as_ref()
+                    .map_or(0, |c| std::mem::size_of_val(c.as_slice())),
+            aabb: self.aabb,
+        })
+    }
+}
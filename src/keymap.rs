@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use winit::keyboard::KeyCode;
+
+/// actions that can be triggered with a hotkey
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    ToggleTrackingShot,
+    ToggleGallery,
+    ToggleInset,
+    ToggleUi,
+    ToggleHud,
+    ToggleCameraPath,
+    SeekBackward,
+    SeekForward,
+    SaveView,
+    /// triggered with alt
+    SaveViewToScene,
+    /// triggered with alt
+    Reload,
+    RandomCamera,
+    NearestCamera,
+    NextCamera,
+    PreviousCamera,
+    /// triggered with ctrl
+    Open,
+    Screenshot,
+    ToggleFullscreen,
+    /// stop or resume following the presenter of a sync session
+    ToggleSyncFollow,
+    /// adds a cubemap panorama at the camera position to the tour
+    CapturePanorama,
+    /// steps through the spherical harmonics degrees of the point cloud
+    CycleShDegree,
+    IncreaseScaling,
+    DecreaseScaling,
+    /// also triggered with [HELP_KEY]
+    ToggleHelp,
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    RollLeft,
+    RollRight,
+}
+
+/// toggles the help overlay in addition to the key bound to [Action::ToggleHelp]
+pub const HELP_KEY: KeyCode = KeyCode::F1;
+
+/// keys that are not part of the key map, shown in the help overlay
+#[cfg(feature = "ui")]
+pub const FIXED_BINDINGS: &[(&str, &str)] = &[
+    ("0 - 9", "jump to scene camera"),
+    ("left mouse", "orbit"),
+    ("right mouse", "pan"),
+    ("mouse wheel", "zoom"),
+];
+
+impl Action {
+    /// short description shown in the help overlay
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::ToggleTrackingShot => "start / stop tracking shot through saved views",
+            Action::ToggleGallery => "show / hide gallery",
+            Action::ToggleInset => "show / hide rear view",
+            Action::ToggleUi => "show / hide user interface",
+            Action::ToggleHud => "show / hide frame rate overlay",
+            Action::ToggleCameraPath => "play / pause camera path",
+            Action::SeekBackward => "seek animation backward",
+            Action::SeekForward => "seek animation forward",
+            Action::SaveView => "save current view",
+            Action::SaveViewToScene => "save current view to scene file",
+            Action::Reload => "reload point cloud and scene",
+            Action::RandomCamera => "jump to random scene camera",
+            Action::NearestCamera => "jump to nearest scene camera",
+            Action::NextCamera => "jump to next scene camera",
+            Action::PreviousCamera => "jump to previous scene camera",
+            Action::Open => "open file",
+            Action::Screenshot => "save screenshot",
+            Action::ToggleFullscreen => "toggle fullscreen",
+            Action::ToggleSyncFollow => "follow / stop following presenter",
+            Action::CapturePanorama => "capture panorama for virtual tour",
+            Action::CycleShDegree => "cycle spherical harmonics degree",
+            Action::IncreaseScaling => "enlarge splats",
+            Action::DecreaseScaling => "shrink splats",
+            Action::ToggleHelp => "show / hide this help",
+            Action::MoveForward => "move forward",
+            Action::MoveBackward => "move backward",
+            Action::MoveLeft => "move left",
+            Action::MoveRight => "move right",
+            Action::MoveUp => "move up",
+            Action::MoveDown => "move down",
+            Action::RollLeft => "roll left",
+            Action::RollRight => "roll right",
+        }
+    }
+
+    /// camera movement, handled by the [crate::CameraController] while the key is pressed
+    pub fn is_navigation(&self) -> bool {
+        matches!(
+            self,
+            Action::MoveForward
+                | Action::MoveBackward
+                | Action::MoveLeft
+                | Action::MoveRight
+                | Action::MoveUp
+                | Action::MoveDown
+                | Action::RollLeft
+                | Action::RollRight
+        )
+    }
+
+    /// actions that are only triggered while alt is pressed
+    pub fn needs_alt(&self) -> bool {
+        matches!(self, Action::SaveViewToScene | Action::Reload)
+    }
+
+    /// actions that are only triggered while ctrl is pressed
+    pub fn needs_ctrl(&self) -> bool {
+        matches!(self, Action::Open)
+    }
+}
+
+/// one or more keys of an action, a single key can be written without brackets in the config file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Keys {
+    One(KeyCode),
+    Many(Vec<KeyCode>),
+}
+
+/// maps actions to keys
+/// actions that are missing in the config file keep their default keys
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<Action, Keys>", into = "BTreeMap<Action, Keys>")]
+pub struct KeyMap {
+    keys: BTreeMap<Action, Vec<KeyCode>>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            keys: BTreeMap::from([
+                (Action::ToggleTrackingShot, vec![KeyCode::KeyT]),
+                (Action::ToggleGallery, vec![KeyCode::KeyG]),
+                (Action::ToggleInset, vec![KeyCode::KeyV]),
+                (Action::ToggleUi, vec![KeyCode::KeyU]),
+                (Action::ToggleHud, vec![KeyCode::KeyF]),
+                (Action::ToggleCameraPath, vec![KeyCode::KeyP]),
+                (Action::SeekBackward, vec![KeyCode::Comma]),
+                (Action::SeekForward, vec![KeyCode::Period]),
+                (Action::SaveView, vec![KeyCode::KeyC]),
+                (Action::SaveViewToScene, vec![KeyCode::KeyC]),
+                (Action::Reload, vec![KeyCode::KeyR]),
+                (Action::RandomCamera, vec![KeyCode::KeyR]),
+                (Action::NearestCamera, vec![KeyCode::KeyN]),
+                (Action::NextCamera, vec![KeyCode::PageUp]),
+                (Action::PreviousCamera, vec![KeyCode::PageDown]),
+                (Action::Open, vec![KeyCode::KeyO]),
+                (Action::Screenshot, vec![KeyCode::F12]),
+                (Action::ToggleFullscreen, vec![KeyCode::F11]),
+                (Action::ToggleSyncFollow, vec![KeyCode::KeyB]),
+                (Action::CapturePanorama, vec![KeyCode::KeyM]),
+                (Action::CycleShDegree, vec![KeyCode::KeyL]),
+                (Action::IncreaseScaling, vec![KeyCode::BracketRight]),
+                (Action::DecreaseScaling, vec![KeyCode::BracketLeft]),
+                (Action::ToggleHelp, vec![KeyCode::KeyH]),
+                (Action::MoveForward, vec![KeyCode::KeyW, KeyCode::ArrowUp]),
+                (
+                    Action::MoveBackward,
+                    vec![KeyCode::KeyS, KeyCode::ArrowDown],
+                ),
+                (Action::MoveLeft, vec![KeyCode::KeyA, KeyCode::ArrowLeft]),
+                (Action::MoveRight, vec![KeyCode::KeyD, KeyCode::ArrowRight]),
+                (Action::MoveUp, vec![KeyCode::Space]),
+                (Action::MoveDown, vec![KeyCode::ShiftLeft]),
+                (Action::RollLeft, vec![KeyCode::KeyQ]),
+                (Action::RollRight, vec![KeyCode::KeyE]),
+            ]),
+        }
+    }
+}
+
+impl From<BTreeMap<Action, Keys>> for KeyMap {
+    fn from(keys: BTreeMap<Action, Keys>) -> Self {
+        let mut map = Self::default();
+        map.keys.extend(keys.into_iter().map(|(action, keys)| {
+            let keys = match keys {
+                Keys::One(key) => vec![key],
+                Keys::Many(keys) => keys,
+            };
+            (action, keys)
+        }));
+        map
+    }
+}
+
+impl From<KeyMap> for BTreeMap<Action, Keys> {
+    fn from(map: KeyMap) -> Self {
+        map.keys
+            .into_iter()
+            .map(|(action, mut keys)| {
+                let keys = if keys.len() == 1 {
+                    Keys::One(keys.remove(0))
+                } else {
+                    Keys::Many(keys)
+                };
+                (action, keys)
+            })
+            .collect()
+    }
+}
+
+impl KeyMap {
+    /// first key of the action
+    pub fn key(&self, action: Action) -> Option<KeyCode> {
+        self.keys(action).first().copied()
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys.get(&action).map_or(&[], |k| k.as_slice())
+    }
+
+    /// replaces all keys of the action
+    pub fn set(&mut self, action: Action, key: KeyCode) {
+        self.keys.insert(action, vec![key]);
+    }
+
+    /// all actions with their keys, sorted by action
+    pub fn bindings(&self) -> impl Iterator<Item = (Action, &[KeyCode])> + '_ {
+        self.keys.iter().map(|(a, k)| (*a, k.as_slice()))
+    }
+
+    /// key combination of the action including modifiers, e.g. "Alt+C"
+    /// multiple keys are separated by " / "
+    pub fn shortcut(&self, action: Action) -> Option<String> {
+        let keys = self.keys(action);
+        if keys.is_empty() {
+            return None;
+        }
+        let key = keys
+            .iter()
+            .map(|k| key_name(*k))
+            .collect::<Vec<_>>()
+            .join(" / ");
+        Some(if action.needs_alt() {
+            format!("Alt+{key}")
+        } else if action.needs_ctrl() {
+            format!("Ctrl+{key}")
+        } else {
+            key
+        })
+    }
+
+    /// action bound to `key`, navigation actions are looked up with [KeyMap::navigation]
+    /// if alt is pressed, actions that need alt are preferred
+    pub fn action(&self, key: KeyCode, alt_pressed: bool, ctrl_pressed: bool) -> Option<Action> {
+        self.bound(key, alt_pressed, ctrl_pressed, false)
+            .or((key == HELP_KEY).then_some(Action::ToggleHelp))
+    }
+
+    /// camera movement bound to `key`
+    /// a key can move the camera and trigger another action at the same time
+    pub fn navigation(
+        &self,
+        key: KeyCode,
+        alt_pressed: bool,
+        ctrl_pressed: bool,
+    ) -> Option<Action> {
+        self.bound(key, alt_pressed, ctrl_pressed, true)
+    }
+
+    fn bound(
+        &self,
+        key: KeyCode,
+        alt_pressed: bool,
+        ctrl_pressed: bool,
+        navigation: bool,
+    ) -> Option<Action> {
+        let mut actions = self
+            .keys
+            .iter()
+            .filter(|(a, k)| {
+                k.contains(&key)
+                    && a.is_navigation() == navigation
+                    && (ctrl_pressed || !a.needs_ctrl())
+            })
+            .map(|(a, _)| *a);
+        if alt_pressed {
+            let candidates: Vec<Action> = actions.collect();
+            candidates
+                .iter()
+                .find(|a| a.needs_alt())
+                .or(candidates.iter().find(|a| !a.needs_alt()))
+                .copied()
+        } else {
+            actions.find(|a| !a.needs_alt())
+        }
+    }
+}
+
+/// readable name of a key, e.g. "H" for [KeyCode::KeyH]
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    name.strip_prefix("Key")
+        .or(name.strip_prefix("Digit"))
+        .unwrap_or(&name)
+        .to_string()
+}
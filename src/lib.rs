@@ -1,66 +1,422 @@
+//! 3D gaussian splatting viewer and renderer
+//!
+//! the viewer is opened with [Viewer] and configured with [RenderConfig],
+//! point clouds can be rendered offline with [GaussianRenderer] on a [WGPUContext]
+//! see [prelude] for the stable public api
+
+#[cfg(feature = "ui")]
 use std::{
     io::{Read, Seek},
     path::{Path, PathBuf},
     sync::Arc,
 };
 
-use image::Pixel;
-#[cfg(target_arch = "wasm32")]
-use instant::{Duration, Instant};
+#[cfg(feature = "ui")]
 use renderer::Display;
-#[cfg(not(target_arch = "wasm32"))]
-use std::time::{Duration, Instant};
-use wgpu::{util::DeviceExt, Backends, Extent3d};
-
-use cgmath::{Deg, EuclideanSpace, Point3, Quaternion, UlpsEq, Vector2, Vector3};
+#[cfg(feature = "ui")]
+use time::{Duration, Instant};
+use wgpu::Backends;
+#[cfg(feature = "ui")]
+use wgpu::{util::DeviceExt, Extent3d};
+
+#[cfg(feature = "ui")]
+use cgmath::{Deg, EuclideanSpace, Point3, Quaternion, Rotation3, UlpsEq, Vector2, Vector3};
+#[cfg(feature = "ui")]
 use egui::FullOutput;
+#[cfg(feature = "ui")]
 use num_traits::One;
+#[cfg(feature = "ui")]
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
+#[cfg(feature = "ui")]
 use utils::key_to_num;
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
 use utils::RingBuffer;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(feature = "ui", target_arch = "wasm32"))]
 use wasm_bindgen::prelude::wasm_bindgen;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+use winit::event_loop::ControlFlow;
+#[cfg(feature = "ui")]
 use winit::{
     dpi::PhysicalSize,
     event::{DeviceEvent, ElementState, Event, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
-    keyboard::{KeyCode, PhysicalKey},
-    window::{Window, WindowBuilder},
+    event_loop::EventLoop,
+    keyboard::PhysicalKey,
+    monitor::MonitorHandle,
+    window::{Fullscreen, Window, WindowBuilder},
 };
 
 mod animation;
+#[cfg(feature = "ui")]
 mod ui;
-pub use animation::{Animation, Sampler, TrackingShot, Transition};
+pub use animation::{Animation, ConstantSpeed, Lerp, Sampler, TrackingShot, Transition};
+#[cfg(feature = "ui")]
+mod bloom;
+#[cfg(feature = "ui")]
+pub use bloom::Bloom;
 mod camera;
 pub use camera::{Camera, PerspectiveCamera, PerspectiveProjection};
+mod camera_path;
+pub use camera_path::{write_srt, CameraPath, CameraPathKeyframe, Caption, PathInterpolation};
+#[cfg(feature = "ui")]
+mod composite;
+#[cfg(feature = "ui")]
+use composite::FeedCompositor;
+#[cfg(feature = "ui")]
+pub use composite::{FeedConfig, FeedMode};
+#[cfg(feature = "input")]
 mod controller;
+#[cfg(feature = "input")]
 pub use controller::CameraController;
+#[cfg(feature = "ui")]
+mod env_map;
+mod error;
+pub use error::WebSplatError;
+#[cfg(feature = "input")]
+mod keymap;
+#[cfg(feature = "input")]
+pub use keymap::{Action, KeyMap};
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+mod event_log;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+use event_log::EventLog;
+#[cfg(feature = "ui")]
+mod dynamic_resolution;
+#[cfg(feature = "ui")]
+pub use dynamic_resolution::DynamicResolution;
+#[cfg(feature = "ui")]
+mod exposure;
+#[cfg(feature = "ui")]
+pub use exposure::AutoExposure;
+pub mod culling;
+mod frame_arena;
+pub use frame_arena::{ArenaVec, FrameArena, FrameArenaStats};
+#[cfg(feature = "ui")]
+mod gallery;
+#[cfg(feature = "ui")]
+pub use gallery::{Gallery, GalleryEntry};
+#[cfg(feature = "ui")]
+mod grid;
+#[cfg(all(
+    feature = "native-menu",
+    any(target_os = "windows", target_os = "macos")
+))]
+mod native_menu;
+#[cfg(feature = "ui")]
+pub use grid::MAX_GRID_VIEWS;
+#[cfg(feature = "ui")]
+mod hints;
+mod oit;
 mod pointcloud;
-pub use pointcloud::PointCloud;
+pub use pointcloud::{Aabb, ChunkedUpload, Gaussian, PointCloud, ShStorage};
 
 pub mod io;
+pub mod prelude;
+#[cfg(all(feature = "python", not(target_arch = "wasm32")))]
+mod python;
 
+mod render_texture;
+pub use render_texture::RenderTexture;
 mod renderer;
-pub use renderer::{GaussianRenderer, SplattingArgs};
+#[cfg(feature = "ui")]
+use renderer::{create_multisampled_view, supported_sample_count};
+pub use renderer::{
+    ColorGrading, ColorSpace, DepthOfField, Fog, FogFalloff, GaussianRenderer, GlareFilter,
+    RenderMode, RenderSettings, Tonemapping, Transparency, MAX_CLIP_PLANES,
+};
+
+pub mod task;
+pub mod time;
 
 mod scene;
+#[cfg(not(target_arch = "wasm32"))]
+mod sequence;
+#[cfg(all(feature = "ui", target_arch = "wasm32"))]
+mod share;
+#[cfg(feature = "ui")]
+mod stereo;
+#[cfg(feature = "ui")]
+pub use stereo::{Stereo, StereoMode};
+mod stochastic;
+#[cfg(feature = "ui")]
+mod sync;
+#[cfg(all(feature = "openxr", target_arch = "wasm32"))]
+mod xr;
+#[cfg(feature = "ui")]
 use crate::utils::GPUStopwatch;
+#[cfg(not(target_arch = "wasm32"))]
+pub use sequence::PointCloudSequence;
+#[cfg(all(feature = "ui", target_arch = "wasm32"))]
+pub use share::SharePeer;
+#[cfg(feature = "ui")]
+pub use sync::SyncConfig;
 
-pub use self::scene::{Scene, SceneCamera, Split};
+pub use self::scene::{Scene, SceneCamera, SceneFormat, Split};
 
-pub mod gpu_rs;
+mod gpu_rs;
+#[cfg(feature = "ui")]
 mod ui_renderer;
 mod uniform;
+#[cfg(feature = "ui")]
+mod user_config;
+#[cfg(feature = "ui")]
+pub use user_config::{PresentMode, UserConfig};
 mod utils;
+pub use utils::{download_texture, math, simd, unpremultiply};
+mod watermark;
+pub use watermark::{Watermark, WatermarkPosition};
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+mod tour;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub use tour::{Station, Tour};
+#[cfg(all(feature = "watch", not(target_arch = "wasm32")))]
+mod watcher;
+#[cfg(all(feature = "watch", not(target_arch = "wasm32")))]
+pub use watcher::FolderWatcher;
+
+/// number of splats that are uploaded per frame with [RenderConfig::chunked_upload]
+#[cfg(feature = "ui")]
+const UPLOAD_CHUNK_SIZE: usize = 1 << 18;
+/// number of viewpoints proposed for a newly loaded point cloud
+#[cfg(feature = "ui")]
+const NUM_SUGGESTED_VIEWS: usize = 5;
+/// factor by which the splat scale hotkeys change the gaussian scaling
+#[cfg(feature = "ui")]
+const SCALING_STEP: f32 = 1.1;
+/// range of the render resolution relative to the window resolution
+#[cfg(feature = "ui")]
+pub(crate) const RENDER_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=2.;
+/// resolution of a cube face of captured panoramas
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+const PANORAMA_FACE_SIZE: u32 = 1024;
+
+#[cfg(feature = "ui")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FullscreenMode {
+    /// window that covers the whole monitor
+    #[default]
+    Borderless,
+    /// changes the video mode of the monitor
+    Exclusive,
+}
+
+#[cfg(feature = "ui")]
+impl std::str::FromStr for FullscreenMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "borderless" => Ok(Self::Borderless),
+            "exclusive" => Ok(Self::Exclusive),
+            _ => Err(anyhow::anyhow!("unknown fullscreen mode '{}'", s)),
+        }
+    }
+}
 
+#[cfg(feature = "ui")]
+impl FullscreenMode {
+    /// exclusive mode uses the video mode with the monitor resolution and the highest refresh rate
+    /// falls back to borderless if the monitor has no matching video mode
+    fn to_winit(self, monitor: Option<MonitorHandle>) -> Fullscreen {
+        match self {
+            FullscreenMode::Exclusive => {
+                let mode = monitor.as_ref().and_then(|m| {
+                    m.video_modes()
+                        .filter(|v| v.size() == m.size())
+                        .max_by_key(|v| (v.bit_depth(), v.refresh_rate_millihertz()))
+                });
+                match mode {
+                    Some(mode) => Fullscreen::Exclusive(mode),
+                    None => {
+                        log::warn!("no exclusive video mode found, using borderless fullscreen");
+                        Fullscreen::Borderless(monitor)
+                    }
+                }
+            }
+            FullscreenMode::Borderless => Fullscreen::Borderless(monitor),
+        }
+    }
+}
+
+#[cfg(feature = "ui")]
 pub struct RenderConfig {
     pub no_vsync: bool,
+    /// environment map behind the splats
+    /// equirectangular image (e.g. hdr or exr) or directory with the six faces of a cubemap
     pub skybox: Option<PathBuf>,
     pub hdr: bool,
+    /// storage backend for the sh coefficients
+    /// point clouds that exceed the storage buffer binding size always use textures
+    pub sh_storage: ShStorage,
+    /// camera path that is played back after startup
+    pub camera_path: Option<PathBuf>,
+    /// show the gallery of recently opened scenes at startup
+    pub show_gallery: bool,
+    /// reload the point cloud when the file changes on disk
+    pub watch: bool,
+    /// per-frame point cloud files of a dynamic capture
+    pub sequence: Vec<PathBuf>,
+    /// playback rate of the sequence in frames per second
+    pub sequence_fps: f32,
+    /// quantize sh coefficients and covariances to 8/16 bit when loading uncompressed point clouds
+    pub low_memory: bool,
+    /// build a level of detail hierarchy when loading point clouds
+    pub lod: bool,
+    /// store the covariances and sh coefficients as f16 on the gpu when loading uncompressed point clouds
+    pub half_precision: bool,
+    /// pack the covariances into a rotation and scale when loading uncompressed point clouds
+    pub packed_covariance: bool,
+    /// size of the Mip-Splatting 3D filter (e.g. [io::DEFAULT_FILTER_3D_SIZE])
+    /// requires a scene file with the training cameras
+    pub filter_3d: Option<f32>,
+    /// upload the splats over multiple frames after loading, sorted coarse to fine
+    pub chunked_upload: bool,
+    /// seed for all random features (e.g. random camera selection)
+    /// a random seed is used if not set
+    pub seed: Option<u64>,
+    /// index of the scene camera that is shown at startup
+    pub camera: Option<usize>,
+    /// limits the spherical harmonics degree used for rendering
+    pub max_sh_deg: Option<u32>,
+    /// crop file with the region of interest of the point cloud
+    pub crop: Option<PathBuf>,
+    /// reveal the point cloud at startup by growing the clipping box from its center to the bounding box
+    pub reveal: Option<Duration>,
+    /// external image that is composited with the splats
+    pub feed: Option<FeedConfig>,
+    /// initial window size, derived from the first scene camera if not set
+    pub window_size: Option<Vector2<u32>>,
+    /// open the window in fullscreen mode
+    /// F11 toggles between window and this mode (borderless if not set)
+    pub fullscreen: Option<FullscreenMode>,
+    /// clear color behind the splats
+    pub background_color: Option<wgpu::Color>,
+    /// clear the background with alpha zero and present with premultiplied alpha
+    /// so the window or canvas can be composited over other content
+    pub transparent: bool,
+    /// start with hidden user interface
+    pub no_gui: bool,
+    /// settings from the config file (controller, present mode and keys)
+    pub user_config: UserConfig,
+    /// file the user config was loaded from and is saved back to
+    pub user_config_path: Option<PathBuf>,
+    /// key for encrypted point cloud containers
+    pub encryption_key: Option<io::EncryptionKey>,
+    /// downloads point clouds that are opened or reloaded by url
+    #[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+    pub remote_loader: Option<io::remote::RemoteLoader>,
+    /// directory in which a json event log of the session is written (camera poses, settings, time per region)
+    pub event_log: Option<PathBuf>,
+    /// websocket room for camera sync with other viewers
+    pub sync: Option<SyncConfig>,
+    /// adapt the exposure to the rendered image, requires `hdr` or `tonemapping`
+    pub auto_exposure: Option<AutoExposure>,
+    /// suppress small clusters of very bright splats (e.g. floaters around light sources)
+    pub glare_filter: Option<GlareFilter>,
+    /// blur the parts of the scene that are not in focus
+    pub depth_of_field: Option<DepthOfField>,
+    /// glow around bright regions
+    pub bloom: Option<Bloom>,
+    /// darkening towards the image corners (0 to 1)
+    pub vignette: f32,
+    /// contrast adaptive sharpening of the rendered image (0 to 1)
+    pub sharpening: f32,
+    /// gamma, saturation and white balance of the presented colors
+    pub color_grading: ColorGrading,
+    /// distance fog that hides the far edge of large scenes
+    pub fog: Option<Fog>,
+    /// render a left and a right eye view for 3d displays or anaglyph glasses
+    pub stereo: Option<Stereo>,
+    /// scene cameras that are shown next to the current view in a grid (up to [MAX_GRID_VIEWS] - 1)
+    /// ignored in stereo mode
+    pub grid: Vec<usize>,
+    /// tone mapping of the rendered colors, renders into a 16 bit float target if set
+    pub tonemapping: Tonemapping,
+    /// color space in which the splats are blended
+    pub color_space: ColorSpace,
+    /// transfer function of the presented colors
+    pub output_color_space: ColorSpace,
+    /// debug view of the splats (see [RenderMode])
+    pub render_mode: RenderMode,
+    /// blending of overlapping splats (see [Transparency])
+    pub transparency: Transparency,
+    /// samples per pixel for multisample anti-aliasing of the splat edges (1 disables msaa)
+    /// reduced to the highest sample count the gpu supports
+    pub msaa: u32,
+    /// resolution of the splat rendering relative to the window (0.5 to 2)
+    /// lower values are faster, higher values supersample the image
+    pub render_scale: f32,
+    /// lower the render scale while the camera moves fast
+    pub dynamic_resolution: Option<DynamicResolution>,
+}
+
+#[cfg(feature = "ui")]
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            no_vsync: false,
+            skybox: None,
+            hdr: false,
+            sh_storage: ShStorage::default(),
+            camera_path: None,
+            show_gallery: false,
+            watch: false,
+            sequence: Vec::new(),
+            sequence_fps: 30.,
+            low_memory: false,
+            lod: false,
+            half_precision: false,
+            packed_covariance: false,
+            filter_3d: None,
+            chunked_upload: false,
+            seed: None,
+            camera: None,
+            max_sh_deg: None,
+            crop: None,
+            reveal: None,
+            feed: None,
+            window_size: None,
+            fullscreen: None,
+            background_color: None,
+            transparent: false,
+            no_gui: false,
+            user_config: UserConfig::default(),
+            user_config_path: None,
+            encryption_key: None,
+            #[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+            remote_loader: None,
+            event_log: None,
+            sync: None,
+            auto_exposure: None,
+            glare_filter: None,
+            depth_of_field: None,
+            bloom: None,
+            vignette: 0.,
+            sharpening: 0.,
+            color_grading: ColorGrading::default(),
+            fog: None,
+            stereo: None,
+            grid: Vec::new(),
+            tonemapping: Tonemapping::default(),
+            color_space: ColorSpace::default(),
+            output_color_space: ColorSpace::default(),
+            render_mode: RenderMode::default(),
+            transparency: Transparency::default(),
+            msaa: 1,
+            render_scale: 1.,
+            dynamic_resolution: None,
+        }
+    }
 }
 
+/// features requested by native contexts
+#[cfg(not(target_arch = "wasm32"))]
+const NATIVE_FEATURES: wgpu::Features = wgpu::Features::TIMESTAMP_QUERY
+    .union(wgpu::Features::TEXTURE_FORMAT_16BIT_NORM)
+    .union(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+    .union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS);
+
 pub struct WGPUContext {
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
@@ -68,7 +424,7 @@ pub struct WGPUContext {
 }
 
 impl WGPUContext {
-    pub async fn new_instance() -> Self {
+    pub async fn new_instance() -> Result<Self, WebSplatError> {
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: Backends::PRIMARY,
             ..Default::default()
@@ -77,20 +433,62 @@ impl WGPUContext {
         return WGPUContext::new(&instance, None).await;
     }
 
-    pub async fn new(instance: &wgpu::Instance, surface: Option<&wgpu::Surface<'static>>) -> Self {
+    pub async fn new(
+        instance: &wgpu::Instance,
+        surface: Option<&wgpu::Surface<'static>>,
+    ) -> Result<Self, WebSplatError> {
         let adapter = wgpu::util::initialize_adapter_from_env_or_default(instance, surface)
             .await
-            .unwrap();
+            .ok_or(WebSplatError::NoAdapter)?;
         log::info!("using {}", adapter.get_info().name);
 
         #[cfg(target_arch = "wasm32")]
         let required_features = wgpu::Features::default();
         #[cfg(not(target_arch = "wasm32"))]
-        let required_features = wgpu::Features::TIMESTAMP_QUERY
-            | wgpu::Features::TEXTURE_FORMAT_16BIT_NORM
-            | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
-            | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS;
+        let required_features = NATIVE_FEATURES;
+        Self::from_adapter(adapter, required_features).await
+    }
+
+    /// context on a software rasterizer (e.g. lavapipe or WARP) for headless machines without a gpu
+    /// falls back to the wgpu fallback adapter if no cpu device is found
+    /// features that the rasterizer does not support (e.g. timestamp queries) are not requested,
+    /// so the context is meant for offline rendering and not for the viewer
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn new_software() -> Result<Self, WebSplatError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: Backends::all(),
+            ..Default::default()
+        });
+        let cpu_adapter = instance
+            .enumerate_adapters(Backends::all())
+            .into_iter()
+            .find(|adapter| adapter.get_info().device_type == wgpu::DeviceType::Cpu);
+        let adapter = match cpu_adapter {
+            Some(adapter) => adapter,
+            None => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    force_fallback_adapter: true,
+                    ..Default::default()
+                })
+                .await
+                .ok_or(WebSplatError::NoAdapter)?,
+        };
+        log::info!("using software adapter {}", adapter.get_info().name);
 
+        let required_features = NATIVE_FEATURES & adapter.features();
+        if required_features != NATIVE_FEATURES {
+            log::warn!(
+                "software adapter does not support {:?}",
+                NATIVE_FEATURES - required_features
+            );
+        }
+        Self::from_adapter(adapter, required_features).await
+    }
+
+    async fn from_adapter(
+        adapter: wgpu::Adapter,
+        required_features: wgpu::Features,
+    ) -> Result<Self, WebSplatError> {
         let adapter_limits = adapter.limits();
 
         let (device, queue) = adapter
@@ -115,18 +513,18 @@ impl WGPUContext {
                 },
                 None,
             )
-            .await
-            .unwrap();
+            .await?;
 
-        Self {
+        Ok(Self {
             device,
             queue,
             adapter,
-        }
+        })
     }
 }
 
-pub struct WindowContext {
+#[cfg(feature = "ui")]
+pub(crate) struct WindowContext {
     wgpu_context: WGPUContext,
     surface: wgpu::Surface<'static>,
     config: wgpu::SurfaceConfiguration,
@@ -135,35 +533,120 @@ pub struct WindowContext {
 
     pc: PointCloud,
     pointcloud_file_path: Option<PathBuf>,
+    metadata: Option<io::Metadata>,
+    /// good viewpoints of the current point cloud, see [io::ViewSuggester::suggest]
+    suggested_views: Vec<io::SuggestedView>,
+    /// computes the suggested views of a new point cloud in the background
+    suggesting_views: Option<task::Task<Vec<io::SuggestedView>>>,
+    /// spatially separated parts of the current point cloud, e.g. the rooms of a building scan
+    partitions: Option<io::Partitions>,
+    /// visibility of the partitions
+    partition_visible: Vec<bool>,
+    /// occupancy of the current point cloud, keeps the camera from zooming through surfaces
+    density_grid: Option<io::DensityGrid>,
+    /// statistics of the current point cloud, computed when it is loaded
+    pc_stats: Option<io::PointCloudStats>,
+    gpu_class: hints::GpuClass,
+    /// the performance hints of the current point cloud were dismissed
+    hints_dismissed: bool,
     renderer: GaussianRenderer,
     animation: Option<(Animation<PerspectiveCamera>, bool)>,
+    clipping_box_animation: Option<Animation<Aabb<f32>>>,
+    chunked_upload: Option<ChunkedUpload>,
+    /// random number generator used by all random features, seeded for reproducible demos
+    rng: StdRng,
     controller: CameraController,
     scene: Option<Scene>,
     scene_file_path: Option<PathBuf>,
     current_view: Option<usize>,
     ui_renderer: ui_renderer::EguiWGPU,
     fps: f32,
+    /// cpu time spent on updating and recording a frame in ms (ema)
+    cpu_frame_time: f32,
     ui_visible: bool,
+    hud_visible: bool,
+    help_visible: bool,
+    fullscreen_mode: FullscreenMode,
+    present_mode: PresentMode,
+    /// frame rate cap in frames per second
+    max_fps: Option<f32>,
+    keys: KeyMap,
+    user_config: UserConfig,
+    user_config_path: Option<PathBuf>,
+    load_options: io::LoadOptions,
 
     #[cfg(not(target_arch = "wasm32"))]
     history: RingBuffer<(Duration, Duration, Duration)>,
+    /// transient cpu data of the current frame, the renderers have their own arenas
+    frame_arena: FrameArena,
     display: Display,
 
-    splatting_args: SplattingArgs,
+    splatting_args: RenderSettings,
+    /// the background is cleared with alpha zero instead of the background color
+    transparent: bool,
+    /// resolution of the display texture relative to the surface
+    render_scale: f32,
+    /// lowers the render scale while the camera moves
+    dynamic_resolution: Option<dynamic_resolution::MotionScaler>,
+    /// factor of the dynamic resolution applied to the render scale
+    motion_scale: f32,
+    /// only the n largest splats are drawn in the ellipsoid render modes
+    largest_splats: Option<usize>,
 
     saved_cameras: Vec<SceneCamera>,
-    #[cfg(feature = "video")]
-    cameras_save_path: String,
+    camera_path: Option<CameraPath>,
+    /// the running animation plays the camera path (and its captions)
+    camera_path_playing: bool,
+    gallery: Gallery,
+    gallery_visible: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    watcher: Option<watcher::FileWatcher>,
+    /// menu bar of the operating system, replaces the menus of the egui menu bar
+    #[cfg(all(
+        feature = "native-menu",
+        any(target_os = "windows", target_os = "macos")
+    ))]
+    native_menu: Option<native_menu::NativeMenu>,
+    /// point cloud that is loaded in the background
+    #[cfg(not(target_arch = "wasm32"))]
+    loading: Option<task::Task<LoadedPointCloud>>,
+    #[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+    remote_loader: Option<io::remote::RemoteLoader>,
+    #[cfg(not(target_arch = "wasm32"))]
+    event_log: Option<EventLog>,
+    /// panoramas captured in this session, opened on the first capture
+    #[cfg(not(target_arch = "wasm32"))]
+    tour: Option<Tour>,
+    /// automatic exposure, only available with hdr rendering
+    exposure: Option<exposure::ExposureController>,
+    #[cfg(not(target_arch = "wasm32"))]
+    sequence: Option<PointCloudSequence>,
+    sync: Option<sync::SyncSession>,
+    /// picture-in-picture rear view and its egui texture
+    inset: Option<(RenderTexture, egui::TextureId)>,
+    /// the window shows a left and a right eye view
+    stereo: Option<stereo::StereoCompositor>,
+    /// immersive VR session, the eyes of the stereo view are head tracked
+    #[cfg(all(feature = "openxr", target_arch = "wasm32"))]
+    xr: Option<xr::XrViewer>,
+    /// the window shows the current view next to the views of scene cameras
+    grid: Option<grid::ViewportGrid>,
+    /// renderers of the additional views of the stereo and grid modes
+    /// every view has its own renderer so all views can be rendered in the same frame
+    view_renderers: Vec<GaussianRenderer>,
+    feed: Option<FeedCompositor>,
     stopwatch: Option<GPUStopwatch>,
 }
 
+#[cfg(feature = "ui")]
 impl WindowContext {
     // Creating some of the wgpu types requires async code
     async fn new<R: Read + Seek>(
         window: Window,
         pc_file: R,
+        scene: Option<&Scene>,
         render_config: &RenderConfig,
-    ) -> anyhow::Result<Self> {
+    ) -> Result<Self, WebSplatError> {
         let mut size = window.inner_size();
         if size == PhysicalSize::new(0, 0) {
             size = PhysicalSize::new(800, 600);
@@ -175,7 +658,7 @@ impl WindowContext {
 
         let surface: wgpu::Surface = instance.create_surface(window.clone())?;
 
-        let wgpu_context = WGPUContext::new(&instance, Some(&surface)).await;
+        let wgpu_context = WGPUContext::new(&instance, Some(&surface)).await?;
 
         log::info!("device: {:?}", wgpu_context.adapter.get_info().name);
 
@@ -192,35 +675,119 @@ impl WindowContext {
             .unwrap_or(&surface_caps.formats[0])
             .clone();
 
-        let render_format = if render_config.hdr {
+        // linear colors need more precision in dark regions than 8 bits
+        let render_format = if render_config.hdr
+            || render_config.tonemapping != Tonemapping::None
+            || render_config.color_space == ColorSpace::Linear
+        {
             wgpu::TextureFormat::Rgba16Float
         } else {
             wgpu::TextureFormat::Rgba8Unorm
         };
 
+        let present_mode = surface_present_mode(
+            &surface_caps,
+            if render_config.no_vsync {
+                PresentMode::AutoNoVsync
+            } else {
+                render_config.user_config.present_mode
+            },
+        );
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
             width: size.width,
             height: size.height,
             desired_maximum_frame_latency: 2,
-            present_mode: if render_config.no_vsync {
-                wgpu::PresentMode::AutoNoVsync
-            } else {
-                wgpu::PresentMode::AutoVsync
-            },
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            present_mode: present_mode.into(),
+            alpha_mode: surface_alpha_mode(&surface_caps, render_config.transparent),
             view_formats: vec![surface_format.remove_srgb_suffix()],
         };
         surface.configure(&device, &config);
 
-        let pc_raw = io::GenericGaussianPointCloud::load(pc_file)?;
-        let pc = PointCloud::new(&device, pc_raw)?;
-        log::info!("loaded point cloud with {:} points", pc.num_points());
+        let load_options = io::LoadOptions {
+            crop: match &render_config.crop {
+                Some(path) => Some(io::CropRegion::load(path)?),
+                None => None,
+            },
+            low_memory: render_config.low_memory,
+            lod: render_config.lod,
+            filter_3d: render_config.filter_3d.map(|size| {
+                if scene.is_none() {
+                    log::warn!("3D filter requires a scene file with the training cameras");
+                }
+                io::Filter3D {
+                    size,
+                    cameras: scene.map(|s| s.cameras(None)).unwrap_or_default(),
+                }
+            }),
+            key: render_config.encryption_key.clone(),
+            half_precision: render_config.half_precision,
+            packed_covariance: render_config.packed_covariance,
+            ..Default::default()
+        };
+        let pc_raw = io::GenericGaussianPointCloud::load_with_options(pc_file, &load_options)?;
+        let metadata = pc_raw.metadata.clone();
+        let suggesting_views = suggest_views(&pc_raw);
+        let density_grid = density_grid(&pc_raw);
+        let partitions = partitions(&pc_raw, density_grid.as_ref());
+        let pc_stats = point_cloud_stats(&pc_raw);
+        let sh_buffer_size = pc_raw.gpu_sh_buffer_size(pointcloud::GaussianLayout {
+            half_precision: pc_raw.half_precision,
+            ..Default::default()
+        }) as u64;
+        let sh_storage = if !pc_raw.compressed()
+            && sh_buffer_size > device.limits().max_storage_buffer_binding_size as u64
+        {
+            log::info!(
+                "sh coefficients ({} bytes) exceed storage buffer binding size, using texture storage",
+                sh_buffer_size
+            );
+            ShStorage::Texture
+        } else {
+            render_config.sh_storage
+        };
+        let (pc, chunked_upload) = if render_config.chunked_upload {
+            let (pc, upload) =
+                ChunkedUpload::new(&device, &queue, pc_raw, sh_storage, UPLOAD_CHUNK_SIZE)?;
+            (pc, Some(upload))
+        } else {
+            (
+                PointCloud::new_with_sh_storage(&device, &queue, pc_raw, sh_storage)?,
+                None,
+            )
+        };
+        tracing::info!(
+            num_points = pc.num_points(),
+            sh_deg = pc.sh_deg(),
+            chunked_upload = chunked_upload.is_some(),
+            "uploaded point cloud"
+        );
 
-        let renderer =
-            GaussianRenderer::new(&device, &queue, render_format, pc.sh_deg(), pc.compressed())
-                .await;
+        let sample_count = supported_sample_count(
+            &wgpu_context.adapter,
+            &wgpu_context.device,
+            render_format,
+            render_config.msaa,
+        );
+        if sample_count != render_config.msaa.max(1) {
+            log::warn!(
+                "{}x msaa is not supported for {:?}, using {}x",
+                render_config.msaa,
+                render_format,
+                sample_count
+            );
+        }
+        let renderer = GaussianRenderer::new_multisampled(
+            &device,
+            &queue,
+            render_format,
+            sample_count,
+            pc.sh_deg(),
+            pc.compressed(),
+            pc.sh_storage(),
+        )
+        .await;
 
         let aabb = pc.bbox();
         let aspect = size.width as f32 / size.height as f32;
@@ -235,18 +802,34 @@ impl WindowContext {
             ),
         );
 
-        let mut controller = CameraController::new(0.1, 0.05);
+        let mut controller = CameraController::new(
+            render_config.user_config.controller_speed,
+            render_config.user_config.controller_sensitivity,
+        );
         controller.center = pc.center();
+        controller.zoom_collision = render_config.user_config.zoom_collision;
         // controller.up = pc.up;
         let ui_renderer = ui_renderer::EguiWGPU::new(device, surface_format, &window);
 
-        let display = Display::new(
+        let render_scale = render_config
+            .render_scale
+            .clamp(*RENDER_SCALE_RANGE.start(), *RENDER_SCALE_RANGE.end());
+        let render_size = scaled_size(size.width, size.height, render_scale);
+        let mut display = Display::new(
             device,
             render_format,
             surface_format.remove_srgb_suffix(),
-            size.width,
-            size.height,
+            sample_count,
+            render_size.x,
+            render_size.y,
         );
+        display.set_depth_of_field(queue, render_config.depth_of_field);
+        display.set_bloom(queue, render_config.bloom);
+        display.set_vignette(queue, render_config.vignette);
+        display.set_sharpening(queue, render_config.sharpening);
+        display.set_color_grading(queue, render_config.color_grading);
+        display.set_tonemapping(queue, render_config.tonemapping);
+        display.set_output_color_space(queue, render_config.output_color_space);
 
         let stopwatch = if cfg!(not(target_arch = "wasm32")) {
             Some(GPUStopwatch::new(device, Some(3)))
@@ -254,6 +837,66 @@ impl WindowContext {
             None
         };
 
+        let exposure = match render_config.auto_exposure {
+            Some(settings) if render_format == wgpu::TextureFormat::Rgba16Float => {
+                Some(exposure::ExposureController::new(device, settings))
+            }
+            Some(_) => {
+                log::warn!("auto exposure requires hdr rendering, ignoring it");
+                None
+            }
+            None => None,
+        };
+        let gpu_class = hints::GpuClass::detect(&wgpu_context.adapter.get_info());
+        let stereo = render_config.stereo.map(|stereo| {
+            stereo::StereoCompositor::new(
+                device,
+                queue,
+                stereo,
+                surface_format.remove_srgb_suffix(),
+                size.width,
+                size.height,
+            )
+        });
+        let grid_cameras = &render_config.grid[..render_config.grid.len().min(MAX_GRID_VIEWS - 1)];
+        let grid = (stereo.is_none() && !grid_cameras.is_empty()).then(|| {
+            grid::ViewportGrid::new(
+                device,
+                grid_cameras.to_vec(),
+                surface_format.remove_srgb_suffix(),
+                size.width,
+                size.height,
+            )
+        });
+        let num_views = match (&stereo, &grid) {
+            (Some(_), _) => 2,
+            (None, Some(grid)) => grid.num_views(),
+            (None, None) => 1,
+        };
+        let mut view_renderers = Vec::with_capacity(num_views - 1);
+        for _ in 1..num_views {
+            view_renderers.push(
+                GaussianRenderer::new_multisampled(
+                    device,
+                    queue,
+                    render_format,
+                    sample_count,
+                    pc.sh_deg(),
+                    pc.compressed(),
+                    pc.sh_storage(),
+                )
+                .await,
+            );
+        }
+
+        #[cfg(all(
+            feature = "native-menu",
+            any(target_os = "windows", target_os = "macos")
+        ))]
+        let native_menu = native_menu::NativeMenu::new(window.clone())
+            .map_err(|err| log::warn!("failed to create native menu: {:?}", err))
+            .ok();
+
         Ok(Self {
             wgpu_context,
             scale_factor: window.scale_factor() as f32,
@@ -261,74 +904,1029 @@ impl WindowContext {
             surface,
             config,
             renderer,
-            splatting_args: SplattingArgs {
+            splatting_args: RenderSettings {
                 camera: view_camera,
-                viewport: Vector2::new(size.width, size.height),
+                viewport: render_size,
                 gaussian_scaling: 1.,
-                max_sh_deg: pc.sh_deg(),
+                max_sh_deg: render_config
+                    .max_sh_deg
+                    .map_or(pc.sh_deg(), |deg| deg.min(pc.sh_deg())),
                 show_env_map: false,
                 mip_splatting: None,
                 kernel_size: None,
+                opacity_threshold: 0.,
+                color_space: render_config.color_space,
+                render_mode: render_config.render_mode,
+                transparency: render_config.transparency,
+                min_scale: 0.,
+                fog: render_config.fog,
+                glare_filter: render_config.glare_filter,
+                exposure: 1.,
                 clipping_box: None,
+                clip_planes: Default::default(),
+                crop_box: None,
                 walltime: Duration::ZERO,
                 scene_center: None,
                 scene_extend: None,
-                background_color: wgpu::Color::BLACK,
+                background_color: render_config.background_color.unwrap_or(wgpu::Color::BLACK),
                 resolution: Vector2::new(size.width, size.height),
             },
+            transparent: render_config.transparent,
+            render_scale,
+            dynamic_resolution: render_config
+                .dynamic_resolution
+                .map(dynamic_resolution::MotionScaler::new),
+            motion_scale: 1.,
+            largest_splats: None,
             pc,
             // camera: view_camera,
             controller,
             ui_renderer,
             fps: 0.,
+            cpu_frame_time: 0.,
             #[cfg(not(target_arch = "wasm32"))]
             history: RingBuffer::new(512),
-            ui_visible: true,
+            frame_arena: FrameArena::new(),
+            ui_visible: !render_config.no_gui,
+            hud_visible: false,
+            help_visible: false,
+            fullscreen_mode: render_config.fullscreen.unwrap_or_default(),
+            present_mode,
+            max_fps: render_config.user_config.max_fps,
+            keys: render_config.user_config.keys.clone(),
+            user_config: render_config.user_config.clone(),
+            user_config_path: render_config.user_config_path.clone(),
+            load_options,
             display,
             saved_cameras: Vec::new(),
-            #[cfg(feature = "video")]
-            cameras_save_path: "cameras_saved.json".to_string(),
+            camera_path: None,
+            camera_path_playing: false,
+            gallery: Gallery::load(Gallery::default_dir()),
+            gallery_visible: render_config.show_gallery,
+            #[cfg(not(target_arch = "wasm32"))]
+            watcher: None,
+            #[cfg(all(
+                feature = "native-menu",
+                any(target_os = "windows", target_os = "macos")
+            ))]
+            native_menu,
+            #[cfg(not(target_arch = "wasm32"))]
+            loading: None,
+            #[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+            remote_loader: render_config.remote_loader.clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            event_log: render_config
+                .event_log
+                .as_ref()
+                .and_then(|dir| match EventLog::new(dir) {
+                    Ok(log) => Some(log),
+                    Err(err) => {
+                        log::error!("failed to create event log: {:?}", err);
+                        None
+                    }
+                }),
+            #[cfg(not(target_arch = "wasm32"))]
+            sequence: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            tour: None,
+            sync: render_config.sync.as_ref().and_then(|config| {
+                match sync::SyncSession::connect(config) {
+                    Ok(session) => Some(session),
+                    Err(err) => {
+                        log::error!("failed to join sync room: {:?}", err);
+                        None
+                    }
+                }
+            }),
+            inset: None,
+            stereo,
+            #[cfg(all(feature = "openxr", target_arch = "wasm32"))]
+            xr: None,
+            grid,
+            view_renderers,
+            feed: None,
             animation: None,
+            clipping_box_animation: None,
+            chunked_upload,
+            rng: match render_config.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
             scene: None,
             current_view: None,
             pointcloud_file_path: None,
+            metadata,
+            suggested_views: Vec::new(),
+            suggesting_views,
+            partition_visible: partitions
+                .as_ref()
+                .map_or(Vec::new(), |p| vec![true; p.parts.len()]),
+            partitions,
+            density_grid,
+            pc_stats,
+            gpu_class,
+            hints_dismissed: false,
             scene_file_path: None,
+            exposure,
 
             stopwatch,
         })
     }
 
+    /// reloads the point cloud (and scene) in the background, the camera is kept
+    #[cfg(not(target_arch = "wasm32"))]
     fn reload(&mut self) -> anyhow::Result<()> {
-        if let Some(file_path) = &self.pointcloud_file_path {
-            log::info!("reloading volume from {:?}", file_path);
-            let file = std::fs::File::open(file_path)?;
-            let pc_raw = io::GenericGaussianPointCloud::load(file)?;
-            self.pc = PointCloud::new(&self.wgpu_context.device, pc_raw)?;
-        } else {
+        let Some(file_path) = self.pointcloud_file_path.clone() else {
             return Err(anyhow::anyhow!("no pointcloud file path present"));
+        };
+        log::info!("reloading volume from {:?}", file_path);
+        self.open_pointcloud(file_path, self.scene_file_path.clone(), true);
+        Ok(())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn reload(&mut self) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("reloading is not supported in the browser"))
+    }
+
+    /// combines the metadata embedded in the point cloud file with the metadata sidecar file
+    fn update_metadata(&mut self, embedded: Option<io::Metadata>) {
+        let sidecar = self
+            .pointcloud_file_path
+            .as_deref()
+            .and_then(io::Metadata::load_sidecar);
+        self.metadata = match (embedded, sidecar) {
+            (Some(embedded), Some(sidecar)) => Some(embedded.merge(sidecar)),
+            (embedded, sidecar) => embedded.or(sidecar),
+        };
+    }
+
+    /// replaces the point cloud and recreates the renderer if the new point cloud requires a different pipeline
+    fn set_pointcloud(&mut self, pc: PointCloud) {
+        let pipeline_changed = pc.sh_deg() != self.pc.sh_deg()
+            || pc.compressed() != self.pc.compressed()
+            || pc.sh_storage() != self.pc.sh_storage();
+        if pipeline_changed {
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                self.renderer = pollster::block_on(GaussianRenderer::new_multisampled(
+                    &self.wgpu_context.device,
+                    &self.wgpu_context.queue,
+                    self.renderer.color_format(),
+                    self.renderer.sample_count(),
+                    pc.sh_deg(),
+                    pc.compressed(),
+                    pc.sh_storage(),
+                ));
+            }
+            #[cfg(target_arch = "wasm32")]
+            log::error!("point cloud requires a different render pipeline");
+        }
+        self.splatting_args.max_sh_deg = self.splatting_args.max_sh_deg.min(pc.sh_deg());
+        self.pc = pc;
+        self.chunked_upload.take();
+        // the frames of a sequence belong to the replaced point cloud
+        #[cfg(not(target_arch = "wasm32"))]
+        self.sequence.take();
+        if pipeline_changed && self.inset.is_some() {
+            self.set_inset_visible(true);
+        }
+        if pipeline_changed {
+            let num_renderers = self.view_renderers.len();
+            self.view_renderers.clear();
+            if !self.resize_view_renderers(num_renderers) {
+                log::error!("the additional views require a different render pipeline");
+                self.stereo = None;
+                self.grid = None;
+            }
+        }
+        self.hints_dismissed = false;
+        self.log_performance_hints();
+    }
+
+    /// cpu memory used for the transient data of the last frame by the window and its renderers
+    fn frame_arena_stats(&self) -> FrameArenaStats {
+        self.view_renderers
+            .iter()
+            .chain([&self.renderer])
+            .fold(self.frame_arena.stats(), |stats, renderer| {
+                stats.merge(renderer.frame_arena_stats())
+            })
+    }
+
+    /// number of splats that are drawn for the current view
+    /// the level of detail hierarchy draws fewer splats than the point cloud has
+    fn num_drawn_splats(&self) -> u32 {
+        match self.pc.lod().filter(|lod| lod.enabled) {
+            Some(lod) => {
+                let args = &self.splatting_args;
+                let (_, num_selected) = lod.octree().select(
+                    &args.camera,
+                    args.viewport,
+                    args.clipping_box,
+                    lod.threshold,
+                    &self.frame_arena,
+                );
+                num_selected
+            }
+            None => self.pc.num_points(),
+        }
+    }
+
+    /// settings that would speed up rendering of the current point cloud
+    /// applied hints are not suggested again, as they change the settings the hints are computed from
+    fn performance_hints(&self) -> Vec<hints::PerformanceHint> {
+        hints::performance_hints(
+            self.gpu_class,
+            self.num_drawn_splats() as usize,
+            self.splatting_args.max_sh_deg,
+            self.pc.compressed(),
+            self.pc.lod().is_some_and(|lod| lod.enabled),
+        )
+    }
+
+    /// hints that can be applied in the user interface
+    /// hints that require a reload are skipped if the point cloud was not loaded from a file
+    fn applicable_performance_hints(&self) -> Vec<hints::PerformanceHint> {
+        self.performance_hints()
+            .into_iter()
+            .filter(|h| !h.needs_reload() || self.pointcloud_file_path.is_some())
+            .collect()
+    }
+
+    fn log_performance_hints(&self) {
+        let hints = self.performance_hints();
+        if !hints.is_empty() {
+            log::warn!(
+                "{} splats may render slowly on this gpu ({:?}), consider:",
+                self.num_drawn_splats(),
+                self.gpu_class
+            );
+            for hint in hints {
+                log::warn!("  {}", hint.description());
+            }
+        }
+    }
+
+    fn apply_performance_hint(&mut self, hint: hints::PerformanceHint) -> anyhow::Result<()> {
+        match hint {
+            hints::PerformanceHint::Lod => {
+                if let Some(lod) = self.pc.lod_mut() {
+                    lod.enabled = true;
+                    return Ok(());
+                }
+                self.load_options.lod = true;
+                self.reload()
+            }
+            hints::PerformanceHint::LowMemory => {
+                self.load_options.low_memory = true;
+                self.reload()
+            }
+            hints::PerformanceHint::ShDegree(deg) => {
+                self.splatting_args.max_sh_deg = self.splatting_args.max_sh_deg.min(deg);
+                Ok(())
+            }
+        }
+    }
+
+    /// loads the frames of a dynamic capture
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_sequence(&mut self, files: &[PathBuf], fps: f32) -> anyhow::Result<()> {
+        // the current point cloud becomes a frame of the sequence, so it has to be complete
+        if let Some(mut upload) = self.chunked_upload.take() {
+            while upload.upload_next(&self.wgpu_context.queue, &mut self.pc) {}
+        }
+        let sequence = PointCloudSequence::load(
+            &self.wgpu_context.device,
+            &self.wgpu_context.queue,
+            files,
+            self.pc.sh_storage(),
+            &self.load_options,
+            fps,
+            &mut self.pc,
+        )?;
+        self.sequence = Some(sequence);
+        // the partitions belong to the first frame
+        self.set_partitions(None);
+        Ok(())
+    }
+
+    /// jumps to a frame of the sequence
+    #[cfg(not(target_arch = "wasm32"))]
+    fn seek_sequence(&mut self, frame: usize) {
+        if let Some(sequence) = &mut self.sequence {
+            if sequence.seek(frame, &mut self.pc) {
+                // force a redraw of the scene
+                self.splatting_args.resolution = Vector2::new(0, 0);
+            }
+        }
+    }
+
+    /// reloads the point cloud if the watched file changed
+    #[cfg(not(target_arch = "wasm32"))]
+    fn check_file_changes(&mut self) {
+        if self.watcher.as_mut().is_some_and(|w| w.poll()) {
+            if let Err(err) = self.reload() {
+                log::error!("failed to reload volume: {:?}", err);
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn watch_pointcloud_file(&mut self) {
+        // point clouds opened by url are not watched
+        let path = self
+            .pointcloud_file_path
+            .as_ref()
+            .filter(|path| path.exists());
+        self.watcher = path.and_then(|path| match watcher::FileWatcher::new(path) {
+            Ok(w) => Some(w),
+            Err(err) => {
+                log::error!("failed to watch {:?}: {:?}", path, err);
+                None
+            }
+        });
+    }
+
+    /// starts loading the point cloud (and scene) in the background
+    /// a load that is still running is cancelled
+    /// urls are downloaded with the remote loader, a reload keeps the camera and the crop region
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_pointcloud(&mut self, pc_path: PathBuf, scene_path: Option<PathBuf>, reload: bool) {
+        log::info!("opening {:?}", pc_path);
+        if let Some(filter) = self.load_options.filter_3d.as_mut().filter(|_| !reload) {
+            // the filter depends on the training cameras of the new point cloud
+            let load_cameras = |path: &PathBuf| -> anyhow::Result<Vec<SceneCamera>> {
+                Ok(Scene::open(path)?.cameras(None))
+            };
+            filter.cameras = match scene_path.as_ref().map(load_cameras) {
+                Some(Ok(cameras)) => cameras,
+                Some(Err(err)) => {
+                    log::warn!("cannot load cameras for 3D filter: {:?}", err);
+                    Vec::new()
+                }
+                None => Vec::new(),
+            };
+        }
+        let mut load_options = self.load_options.clone();
+        if !reload {
+            load_options.crop = io::CropRegion::load_sidecar(&pc_path);
+        }
+        #[cfg(feature = "remote")]
+        let remote_loader = self.remote_loader.clone();
+        let name = format!(
+            "loading {}",
+            pc_path.file_name().unwrap_or_default().to_string_lossy()
+        );
+        self.loading = Some(task::Task::spawn(&name, move |token| {
+            #[cfg(feature = "remote")]
+            let file = open_remote(&pc_path, remote_loader, &token)?;
+            #[cfg(not(feature = "remote"))]
+            let file = std::fs::File::open(&pc_path)?;
+            let file = task::CancellableReader::new(file, token.clone());
+            load_options.cancellation = token.clone();
+            let pc_raw = io::GenericGaussianPointCloud::load_with_options(file, &load_options)?;
+            token.check()?;
+            let density_grid = density_grid(&pc_raw);
+            token.check()?;
+            let partitions = partitions(&pc_raw, density_grid.as_ref());
+            token.check()?;
+            let stats = point_cloud_stats(&pc_raw);
+            Ok(LoadedPointCloud {
+                pc_raw,
+                partitions,
+                density_grid,
+                stats,
+                crop: load_options.crop,
+                pc_path,
+                scene_path,
+                reload,
+            })
+        }));
+    }
+
+    /// replaces the point cloud (and scene) with a point cloud loaded in the background
+    /// the renderer is recreated if the new point cloud requires a different pipeline
+    #[cfg(not(target_arch = "wasm32"))]
+    fn finish_open_pointcloud(&mut self, loaded: LoadedPointCloud) -> anyhow::Result<()> {
+        let LoadedPointCloud {
+            pc_raw,
+            partitions,
+            density_grid,
+            stats,
+            crop,
+            pc_path,
+            scene_path,
+            reload,
+        } = loaded;
+        let metadata = pc_raw.metadata.clone();
+        self.suggested_views.clear();
+        self.suggesting_views = suggest_views(&pc_raw);
+        let pc = PointCloud::new_with_sh_storage(
+            &self.wgpu_context.device,
+            &self.wgpu_context.queue,
+            pc_raw,
+            self.pc.sh_storage(),
+        )?;
+        self.load_options.crop = crop;
+        self.density_grid = density_grid;
+        self.pc_stats = stats;
+        self.set_pointcloud(pc);
+        self.set_partitions(partitions);
+        if reload {
+            self.update_metadata(metadata);
+            if let Some(scene_path) = &scene_path {
+                log::info!("reloading scene from {:?}", scene_path);
+                self.set_scene(Scene::open(scene_path)?);
+            }
+            return Ok(());
+        }
+        self.splatting_args.max_sh_deg = self.pc.sh_deg();
+
+        let aabb = self.pc.bbox();
+        self.update_camera(PerspectiveCamera::new(
+            aabb.center() - Vector3::new(1., 1., 1.) * aabb.radius() * 0.5,
+            Quaternion::one(),
+            self.splatting_args.camera.projection,
+        ));
+        self.controller.center = self.pc.center();
+        self.animation.take();
+        self.set_clipping_box(None);
+        self.current_view.take();
+        self.scene.take();
+        self.saved_cameras.clear();
+        self.splatting_args.scene_extend = None;
+        self.splatting_args.walltime = Duration::ZERO;
+
+        if let Some(scene_path) = &scene_path {
+            let scene = Scene::open(scene_path)?;
+            self.set_scene(scene);
+            self.set_scene_camera(0);
+        }
+        self.gallery.add_recent(&pc_path, scene_path.as_deref());
+        self.pointcloud_file_path = Some(pc_path);
+        self.scene_file_path = scene_path;
+        self.update_metadata(metadata);
+        if self.watcher.is_some() {
+            self.watch_pointcloud_file();
+        }
+        Ok(())
+    }
+
+    /// applies the point cloud once the background load finished
+    /// returns true if the point cloud was replaced
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_loading(&mut self) -> bool {
+        let Some(result) = self.loading.as_ref().and_then(|task| task.poll()) else {
+            return false;
+        };
+        self.loading.take();
+        match result.and_then(|loaded| self.finish_open_pointcloud(loaded)) {
+            Ok(()) => true,
+            Err(err) if task::is_cancelled(&err) => {
+                log::info!("loading was cancelled");
+                false
+            }
+            Err(err) => {
+                log::error!("failed to open point cloud: {:?}", err);
+                false
+            }
+        }
+    }
+
+    /// takes the suggested views once they are computed
+    /// returns true if the suggestions changed
+    fn poll_suggested_views(&mut self) -> bool {
+        let Some(result) = self.suggesting_views.as_ref().and_then(|task| task.poll()) else {
+            return false;
+        };
+        self.suggesting_views.take();
+        match result {
+            Ok(views) => {
+                self.suggested_views = views;
+                true
+            }
+            Err(err) if task::is_cancelled(&err) => false,
+            Err(err) => {
+                log::warn!("failed to suggest views: {:?}", err);
+                false
+            }
+        }
+    }
+
+    /// lets the user pick a point cloud or scene file
+    /// scene files (json or colmap `cameras.bin`/`images.bin`) are used for the current point cloud, for point clouds a `cameras.json` next to the file is loaded if present
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_file_dialog(&mut self) -> anyhow::Result<()> {
+        let Some(path) = rfd::FileDialog::new()
+            .set_title("Open point cloud or scene")
+            .add_filter(
+                "Point cloud or scene",
+                &["ply", "splat", "npz", "wsc", "json", "bin"],
+            )
+            .pick_file()
+        else {
+            return Ok(());
+        };
+        if path
+            .extension()
+            .is_some_and(|ext| ext == "json" || ext == "bin")
+        {
+            let scene = Scene::open(&path)?;
+            self.saved_cameras.clear();
+            self.set_scene(scene);
+            self.set_scene_camera(0);
+            self.scene_file_path = Some(path);
+            Ok(())
+        } else {
+            let scene_path = path
+                .parent()
+                .map(|dir| dir.join("cameras.json"))
+                .filter(|p| p.exists());
+            self.open_pointcloud(path, scene_path, false);
+            Ok(())
         }
-        if let Some(scene_path) = &self.scene_file_path {
-            log::info!("reloading scene from {:?}", scene_path);
-            let file = std::fs::File::open(scene_path)?;
+    }
 
-            self.set_scene(Scene::from_json(file)?);
+    /// loads the feed image and moves the camera to the pose of the image
+    fn load_feed(&mut self, config: &FeedConfig) -> anyhow::Result<()> {
+        let img = image::open(&config.image)?.to_rgba8();
+        let mut feed = FeedCompositor::new(
+            &self.wgpu_context.device,
+            self.renderer.color_format(),
+            self.renderer.sample_count(),
+        );
+        feed.set_image(&self.wgpu_context.device, &self.wgpu_context.queue, &img);
+        feed.set_opacity(&self.wgpu_context.queue, config.opacity);
+        if let Some(depth) = &config.depth {
+            let depth = image::open(depth)?.to_luma16();
+            feed.set_depth_image(
+                &self.wgpu_context.device,
+                &self.wgpu_context.queue,
+                &depth,
+                config.depth_scale,
+            );
+        } else if config.mode == FeedMode::Depth {
+            log::warn!("feed has no depth image, it is drawn behind the splats");
+        }
+        feed.mode = config.mode;
+        self.feed = Some(feed);
+        if let Some(camera_path) = &config.camera {
+            let camera: SceneCamera = serde_json::from_reader(std::io::BufReader::new(
+                std::fs::File::open(camera_path)?,
+            ))?;
+            self.set_camera(camera, Duration::ZERO);
         }
         Ok(())
     }
 
+    fn scale_splats(&mut self, factor: f32) {
+        let range = renderer::GAUSSIAN_SCALING_RANGE;
+        self.splatting_args.gaussian_scaling =
+            (self.splatting_args.gaussian_scaling * factor).clamp(*range.start(), *range.end());
+    }
+
+    /// color the render targets are cleared with
+    fn clear_color(&self) -> wgpu::Color {
+        if self.transparent {
+            wgpu::Color::TRANSPARENT
+        } else {
+            self.splatting_args.background_color
+        }
+    }
+
+    fn set_transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+        let caps = self.surface.get_capabilities(&self.wgpu_context.adapter);
+        self.config.alpha_mode = surface_alpha_mode(&caps, transparent);
+        self.surface
+            .configure(&self.wgpu_context.device, &self.config);
+        // force a redraw
+        self.splatting_args.resolution = Vector2::new(0, 0);
+    }
+
+    /// falls back to v-sync if the surface does not support `mode`
+    fn set_present_mode(&mut self, mode: PresentMode) {
+        let caps = self.surface.get_capabilities(&self.wgpu_context.adapter);
+        self.present_mode = surface_present_mode(&caps, mode);
+        self.config.present_mode = self.present_mode.into();
+        self.surface
+            .configure(&self.wgpu_context.device, &self.config);
+    }
+
+    /// minimum time between two frames of the frame rate cap
+    fn min_frame_time(&self) -> Option<Duration> {
+        self.max_fps
+            .filter(|fps| *fps > 0.)
+            .map(|fps| Duration::from_secs_f32(1. / fps))
+    }
+
+    fn toggle_inset(&mut self) {
+        self.set_inset_visible(self.inset.is_none());
+    }
+
+    fn set_inset_visible(&mut self, visible: bool) {
+        if let Some((_, id)) = self.inset.take() {
+            self.ui_renderer.renderer.free_texture(&id);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if visible {
+            let (width, height) = self.inset_size();
+            let inset = pollster::block_on(RenderTexture::new(
+                &self.wgpu_context.device,
+                &self.wgpu_context.queue,
+                &self.pc,
+                width,
+                height,
+            ));
+            let id = self.ui_renderer.renderer.register_native_texture(
+                &self.wgpu_context.device,
+                inset.srgb_view(),
+                wgpu::FilterMode::Linear,
+            );
+            self.inset = Some((inset, id));
+            // force a redraw so the inset is filled
+            self.splatting_args.resolution = Vector2::new(0, 0);
+        }
+        #[cfg(target_arch = "wasm32")]
+        if visible {
+            log::error!("picture-in-picture is not supported on the web");
+        }
+    }
+
+    /// creates or frees renderers so there are `num` renderers for the additional views
+    /// the renderers can only be created at startup on the web, returns false if they are missing
+    fn resize_view_renderers(&mut self, num: usize) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.view_renderers.truncate(num);
+        #[cfg(not(target_arch = "wasm32"))]
+        while self.view_renderers.len() < num {
+            self.view_renderers
+                .push(pollster::block_on(GaussianRenderer::new_multisampled(
+                    &self.wgpu_context.device,
+                    &self.wgpu_context.queue,
+                    self.renderer.color_format(),
+                    self.renderer.sample_count(),
+                    self.pc.sh_deg(),
+                    self.pc.compressed(),
+                    self.pc.sh_storage(),
+                )));
+        }
+        self.view_renderers.len() >= num
+    }
+
+    /// number of views that are rendered every frame
+    fn num_views(&self) -> usize {
+        match (&self.stereo, &self.grid) {
+            (Some(_), _) => 2,
+            (None, Some(grid)) => grid.num_views(),
+            (None, None) => 1,
+        }
+    }
+
+    /// shows a left and a right eye view, replaces the viewport grid
+    fn set_stereo(&mut self, stereo: Option<Stereo>) {
+        let Some(stereo) = stereo else {
+            if self.stereo.take().is_some() {
+                self.resize_view_renderers(0);
+            }
+            #[cfg(all(feature = "openxr", target_arch = "wasm32"))]
+            {
+                self.xr = None;
+            }
+            return;
+        };
+        if let Some(compositor) = &mut self.stereo {
+            compositor.set_stereo(&self.wgpu_context.queue, stereo);
+            return;
+        }
+        if !self.resize_view_renderers(1) {
+            log::error!("stereo rendering can only be enabled at startup on the web");
+            return;
+        }
+        self.grid = None;
+        self.stereo = Some(stereo::StereoCompositor::new(
+            &self.wgpu_context.device,
+            &self.wgpu_context.queue,
+            stereo,
+            self.config.format.remove_srgb_suffix(),
+            self.config.width,
+            self.config.height,
+        ));
+    }
+
+    /// starts an immersive VR session that shows the side by side stereo view
+    /// the viewer has to be started in stereo mode (e.g. with the `xr` query parameter)
+    #[cfg(all(feature = "openxr", target_arch = "wasm32"))]
+    fn enter_xr(&mut self) {
+        use winit::platform::web::WindowExtWebSys;
+
+        let Some(stereo) = self.stereo.as_ref().map(|s| s.stereo()) else {
+            log::error!("VR requires the viewer to be started in stereo mode");
+            return;
+        };
+        let Some(canvas) = self.window.canvas() else {
+            return;
+        };
+        // the canvas is split into the two eyes
+        self.set_stereo(Some(Stereo {
+            mode: StereoMode::SideBySide,
+            ..stereo
+        }));
+        self.xr = Some(xr::XrViewer::start(
+            canvas,
+            self.splatting_args.camera,
+            stereo,
+        ));
+    }
+
+    /// shows the current view next to the views of up to three scene cameras
+    /// an empty list hides the grid, replaces the stereo mode
+    fn set_grid(&mut self, mut cameras: Vec<usize>) {
+        cameras.truncate(MAX_GRID_VIEWS - 1);
+        if cameras.is_empty() {
+            if self.grid.take().is_some() {
+                self.resize_view_renderers(0);
+            }
+            return;
+        }
+        if !self.resize_view_renderers(cameras.len()) {
+            log::error!("the viewport grid can only be enabled at startup on the web");
+            return;
+        }
+        self.stereo = None;
+        #[cfg(all(feature = "openxr", target_arch = "wasm32"))]
+        {
+            self.xr = None;
+        }
+        match &mut self.grid {
+            Some(grid) => grid.set_cameras(&self.wgpu_context.device, cameras),
+            None => {
+                self.grid = Some(grid::ViewportGrid::new(
+                    &self.wgpu_context.device,
+                    cameras,
+                    self.config.format.remove_srgb_suffix(),
+                    self.config.width,
+                    self.config.height,
+                ))
+            }
+        }
+    }
+
+    /// settings of one view of the stereo or grid mode, view 0 is the main view
+    /// grid views show the current camera if their scene camera does not exist
+    fn view_args(&self, view: usize) -> RenderSettings {
+        let mut args = self.splatting_args;
+        if let Some(stereo) = &self.stereo {
+            args.camera = stereo
+                .stereo()
+                .eye_camera(args.camera, args.viewport, view == 1);
+            // the head tracked eyes replace the eyes of the stereo camera
+            #[cfg(all(feature = "openxr", target_arch = "wasm32"))]
+            if let Some(camera) = self.xr.as_ref().and_then(|xr| xr.eye_camera(view == 1)) {
+                args.camera = camera;
+            }
+        }
+        if let Some(grid) = &self.grid {
+            let scene_camera = view
+                .checked_sub(1)
+                .and_then(|i| self.scene.as_ref()?.camera(grid.cameras()[i]));
+            if let Some(camera) = scene_camera {
+                args.camera = camera.into();
+                args.camera.fit_near_far(self.pc.bbox());
+            }
+            let size = grid.tile_size();
+            args.camera.projection.resize(size.x, size.y);
+        }
+        args
+    }
+
+    fn view_renderer(&self, view: usize) -> &GaussianRenderer {
+        match view {
+            0 => &self.renderer,
+            _ => &self.view_renderers[view - 1],
+        }
+    }
+
+    /// size of the inset texture, a quarter of the window width
+    fn inset_size(&self) -> (u32, u32) {
+        let width = (self.config.width / 4).max(1);
+        (width, width * self.config.height / self.config.width.max(1))
+    }
+
+    fn toggle_gallery(&mut self) {
+        self.gallery_visible = !self.gallery_visible;
+    }
+
+    /// renders the current view into a small image
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_thumbnail(&mut self) -> anyhow::Result<image::RgbaImage> {
+        let width = gallery::THUMBNAIL_WIDTH;
+        let height = (width * self.config.height / self.config.width.max(1)).max(1);
+        let mut args = self.splatting_args;
+        args.viewport = Vector2::new(width, height);
+        self.render_offscreen(args)
+    }
+
+    /// renders the point cloud with `args` into an image of the viewport size
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_offscreen(&mut self, mut args: RenderSettings) -> anyhow::Result<image::RgbaImage> {
+        // offscreen renders are not resolved by the display pass and must be srgb already
+        args.color_space = ColorSpace::Srgb;
+        let device = &self.wgpu_context.device;
+        let queue = &self.wgpu_context.queue;
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen texture"),
+            size: Extent3d {
+                width: args.viewport.x,
+                height: args.viewport.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.renderer.color_format(),
+            usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let target_view = target.create_view(&Default::default());
+        let msaa_view = create_multisampled_view(
+            device,
+            self.renderer.color_format(),
+            args.viewport.x,
+            args.viewport.y,
+            self.renderer.sample_count(),
+        );
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("offscreen encoder"),
+        });
+        self.renderer
+            .prepare(&mut encoder, device, queue, &self.pc, args, &mut None);
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("offscreen render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: msaa_view.as_ref().unwrap_or(&target_view),
+                    resolve_target: msaa_view.as_ref().map(|_| &target_view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(self.clear_color()),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+            self.renderer.render(&mut render_pass, &self.pc);
+        }
+        queue.submit([encoder.finish()]);
+        let img = pollster::block_on(utils::download_texture(&target, device, queue))?;
+
+        // the renderer uniforms now hold the offscreen settings, force a redraw of the scene
+        self.splatting_args.resolution = Vector2::new(0, 0);
+        Ok(img)
+    }
+
+    /// renders a cubemap at the current camera position and adds it as a new station to the tour
+    /// the faces are aligned with the current view direction
+    #[cfg(not(target_arch = "wasm32"))]
+    fn capture_panorama(&mut self) -> anyhow::Result<PathBuf> {
+        let camera = self.splatting_args.camera;
+        let mut faces = Vec::with_capacity(tour::CUBE_FACES.len());
+        for i in 0..tour::CUBE_FACES.len() {
+            let mut args = self.splatting_args;
+            args.viewport = Vector2::new(PANORAMA_FACE_SIZE, PANORAMA_FACE_SIZE);
+            args.camera = PerspectiveCamera::new(
+                camera.position,
+                tour::face_rotation(i) * camera.rotation,
+                PerspectiveProjection::new(
+                    args.viewport,
+                    Vector2::new(Deg(90.), Deg(90.)),
+                    0.01,
+                    1000.,
+                ),
+            );
+            args.camera.fit_near_far(self.pc.bbox());
+            faces.push(self.render_offscreen(args)?);
+        }
+        let tour = match &mut self.tour {
+            Some(tour) => tour,
+            None => self.tour.insert(Tour::open(&Tour::default_dir())?),
+        };
+        tour.add_station(camera.position, camera.rotation, &faces)
+    }
+
+    /// switches between window and fullscreen mode
+    /// the surface and camera aspect ratio are updated by the resize event that follows
+    fn toggle_fullscreen(&mut self) {
+        if self.window.fullscreen().is_some() {
+            self.window.set_fullscreen(None);
+        } else {
+            let monitor = self.window.current_monitor();
+            self.window
+                .set_fullscreen(Some(self.fullscreen_mode.to_winit(monitor)));
+        }
+    }
+
+    /// copies the last rendered frame (without user interface) to the cpu
+    /// the frame is stored gamma encoded, so the image can be saved as is even if the surface format is not srgb
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn capture_frame(&mut self) -> anyhow::Result<image::RgbaImage> {
+        let device = &self.wgpu_context.device;
+        let queue = &self.wgpu_context.queue;
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("screenshot texture"),
+            size: Extent3d {
+                width: self.config.width,
+                height: self.config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // the display pipeline writes to a non-srgb view of the surface
+            format: self.config.format.remove_srgb_suffix(),
+            usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let target_view = target.create_view(&Default::default());
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("screenshot encoder"),
+        });
+        self.display.render(
+            &mut encoder,
+            &target_view,
+            self.clear_color(),
+            self.renderer.camera(),
+            &self.renderer.render_settings(),
+        );
+        queue.submit([encoder.finish()]);
+        let mut img = pollster::block_on(utils::download_texture(&target, device, queue))?;
+        if self.transparent {
+            // png files store straight alpha
+            utils::unpremultiply(&mut img);
+        }
+        Ok(img)
+    }
+
+    /// saves the current frame as `screenshot-<unix time in ms>.png` in the working directory
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_screenshot(&mut self) -> anyhow::Result<PathBuf> {
+        let img = self.capture_frame()?;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_millis();
+        let path = PathBuf::from(format!("screenshot-{timestamp}.png"));
+        img.save(&path)?;
+        Ok(path)
+    }
+
+    /// lazily renders the thumbnail for the current point cloud
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_thumbnail(&mut self) {
+        if self.splatting_args.walltime < Duration::from_secs(5) || !self.gallery.needs_thumbnail()
+        {
+            return;
+        }
+        match self.render_thumbnail() {
+            Ok(img) => {
+                if let Err(err) = self.gallery.set_thumbnail(&img) {
+                    log::warn!("failed to save thumbnail: {err}");
+                }
+            }
+            Err(err) => log::warn!("failed to render thumbnail: {err}"),
+        }
+    }
+
     fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>, scale_factor: Option<f32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface
                 .configure(&self.wgpu_context.device, &self.config);
-            self.display
-                .resize(&self.wgpu_context.device, new_size.width, new_size.height);
+            self.resize_render_target();
             self.splatting_args
                 .camera
                 .projection
                 .resize(new_size.width, new_size.height);
-            self.splatting_args.viewport = Vector2::new(new_size.width, new_size.height);
+            if let Some(stereo) = &mut self.stereo {
+                stereo.resize(&self.wgpu_context.device, new_size.width, new_size.height);
+            }
+            if let Some(grid) = &mut self.grid {
+                grid.resize(&self.wgpu_context.device, new_size.width, new_size.height);
+            }
+            let (inset_width, inset_height) = self.inset_size();
+            if let Some((inset, id)) = &mut self.inset {
+                if inset.resize(&self.wgpu_context.device, inset_width, inset_height) {
+                    self.ui_renderer
+                        .renderer
+                        .update_egui_texture_from_wgpu_texture(
+                            &self.wgpu_context.device,
+                            inset.srgb_view(),
+                            wgpu::FilterMode::Linear,
+                            *id,
+                        );
+                }
+            }
             self.splatting_args
                 .camera
                 .projection
@@ -341,10 +1939,75 @@ impl WindowContext {
         }
     }
 
+    /// size of the display texture the splats are rendered to
+    fn render_size(&self) -> Vector2<u32> {
+        scaled_size(
+            self.config.width,
+            self.config.height,
+            self.render_scale * self.motion_scale,
+        )
+    }
+
+    fn resize_render_target(&mut self) {
+        let size = self.render_size();
+        self.display
+            .resize(&self.wgpu_context.device, size.x, size.y);
+        self.splatting_args.viewport = size;
+    }
+
+    /// scale of the smallest of the [WindowContext::largest_splats], 0 if all splats are drawn
+    fn min_scale(&self) -> f32 {
+        if !self.splatting_args.render_mode.is_ellipsoid() {
+            return 0.;
+        }
+        match (self.largest_splats, &self.pc_stats) {
+            (Some(n), Some(stats)) => stats
+                .largest_scales
+                .get(n.saturating_sub(1))
+                .copied()
+                .unwrap_or(0.),
+            _ => 0.,
+        }
+    }
+
+    /// renders the splats at `scale` times the window resolution
+    /// the display pass filters the image to the window size
+    fn set_render_scale(&mut self, scale: f32) {
+        self.render_scale = scale.clamp(*RENDER_SCALE_RANGE.start(), *RENDER_SCALE_RANGE.end());
+        self.resize_render_target();
+        // force a redraw of the scene
+        self.splatting_args.resolution = Vector2::new(0, 0);
+    }
+
+    fn dynamic_resolution(&self) -> Option<DynamicResolution> {
+        self.dynamic_resolution.as_ref().map(|d| d.settings)
+    }
+
+    fn set_dynamic_resolution(&mut self, settings: Option<DynamicResolution>) {
+        match (&mut self.dynamic_resolution, settings) {
+            (Some(scaler), Some(settings)) => scaler.settings = settings,
+            (_, settings) => {
+                self.dynamic_resolution = settings.map(dynamic_resolution::MotionScaler::new);
+                self.motion_scale = 1.;
+                self.resize_render_target();
+            }
+        }
+    }
+
     /// returns whether redraw is required
     fn ui(&mut self) -> (bool, egui::FullOutput) {
+        self.frame_arena.reset();
         self.ui_renderer.begin_frame(&self.window);
-        let request_redraw = ui::ui(self);
+        #[cfg(not(target_arch = "wasm32"))]
+        ui::take_measurements(self);
+        let request_redraw = if self.ui_visible { ui::ui(self) } else { false };
+        if self.hud_visible {
+            ui::hud(self);
+        }
+        if self.help_visible {
+            ui::help(self);
+        }
+        ui::captions(self);
 
         let shapes = self.ui_renderer.end_frame(&self.window);
 
@@ -352,12 +2015,68 @@ impl WindowContext {
     }
 
     /// returns whether the sceen changed and we need a redraw
-    fn update(&mut self, dt: Duration)  {
+    fn update(&mut self, dt: Duration) -> bool {
         // ema fps update
+        #[allow(unused_mut)]
+        let mut scene_changed = false;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(sequence) = &mut self.sequence {
+            scene_changed |= sequence.update(dt, &mut self.pc);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            scene_changed |= self.poll_loading();
+        }
+        scene_changed |= self.poll_suggested_views();
+        #[cfg(all(
+            feature = "native-menu",
+            any(target_os = "windows", target_os = "macos")
+        ))]
+        for action in self
+            .native_menu
+            .as_ref()
+            .map(|m| m.actions())
+            .unwrap_or_default()
+        {
+            self.run_action(action);
+            scene_changed = true;
+        }
 
         if self.splatting_args.walltime < Duration::from_secs(5) {
             self.splatting_args.walltime += dt;
         }
+        self.splatting_args.min_scale = self.min_scale();
+        if let Some(exposure) = &mut self.exposure {
+            if exposure.update(&self.wgpu_context.device, dt) {
+                self.display
+                    .set_exposure(&self.wgpu_context.queue, exposure.exposure());
+                scene_changed = true;
+            }
+        }
+        self.splatting_args.exposure = self.display.exposure();
+        #[cfg(all(feature = "openxr", target_arch = "wasm32"))]
+        if let Some(xr) = &mut self.xr {
+            if xr.ended() {
+                self.xr = None;
+            } else {
+                scene_changed |= xr.update();
+            }
+        }
+        if let Some(upload) = &mut self.chunked_upload {
+            scene_changed |= upload.upload_next(&self.wgpu_context.queue, &mut self.pc);
+            if upload.done(&self.pc) {
+                self.chunked_upload.take();
+            }
+        }
+        if let Some(animation) = &mut self.clipping_box_animation {
+            // the clipping box is part of the splatting args, so every change triggers
+            // a new preprocessing (culling) and sorting pass
+            self.splatting_args.clipping_box = Some(animation.update(dt));
+            scene_changed = true;
+            if animation.done() {
+                self.clipping_box_animation.take();
+            }
+        }
         if let Some((next_camera, playing)) = &mut self.animation {
             if self.controller.user_inptut {
                 self.cancle_animation()
@@ -374,8 +2093,11 @@ impl WindowContext {
                 }
             }
         } else {
-            self.controller
-                .update_camera(&mut self.splatting_args.camera, dt);
+            self.controller.update_camera_with_collision(
+                &mut self.splatting_args.camera,
+                dt,
+                self.density_grid.as_ref(),
+            );
 
             // check if camera moved out of selected view
             if let Some(idx) = self.current_view {
@@ -398,8 +2120,32 @@ impl WindowContext {
             }
         }
 
+        if let Some(sync) = &mut self.sync {
+            if sync.update(&mut self.splatting_args.camera) {
+                // the presenter camera replaces any running animation
+                self.cancle_animation();
+            }
+        }
+
         let aabb = self.pc.bbox();
         self.splatting_args.camera.fit_near_far(aabb);
+        if let Some(scaler) = &mut self.dynamic_resolution {
+            let motion_scale = scaler.update(&self.splatting_args.camera, aabb.radius(), dt);
+            if motion_scale != self.motion_scale {
+                // the new viewport triggers the redraw
+                self.motion_scale = motion_scale;
+                self.resize_render_target();
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(event_log) = &mut self.event_log {
+            event_log.update(
+                &self.splatting_args,
+                self.scene.as_ref(),
+                self.pointcloud_file_path.as_deref(),
+            );
+        }
+        scene_changed
     }
 
     fn render(
@@ -407,6 +2153,10 @@ impl WindowContext {
         redraw_scene: bool,
         shapes: Option<FullOutput>,
     ) -> Result<(), wgpu::SurfaceError> {
+        // with multiple views the display texture holds the last view of the previous frame
+        let num_views = self.num_views();
+        let redraw_scene = redraw_scene || num_views > 1;
+        let _span = tracing::trace_span!("render", redraw_scene).entered();
         self.stopwatch.as_mut().map(|s| s.reset());
 
         let output = self.surface.get_current_texture()?;
@@ -424,17 +2174,6 @@ impl WindowContext {
                     label: Some("render command encoder"),
                 });
 
-        if redraw_scene {
-            self.renderer.prepare(
-                &mut encoder,
-                &self.wgpu_context.device,
-                &self.wgpu_context.queue,
-                &self.pc,
-                self.splatting_args,
-                (&mut self.stopwatch).into(),
-            );
-        }
-
         let ui_state = shapes.map(|shapes| {
             self.ui_renderer.prepare(
                 PhysicalSize {
@@ -449,35 +2188,56 @@ impl WindowContext {
             )
         });
 
-        if let Some(stopwatch) = &mut self.stopwatch {
-            stopwatch.start(&mut encoder, "rasterization").unwrap();
-        }
         if redraw_scene {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("render pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: self.display.texture(),
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(self.splatting_args.background_color),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                ..Default::default()
-            });
-            self.renderer.render(&mut render_pass, &self.pc);
-        }
-        if let Some(stopwatch) = &mut self.stopwatch {
-            stopwatch.stop(&mut encoder, "rasterization").unwrap();
+            self.record_scene(&mut encoder, self.view_args(0), 0);
+            let render_size = self.render_size();
+            if let Some(exposure) = &mut self.exposure {
+                exposure.record(
+                    &self.wgpu_context.device,
+                    &mut encoder,
+                    self.display.texture(),
+                    (render_size.x, render_size.y),
+                );
+            }
+            if let Some((inset, _)) = &mut self.inset {
+                // rear view: turn the camera around its up axis
+                let mut args = self.splatting_args;
+                args.camera.rotation = Quaternion::from_angle_y(Deg(180.)) * args.camera.rotation;
+                inset.render(
+                    &mut encoder,
+                    &self.wgpu_context.device,
+                    &self.wgpu_context.queue,
+                    &self.pc,
+                    args,
+                );
+            }
         }
 
-        self.display.render(
-            &mut encoder,
-            &view_rgb,
-            self.splatting_args.background_color,
-            self.renderer.camera(),
-            &self.renderer.render_settings(),
-        );
+        for view in 0..num_views {
+            // the display texture is reused for every view once the previous view is displayed
+            if view > 0 {
+                self.record_scene(&mut encoder, self.view_args(view), view);
+            }
+            let target = match (&self.stereo, &self.grid) {
+                (Some(stereo), _) => stereo.eye_view(view == 1),
+                (None, Some(grid)) => grid.tile_view(view),
+                (None, None) => &view_rgb,
+            };
+            let renderer = self.view_renderer(view);
+            self.display.render(
+                &mut encoder,
+                target,
+                self.clear_color(),
+                renderer.camera(),
+                &renderer.render_settings(),
+            );
+        }
+        if let Some(stereo) = &self.stereo {
+            stereo.render(&mut encoder, &view_rgb);
+        }
+        if let Some(grid) = &self.grid {
+            grid.render(&mut encoder, &view_rgb);
+        }
         self.stopwatch.as_mut().map(|s| s.end(&mut encoder));
 
         if let Some(state) = &ui_state {
@@ -500,12 +2260,113 @@ impl WindowContext {
             self.ui_renderer.cleanup(ui_state)
         }
         self.wgpu_context.queue.submit([encoder.finish()]);
+        if let Some(exposure) = &mut self.exposure {
+            exposure.after_submit();
+        }
 
         output.present();
         self.splatting_args.resolution = Vector2::new(self.config.width, self.config.height);
         Ok(())
     }
 
+    /// records the splats seen with `args` into the display texture
+    /// together with the passes that the display pass reads (expected depth and bloom)
+    /// `view` selects the renderer, 0 is the main view (see [WindowContext::view_renderers])
+    fn record_scene(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        args: RenderSettings,
+        view: usize,
+    ) {
+        let clear_color = args.color_space.from_srgb(self.clear_color());
+        let renderer = match view {
+            0 => &mut self.renderer,
+            _ => &mut self.view_renderers[view - 1],
+        };
+        // only the main view is timed
+        let mut no_stopwatch = None;
+        let stopwatch = if view > 0 {
+            &mut no_stopwatch
+        } else {
+            &mut self.stopwatch
+        };
+        if args.transparency == Transparency::Stochastic {
+            renderer.set_noise_offset(Vector2::new(self.rng.gen(), self.rng.gen()));
+        }
+        renderer.prepare(
+            encoder,
+            &self.wgpu_context.device,
+            &self.wgpu_context.queue,
+            &self.pc,
+            args,
+            stopwatch,
+        );
+        if let Some(stopwatch) = stopwatch {
+            stopwatch.start(encoder, "rasterization").unwrap();
+        }
+        let overdraw = args.render_mode == RenderMode::Overdraw;
+        if overdraw {
+            self.display.clear_overdraw(encoder);
+        }
+        // rendered before the splats, the feed is depth tested against it
+        let feed_depth = self.feed.as_ref().is_some_and(|f| f.needs_splat_depth());
+        if !overdraw && (self.display.depth_of_field().is_some() || feed_depth) {
+            renderer.render_expected_depth(
+                &self.wgpu_context.device,
+                encoder,
+                &self.pc,
+                self.display.expected_depth_target(),
+            );
+        }
+        if let Some(feed) = self.feed.as_mut().filter(|_| feed_depth && !overdraw) {
+            feed.set_splat_depth(
+                &self.wgpu_context.device,
+                self.display.expected_depth_target(),
+            );
+        }
+        {
+            let (view, resolve_target) = self.display.render_target();
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(clear_color),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+            if let Some(feed) = &self.feed {
+                feed.render(&mut render_pass, FeedMode::Behind);
+            }
+            if overdraw {
+                renderer.render_overdraw(
+                    &mut render_pass,
+                    &self.pc,
+                    self.display.overdraw_bind_group(),
+                );
+            } else {
+                renderer.render(&mut render_pass, &self.pc);
+            }
+            if let Some(feed) = &self.feed {
+                feed.render(&mut render_pass, FeedMode::Front);
+            }
+        }
+        if !overdraw && self.display.bloom().is_some() {
+            self.display.record_bloom(
+                &self.wgpu_context.device,
+                &self.wgpu_context.queue,
+                encoder,
+                args.color_space,
+            );
+        }
+        if let Some(stopwatch) = stopwatch {
+            stopwatch.stop(encoder, "rasterization").unwrap();
+        }
+    }
+
     fn set_scene(&mut self, scene: Scene) {
         self.splatting_args.scene_extend = Some(scene.extend());
         let mut center = Point3::origin();
@@ -528,21 +2389,15 @@ impl WindowContext {
     }
 
     fn set_env_map<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<()> {
-        let env_map_exr = image::open(path)?;
-        let env_map_data: Vec<[f32; 4]> = env_map_exr
-            .as_rgb32f()
-            .ok_or(anyhow::anyhow!("env map must be rgb"))?
-            .pixels()
-            .map(|p| p.to_rgba().0)
-            .collect();
+        let env_map = env_map::load_env_map(path.as_ref())?;
 
         let env_texture = self.wgpu_context.device.create_texture_with_data(
             &self.wgpu_context.queue,
             &wgpu::TextureDescriptor {
                 label: Some("env map texture"),
                 size: Extent3d {
-                    width: env_map_exr.width(),
-                    height: env_map_exr.height(),
+                    width: env_map.width(),
+                    height: env_map.height(),
                     depth_or_array_layers: 1,
                 },
                 mip_level_count: 1,
@@ -553,7 +2408,7 @@ impl WindowContext {
                 view_formats: &[],
             },
             wgpu::util::TextureDataOrder::LayerMajor,
-            bytemuck::cast_slice(&env_map_data.as_slice()),
+            bytemuck::cast_slice(env_map.as_raw()),
         );
         self.display.set_env_map(
             &self.wgpu_context.device,
@@ -572,6 +2427,45 @@ impl WindowContext {
                 Box::new(shot),
             );
             self.animation = Some((a, true));
+            self.camera_path_playing = false;
+        }
+    }
+
+    fn set_camera_path(&mut self, path: CameraPath) {
+        self.camera_path = Some(path);
+        self.start_camera_path();
+    }
+
+    fn start_camera_path(&mut self) {
+        if let Some(path) = &self.camera_path {
+            let a = Animation::new(path.duration(), false, Box::new(path.clone()));
+            self.animation = Some((a, true));
+            self.camera_path_playing = true;
+        }
+    }
+
+    /// toggles camera path playback
+    /// restarts the path if no animation is running
+    fn toggle_camera_path(&mut self) {
+        match &mut self.animation {
+            Some((_, playing)) => {
+                *playing = !*playing;
+                if !*playing {
+                    self.controller.reset_to_camera(self.splatting_args.camera);
+                }
+            }
+            None => self.start_camera_path(),
+        }
+    }
+
+    /// moves the current animation forward or backward by the given amount of seconds
+    fn seek_animation(&mut self, seconds: f32) {
+        if let Some((animation, _)) = &mut self.animation {
+            let duration = animation.duration().as_secs_f32();
+            if duration > 0. {
+                let progress = animation.progress() + seconds / duration;
+                animation.set_progress(progress.clamp(0., 1.));
+            }
         }
     }
 
@@ -599,6 +2493,43 @@ impl WindowContext {
         }
     }
 
+    /// moves the camera to the i-th suggested view, the projection is kept
+    fn set_suggested_view(&mut self, i: usize) {
+        if let Some(view) = self.suggested_views.get(i) {
+            let camera = PerspectiveCamera::new(
+                view.camera.position,
+                view.camera.rotation,
+                self.splatting_args.camera.projection,
+            );
+            self.current_view.take();
+            self.set_camera(camera, Duration::from_millis(500));
+        }
+    }
+
+    /// partitions of a new point cloud, all partitions are visible
+    fn set_partitions(&mut self, partitions: Option<io::Partitions>) {
+        self.partition_visible = partitions
+            .as_ref()
+            .map_or(Vec::new(), |p| vec![true; p.parts.len()]);
+        self.partitions = partitions;
+        self.pc
+            .show_all(&self.wgpu_context.queue, &self.frame_arena);
+    }
+
+    /// hides the splats of the partitions with a false flag
+    fn set_partition_visibility(&mut self, visible: Vec<bool>) {
+        if let Some(partitions) = &self.partitions {
+            self.pc.set_hidden(
+                &self.wgpu_context.queue,
+                &partitions.hidden_splats(&visible),
+                &self.frame_arena,
+            );
+            // force a redraw of the scene
+            self.splatting_args.resolution = Vector2::new(0, 0);
+        }
+        self.partition_visible = visible;
+    }
+
     pub fn set_camera<C: Into<PerspectiveCamera>>(
         &mut self,
         camera: C,
@@ -619,9 +2550,47 @@ impl WindowContext {
                 )),
             );
             self.animation = Some((a, true));
+            self.camera_path_playing = false;
         }
     }
 
+    /// caption of the camera path at the current playback time
+    fn current_caption(&self) -> Option<&str> {
+        let (animation, _) = self
+            .animation
+            .as_ref()
+            .filter(|_| self.camera_path_playing)?;
+        let path = self.camera_path.as_ref()?;
+        path.caption_at(animation.progress() * path.duration().as_secs_f32())
+    }
+
+    /// splats outside of the clipping box are not rendered
+    /// `None` shows the whole point cloud
+    pub fn set_clipping_box(&mut self, clipping_box: Option<Aabb<f32>>) {
+        self.clipping_box_animation.take();
+        self.splatting_args.clipping_box = clipping_box;
+    }
+
+    /// animates the clipping box, e.g. to reveal the scene by growing the box
+    /// `from` defaults to the current clipping box or the bounding box of the point cloud
+    pub fn animate_clipping_box(
+        &mut self,
+        from: Option<Aabb<f32>>,
+        to: Aabb<f32>,
+        duration: Duration,
+        interp_fn: fn(f32) -> f32,
+    ) {
+        let from = from
+            .or(self.splatting_args.clipping_box)
+            .unwrap_or(*self.pc.bbox());
+        self.splatting_args.clipping_box = Some(from);
+        self.clipping_box_animation = Some(Animation::new(
+            duration,
+            false,
+            Box::new(Transition::new(from, to, interp_fn)),
+        ));
+    }
+
     fn update_camera(&mut self, camera: PerspectiveCamera) {
         self.splatting_args.camera = camera;
         self.splatting_args
@@ -630,6 +2599,121 @@ impl WindowContext {
             .resize(self.config.width, self.config.height);
     }
 
+    /// stores the current background color, window size, zoom collision and frame rate settings
+    /// as defaults in the config file
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_user_config(&mut self) -> anyhow::Result<()> {
+        let bg = self.splatting_args.background_color;
+        self.user_config.background_color = Some([bg.r, bg.g, bg.b, bg.a]);
+        let size = self.window.inner_size();
+        self.user_config.window_size = Some([size.width, size.height]);
+        let path = self
+            .user_config_path
+            .clone()
+            .unwrap_or_else(UserConfig::default_path);
+        self.user_config.zoom_collision = self.controller.zoom_collision;
+        self.user_config.present_mode = self.present_mode;
+        self.user_config.max_fps = self.max_fps;
+        self.user_config.save(&path)
+    }
+
+    /// true if the actions are in the menu bar of the operating system
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn has_native_menu(&self) -> bool {
+        #[cfg(all(
+            feature = "native-menu",
+            any(target_os = "windows", target_os = "macos")
+        ))]
+        return self.native_menu.is_some();
+        #[cfg(not(all(
+            feature = "native-menu",
+            any(target_os = "windows", target_os = "macos")
+        )))]
+        false
+    }
+
+    /// runs an action triggered by a key or the menu bar
+    fn run_action(&mut self, action: Action) {
+        match action {
+            Action::ToggleTrackingShot => {
+                if self.animation.is_none() {
+                    self.start_tracking_shot();
+                } else {
+                    self.stop_animation()
+                }
+            }
+            Action::ToggleGallery => self.toggle_gallery(),
+            Action::ToggleInset => self.toggle_inset(),
+            Action::ToggleUi => self.ui_visible = !self.ui_visible,
+            Action::ToggleHud => self.hud_visible = !self.hud_visible,
+            Action::ToggleHelp => self.help_visible = !self.help_visible,
+            Action::ToggleFullscreen => self.toggle_fullscreen(),
+            Action::ToggleSyncFollow => {
+                if let Some(sync) = &mut self.sync {
+                    sync.set_detached(!sync.detached());
+                }
+            }
+            Action::ToggleCameraPath => self.toggle_camera_path(),
+            Action::IncreaseScaling => self.scale_splats(SCALING_STEP),
+            Action::DecreaseScaling => self.scale_splats(1. / SCALING_STEP),
+            Action::CycleShDegree => {
+                self.splatting_args.max_sh_deg =
+                    (self.splatting_args.max_sh_deg + 1) % (self.pc.sh_deg() + 1);
+            }
+            Action::SeekBackward => self.seek_animation(-1.),
+            Action::SeekForward => self.seek_animation(1.),
+            Action::SaveViewToScene => {
+                if let Err(err) = self.save_view_to_scene() {
+                    log::error!("failed to save view to scene: {:?}", err);
+                }
+            }
+            Action::SaveView => self.save_view(),
+            Action::Reload => {
+                if let Err(err) = self.reload() {
+                    log::error!("failed to reload volume: {:?}", err);
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::CapturePanorama => match self.capture_panorama() {
+                Ok(path) => log::info!("saved panorama to {:?}", path),
+                Err(err) => log::error!("failed to capture panorama: {:?}", err),
+            },
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::Screenshot => match self.save_screenshot() {
+                Ok(path) => log::info!("saved screenshot to {:?}", path),
+                Err(err) => log::error!("failed to save screenshot: {:?}", err),
+            },
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::Open => {
+                if let Err(err) = self.open_file_dialog() {
+                    log::error!("failed to open file: {:?}", err);
+                }
+            }
+            Action::RandomCamera
+            | Action::NearestCamera
+            | Action::NextCamera
+            | Action::PreviousCamera => {
+                let Some(scene) = &self.scene else {
+                    return;
+                };
+                let new_camera = match action {
+                    Action::RandomCamera => Some(self.rng.gen_range(0..scene.num_cameras())),
+                    Action::NearestCamera => {
+                        scene.nearest_camera(self.splatting_args.camera.position, None)
+                    }
+                    Action::NextCamera => {
+                        Some(self.current_view.map_or(0, |v| v + 1) % scene.num_cameras())
+                    }
+                    _ => Some(self.current_view.map_or(0, |v| v - 1) % scene.num_cameras()),
+                };
+                if let Some(new_camera) = new_camera {
+                    self.set_scene_camera(new_camera);
+                }
+            }
+            _ => {}
+        }
+    }
+
     fn save_view(&mut self) {
         let max_scene_id = if let Some(scene) = &self.scene {
             scene.cameras(None).iter().map(|c| c.id).max().unwrap_or(0)
@@ -646,24 +2730,278 @@ impl WindowContext {
             Split::Test,
         ));
     }
+
+    /// appends the current view to the loaded scene and writes the scene file back to disk
+    /// scenes that are not in the cameras.json format are written to a new file, see [Scene::save_path]
+    fn save_view_to_scene(&mut self) -> anyhow::Result<()> {
+        let scene_path = self
+            .scene_file_path
+            .clone()
+            .ok_or(anyhow::anyhow!("no scene file path present"))?;
+        let camera = SceneCamera::from_perspective(
+            self.splatting_args.camera,
+            String::new(),
+            0,
+            Vector2::new(self.config.width, self.config.height),
+            Split::Train,
+        );
+        let scene = self
+            .scene
+            .get_or_insert_with(|| Scene::from_cameras(Vec::new()));
+        let id = scene.add_camera(camera);
+        let saved_path = scene.save(&scene_path)?;
+        log::info!("saved view {id} to {:?}", saved_path);
+        self.scene_file_path = Some(saved_path);
+        Ok(())
+    }
+}
+
+/// starts computing the suggested views in the background
+/// errors are only logged, the viewer works without suggestions
+#[cfg(feature = "ui")]
+fn suggest_views(pc: &io::GenericGaussianPointCloud) -> Option<task::Task<Vec<io::SuggestedView>>> {
+    let suggester = pc
+        .view_suggester()
+        .map_err(|err| log::warn!("failed to suggest views: {:?}", err))
+        .ok()?;
+    let suggest = move |token: task::CancellationToken| {
+        let _span = tracing::info_span!("suggest_views").entered();
+        suggester.suggest(NUM_SUGGESTED_VIEWS, &token)
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    return Some(task::Task::spawn("suggesting views", suggest));
+    #[cfg(target_arch = "wasm32")]
+    return Some(task::Task::spawn_local(
+        "suggesting views",
+        |token| async move { suggest(token) },
+    ));
+}
+
+/// errors are only logged, zooming is not limited without a density grid
+#[cfg(feature = "ui")]
+fn density_grid(pc: &io::GenericGaussianPointCloud) -> Option<io::DensityGrid> {
+    let _span = tracing::info_span!("density_grid").entered();
+    pc.density_grid()
+        .map_err(|err| log::warn!("failed to compute density grid: {:?}", err))
+        .ok()
+}
+
+#[cfg(feature = "ui")]
+fn partitions(
+    pc: &io::GenericGaussianPointCloud,
+    density_grid: Option<&io::DensityGrid>,
+) -> Option<io::Partitions> {
+    let _span = tracing::info_span!("partitions").entered();
+    density_grid.map(|grid| pc.partitions(grid))
+}
+
+/// result of a background load
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+struct LoadedPointCloud {
+    pc_raw: io::GenericGaussianPointCloud,
+    partitions: Option<io::Partitions>,
+    density_grid: Option<io::DensityGrid>,
+    stats: Option<io::PointCloudStats>,
+    crop: Option<io::CropRegion>,
+    pc_path: PathBuf,
+    scene_path: Option<PathBuf>,
+    /// the point cloud replaces the current one without resetting the camera
+    reload: bool,
+}
+
+/// opens a local file or downloads a url into the cache of the remote loader
+/// the download stops when the token is cancelled and is resumed by the next attempt
+#[cfg(all(feature = "ui", feature = "remote", not(target_arch = "wasm32")))]
+fn open_remote(
+    path: &Path,
+    remote_loader: Option<io::remote::RemoteLoader>,
+    token: &task::CancellationToken,
+) -> anyhow::Result<std::fs::File> {
+    match (path.to_str(), remote_loader) {
+        (Some(url), Some(mut loader)) if io::remote::RemoteLoader::is_url(url) => {
+            loader.set_cancellation_token(token.clone());
+            loader.fetch(url)
+        }
+        _ => Ok(std::fs::File::open(path)?),
+    }
+}
+
+#[cfg(feature = "ui")]
+fn scaled_size(width: u32, height: u32, scale: f32) -> Vector2<u32> {
+    Vector2::new(
+        ((width as f32 * scale).round() as u32).max(1),
+        ((height as f32 * scale).round() as u32).max(1),
+    )
+}
+
+/// `mode` if the surface supports it, otherwise v-sync
+#[cfg(feature = "ui")]
+fn surface_present_mode(caps: &wgpu::SurfaceCapabilities, mode: PresentMode) -> PresentMode {
+    // the auto modes are always supported
+    if caps.present_modes.contains(&mode.into()) {
+        mode
+    } else {
+        log::warn!("present mode {:?} is not supported", mode);
+        PresentMode::AutoVsync
+    }
+}
+
+/// premultiplied alpha for transparent backgrounds, the surface is opaque otherwise
+/// the window is always created transparent, so opaque surfaces have to ignore the alpha channel
+#[cfg(feature = "ui")]
+fn surface_alpha_mode(
+    caps: &wgpu::SurfaceCapabilities,
+    transparent: bool,
+) -> wgpu::CompositeAlphaMode {
+    if !transparent {
+        return if caps.alpha_modes.contains(&wgpu::CompositeAlphaMode::Opaque) {
+            wgpu::CompositeAlphaMode::Opaque
+        } else {
+            wgpu::CompositeAlphaMode::Auto
+        };
+    }
+    if caps
+        .alpha_modes
+        .contains(&wgpu::CompositeAlphaMode::PreMultiplied)
+    {
+        wgpu::CompositeAlphaMode::PreMultiplied
+    } else {
+        log::warn!(
+            "surface does not support premultiplied alpha, the background is not transparent"
+        );
+        wgpu::CompositeAlphaMode::Auto
+    }
+}
+
+#[cfg(feature = "ui")]
+fn point_cloud_stats(pc: &io::GenericGaussianPointCloud) -> Option<io::PointCloudStats> {
+    pc.stats()
+        .map_err(|err| log::warn!("failed to compute point cloud statistics: {:?}", err))
+        .ok()
 }
 
 pub fn smoothstep(x: f32) -> f32 {
     return x * x * (3.0 - 2.0 * x);
 }
 
-pub async fn open_window<R: Read + Seek + Send + Sync + 'static>(
+/// shows an error message to the user
+/// native builds open a message dialog, the web build shows the error pane of the page
+#[cfg(feature = "ui")]
+fn show_error(err: &WebSplatError) {
+    log::error!("{}", err);
+    #[cfg(not(target_arch = "wasm32"))]
+    rfd::MessageDialog::new()
+        .set_level(rfd::MessageLevel::Error)
+        .set_title("web-splats")
+        .set_description(err.to_string())
+        .set_buttons(rfd::MessageButtons::Ok)
+        .show();
+    #[cfg(target_arch = "wasm32")]
+    if let Some(doc) = web_sys::window().and_then(|win| win.document()) {
+        if let Some(spinner) = doc.get_element_by_id("spinner") {
+            spinner.set_attribute("style", "display:none;").ok();
+        }
+        if let Some(pane) = doc.get_element_by_id("loading-error") {
+            pane.set_attribute("style", "display:flex;").ok();
+            if let Ok(Some(text)) = pane.query_selector("p") {
+                text.set_text_content(Some(&err.to_string()));
+            }
+        }
+    }
+}
+
+/// the interactive viewer window
+///
+/// ```no_run
+/// # async fn run() -> Result<(), web_splats::WebSplatError> {
+/// use web_splats::prelude::*;
+///
+/// let file = std::fs::File::open("point_cloud.ply")?;
+/// Viewer::new(RenderConfig::default())
+///     .open(file, None, Some("point_cloud.ply".into()), None)
+///     .await
+/// # }
+/// ```
+#[cfg(feature = "ui")]
+pub struct Viewer {
+    config: RenderConfig,
+}
+
+#[cfg(feature = "ui")]
+impl Viewer {
+    pub fn new(config: RenderConfig) -> Self {
+        Self { config }
+    }
+
+    /// opens the window with the point cloud in `file` and runs the event loop until the window is closed
+    /// the scene is read from `scene_file` or, if no reader is given, opened from `scene_file_path`
+    /// the paths are used to reload and watch the files
+    /// errors during setup are shown to the user before they are returned
+    pub async fn open<R: Read + Seek + Send + Sync + 'static>(
+        self,
+        file: R,
+        scene_file: Option<R>,
+        pointcloud_file_path: Option<PathBuf>,
+        scene_file_path: Option<PathBuf>,
+    ) -> Result<(), WebSplatError> {
+        open_window(
+            file,
+            scene_file,
+            self.config,
+            pointcloud_file_path,
+            scene_file_path,
+        )
+        .await
+    }
+}
+
+/// opens the viewer window and runs the event loop until the window is closed, see [Viewer::open]
+#[cfg(feature = "ui")]
+async fn open_window<R: Read + Seek + Send + Sync + 'static>(
     file: R,
     scene_file: Option<R>,
     config: RenderConfig,
     pointcloud_file_path: Option<PathBuf>,
     scene_file_path: Option<PathBuf>,
-) {
+) -> Result<(), WebSplatError> {
+    // a subscriber installed by the library user is kept, otherwise `RUST_LOG` controls the verbosity
     #[cfg(not(target_arch = "wasm32"))]
-    env_logger::init();
-    let event_loop = EventLoop::new().unwrap();
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init()
+        .ok();
+    let result = run_window(
+        file,
+        scene_file,
+        config,
+        pointcloud_file_path,
+        scene_file_path,
+    )
+    .await;
+    if let Err(err) = &result {
+        show_error(err);
+    }
+    result
+}
+
+#[cfg(feature = "ui")]
+async fn run_window<R: Read + Seek + Send + Sync + 'static>(
+    file: R,
+    scene_file: Option<R>,
+    config: RenderConfig,
+    pointcloud_file_path: Option<PathBuf>,
+    scene_file_path: Option<PathBuf>,
+) -> Result<(), WebSplatError> {
+    let event_loop = EventLoop::new()?;
 
-    let scene = scene_file.and_then(|f| match Scene::from_json(f) {
+    // scenes given by path can also be colmap reconstructions, see [Scene::open]
+    let scene = match (scene_file, &scene_file_path) {
+        (Some(f), _) => Some(Scene::from_json(f)),
+        #[cfg(not(target_arch = "wasm32"))]
+        (None, Some(path)) => Some(Scene::open(path)),
+        _ => None,
+    }
+    .and_then(|result| match result {
         Ok(s) => Some(s),
         Err(err) => {
             log::error!("cannot load scene: {:?}", err);
@@ -671,8 +3009,9 @@ pub async fn open_window<R: Read + Seek + Send + Sync + 'static>(
         }
     });
 
-    let window_size = if let Some(scene) = &scene {
-        let camera = scene.camera(0).unwrap();
+    let window_size = if let Some(size) = config.window_size {
+        PhysicalSize::new(size.x, size.y)
+    } else if let Some(camera) = scene.as_ref().and_then(|s| s.camera(0)) {
         let factor = 1200. / camera.width as f32;
         PhysicalSize::new(
             (camera.width as f32 * factor) as u32,
@@ -682,11 +3021,17 @@ pub async fn open_window<R: Read + Seek + Send + Sync + 'static>(
         PhysicalSize::new(800, 600)
     };
 
+    // the background can be made transparent at runtime, see surface_alpha_mode
     let window = WindowBuilder::new()
         .with_title("web-splats")
         .with_inner_size(window_size)
-        .build(&event_loop)
-        .unwrap();
+        .with_transparent(true)
+        .with_fullscreen(
+            config
+                .fullscreen
+                .map(|mode| mode.to_winit(event_loop.primary_monitor())),
+        )
+        .build(&event_loop)?;
 
     #[cfg(target_arch = "wasm32")]
     {
@@ -714,6 +3059,7 @@ pub async fn open_window<R: Read + Seek + Send + Sync + 'static>(
     }
 
     // limit the redraw rate to the monitor refresh rate
+    #[cfg(not(target_arch = "wasm32"))]
     let min_wait = window
         .current_monitor()
         .map(|m| {
@@ -722,21 +3068,59 @@ pub async fn open_window<R: Read + Seek + Send + Sync + 'static>(
         })
         .unwrap_or(Duration::from_millis(17));
 
-    let mut state = WindowContext::new(window, file, &config).await.unwrap();
+    let mut state = WindowContext::new(window, file, scene.as_ref(), &config).await?;
+    if let Some(path) = &pointcloud_file_path {
+        state.gallery.add_recent(path, scene_file_path.as_deref());
+    }
     state.pointcloud_file_path = pointcloud_file_path;
+    state.update_metadata(state.metadata.clone());
+    state.log_performance_hints();
+    #[cfg(not(target_arch = "wasm32"))]
+    if config.watch {
+        state.watch_pointcloud_file();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if !config.sequence.is_empty() {
+        if let Err(err) = state.load_sequence(&config.sequence, config.sequence_fps) {
+            log::error!("failed to load sequence: {:?}", err);
+        }
+    }
 
     if let Some(scene) = scene {
         state.set_scene(scene);
-        state.set_scene_camera(0);
+        state.set_scene_camera(config.camera.unwrap_or(0));
         state.scene_file_path = scene_file_path;
     }
 
+    if let Some(camera_path) = &config.camera_path {
+        match std::fs::File::open(camera_path)
+            .map_err(anyhow::Error::from)
+            .and_then(CameraPath::from_json)
+        {
+            Ok(path) => state.set_camera_path(path),
+            Err(e) => log::error!("failed to load camera path: {e}"),
+        }
+    }
+
     if let Some(skybox) = &config.skybox {
         if let Err(e) = state.set_env_map(skybox.as_path()) {
             log::error!("failed do set skybox: {e}");
         }
     }
 
+    if let Some(feed) = &config.feed {
+        if let Err(e) = state.load_feed(feed) {
+            log::error!("failed to load feed: {e}");
+        }
+    }
+
+    if let Some(duration) = config.reveal {
+        let center = state.pc.bbox().center();
+        let bbox = *state.pc.bbox();
+        state.animate_clipping_box(Some(Aabb::new(center, center)), bbox, duration, smoothstep);
+    }
+
     #[cfg(target_arch = "wasm32")]
     web_sys::window()
         .and_then(|win| win.document())
@@ -775,53 +3159,30 @@ pub async fn open_window<R: Read + Seek + Send + Sync + 'static>(
             WindowEvent::CloseRequested => {log::info!("close!");target.exit()},
             WindowEvent::ModifiersChanged(m)=>{
                 state.controller.alt_pressed = m.state().alt_key();
+                state.controller.ctrl_pressed = m.state().control_key();
             }
             WindowEvent::KeyboardInput { event, .. } => {
                 if let PhysicalKey::Code(key) = event.physical_key{
                 if event.state == ElementState::Released{
-
-                    if key == KeyCode::KeyT{
-                        if state.animation.is_none(){
-                            state.start_tracking_shot();
-                        }else{
-                            state.stop_animation()
-                        }
-                    }else if key == KeyCode::KeyU{
-                        state.ui_visible = !state.ui_visible;
-                        
-                    }else if key == KeyCode::KeyC{
-                        state.save_view();
-                    } else  if key == KeyCode::KeyR && state.controller.alt_pressed{
-                        if let Err(err) = state.reload(){
-                            log::error!("failed to reload volume: {:?}", err);
-                        }   
-                    }else if let Some(scene) = &state.scene{
-
-                        let new_camera = 
-                        if let Some(num) = key_to_num(key){
-                            Some(num as usize)
-                        }
-                        else if key == KeyCode::KeyR{
-                            Some(rand::random::<usize>()%scene.num_cameras())
-                        }else if key == KeyCode::KeyN{
-                            scene.nearest_camera(state.splatting_args.camera.position,None)
-                        }else if key == KeyCode::PageUp{
-                            Some(state.current_view.map_or(0, |v|v+1) % scene.num_cameras())
-                        }else if key == KeyCode::KeyT{
-                            Some(state.current_view.map_or(0, |v|v+1) % scene.num_cameras())
-                        }
-                        else if key == KeyCode::PageDown{
-                            Some(state.current_view.map_or(0, |v|v-1) % scene.num_cameras())
-                        }else{None};
-
-                        if let Some(new_camera) = new_camera{
-                            state.set_scene_camera(new_camera);
+                    let action = state.keys.action(key, state.controller.alt_pressed, state.controller.ctrl_pressed);
+                    match action {
+                        Some(action) => state.run_action(action),
+                        None => if let Some(num) = key_to_num(key) {
+                            if state.scene.is_some() {
+                                state.set_scene_camera(num as usize);
+                            }
                         }
                     }
                 }
-                state
-                    .controller
-                    .process_keyboard(key, event.state == ElementState::Pressed);
+                if let Some(action) = state.keys.navigation(
+                    key,
+                    state.controller.alt_pressed,
+                    state.controller.ctrl_pressed,
+                ) {
+                    state
+                        .controller
+                        .process_keyboard(action, event.state == ElementState::Pressed);
+                }
             }
             }
             WindowEvent::MouseWheel { delta, .. } => match delta {
@@ -832,6 +3193,13 @@ pub async fn open_window<R: Read + Seek + Send + Sync + 'static>(
                     state.controller.process_scroll(p.y as f32 / 100.)
                 }
             },
+            WindowEvent::Touch(touch) => {
+                state.controller.process_touch(
+                    touch.id,
+                    touch.phase,
+                    Vector2::new(touch.location.x as f32, touch.location.y as f32),
+                );
+            }
             WindowEvent::MouseInput { state:button_state, button, .. }=>{
                 match button {
                     winit::event::MouseButton::Left =>                         state.controller.left_mouse_pressed = *button_state == ElementState::Pressed,
@@ -840,38 +3208,72 @@ pub async fn open_window<R: Read + Seek + Send + Sync + 'static>(
                 }
             }
             WindowEvent::RedrawRequested => {
-                if !config.no_vsync{
+                let now = Instant::now();
+                // the frame rate cap postpones redraws until the frame time has passed
+                if let Some(frame_time) = state.min_frame_time().filter(|t| now - last < *t) {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    target.set_control_flow(ControlFlow::WaitUntil(last + frame_time));
+                    #[cfg(target_arch = "wasm32")]
+                    {
+                        let _ = frame_time;
+                        state.window.request_redraw();
+                    }
+                    return;
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if state.present_mode.is_vsync(){
                     // make sure the next redraw is called with a small delay
                     target.set_control_flow(ControlFlow::wait_duration(min_wait));
+                } else {
+                    target.set_control_flow(ControlFlow::Wait);
                 }
-                let now = Instant::now();
                 let dt = now-last;
                 last = now;
 
                 let old_settings = state.splatting_args.clone();
-                state.update(dt);
+                let scene_changed = state.update(dt);
 
                 let (redraw_ui,shapes) = state.ui();
 
                 let resolution_change = state.splatting_args.resolution != Vector2::new(state.config.width, state.config.height);
 
-                let request_redraw = old_settings != state.splatting_args || resolution_change;
+                // stochastic transparency accumulates frames until the view converges
+                let request_redraw = scene_changed || old_settings != state.splatting_args || resolution_change
+                    || !state.renderer.is_converged();
     
                 if request_redraw || redraw_ui{
                     state.fps = (1. / dt.as_secs_f32()) * 0.05 + state.fps * 0.95;
-                    match state.render(request_redraw,state.ui_visible.then_some(shapes)) {
+                    match state.render(request_redraw,(state.ui_visible || state.hud_visible || state.help_visible).then_some(shapes)) {
                         Ok(_) => {}
                         // Reconfigure the surface if lost
-                        Err(wgpu::SurfaceError::Lost) => state.resize(state.window.inner_size(), None),
+                        Err(wgpu::SurfaceError::Lost) => {
+                            tracing::warn!("surface lost, reconfiguring");
+                            state.resize(state.window.inner_size(), None)
+                        }
                         // The system is out of memory, we should probably quit
-                        Err(wgpu::SurfaceError::OutOfMemory) =>target.exit(),
+                        Err(wgpu::SurfaceError::OutOfMemory) => {
+                            tracing::error!("surface out of memory");
+                            target.exit()
+                        }
                         // All other errors (Outdated, Timeout) should be resolved by the next frame
-                        Err(e) => println!("error: {:?}", e),
+                        Err(e) => tracing::warn!(error = ?e, "surface error"),
                     }
+                    let cpu_time = now.elapsed().as_secs_f32() * 1000.;
+                    state.cpu_frame_time = cpu_time * 0.05 + state.cpu_frame_time * 0.95;
                 }
-                if config.no_vsync{
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    state.check_file_changes();
+                    state.update_thumbnail();
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if !state.present_mode.is_vsync(){
                     state.window.request_redraw();
                 }
+                // on the web redraw requests are scheduled with requestAnimationFrame
+                // so the browser limits the redraw rate to the display refresh rate
+                #[cfg(target_arch = "wasm32")]
+                state.window.request_redraw();
             }
             _ => {}
         },
@@ -882,10 +3284,137 @@ pub async fn open_window<R: Read + Seek + Send + Sync + 'static>(
             state.controller.process_mouse(delta.0 as f32, delta.1 as f32)
         }
         _ => {},
-    }).unwrap();
+    })?;
+    Ok(())
+}
+
+/// query parameters of the page url
+#[cfg(all(feature = "ui", target_arch = "wasm32"))]
+fn query_params() -> Option<web_sys::UrlSearchParams> {
+    let search = web_sys::window()?.location().search().ok()?;
+    web_sys::UrlSearchParams::new_with_str(&search).ok()
+}
+
+#[cfg(all(feature = "ui", target_arch = "wasm32"))]
+fn query_param<T: std::str::FromStr>(
+    params: &Option<web_sys::UrlSearchParams>,
+    name: &str,
+) -> Option<T> {
+    params.as_ref()?.get(name)?.parse().ok()
+}
+
+/// reloads the page with the point cloud (and scene) url in the query parameters
+#[cfg(all(feature = "ui", target_arch = "wasm32"))]
+pub(crate) fn open_page(pc_url: &Path, scene_url: Option<&Path>) {
+    let encode = |url: &Path| String::from(js_sys::encode_uri_component(&url.to_string_lossy()));
+    let mut search = format!("?file={}", encode(pc_url));
+    if let Some(scene_url) = scene_url {
+        search += &format!("&scene={}", encode(scene_url));
+    }
+    if let Some(location) = web_sys::window().map(|win| win.location()) {
+        if let Err(err) = location.set_search(&search) {
+            log::error!("cannot open {:?}: {:?}", pc_url, err);
+        }
+    }
+}
+
+/// render config of the web viewer
+/// `camera`, `sh`, `seed`, `reveal`, `chunked_upload`, `low_memory`, `lod`, `half_precision`, `packed_covariance`, `filter_3d`, `transparent`, `sync`, `present`, `gallery`, `stereo`, `ipd` and `xr` can be set with url query parameters
+/// e.g. `?file=point_cloud.ply&scene=cameras.json&camera=3&sh=1`
+#[cfg(all(feature = "ui", target_arch = "wasm32"))]
+fn web_render_config() -> RenderConfig {
+    let params = query_params();
+    RenderConfig {
+        no_vsync: false,
+        skybox: None,
+        hdr: false,
+        sh_storage: ShStorage::Buffer,
+        camera_path: None,
+        show_gallery: query_param(&params, "gallery").unwrap_or(false),
+        watch: false,
+        sequence: Vec::new(),
+        sequence_fps: 30.,
+        low_memory: query_param(&params, "low_memory").unwrap_or(false),
+        lod: query_param(&params, "lod").unwrap_or(false),
+        half_precision: query_param(&params, "half_precision").unwrap_or(false),
+        packed_covariance: query_param(&params, "packed_covariance").unwrap_or(false),
+        filter_3d: query_param(&params, "filter_3d"),
+        chunked_upload: query_param(&params, "chunked_upload").unwrap_or(true),
+        seed: query_param(&params, "seed"),
+        camera: query_param(&params, "camera"),
+        max_sh_deg: query_param(&params, "sh"),
+        crop: None,
+        reveal: query_param(&params, "reveal").map(Duration::from_secs_f32),
+        feed: None,
+        window_size: None,
+        fullscreen: None,
+        background_color: None,
+        transparent: query_param(&params, "transparent").unwrap_or(false),
+        no_gui: query_param(&params, "no_gui").unwrap_or(false),
+        event_log: None,
+        auto_exposure: None,
+        glare_filter: query_param(&params, "glare")
+            .unwrap_or(false)
+            .then(GlareFilter::default),
+        depth_of_field: query_param(&params, "dof_focus").map(|focus_distance| DepthOfField {
+            focus_distance,
+            aperture: query_param(&params, "dof_aperture").unwrap_or(8.),
+        }),
+        bloom: query_param(&params, "bloom")
+            .unwrap_or(false)
+            .then(Bloom::default),
+        vignette: query_param(&params, "vignette").unwrap_or(0.),
+        sharpening: query_param(&params, "sharpening").unwrap_or(0.),
+        color_grading: ColorGrading {
+            gamma: query_param(&params, "gamma").unwrap_or(1.),
+            saturation: query_param(&params, "saturation").unwrap_or(1.),
+            temperature: query_param(&params, "temperature").unwrap_or(0.),
+            tint: query_param(&params, "tint").unwrap_or(0.),
+        },
+        fog: query_param(&params, "fog_end").map(|end| {
+            Fog::linear(
+                wgpu::Color::BLACK,
+                query_param(&params, "fog_start").unwrap_or(0.),
+                end,
+            )
+        }),
+        // VR shows the side by side stereo view
+        stereo: query_param(&params, "stereo")
+            .or(query_param(&params, "xr")
+                .unwrap_or(false)
+                .then_some(StereoMode::SideBySide))
+            .map(|mode| Stereo {
+                mode,
+                ipd: query_param(&params, "ipd").unwrap_or(Stereo::default().ipd),
+            }),
+        grid: Vec::new(),
+        tonemapping: query_param(&params, "tonemapping").unwrap_or_default(),
+        color_space: query_param(&params, "color_space").unwrap_or_default(),
+        output_color_space: query_param(&params, "output_color_space").unwrap_or_default(),
+        render_mode: query_param(&params, "render_mode").unwrap_or_default(),
+        transparency: query_param(&params, "transparency").unwrap_or_default(),
+        msaa: query_param(&params, "msaa").unwrap_or(1),
+        render_scale: query_param(&params, "render_scale").unwrap_or(1.),
+        dynamic_resolution: query_param(&params, "dynamic_resolution").map(|motion_scale| {
+            DynamicResolution {
+                motion_scale,
+                ..Default::default()
+            }
+        }),
+        sync: query_param::<String>(&params, "sync").map(|url| SyncConfig {
+            url,
+            presenter: query_param(&params, "present").unwrap_or(false),
+        }),
+        user_config: UserConfig {
+            max_fps: query_param(&params, "max_fps"),
+            ..Default::default()
+        },
+        user_config_path: None,
+        encryption_key: None,
+    }
 }
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(feature = "ui", target_arch = "wasm32"))]
 #[wasm_bindgen]
 pub async fn run_wasm(
     pc: Vec<u8>,
@@ -900,15 +3429,81 @@ pub async fn run_wasm(
     let pc_reader = Cursor::new(pc);
     let scene_reader = scene.map(|d: Vec<u8>| Cursor::new(d));
 
-    wasm_bindgen_futures::spawn_local(open_window(
-        pc_reader,
-        scene_reader,
-        RenderConfig {
-            no_vsync: false,
-            skybox: None,
-            hdr: false,
-        },
-        pc_file.and_then(|s| PathBuf::from_str(s.as_str()).ok()),
-        scene_file.and_then(|s| PathBuf::from_str(s.as_str()).ok()),
-    ));
+    wasm_bindgen_futures::spawn_local(async move {
+        // the error is already shown on the page
+        open_window(
+            pc_reader,
+            scene_reader,
+            web_render_config(),
+            pc_file.and_then(|s| PathBuf::from_str(s.as_str()).ok()),
+            scene_file.and_then(|s| PathBuf::from_str(s.as_str()).ok()),
+        )
+        .await
+        .ok();
+    });
+}
+
+/// downloads a file with the fetch api
+/// the token is sent as `Authorization: Bearer <token>` header
+#[cfg(all(feature = "ui", target_arch = "wasm32"))]
+async fn fetch_bytes(
+    url: &str,
+    auth_token: Option<&str>,
+) -> Result<Vec<u8>, wasm_bindgen::JsValue> {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    let window = web_sys::window().ok_or("no window")?;
+    let headers = web_sys::Headers::new()?;
+    if let Some(token) = auth_token {
+        headers.set("Authorization", &format!("Bearer {token}"))?;
+    }
+    let mut init = web_sys::RequestInit::new();
+    init.method("GET").headers(&headers);
+    let request = web_sys::Request::new_with_str_and_init(url, &init)?;
+    let response: web_sys::Response = JsFuture::from(window.fetch_with_request(&request))
+        .await?
+        .dyn_into()?;
+    if !response.ok() {
+        return Err(format!("cannot download '{}': {}", url, response.status_text()).into());
+    }
+    let data = JsFuture::from(response.array_buffer()?).await?;
+    Ok(js_sys::Uint8Array::new(&data).to_vec())
+}
+
+/// entry point for the web viewer
+/// downloads the point cloud (and the optional scene file) and opens the viewer in a canvas appended to the document body
+/// if no urls are given, they are read from the `file` and `scene` query parameters of the page url
+/// or the most recently opened scene of the gallery is loaded
+/// private files can be loaded with signed urls or by passing a bearer token
+#[cfg(all(feature = "ui", target_arch = "wasm32"))]
+#[wasm_bindgen]
+pub async fn start(
+    pc_url: Option<String>,
+    scene_url: Option<String>,
+    auth_token: Option<String>,
+) -> Result<(), wasm_bindgen::JsValue> {
+    let params = query_params();
+    let (pc_url, scene_url) = match pc_url.or_else(|| query_param(&params, "file")) {
+        Some(pc_url) => (pc_url, scene_url.or_else(|| query_param(&params, "scene"))),
+        None => {
+            let gallery = Gallery::load(Gallery::default_dir());
+            let recent = gallery
+                .most_recent()
+                .cloned()
+                .ok_or("no point cloud file specified")?;
+            (
+                recent.pointcloud.to_string_lossy().to_string(),
+                recent.scene.map(|s| s.to_string_lossy().to_string()),
+            )
+        }
+    };
+
+    let pc = fetch_bytes(&pc_url, auth_token.as_deref()).await?;
+    let scene = match &scene_url {
+        Some(url) => Some(fetch_bytes(url, auth_token.as_deref()).await?),
+        None => None,
+    };
+    run_wasm(pc, scene, Some(pc_url), scene_url).await;
+    Ok(())
 }
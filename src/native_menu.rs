@@ -0,0 +1,121 @@
+//! native menu bar of the viewer window (windows and macos)
+//! the entries trigger the same actions as the egui menu bar, which only keeps the loading indicator while the native menu is attached
+//! linux is not supported, muda can only attach menus to gtk windows
+
+use std::sync::{
+    mpsc::{self, Receiver},
+    Arc,
+};
+
+use muda::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu};
+use winit::window::Window;
+
+use crate::keymap::Action;
+
+pub(crate) struct NativeMenu {
+    // the menu is detached when it is dropped
+    _menu: Menu,
+    items: Vec<(MenuItem, Action)>,
+    events: Receiver<MenuId>,
+}
+
+impl NativeMenu {
+    /// attaches the menu bar to the window (windows) or the application (macos)
+    pub fn new(window: Arc<Window>) -> anyhow::Result<Self> {
+        let mut items = Vec::new();
+        let mut item = |label: &str, action: Action| {
+            let menu_item = MenuItem::new(label, true, None);
+            items.push((menu_item.clone(), action));
+            menu_item
+        };
+        let separator = PredefinedMenuItem::separator;
+
+        let file = Submenu::with_items(
+            "&File",
+            true,
+            &[
+                &item("&Open…", Action::Open),
+                &item("&Reload", Action::Reload),
+                &item("&Gallery", Action::ToggleGallery),
+            ],
+        )?;
+        let view = Submenu::with_items(
+            "&View",
+            true,
+            &[
+                &item("User Interface", Action::ToggleUi),
+                &item("HUD", Action::ToggleHud),
+                &item("Keyboard Shortcuts", Action::ToggleHelp),
+                &separator(),
+                &item("Rear View", Action::ToggleInset),
+                &item("Fullscreen", Action::ToggleFullscreen),
+                &separator(),
+                &item("Next Camera", Action::NextCamera),
+                &item("Previous Camera", Action::PreviousCamera),
+                &item("Nearest Camera", Action::NearestCamera),
+                &separator(),
+                &item("Tracking Shot", Action::ToggleTrackingShot),
+                &item("Camera Path", Action::ToggleCameraPath),
+            ],
+        )?;
+        let export = Submenu::with_items(
+            "&Export",
+            true,
+            &[
+                &item("Screenshot", Action::Screenshot),
+                &item("Capture Panorama", Action::CapturePanorama),
+                &separator(),
+                &item("Save View", Action::SaveView),
+                &item("Save View to Scene", Action::SaveViewToScene),
+            ],
+        )?;
+
+        let menu = Menu::new();
+        // the first submenu is the application menu on macos
+        #[cfg(target_os = "macos")]
+        menu.append(&Submenu::with_items(
+            "Web Splat",
+            true,
+            &[&PredefinedMenuItem::quit(None)],
+        )?)?;
+        menu.append_items(&[&file, &view, &export])?;
+
+        #[cfg(target_os = "windows")]
+        {
+            use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+            let RawWindowHandle::Win32(handle) = window.window_handle()?.as_raw() else {
+                anyhow::bail!("window is not a win32 window");
+            };
+            unsafe { menu.init_for_hwnd(handle.hwnd.get()) }?;
+        }
+        #[cfg(target_os = "macos")]
+        menu.init_for_nsapp();
+
+        // the event loop waits for window events, so the handler requests a redraw to process the clicks
+        let (sender, events) = mpsc::channel();
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            if sender.send(event.id).is_ok() {
+                window.request_redraw();
+            }
+        }));
+
+        Ok(Self {
+            _menu: menu,
+            items,
+            events,
+        })
+    }
+
+    /// actions of the entries clicked since the last call
+    pub fn actions(&self) -> Vec<Action> {
+        self.events
+            .try_iter()
+            .filter_map(|id| {
+                self.items
+                    .iter()
+                    .find(|(item, _)| item.id() == &id)
+                    .map(|(_, action)| *action)
+            })
+            .collect()
+    }
+}
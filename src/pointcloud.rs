@@ -8,7 +8,9 @@ use std::fmt::Debug;
 use std::mem;
 use wgpu::util::DeviceExt;
 
-use crate::io::GenericGaussianPointCloud;
+use crate::animation::Lerp;
+use crate::frame_arena::FrameArena;
+use crate::io::{GenericGaussianPointCloud, LodRange, Octree, DEFAULT_LOD_THRESHOLD};
 use crate::uniform::UniformBuffer;
 
 #[repr(C)]
@@ -33,14 +35,54 @@ impl Default for Gaussian {
     }
 }
 
+/// positions are kept at full precision, the covariance is stored as f16
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Gaussian {
-    pub xyz: Point3<f16>,
-    pub opacity: f16,
+    pub xyz: Point3<f32>,
+    pub opacity: f32,
     pub cov: [f16; 6],
 }
 
+/// gpu memory layout of uncompressed splats
+/// positions and opacities are always stored as f32, the half precision layout stores [Gaussian] as is
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GaussianLayout {
+    /// covariances and sh coefficients are stored as f16, see [crate::io::LoadOptions::half_precision]
+    pub half_precision: bool,
+    /// log scale range of the covariances, which are packed into a rotation and a scale
+    /// with 10 bits per component, the covariances are stored as f16 if not set
+    pub packed_covariance: Option<(f32, f32)>,
+}
+
+impl GaussianLayout {
+    /// size of one splat in bytes
+    pub fn size(&self) -> usize {
+        let cov = if self.packed_covariance.is_some() {
+            mem::size_of::<[u32; 2]>()
+        } else if self.half_precision {
+            mem::size_of::<[f16; 6]>()
+        } else {
+            mem::size_of::<[f32; 6]>()
+        };
+        mem::size_of::<[f32; 4]>() + cov
+    }
+
+    /// size of the sh coefficients of one splat in bytes
+    pub fn sh_size(&self) -> usize {
+        if self.half_precision {
+            mem::size_of::<[[f16; 3]; 16]>()
+        } else {
+            mem::size_of::<[[f32; 3]; 16]>()
+        }
+    }
+
+    /// number of Rgba32Uint texels that store the sh coefficients of one splat, see [ShStorage::Texture]
+    pub fn sh_texels(&self) -> u32 {
+        (self.sh_size() / mem::size_of::<[u32; 4]>()) as u32
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Covariance3D(pub [f16; 6]);
@@ -51,16 +93,71 @@ impl Default for Covariance3D {
     }
 }
 
+/// GPU memory layout used for the spherical harmonics coefficients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShStorage {
+    /// a single storage buffer containing all coefficients
+    #[default]
+    Buffer,
+    /// a 2D texture array that is indexed by the splat index
+    /// avoids the storage buffer binding size limit and improves cache behavior on some GPUs
+    Texture,
+}
+
+/// layout of the sh coefficients in the texture array
+/// the shaders derive it from the texture size, see sh_texture.wgsl
+#[derive(Debug, Clone, Copy)]
+pub struct ShTextureLayout {
+    /// texels of one splat, see [GaussianLayout::sh_texels]
+    pub texels_per_splat: u32,
+    pub splats_per_row: u32,
+    pub rows_per_layer: u32,
+}
+
+impl ShTextureLayout {
+    pub fn new(limits: &wgpu::Limits, layout: GaussianLayout) -> Self {
+        Self {
+            texels_per_splat: layout.sh_texels(),
+            splats_per_row: limits.max_texture_dimension_2d / layout.sh_texels(),
+            rows_per_layer: limits.max_texture_dimension_2d,
+        }
+    }
+
+    /// texture size required to store the coefficients of `num_points` splats
+    pub fn texture_size(&self, num_points: u32) -> wgpu::Extent3d {
+        let rows = num_points.div_ceil(self.splats_per_row).max(1);
+        let height = rows.min(self.rows_per_layer);
+        wgpu::Extent3d {
+            width: self.splats_per_row * self.texels_per_splat,
+            height,
+            depth_or_array_layers: rows.div_ceil(height),
+        }
+    }
+}
+
 #[allow(dead_code)]
 pub struct PointCloud {
     splat_2d_buffer: wgpu::Buffer,
 
     bind_group: wgpu::BindGroup,
     render_bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    sh_buffer: Option<wgpu::Buffer>,
+    sh_texture: Option<wgpu::Texture>,
+    /// one bit per splat, selected splats are highlighted
+    selection_buffer: wgpu::Buffer,
+    /// one bit per splat, hidden splats are culled
+    hidden_buffer: wgpu::Buffer,
     num_points: u32,
+    /// number of splats that are uploaded, see [ChunkedUpload]
+    num_loaded: u32,
     sh_deg: u32,
     bbox: Aabb<f32>,
     compressed: bool,
+    layout: GaussianLayout,
+    sh_storage: ShStorage,
+    /// bytes of all buffers and textures of the point cloud
+    gpu_memory: u64,
 
     center: Point3<f32>,
     up: Option<Vector3<f32>>,
@@ -68,6 +165,29 @@ pub struct PointCloud {
     mip_splatting: Option<bool>,
     kernel_size: Option<f32>,
     background_color: Option<wgpu::Color>,
+
+    lod: Option<Lod>,
+}
+
+/// level of detail hierarchy of a point cloud, see [GenericGaussianPointCloud::build_lod]
+pub struct Lod {
+    octree: Octree,
+    /// ranges of the selected splats, see [LodRange]
+    ranges_buffer: wgpu::Buffer,
+    /// nodes smaller than this on screen (in pixels) are drawn with their proxies
+    pub threshold: f32,
+    /// draw the selected nodes, all original splats are drawn otherwise
+    pub enabled: bool,
+}
+
+impl Lod {
+    pub fn octree(&self) -> &Octree {
+        &self.octree
+    }
+
+    pub(crate) fn ranges_buffer(&self) -> &wgpu::Buffer {
+        &self.ranges_buffer
+    }
 }
 
 impl Debug for PointCloud {
@@ -83,6 +203,73 @@ impl PointCloud {
         device: &wgpu::Device,
         pc: GenericGaussianPointCloud,
     ) -> Result<Self, anyhow::Error> {
+        Self::create(device, None, &pc, false)
+    }
+
+    /// creates a point cloud with the given sh coefficient storage backend
+    /// compressed point clouds always use [ShStorage::Buffer]
+    pub fn new_with_sh_storage(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pc: GenericGaussianPointCloud,
+        sh_storage: ShStorage,
+    ) -> Result<Self, anyhow::Error> {
+        Self::create(
+            device,
+            Self::sh_texture_queue(queue, &pc, sh_storage),
+            &pc,
+            false,
+        )
+    }
+
+    /// the queue to upload the sh texture with, `None` if the coefficients are stored in a buffer
+    fn sh_texture_queue<'a>(
+        queue: &'a wgpu::Queue,
+        pc: &GenericGaussianPointCloud,
+        sh_storage: ShStorage,
+    ) -> Option<&'a wgpu::Queue> {
+        match sh_storage {
+            ShStorage::Texture if pc.compressed() => {
+                log::warn!("texture sh storage is not supported for compressed point clouds");
+                None
+            }
+            ShStorage::Texture => Some(queue),
+            ShStorage::Buffer => None,
+        }
+    }
+
+    /// if `sh_texture_queue` is set, the sh coefficients are uploaded into a texture array
+    /// if `chunked` is set, the splat buffers (and sh texture) are left empty and filled by [ChunkedUpload]
+    fn create(
+        device: &wgpu::Device,
+        sh_texture_queue: Option<&wgpu::Queue>,
+        pc: &GenericGaussianPointCloud,
+        chunked: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let max_binding_size = (device.limits().max_storage_buffer_binding_size as u64)
+            .min(device.limits().max_buffer_size);
+        let mut layout = GaussianLayout::default();
+        if !pc.compressed() {
+            layout.half_precision = pc.half_precision;
+            if pc.packed_covariance {
+                layout.packed_covariance = Some(pc.log_scale_range()?);
+            }
+            // pack the covariances if the splats do not fit into a single binding
+            if (pc.num_points * layout.size()) as u64 > max_binding_size
+                && layout.packed_covariance.is_none()
+            {
+                log::info!("splats exceed storage buffer binding size, packing covariances");
+                layout.packed_covariance = Some(pc.log_scale_range()?);
+            }
+        }
+        let gaussian_buffer_size = (pc.num_points * pc.gpu_gaussian_size(layout)) as u64;
+        if gaussian_buffer_size > max_binding_size {
+            return Err(anyhow::anyhow!(
+                "splats ({} bytes) exceed storage buffer binding size ({} bytes)",
+                gaussian_buffer_size,
+                max_binding_size
+            ));
+        }
         let splat_2d_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("2d gaussians buffer"),
             size: (pc.num_points * mem::size_of::<Splat>()) as u64,
@@ -99,17 +286,79 @@ impl PointCloud {
             }],
         });
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("3d gaussians buffer"),
-            contents: pc.gaussian_buffer(),
+        let vertex_buffer = if chunked {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("3d gaussians buffer"),
+                size: gaussian_buffer_size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        } else {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("3d gaussians buffer"),
+                contents: &pc.gpu_gaussian_buffer(0..pc.num_points, layout),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            })
+        };
+
+        let sh_storage = if sh_texture_queue.is_some() {
+            ShStorage::Texture
+        } else {
+            ShStorage::Buffer
+        };
+        let sh_buffer = sh_texture_queue.is_none().then(|| {
+            // compressed point clouds index the sh coefficients, so they are always uploaded at once
+            if chunked && !pc.compressed() {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("sh coefs buffer"),
+                    size: pc.gpu_sh_buffer_size(layout) as u64,
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            } else {
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("sh coefs buffer"),
+                    contents: &pc.gpu_sh_buffer(0..pc.num_points, layout),
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                })
+            }
+        });
+        let sh_texture = sh_texture_queue
+            .map(|queue| Self::create_sh_texture(device, queue, pc, layout, chunked));
+        let sh_texture_view = sh_texture
+            .as_ref()
+            .map(|texture| texture.create_view(&Default::default()));
+        // the shaders always bind the lod ranges, a single entry disables them
+        let lod_ranges_size =
+            pc.lod.as_ref().map_or(1, |octree| octree.max_ranges()) * mem::size_of::<LodRange>();
+        let lod_ranges_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("lod ranges buffer"),
+            size: lod_ranges_size as u64,
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
-
-        let sh_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("sh coefs buffer"),
-            contents: pc.sh_coefs_buffer(),
+        let selection_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("selection buffer"),
+            size: (pc.num_points.div_ceil(32).max(1) * mem::size_of::<u32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let hidden_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("hidden splats buffer"),
+            size: selection_buffer.size(),
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
+        let mut gpu_memory = splat_2d_buffer.size()
+            + lod_ranges_buffer.size()
+            + selection_buffer.size()
+            + hidden_buffer.size()
+            + vertex_buffer.size()
+            + sh_buffer.as_ref().map_or(0, |b| b.size())
+            + sh_texture.as_ref().map_or(0, |t| {
+                // Rgba32Uint
+                t.width() as u64 * t.height() as u64 * t.depth_or_array_layers() as u64 * 16
+            });
 
         let mut bind_group_entries = vec![
             wgpu::BindGroupEntry {
@@ -118,12 +367,28 @@ impl PointCloud {
             },
             wgpu::BindGroupEntry {
                 binding: 1,
-                resource: sh_buffer.as_entire_binding(),
+                resource: match (&sh_buffer, &sh_texture_view) {
+                    (Some(buffer), _) => buffer.as_entire_binding(),
+                    (None, Some(view)) => wgpu::BindingResource::TextureView(view),
+                    (None, None) => unreachable!(),
+                },
             },
             wgpu::BindGroupEntry {
                 binding: 2,
                 resource: splat_2d_buffer.as_entire_binding(),
             },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: lod_ranges_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: selection_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 7,
+                resource: hidden_buffer.as_entire_binding(),
+            },
         ];
 
         let bind_group = if pc.compressed() {
@@ -132,6 +397,7 @@ impl PointCloud {
                 contents: bytemuck::cast_slice(pc.covars.as_ref().unwrap().as_slice()),
                 usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
             });
+            gpu_memory += covars_buffer.size();
             let quantization_uniform = UniformBuffer::new(
                 device,
                 pc.quantization.unwrap(),
@@ -151,6 +417,12 @@ impl PointCloud {
                 layout: &Self::bind_group_layout_compressed(device),
                 entries: &bind_group_entries,
             })
+        } else if sh_storage == ShStorage::Texture {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("point cloud bind group (sh texture)"),
+                layout: &Self::bind_group_layout_sh_texture(device),
+                entries: &bind_group_entries,
+            })
         } else {
             device.create_bind_group(&wgpu::BindGroupDescriptor {
                 label: Some("point cloud bind group"),
@@ -164,9 +436,18 @@ impl PointCloud {
 
             bind_group,
             render_bind_group,
+            vertex_buffer,
+            sh_buffer,
+            sh_texture,
+            selection_buffer,
+            hidden_buffer,
             num_points: pc.num_points as u32,
+            num_loaded: if chunked { 0 } else { pc.num_points as u32 },
             sh_deg: pc.sh_deg,
             compressed: pc.compressed(),
+            layout,
+            sh_storage,
+            gpu_memory,
             bbox: pc.aabb.into(),
             center: pc.center,
             up: pc.up,
@@ -178,25 +459,187 @@ impl PointCloud {
                 b: c[2] as f64,
                 a: 1.,
             }),
+            lod: pc.lod.clone().map(|octree| Lod {
+                octree,
+                ranges_buffer: lod_ranges_buffer,
+                threshold: DEFAULT_LOD_THRESHOLD,
+                enabled: true,
+            }),
         })
     }
 
+    /// uploads the sh coefficients into a Rgba32Uint texture array
+    /// the coefficients of one splat are stored in [GaussianLayout::sh_texels] consecutive texels
+    /// if `empty` is set, the texture is filled later with [PointCloud::write_sh_texture]
+    fn create_sh_texture(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pc: &GenericGaussianPointCloud,
+        layout: GaussianLayout,
+        empty: bool,
+    ) -> wgpu::Texture {
+        let size =
+            ShTextureLayout::new(&device.limits(), layout).texture_size(pc.num_points as u32);
+        let descriptor = wgpu::TextureDescriptor {
+            label: Some("sh coefs texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Uint,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        };
+        if empty {
+            return device.create_texture(&descriptor);
+        }
+        // splats are stored row by row, so the coefficients buffer only needs to be padded
+        let texel_size = 4 * mem::size_of::<u32>() as u64;
+        let mut data = pc.gpu_sh_buffer(0..pc.num_points, layout).into_owned();
+        data.resize(
+            (size.width as u64
+                * size.height as u64
+                * size.depth_or_array_layers as u64
+                * texel_size) as usize,
+            0,
+        );
+        log::debug!(
+            "storing sh coefficients in {}x{}x{} texture",
+            size.width,
+            size.height,
+            size.depth_or_array_layers
+        );
+        device.create_texture_with_data(
+            queue,
+            &descriptor,
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &data,
+        )
+    }
+
+    /// writes the sh coefficients of the splats `first..` into the sh texture
+    /// `coefs` holds the coefficients of consecutive splats, the splats of one row are written at once
+    fn write_sh_texture(&self, queue: &wgpu::Queue, first: usize, coefs: &[u8]) {
+        let Some(texture) = &self.sh_texture else {
+            return;
+        };
+        let texels_per_splat = self.layout.sh_texels();
+        let splat_size = self.layout.sh_size();
+        let splats_per_row = (texture.width() / texels_per_splat) as usize;
+        let rows_per_layer = texture.height() as usize;
+        let end = first + coefs.len() / splat_size;
+        let mut i = first;
+        while i < end {
+            let row = i / splats_per_row;
+            let column = i % splats_per_row;
+            let n = (splats_per_row - column).min(end - i);
+            let data = &coefs[(i - first) * splat_size..(i - first + n) * splat_size];
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: column as u32 * texels_per_splat,
+                        y: (row % rows_per_layer) as u32,
+                        z: (row / rows_per_layer) as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(data.len() as u32),
+                    rows_per_image: None,
+                },
+                wgpu::Extent3d {
+                    width: n as u32 * texels_per_splat,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+            );
+            i += n;
+        }
+    }
+
     pub fn compressed(&self) -> bool {
         self.compressed
     }
 
+    /// gpu memory layout of the splats, the default layout for compressed point clouds
+    pub fn layout(&self) -> GaussianLayout {
+        self.layout
+    }
+
+    pub fn sh_storage(&self) -> ShStorage {
+        self.sh_storage
+    }
+
     pub fn num_points(&self) -> u32 {
         self.num_points
     }
 
+    /// number of splats that are uploaded to the gpu
+    /// smaller than [PointCloud::num_points] while a [ChunkedUpload] is running
+    pub fn num_loaded(&self) -> u32 {
+        self.num_loaded
+    }
+
     pub fn sh_deg(&self) -> u32 {
         self.sh_deg
     }
 
+    /// bytes allocated on the gpu for the splats and sh coefficients
+    pub fn gpu_memory(&self) -> u64 {
+        self.gpu_memory
+    }
+
     pub fn bbox(&self) -> &Aabb<f32> {
         &self.bbox
     }
 
+    /// highlights the splats for which `selected` is true, missing entries are not selected
+    /// the selection is applied the next time the scene is redrawn
+    pub fn set_selection(&self, queue: &wgpu::Queue, selected: &[bool], arena: &FrameArena) {
+        queue.write_buffer(
+            &self.selection_buffer,
+            0,
+            bytemuck::cast_slice(self.splat_bits(selected, arena)),
+        );
+    }
+
+    pub fn clear_selection(&self, queue: &wgpu::Queue, arena: &FrameArena) {
+        self.set_selection(queue, &[], arena);
+    }
+
+    /// culls the splats for which `hidden` is true, missing entries are visible
+    /// the splats are hidden the next time the scene is redrawn
+    pub fn set_hidden(&self, queue: &wgpu::Queue, hidden: &[bool], arena: &FrameArena) {
+        queue.write_buffer(
+            &self.hidden_buffer,
+            0,
+            bytemuck::cast_slice(self.splat_bits(hidden, arena)),
+        );
+    }
+
+    pub fn show_all(&self, queue: &wgpu::Queue, arena: &FrameArena) {
+        self.set_hidden(queue, &[], arena);
+    }
+
+    /// packs one bit per splat into u32 words
+    /// the words are staged in `arena` until they are copied by [wgpu::Queue::write_buffer]
+    fn splat_bits<'a>(&self, bits: &[bool], arena: &'a FrameArena) -> &'a [u32] {
+        let words = arena.alloc_slice::<u32>((self.num_points as usize).div_ceil(32).max(1));
+        for (i, _) in bits
+            .iter()
+            .take(self.num_points as usize)
+            .enumerate()
+            .filter(|(_, b)| **b)
+        {
+            words[i / 32] |= 1 << (i % 32);
+        }
+        words
+    }
+
     pub(crate) fn bind_group(&self) -> &wgpu::BindGroup {
         &self.bind_group
     }
@@ -258,6 +701,36 @@ impl PointCloud {
                     },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         })
     }
@@ -296,6 +769,104 @@ impl PointCloud {
                     },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    pub fn bind_group_layout_sh_texture(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("point cloud sh texture bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Uint,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         })
     }
@@ -327,17 +898,93 @@ impl PointCloud {
         self.center
     }
 
+    pub fn lod(&self) -> Option<&Lod> {
+        self.lod.as_ref()
+    }
+
+    pub fn lod_mut(&mut self) -> Option<&mut Lod> {
+        self.lod.as_mut()
+    }
+
     pub fn up(&self) -> Option<Vector3<f32>> {
         self.up
     }
 }
 
+/// uploads the splats of a loaded point cloud in chunks over multiple frames
+/// the splats are sorted coarse to fine so an approximate scene is visible after the first chunk
+/// the file is read completely before the first chunk is uploaded
+pub struct ChunkedUpload {
+    pc: GenericGaussianPointCloud,
+    chunk_size: usize,
+}
+
+impl ChunkedUpload {
+    /// creates the point cloud with empty splat buffers (and sh texture)
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        mut pc: GenericGaussianPointCloud,
+        sh_storage: ShStorage,
+        chunk_size: usize,
+    ) -> Result<(PointCloud, Self), anyhow::Error> {
+        // the level of detail hierarchy depends on the order of the splats
+        if pc.lod.is_none() {
+            pc.sort_coarse_to_fine()?;
+        }
+        let sh_texture_queue = PointCloud::sh_texture_queue(queue, &pc, sh_storage);
+        let point_cloud = PointCloud::create(device, sh_texture_queue, &pc, true)?;
+        Ok((
+            point_cloud,
+            Self {
+                pc,
+                chunk_size: chunk_size.max(1),
+            },
+        ))
+    }
+
+    /// uploads the next chunk of splats
+    /// returns false if all splats are uploaded
+    pub fn upload_next(&mut self, queue: &wgpu::Queue, pc: &mut PointCloud) -> bool {
+        let start = pc.num_loaded as usize;
+        if start >= self.pc.num_points {
+            return false;
+        }
+        let end = (start + self.chunk_size).min(self.pc.num_points);
+
+        queue.write_buffer(
+            &pc.vertex_buffer,
+            (start * self.pc.gpu_gaussian_size(pc.layout)) as u64,
+            &self.pc.gpu_gaussian_buffer(start..end, pc.layout),
+        );
+        if !self.pc.compressed() {
+            let sh_coefs = self.pc.gpu_sh_buffer(start..end, pc.layout);
+            match &pc.sh_buffer {
+                Some(sh_buffer) => {
+                    queue.write_buffer(sh_buffer, (start * pc.layout.sh_size()) as u64, &sh_coefs)
+                }
+                None => pc.write_sh_texture(queue, start, &sh_coefs),
+            }
+        }
+        pc.num_loaded = end as u32;
+        true
+    }
+
+    pub fn done(&self, pc: &PointCloud) -> bool {
+        pc.num_loaded as usize >= self.pc.num_points
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Splat {
     pub v: Vector4<f16>,
     pub pos: Vector2<f16>,
     pub color: Vector4<f16>,
+    /// depth of the center in normalized device coordinates
+    pub depth: f32,
+    /// bit 0: the splat is selected
+    pub flags: u32,
 }
 
 #[repr(C)]
@@ -349,7 +996,6 @@ pub struct Quantization {
 }
 
 impl Quantization {
-    #[cfg(feature = "npz")]
     pub fn new(zero_point: i32, scale: f32) -> Self {
         Quantization {
             zero_point,
@@ -411,7 +1057,7 @@ impl<F: Float + BaseNum> Aabb<F> {
             Vector3::new(F::zero(), F::one(), F::one()),
             Vector3::new(F::one(), F::one(), F::one()),
         ]
-        .map(|d| self.min + self.max.to_vec().mul_element_wise(d))
+        .map(|d| self.min + self.size().mul_element_wise(d))
     }
 
     pub fn unit() -> Self {
@@ -445,6 +1091,15 @@ impl<F: Float + BaseNum> Aabb<F> {
     }
 }
 
+impl Lerp for Aabb<f32> {
+    fn lerp(&self, other: &Self, amount: f32) -> Self {
+        Self {
+            min: self.min + (other.min - self.min) * amount,
+            max: self.max + (other.max - self.max) * amount,
+        }
+    }
+}
+
 impl Into<Aabb<f32>> for Aabb<f16> {
     fn into(self) -> Aabb<f32> {
         Aabb {
@@ -0,0 +1,21 @@
+//! the stable public api of the crate
+//! `use web_splats::prelude::*;` imports everything needed to open the viewer or to render point clouds offline
+
+pub use crate::{
+    camera::{Camera, PerspectiveCamera, PerspectiveProjection},
+    error::WebSplatError,
+    io::{GenericGaussianPointCloud, LoadOptions},
+    pointcloud::{Aabb, Gaussian, PointCloud},
+    renderer::{
+        ColorSpace, DepthOfField, Fog, FogFalloff, GaussianRenderer, RenderMode, RenderSettings,
+        Transparency,
+    },
+    scene::{Scene, SceneCamera, Split},
+    WGPUContext,
+};
+
+#[cfg(feature = "ui")]
+pub use crate::{
+    stereo::{Stereo, StereoMode},
+    RenderConfig, Viewer,
+};
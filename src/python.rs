@@ -0,0 +1,135 @@
+//! python bindings for offline rendering, build the module with `maturin build --features python`
+//!
+//! ```python
+//! import numpy as np
+//! import web_splats
+//!
+//! renderer = web_splats.Renderer("point_cloud.ply", "cameras.json")
+//! width, height, pixels = renderer.render(0)
+//! img = np.frombuffer(pixels, dtype=np.uint8).reshape(height, width, 4)
+//! ```
+
+use std::{fs::File, path::PathBuf, time::Duration};
+
+use cgmath::Vector2;
+use pyo3::{exceptions::PyRuntimeError, prelude::*, types::PyBytes};
+
+use crate::{
+    download_texture, io::GenericGaussianPointCloud, ColorSpace, PerspectiveCamera, PointCloud,
+    RenderMode, RenderSettings, RenderTexture, Scene, Transparency, WGPUContext,
+};
+
+fn to_py_err(err: impl std::fmt::Display) -> PyErr {
+    PyRuntimeError::new_err(err.to_string())
+}
+
+/// renders the cameras of a scene, the gpu resources are kept between the calls
+#[pyclass(unsendable)]
+struct Renderer {
+    context: WGPUContext,
+    pc: PointCloud,
+    scene: Option<Scene>,
+    texture: Option<RenderTexture>,
+}
+
+#[pymethods]
+impl Renderer {
+    #[new]
+    #[pyo3(signature = (pointcloud, scene = None, software = false))]
+    fn new(pointcloud: PathBuf, scene: Option<PathBuf>, software: bool) -> PyResult<Self> {
+        let context = if software {
+            pollster::block_on(WGPUContext::new_software())
+        } else {
+            pollster::block_on(WGPUContext::new_instance())
+        }
+        .map_err(to_py_err)?;
+        let file = File::open(&pointcloud).map_err(to_py_err)?;
+        let raw = GenericGaussianPointCloud::load(file).map_err(to_py_err)?;
+        let pc = PointCloud::new(&context.device, raw).map_err(to_py_err)?;
+        let scene = scene
+            .map(|path| Scene::open(&path))
+            .transpose()
+            .map_err(to_py_err)?;
+        Ok(Self {
+            context,
+            pc,
+            scene,
+            texture: None,
+        })
+    }
+
+    fn num_cameras(&self) -> usize {
+        self.scene.as_ref().map_or(0, |scene| scene.num_cameras())
+    }
+
+    /// renders the scene camera with the given index at its resolution
+    /// returns the width, the height and the gamma encoded rgba pixels (row major, premultiplied alpha)
+    fn render<'py>(
+        &mut self,
+        py: Python<'py>,
+        camera: usize,
+    ) -> PyResult<(u32, u32, Bound<'py, PyBytes>)> {
+        let scene_camera = self
+            .scene
+            .as_ref()
+            .and_then(|scene| scene.camera(camera))
+            .ok_or_else(|| to_py_err(format!("no camera with index {camera}")))?;
+        let resolution = Vector2::new(scene_camera.width, scene_camera.height);
+        let camera: PerspectiveCamera = scene_camera.into();
+
+        let WGPUContext { device, queue, .. } = &self.context;
+        let texture = self.texture.get_or_insert_with(|| {
+            pollster::block_on(RenderTexture::new(
+                device,
+                queue,
+                &self.pc,
+                resolution.x,
+                resolution.y,
+            ))
+        });
+        texture.resize(device, resolution.x, resolution.y);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("python render encoder"),
+        });
+        texture.render(
+            &mut encoder,
+            device,
+            queue,
+            &self.pc,
+            RenderSettings {
+                camera,
+                viewport: resolution,
+                gaussian_scaling: 1.,
+                max_sh_deg: self.pc.sh_deg(),
+                show_env_map: false,
+                mip_splatting: None,
+                kernel_size: None,
+                opacity_threshold: 0.,
+                color_space: ColorSpace::Srgb,
+                render_mode: RenderMode::Splats,
+                transparency: Transparency::Sorted,
+                min_scale: 0.,
+                fog: None,
+                glare_filter: None,
+                exposure: 1.,
+                clipping_box: None,
+                clip_planes: Default::default(),
+                crop_box: None,
+                walltime: Duration::from_secs(100),
+                scene_center: None,
+                scene_extend: None,
+                background_color: wgpu::Color::TRANSPARENT,
+                resolution,
+            },
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+        let img = pollster::block_on(download_texture(texture.texture(), device, queue))
+            .map_err(to_py_err)?;
+        Ok((img.width(), img.height(), PyBytes::new_bound(py, &img)))
+    }
+}
+
+#[pymodule]
+fn web_splats(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Renderer>()
+}
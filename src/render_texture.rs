@@ -0,0 +1,134 @@
+use cgmath::Vector2;
+
+use crate::{ColorSpace, GaussianRenderer, PointCloud, RenderSettings};
+
+/// format of the render texture
+/// the texture stores gamma encoded colors, use [RenderTexture::srgb_view] to sample it as linear colors
+const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// renders the point cloud from an additional camera into a texture
+/// e.g. for picture-in-picture views like a rear view or a detail inset
+/// every render texture owns its own renderer so it can be rendered in the same frame as the main view
+pub struct RenderTexture {
+    renderer: GaussianRenderer,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    srgb_view: wgpu::TextureView,
+}
+
+impl RenderTexture {
+    pub async fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pc: &PointCloud,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let renderer = GaussianRenderer::new(
+            device,
+            queue,
+            TEXTURE_FORMAT,
+            pc.sh_deg(),
+            pc.compressed(),
+            pc.sh_storage(),
+        )
+        .await;
+        let (texture, view, srgb_view) = Self::create_texture(device, width, height);
+        Self {
+            renderer,
+            texture,
+            view,
+            srgb_view,
+        }
+    }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> (wgpu::Texture, wgpu::TextureView, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("render texture"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TEXTURE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[TEXTURE_FORMAT.add_srgb_suffix()],
+        });
+        let view = texture.create_view(&Default::default());
+        let srgb_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(TEXTURE_FORMAT.add_srgb_suffix()),
+            ..Default::default()
+        });
+        (texture, view, srgb_view)
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    /// view with the raw (gamma encoded) colors
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    /// srgb view of the texture, used to display the texture with egui
+    pub fn srgb_view(&self) -> &wgpu::TextureView {
+        &self.srgb_view
+    }
+
+    pub fn size(&self) -> Vector2<u32> {
+        Vector2::new(self.texture.width(), self.texture.height())
+    }
+
+    /// returns true if the texture was recreated
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) -> bool {
+        if self.size() == Vector2::new(width.max(1), height.max(1)) {
+            return false;
+        }
+        (self.texture, self.view, self.srgb_view) = Self::create_texture(device, width, height);
+        true
+    }
+
+    /// records the rendering of the point cloud into the texture
+    /// the viewport of `args` is replaced with the texture size
+    /// splats are always blended in srgb space, as the texture stores gamma encoded colors
+    pub fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pc: &PointCloud,
+        mut args: RenderSettings,
+    ) {
+        args.viewport = self.size();
+        args.color_space = ColorSpace::Srgb;
+        args.camera
+            .projection
+            .resize(args.viewport.x, args.viewport.y);
+        args.camera.fit_near_far(pc.bbox());
+        self.renderer
+            .prepare(encoder, device, queue, pc, args, &mut None);
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("render texture pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(args.background_color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+        self.renderer.render(&mut render_pass, pc);
+    }
+}
@@ -1,5 +1,12 @@
+#[cfg(feature = "ui")]
+use crate::bloom::{Bloom, BloomChain};
+use crate::frame_arena::{FrameArena, FrameArenaStats};
 use crate::gpu_rs::{GPURSSorter, PointCloudSortStuff};
-use crate::pointcloud::Aabb;
+use crate::io::CropRegion;
+use crate::oit::WeightedBlended;
+use crate::pointcloud::{Aabb, ShStorage};
+use crate::stochastic::StochasticTransparency;
+use crate::time::Duration;
 use crate::utils::GPUStopwatch;
 use crate::{
     camera::{Camera, PerspectiveCamera, VIEWPORT_Y_FLIP},
@@ -8,14 +15,28 @@ use crate::{
 };
 
 use std::num::NonZeroU64;
-use std::time::Duration;
 
-use wgpu::{include_wgsl, Extent3d, MultisampleState};
+use wgpu::Extent3d;
+#[cfg(feature = "ui")]
+use wgpu::{include_wgsl, MultisampleState};
 
-use cgmath::{EuclideanSpace, Matrix4, Point3, SquareMatrix, Vector2, Vector4};
+use cgmath::{EuclideanSpace, Matrix4, Point3, SquareMatrix, Vector2, Vector4, Zero};
 
 pub struct GaussianRenderer {
     pipeline: wgpu::RenderPipeline,
+    /// counts the fragments per pixel instead of blending, see [GaussianRenderer::render_overdraw]
+    overdraw_pipeline: wgpu::RenderPipeline,
+    /// writes the depth of the splats, only if a depth format is set
+    depth_pipeline: Option<wgpu::RenderPipeline>,
+    depth_format: Option<wgpu::TextureFormat>,
+    /// accumulation targets, created for the first frame with [Transparency::WeightedBlended]
+    oit: Option<WeightedBlended>,
+    /// noise and history targets, created for the first frame with [Transparency::Stochastic]
+    stochastic: Option<StochasticTransparency>,
+    /// created by the first call of [GaussianRenderer::render_expected_depth]
+    expected_depth_pipeline: Option<wgpu::RenderPipeline>,
+    /// transparency of the last prepared frame
+    transparency: Transparency,
     camera: UniformBuffer<CameraUniform>,
 
     render_settings: UniformBuffer<SplattingArgsUniform>,
@@ -25,8 +46,11 @@ pub struct GaussianRenderer {
     #[allow(dead_code)]
     draw_indirect: wgpu::BindGroup,
     color_format: wgpu::TextureFormat,
+    sample_count: u32,
     sorter: GPURSSorter,
     sorter_suff: Option<PointCloudSortStuff>,
+    /// transient cpu data of the last prepared frame (e.g. the level of detail selection)
+    frame_arena: FrameArena,
 }
 
 impl GaussianRenderer {
@@ -36,50 +60,33 @@ impl GaussianRenderer {
         color_format: wgpu::TextureFormat,
         sh_deg: u32,
         compressed: bool,
+        sh_storage: ShStorage,
     ) -> Self {
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("render pipeline layout"),
-            bind_group_layouts: &[
-                &PointCloud::bind_group_layout_render(device), // Needed for points_2d (on binding 2)
-                &GPURSSorter::bind_group_layout_rendering(device), // Needed for indices   (on binding 4)
-            ],
-            push_constant_ranges: &[],
-        });
-
-        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/gaussian.wgsl"));
+        Self::new_multisampled(
+            device,
+            queue,
+            color_format,
+            1,
+            sh_deg,
+            compressed,
+            sh_storage,
+        )
+        .await
+    }
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("render pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: color_format,
-                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: Default::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleStrip,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-        });
+    /// renderer for multisampled render targets (msaa) with `sample_count` samples per pixel
+    /// the render pass must resolve the multisampled target, see [create_multisampled_view]
+    pub async fn new_multisampled(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        color_format: wgpu::TextureFormat,
+        sample_count: u32,
+        sh_deg: u32,
+        compressed: bool,
+        sh_storage: ShStorage,
+    ) -> Self {
+        let (pipeline, overdraw_pipeline, depth_pipeline) =
+            Self::create_pipelines(device, color_format, sample_count, None);
 
         let draw_indirect_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("indirect draw buffer"),
@@ -104,16 +111,25 @@ impl GaussianRenderer {
         let sorter = GPURSSorter::new(device, queue).await;
 
         let camera = UniformBuffer::new_default(device, Some("camera uniform buffer"));
-        let preprocess = PreprocessPipeline::new(device, sh_deg, compressed);
+        let preprocess = PreprocessPipeline::new(device, sh_deg, compressed, sh_storage);
         GaussianRenderer {
             pipeline,
+            overdraw_pipeline,
+            depth_pipeline,
+            depth_format: None,
+            oit: None,
+            stochastic: None,
+            expected_depth_pipeline: None,
+            transparency: Transparency::Sorted,
             camera,
             preprocess,
             draw_indirect_buffer,
             draw_indirect,
             color_format,
+            sample_count,
             sorter,
             sorter_suff: None,
+            frame_arena: FrameArena::new(),
             render_settings: UniformBuffer::new_default(
                 device,
                 Some("render settings uniform buffer"),
@@ -121,16 +137,138 @@ impl GaussianRenderer {
         }
     }
 
+    /// pipelines for blending the splats, counting the overdraw and writing the depth
+    /// the depth pipeline is only created if `depth_format` is set
+    fn create_pipelines(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        sample_count: u32,
+        depth_format: Option<wgpu::TextureFormat>,
+    ) -> (
+        wgpu::RenderPipeline,
+        wgpu::RenderPipeline,
+        Option<wgpu::RenderPipeline>,
+    ) {
+        // Needed for points_2d (on binding 2)
+        let render_layout = PointCloud::bind_group_layout_render(device);
+        // Needed for indices   (on binding 4)
+        let sorter_layout = GPURSSorter::bind_group_layout_rendering(device);
+        // Needed for the opacity threshold
+        let settings_layout = UniformBuffer::<SplattingArgsUniform>::bind_group_layout(device);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("render pipeline layout"),
+            bind_group_layouts: &[&render_layout, &sorter_layout, &settings_layout],
+            push_constant_ranges: &[],
+        });
+        let overdraw_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("overdraw pipeline layout"),
+            bind_group_layouts: &[
+                &render_layout,
+                &sorter_layout,
+                &settings_layout,
+                &Self::overdraw_bind_group_layout(device),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/gaussian.wgsl"));
+
+        let depth_stencil = |depth_write_enabled| {
+            depth_format.map(|format| wgpu::DepthStencilState {
+                format,
+                depth_write_enabled,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: Default::default(),
+                bias: Default::default(),
+            })
+        };
+        let create_pipeline =
+            |label: &str, layout: &wgpu::PipelineLayout, entry_point: &str, depth_write: bool| {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: "vs_main",
+                        buffers: &[],
+                        compilation_options: Default::default(),
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point,
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: color_format,
+                            blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                            write_mask: if depth_write {
+                                wgpu::ColorWrites::empty()
+                            } else {
+                                wgpu::ColorWrites::ALL
+                            },
+                        })],
+                        compilation_options: Default::default(),
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleStrip,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: None,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: depth_stencil(depth_write),
+                    multisample: wgpu::MultisampleState {
+                        count: sample_count,
+                        ..Default::default()
+                    },
+                    multiview: None,
+                })
+            };
+        (
+            create_pipeline("render pipeline", &pipeline_layout, "fs_main", false),
+            create_pipeline("overdraw pipeline", &overdraw_layout, "fs_overdraw", false),
+            depth_format
+                .is_some()
+                .then(|| create_pipeline("depth pipeline", &pipeline_layout, "fs_depth", true)),
+        )
+    }
+
+    /// format of the depth attachment of the render pass
+    /// `None` if the render pass has no depth attachment
+    pub fn depth_format(&self) -> Option<wgpu::TextureFormat> {
+        self.depth_format
+    }
+
+    /// enables a depth attachment in the render pass, e.g. to composite the splats with meshes
+    /// the splats are depth tested against the attachment and write the depth of their center
+    /// where they are mostly opaque, the depth uses the projection of the splatting camera
+    pub fn set_depth_format(
+        &mut self,
+        device: &wgpu::Device,
+        depth_format: Option<wgpu::TextureFormat>,
+    ) {
+        (self.pipeline, self.overdraw_pipeline, self.depth_pipeline) =
+            Self::create_pipelines(device, self.color_format, self.sample_count, depth_format);
+        self.depth_format = depth_format;
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    #[cfg(feature = "ui")]
     pub(crate) fn camera(&self) -> &UniformBuffer<CameraUniform> {
         &self.camera
     }
 
+    /// projects all splats in a compute pass (covariance, conic, color and depth key)
+    /// visible splats are appended to a compact buffer that the vertex shader reads per instance
     fn preprocess<'a>(
         &'a mut self,
         encoder: &'a mut wgpu::CommandEncoder,
         queue: &wgpu::Queue,
         pc: &'a PointCloud,
-        render_settings: SplattingArgs,
+        render_settings: RenderSettings,
     ) {
         let camera = render_settings.camera;
         let uniform = self.camera.as_mut();
@@ -139,8 +277,33 @@ impl GaussianRenderer {
         uniform.set_camera(camera);
         self.camera.sync(queue);
 
+        // splats that are not uploaded yet are skipped
+        let mut num_splats = pc.num_loaded();
+        let mut lod_ranges = 0;
+        self.frame_arena.reset();
+        if let Some(lod) = pc.lod().filter(|_| pc.num_loaded() < pc.num_points()) {
+            // the proxies are uploaded last, the hierarchy is used once all splats are uploaded
+            num_splats = num_splats.min(lod.octree().num_splats());
+        } else if let Some(lod) = pc.lod() {
+            let (ranges, num_selected) = if lod.enabled {
+                lod.octree().select(
+                    &camera,
+                    render_settings.viewport,
+                    render_settings.clipping_box,
+                    lod.threshold,
+                    &self.frame_arena,
+                )
+            } else {
+                lod.octree().select_all(&self.frame_arena)
+            };
+            num_splats = num_selected;
+            lod_ranges = ranges.len() as u32;
+            queue.write_buffer(lod.ranges_buffer(), 0, bytemuck::cast_slice(ranges));
+        }
+
         let settings_uniform = self.render_settings.as_mut();
         *settings_uniform = SplattingArgsUniform::from_args_and_pc(render_settings, pc);
+        settings_uniform.lod_ranges = lod_ranges;
         self.render_settings.sync(queue);
 
         // TODO perform this in vertex buffer after draw call
@@ -159,6 +322,7 @@ impl GaussianRenderer {
         self.preprocess.run(
             encoder,
             pc,
+            num_splats,
             &self.camera,
             &self.render_settings,
             depth_buffer,
@@ -176,7 +340,7 @@ impl GaussianRenderer {
                 &self.draw_indirect_buffer.slice(..),
                 move |b| {
                     let download = b.unwrap();
-                    let data = download.as_ref();
+                    let data: &[u8] = &download;
                     let num_points = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
                     tx.send(num_points).unwrap();
                 },
@@ -193,9 +357,10 @@ impl GaussianRenderer {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         pc: &PointCloud,
-        render_settings: SplattingArgs,
+        render_settings: RenderSettings,
         stopwatch: &mut Option<GPUStopwatch>,
     ) {
+        let _span = tracing::trace_span!("prepare", num_points = pc.num_points()).entered();
         if self.sorter_suff.is_none()
             || self
                 .sorter_suff
@@ -224,17 +389,21 @@ impl GaussianRenderer {
         if let Some(stopwatch) = stopwatch {
             stopwatch.stop(encoder, "preprocess").unwrap();
         }
+        self.transparency = render_settings.transparency;
         // sort 2d splats
-        if let Some(stopwatch) = stopwatch {
-            stopwatch.start(encoder, "sorting").unwrap();
-        }
-        self.sorter.record_sort_indirect(
-            &self.sorter_suff.as_ref().unwrap().sorter_bg,
-            &self.sorter_suff.as_ref().unwrap().sorter_dis,
-            encoder,
-        );
-        if let Some(stopwatch) = stopwatch {
-            stopwatch.stop(encoder, "sorting").unwrap();
+        // without sorting the splats are drawn in the order in which they were preprocessed
+        if self.transparency == Transparency::Sorted {
+            if let Some(stopwatch) = stopwatch {
+                stopwatch.start(encoder, "sorting").unwrap();
+            }
+            self.sorter.record_sort_indirect(
+                &self.sorter_suff.as_ref().unwrap().sorter_bg,
+                &self.sorter_suff.as_ref().unwrap().sorter_dis,
+                encoder,
+            );
+            if let Some(stopwatch) = stopwatch {
+                stopwatch.stop(encoder, "sorting").unwrap();
+            }
         }
 
         encoder.copy_buffer_to_buffer(
@@ -244,6 +413,70 @@ impl GaussianRenderer {
             std::mem::size_of::<u32>() as u64,
             std::mem::size_of::<u32>() as u64,
         );
+
+        if self.transparency == Transparency::WeightedBlended {
+            let (width, height) = (render_settings.viewport.x, render_settings.viewport.y);
+            let oit = self.oit.get_or_insert_with(|| {
+                WeightedBlended::new(device, self.color_format, self.sample_count, width, height)
+            });
+            oit.resize(device, width, height);
+            oit.accumulate(
+                encoder,
+                [
+                    pc.render_bind_group(),
+                    &self.sorter_suff.as_ref().unwrap().sorter_render_bg,
+                    self.render_settings.bind_group(),
+                    self.camera.bind_group(),
+                ],
+                &self.draw_indirect_buffer,
+            );
+        }
+        if self.transparency == Transparency::Stochastic {
+            let (width, height) = (render_settings.viewport.x, render_settings.viewport.y);
+            let stochastic = self.stochastic.get_or_insert_with(|| {
+                StochasticTransparency::new(
+                    device,
+                    queue,
+                    self.color_format,
+                    self.sample_count,
+                    width,
+                    height,
+                )
+            });
+            stochastic.resize(device, width, height);
+            stochastic.accumulate(
+                encoder,
+                queue,
+                [
+                    pc.render_bind_group(),
+                    &self.sorter_suff.as_ref().unwrap().sorter_render_bg,
+                    self.render_settings.bind_group(),
+                ],
+                &self.draw_indirect_buffer,
+                self.camera.data(),
+                render_settings,
+            );
+        }
+    }
+
+    /// offset of the blue noise mask of [Transparency::Stochastic] in the next frame
+    /// should be random every frame, otherwise the frames are correlated and do not converge
+    pub fn set_noise_offset(&mut self, offset: Vector2<u32>) {
+        if let Some(stochastic) = &mut self.stochastic {
+            stochastic.set_noise_offset(offset);
+        }
+    }
+
+    /// false while [Transparency::Stochastic] accumulates frames of a still view
+    /// until then the view should be redrawn every frame
+    pub fn is_converged(&self) -> bool {
+        self.transparency != Transparency::Stochastic
+            || self.stochastic.as_ref().map_or(true, |s| s.is_converged())
+    }
+
+    /// cpu memory used for the last prepared frame, see [FrameArena]
+    pub fn frame_arena_stats(&self) -> FrameArenaStats {
+        self.frame_arena.stats()
     }
 
     pub fn render<'rpass>(
@@ -251,13 +484,151 @@ impl GaussianRenderer {
         render_pass: &mut wgpu::RenderPass<'rpass>,
         pc: &'rpass PointCloud,
     ) {
+        if let Some(oit) = self
+            .oit
+            .as_ref()
+            .filter(|_| self.transparency == Transparency::WeightedBlended)
+        {
+            // the splats were already accumulated in prepare
+            oit.composite(render_pass);
+        } else if let Some(stochastic) = self
+            .stochastic
+            .as_ref()
+            .filter(|_| self.transparency == Transparency::Stochastic)
+        {
+            stochastic.composite(render_pass);
+        } else {
+            render_pass.set_bind_group(0, pc.render_bind_group(), &[]);
+            render_pass.set_bind_group(
+                1,
+                &self.sorter_suff.as_ref().unwrap().sorter_render_bg,
+                &[],
+            );
+            render_pass.set_bind_group(2, self.render_settings.bind_group(), &[]);
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.draw_indirect(&self.draw_indirect_buffer, 0);
+        }
+        // the depth is written after blending, so splats behind transparent splats are not hidden
+        if let Some(depth_pipeline) = &self.depth_pipeline {
+            render_pass.set_bind_group(0, pc.render_bind_group(), &[]);
+            render_pass.set_bind_group(
+                1,
+                &self.sorter_suff.as_ref().unwrap().sorter_render_bg,
+                &[],
+            );
+            render_pass.set_bind_group(2, self.render_settings.bind_group(), &[]);
+            render_pass.set_pipeline(depth_pipeline);
+            render_pass.draw_indirect(&self.draw_indirect_buffer, 0);
+        }
+    }
+
+    /// blends the expected view space depth of the splats into `target`
+    /// the target holds the depth premultiplied with the opacity (r) and the opacity (g)
+    /// the depth is only correct for [Transparency::Sorted], see [Display::expected_depth_target]
+    pub fn render_expected_depth(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        pc: &PointCloud,
+        target: &wgpu::TextureView,
+    ) {
+        let pipeline = self
+            .expected_depth_pipeline
+            .get_or_insert_with(|| Self::create_expected_depth_pipeline(device));
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("expected depth render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
         render_pass.set_bind_group(0, pc.render_bind_group(), &[]);
         render_pass.set_bind_group(1, &self.sorter_suff.as_ref().unwrap().sorter_render_bg, &[]);
-        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(2, self.render_settings.bind_group(), &[]);
+        render_pass.set_bind_group(3, self.camera.bind_group(), &[]);
+        render_pass.set_pipeline(pipeline);
+        render_pass.draw_indirect(&self.draw_indirect_buffer, 0);
+    }
+
+    fn create_expected_depth_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/gaussian.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("expected depth pipeline layout"),
+            bind_group_layouts: &[
+                &PointCloud::bind_group_layout_render(device),
+                &GPURSSorter::bind_group_layout_rendering(device),
+                &UniformBuffer::<SplattingArgsUniform>::bind_group_layout(device),
+                &UniformBuffer::<CameraUniform>::bind_group_layout(device),
+            ],
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("expected depth pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_expected_depth",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: EXPECTED_DEPTH_FORMAT,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: Default::default(),
+            multiview: None,
+        })
+    }
+
+    /// draws the splats with [GaussianRenderer::overdraw_pipeline]
+    /// `overdraw` holds the fragment counters, see [Display::overdraw_bind_group]
+    pub fn render_overdraw<'rpass>(
+        &'rpass self,
+        render_pass: &mut wgpu::RenderPass<'rpass>,
+        pc: &'rpass PointCloud,
+        overdraw: &'rpass wgpu::BindGroup,
+    ) {
+        render_pass.set_bind_group(0, pc.render_bind_group(), &[]);
+        render_pass.set_bind_group(1, &self.sorter_suff.as_ref().unwrap().sorter_render_bg, &[]);
+        render_pass.set_bind_group(2, self.render_settings.bind_group(), &[]);
+        render_pass.set_bind_group(3, overdraw, &[]);
+        render_pass.set_pipeline(&self.overdraw_pipeline);
 
         render_pass.draw_indirect(&self.draw_indirect_buffer, 0);
     }
 
+    fn overdraw_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("overdraw bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
     pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
         device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("draw indirect"),
@@ -281,9 +652,104 @@ impl GaussianRenderer {
         self.color_format
     }
 
+    #[cfg(feature = "ui")]
     pub(crate) fn render_settings(&self) -> &UniformBuffer<SplattingArgsUniform> {
         &self.render_settings
     }
+
+    /// renders the point cloud from `camera` into a new texture of `size` without a window
+    /// the projection is resized to the aspect ratio of `size`, splats are blended in srgb space
+    /// the texture can be sampled or copied, e.g. to the cpu for thumbnails or evaluation
+    pub fn render_to_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pc: &PointCloud,
+        mut camera: PerspectiveCamera,
+        size: Vector2<u32>,
+    ) -> wgpu::Texture {
+        let size = Vector2::new(size.x.max(1), size.y.max(1));
+        camera.projection.resize(size.x, size.y);
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("render to texture target"),
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.color_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let target_view = target.create_view(&Default::default());
+        let msaa_view =
+            create_multisampled_view(device, self.color_format, size.x, size.y, self.sample_count);
+        let depth_view = self
+            .depth_format
+            .map(|format| create_depth_view(device, format, size.x, size.y, self.sample_count));
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("render to texture encoder"),
+        });
+        let args = RenderSettings {
+            camera,
+            viewport: size,
+            gaussian_scaling: 1.,
+            max_sh_deg: pc.sh_deg(),
+            show_env_map: false,
+            mip_splatting: None,
+            kernel_size: None,
+            opacity_threshold: 0.,
+            color_space: ColorSpace::Srgb,
+            render_mode: RenderMode::Splats,
+            transparency: Transparency::Sorted,
+            min_scale: 0.,
+            fog: None,
+            glare_filter: None,
+            exposure: 1.,
+            clipping_box: None,
+            clip_planes: Default::default(),
+            crop_box: None,
+            walltime: Duration::from_secs(100),
+            scene_center: None,
+            scene_extend: None,
+            background_color: wgpu::Color::TRANSPARENT,
+            resolution: size,
+        };
+        self.prepare(&mut encoder, device, queue, pc, args, &mut None);
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("render to texture pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: msaa_view.as_ref().unwrap_or(&target_view),
+                    resolve_target: msaa_view.as_ref().map(|_| &target_view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: depth_view.as_ref().map(|view| {
+                    wgpu::RenderPassDepthStencilAttachment {
+                        view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.),
+                            store: wgpu::StoreOp::Store,
+                        }),
+                        stencil_ops: None,
+                    }
+                }),
+                ..Default::default()
+            });
+            self.render(&mut render_pass, pc);
+        }
+        queue.submit([encoder.finish()]);
+        target
+    }
 }
 
 #[repr(C)]
@@ -344,15 +810,23 @@ impl CameraUniform {
 struct PreprocessPipeline(wgpu::ComputePipeline);
 
 impl PreprocessPipeline {
-    fn new(device: &wgpu::Device, sh_deg: u32, compressed: bool) -> Self {
+    fn new(device: &wgpu::Device, sh_deg: u32, compressed: bool, sh_storage: ShStorage) -> Self {
+        // compressed point clouds always store their coefficients in a buffer
+        let sh_storage = if compressed {
+            ShStorage::Buffer
+        } else {
+            sh_storage
+        };
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("preprocess pipeline layout"),
             bind_group_layouts: &[
                 &UniformBuffer::<CameraUniform>::bind_group_layout(device),
-                &if !compressed {
-                    PointCloud::bind_group_layout(device)
-                } else {
+                &if compressed {
                     PointCloud::bind_group_layout_compressed(device)
+                } else if sh_storage == ShStorage::Texture {
+                    PointCloud::bind_group_layout_sh_texture(device)
+                } else {
+                    PointCloud::bind_group_layout(device)
                 },
                 &GPURSSorter::bind_group_layout_preprocess(device),
                 &UniformBuffer::<SplattingArgsUniform>::bind_group_layout(device),
@@ -362,7 +836,9 @@ impl PreprocessPipeline {
 
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("preprocess shader"),
-            source: wgpu::ShaderSource::Wgsl(Self::build_shader(sh_deg, compressed).into()),
+            source: wgpu::ShaderSource::Wgsl(
+                Self::build_shader(sh_deg, compressed, sh_storage).into(),
+            ),
         });
         let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
             label: Some("preprocess pipeline"),
@@ -374,17 +850,24 @@ impl PreprocessPipeline {
         Self(pipeline)
     }
 
-    fn build_shader(sh_deg: u32, compressed: bool) -> String {
+    fn build_shader(sh_deg: u32, compressed: bool, sh_storage: ShStorage) -> String {
         let shader_src: &str = if !compressed {
             include_str!("shaders/preprocess.wgsl")
         } else {
             include_str!("shaders/preprocess_compressed.wgsl")
         };
+        // the uncompressed shader reads its sh coefficients through the storage specific sh_word function
+        let sh_src = match (compressed, sh_storage) {
+            (true, _) => String::new(),
+            (false, ShStorage::Buffer) => include_str!("shaders/sh_buffer.wgsl").to_string(),
+            (false, ShStorage::Texture) => include_str!("shaders/sh_texture.wgsl").to_string(),
+        };
         let shader = format!(
             "
         const MAX_SH_DEG:u32 = {:}u;
+        {:}
         {:}",
-            sh_deg, shader_src
+            sh_deg, sh_src, shader_src
         );
         return shader;
     }
@@ -393,6 +876,7 @@ impl PreprocessPipeline {
         &mut self,
         encoder: &'a mut wgpu::CommandEncoder,
         pc: &PointCloud,
+        num_splats: u32,
         camera: &UniformBuffer<CameraUniform>,
         render_settings: &UniformBuffer<SplattingArgsUniform>,
         sort_bg: &wgpu::BindGroup,
@@ -407,25 +891,425 @@ impl PreprocessPipeline {
         pass.set_bind_group(2, &sort_bg, &[]);
         pass.set_bind_group(3, render_settings.bind_group(), &[]);
 
-        let wgs_x = (pc.num_points() as f32 / 256.0).ceil() as u32;
+        let wgs_x = (num_splats as f32 / 256.0).ceil() as u32;
         pass.dispatch_workgroups(wgs_x, 1, 1);
     }
 }
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DisplayUniform {
+    /// linear factor applied to the rendered colors
+    exposure: f32,
+    /// tone mapping operator, see [Tonemapping]
+    tonemapping: u32,
+    /// transfer function of the colors written to the surface, see [ColorSpace]
+    output_color_space: u32,
+    /// depth of field settings, an aperture of 0 disables the blur
+    dof_focus_distance: f32,
+    dof_aperture: f32,
+    /// strength of the bloom, 0 disables it
+    bloom_intensity: f32,
+    /// darkening of the image corners, 0 disables it
+    vignette: f32,
+    /// contrast adaptive sharpening, 0 disables it
+    sharpening: f32,
+    /// color grading, see [ColorGrading]
+    gamma: f32,
+    saturation: f32,
+    temperature: f32,
+    tint: f32,
+}
+
+impl Default for DisplayUniform {
+    fn default() -> Self {
+        Self {
+            exposure: 1.,
+            tonemapping: Tonemapping::None as u32,
+            output_color_space: ColorSpace::Srgb as u32,
+            dof_focus_distance: 1.,
+            dof_aperture: 0.,
+            bloom_intensity: 0.,
+            vignette: 0.,
+            sharpening: 0.,
+            gamma: 1.,
+            saturation: 1.,
+            temperature: 0.,
+            tint: 0.,
+        }
+    }
+}
+
+/// operator that maps the hdr colors (after exposure) to the displayable range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tonemapping {
+    /// colors are clamped to [0, 1]
+    #[default]
+    None = 0,
+    Reinhard = 1,
+    /// filmic curve (fit by Krzysztof Narkowicz)
+    Aces = 2,
+}
+
+impl Tonemapping {
+    pub const ALL: [Tonemapping; 3] = [Tonemapping::None, Tonemapping::Reinhard, Tonemapping::Aces];
+
+    #[cfg(feature = "ui")]
+    fn from_u32(v: u32) -> Self {
+        match v {
+            1 => Self::Reinhard,
+            2 => Self::Aces,
+            _ => Self::None,
+        }
+    }
+}
+
+impl std::fmt::Display for Tonemapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Tonemapping::None => "None",
+            Tonemapping::Reinhard => "Reinhard",
+            Tonemapping::Aces => "ACES",
+        })
+    }
+}
+
+impl std::str::FromStr for Tonemapping {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "reinhard" => Ok(Self::Reinhard),
+            "aces" => Ok(Self::Aces),
+            _ => Err(anyhow::anyhow!(
+                "unknown tone mapping '{}', expected none, reinhard or aces",
+                s
+            )),
+        }
+    }
+}
+
+/// color space of the splat colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    /// colors are used as stored in the point cloud, like during training of most reconstructions
+    #[default]
+    Srgb = 0,
+    /// sh colors are decoded to linear, so splats are blended in linear space
+    /// needs an hdr render target to avoid banding in dark regions
+    Linear = 1,
+}
+
+impl ColorSpace {
+    pub const ALL: [ColorSpace; 2] = [ColorSpace::Srgb, ColorSpace::Linear];
+
+    #[cfg(feature = "ui")]
+    fn from_u32(v: u32) -> Self {
+        match v {
+            1 => Self::Linear,
+            _ => Self::Srgb,
+        }
+    }
+
+    /// converts a srgb color (e.g. the background color) into this color space
+    pub fn from_srgb(self, color: wgpu::Color) -> wgpu::Color {
+        let decode = |c: f64| {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        match self {
+            ColorSpace::Srgb => color,
+            ColorSpace::Linear => wgpu::Color {
+                r: decode(color.r),
+                g: decode(color.g),
+                b: decode(color.b),
+                a: color.a,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for ColorSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ColorSpace::Srgb => "sRGB",
+            ColorSpace::Linear => "Linear",
+        })
+    }
+}
+
+impl std::str::FromStr for ColorSpace {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "srgb" => Ok(Self::Srgb),
+            "linear" => Ok(Self::Linear),
+            _ => Err(anyhow::anyhow!(
+                "unknown color space '{}', expected srgb or linear",
+                s
+            )),
+        }
+    }
+}
+
+/// how the splats are drawn, the debug modes show the shape and placement of the splats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderMode {
+    #[default]
+    Splats = 0,
+    /// outline and principal axes of every splat at two standard deviations
+    Wireframe = 1,
+    /// every splat as an opaque shaded ellipsoid at two standard deviations
+    Ellipsoids = 2,
+    /// fixed size points at the splat centers colored by the view independent (dc) color
+    /// a fast preview of the geometry without blending
+    Points = 3,
+    /// expected (opacity weighted) view depth of every pixel mapped through a colormap
+    /// near is red and far is blue, floaters stand out in front of the surfaces
+    Depth = 4,
+    /// heatmap of the number of blended fragments per pixel, shows why a view is slow to render
+    Overdraw = 5,
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 6] = [
+        RenderMode::Splats,
+        RenderMode::Wireframe,
+        RenderMode::Ellipsoids,
+        RenderMode::Points,
+        RenderMode::Depth,
+        RenderMode::Overdraw,
+    ];
+
+    /// draws the splats as geometry instead of blending gaussians
+    pub fn is_ellipsoid(self) -> bool {
+        matches!(self, RenderMode::Wireframe | RenderMode::Ellipsoids)
+    }
+}
+
+impl std::fmt::Display for RenderMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RenderMode::Splats => "Splats",
+            RenderMode::Wireframe => "Wireframe",
+            RenderMode::Ellipsoids => "Ellipsoids",
+            RenderMode::Points => "Points",
+            RenderMode::Depth => "Depth",
+            RenderMode::Overdraw => "Overdraw",
+        })
+    }
+}
+
+impl std::str::FromStr for RenderMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "splats" => Ok(Self::Splats),
+            "wireframe" => Ok(Self::Wireframe),
+            "ellipsoids" => Ok(Self::Ellipsoids),
+            "points" => Ok(Self::Points),
+            "depth" => Ok(Self::Depth),
+            "overdraw" => Ok(Self::Overdraw),
+            _ => Err(anyhow::anyhow!(
+                "unknown render mode '{}', expected splats, wireframe, ellipsoids, points, depth \
+                 or overdraw",
+                s
+            )),
+        }
+    }
+}
+
+/// how the splats are blended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Transparency {
+    /// splats are sorted by depth every frame and blended back to front (exact)
+    #[default]
+    Sorted,
+    /// weighted blended order independent transparency, skips the sorting entirely
+    /// colors of overlapping splats are averaged with depth based weights,
+    /// so surfaces behind transparent splats shine through more than they should
+    WeightedBlended,
+    /// every fragment is opaque with the probability of its opacity (blue noise threshold)
+    /// the noisy frames are accumulated over time, so still views converge to the sorted result
+    /// and the splats do not pop during fast camera motion
+    Stochastic,
+}
+
+impl Transparency {
+    pub const ALL: [Transparency; 3] = [
+        Transparency::Sorted,
+        Transparency::WeightedBlended,
+        Transparency::Stochastic,
+    ];
+}
+
+impl std::fmt::Display for Transparency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Transparency::Sorted => "Sorted",
+            Transparency::WeightedBlended => "Weighted Blended",
+            Transparency::Stochastic => "Stochastic",
+        })
+    }
+}
+
+impl std::str::FromStr for Transparency {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sorted" => Ok(Self::Sorted),
+            "weighted-blended" | "wboit" | "oit" => Ok(Self::WeightedBlended),
+            "stochastic" => Ok(Self::Stochastic),
+            _ => Err(anyhow::anyhow!(
+                "unknown transparency '{}', expected sorted, weighted-blended or stochastic",
+                s
+            )),
+        }
+    }
+}
+
+/// increase of the fog with the view depth
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FogFalloff {
+    /// no fog closer than `start`, only fog beyond `end`
+    Linear { start: f32, end: f32 },
+    /// the fog covers `1 - exp(-density * depth)`
+    Exponential { density: f32 },
+}
+
+/// distance fog, distant splats are blended towards the fog color
+/// hides the ragged far edge of large captures
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fog {
+    /// srgb color of the fog, a color with alpha zero fades the splats out instead
+    pub color: wgpu::Color,
+    pub falloff: FogFalloff,
+}
+
+impl Fog {
+    /// fog of the given color that starts at `start` and covers everything beyond `end`
+    pub fn linear(color: wgpu::Color, start: f32, end: f32) -> Self {
+        Self {
+            color,
+            falloff: FogFalloff::Linear { start, end },
+        }
+    }
+
+    pub fn exponential(color: wgpu::Color, density: f32) -> Self {
+        Self {
+            color,
+            falloff: FogFalloff::Exponential { density },
+        }
+    }
+}
+
+/// darkens splats that are much brighter from the current view direction than on average
+/// removes bright floater clusters in front of light sources from presentation renders,
+/// small light sources are bright from all directions and are kept
+/// the filter is applied while preprocessing the splats and requires spherical harmonics
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlareFilter {
+    /// splats brighter than `ratio` times the luminance of their view independent color are suppressed
+    pub ratio: f32,
+    /// splats darker than this (after the exposure) are never suppressed
+    pub min_luminance: f32,
+}
+
+impl Default for GlareFilter {
+    fn default() -> Self {
+        Self {
+            ratio: 4.,
+            min_luminance: 0.7,
+        }
+    }
+}
+
+/// depth of field post-processing, the blur radius of a pixel depends on its expected depth
+/// the expected depth is rendered with [GaussianRenderer::render_expected_depth]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthOfField {
+    /// distance of the sharp plane to the camera
+    pub focus_distance: f32,
+    /// blur radius in pixels of objects at infinity, larger values give a shallower depth of field
+    pub aperture: f32,
+}
+
+impl Default for DepthOfField {
+    fn default() -> Self {
+        Self {
+            focus_distance: 5.,
+            aperture: 8.,
+        }
+    }
+}
+
+/// corrections of the presented colors, e.g. for models that are too dark or have a color cast
+/// applied after the exposure, the default values leave the colors unchanged
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorGrading {
+    /// gamma correction after the tone mapping, values above 1 brighten the midtones
+    pub gamma: f32,
+    /// 0 is grayscale, 1 leaves the colors unchanged
+    pub saturation: f32,
+    /// white balance from -1 (cooler, blue) to 1 (warmer, orange)
+    pub temperature: f32,
+    /// white balance from -1 (green) to 1 (magenta)
+    pub tint: f32,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        Self {
+            gamma: 1.,
+            saturation: 1.,
+            temperature: 0.,
+            tint: 0.,
+        }
+    }
+}
+
+/// largest blur radius in pixels, limits the number of samples of the depth of field
+#[cfg(feature = "ui")]
+pub const MAX_DOF_APERTURE: f32 = 32.;
+
+/// view space depth premultiplied with the opacity (r) and the opacity (g)
+pub(crate) const EXPECTED_DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg16Float;
+
+#[cfg(feature = "ui")]
 pub struct Display {
     pipeline: wgpu::RenderPipeline,
     bind_group: wgpu::BindGroup,
     format: wgpu::TextureFormat,
     view: wgpu::TextureView,
+    /// expected depth of the splats for the depth of field, see [Display::expected_depth_target]
+    expected_depth: wgpu::TextureView,
+    /// blurred bright parts of `view`, see [Display::record_bloom]
+    bloom: BloomChain,
+    /// multisampled target the splats are rendered to, resolved into `view`
+    msaa_view: Option<wgpu::TextureView>,
+    sample_count: u32,
     env_bg: wgpu::BindGroup,
     has_env_map: bool,
+    settings: UniformBuffer<DisplayUniform>,
+    /// width of the render target followed by the fragment count of every pixel
+    overdraw: wgpu::Buffer,
+    overdraw_bg: wgpu::BindGroup,
 }
 
+#[cfg(feature = "ui")]
 impl Display {
     pub fn new(
         device: &wgpu::Device,
         source_format: wgpu::TextureFormat,
         target_format: wgpu::TextureFormat,
+        sample_count: u32,
         width: u32,
         height: u32,
     ) -> Self {
@@ -468,21 +1352,213 @@ impl Display {
             multiview: None,
         });
         let env_bg = Self::create_env_map_bg(device, None);
-        let (view, bind_group) = Self::create_render_target(device, source_format, width, height);
+        let settings = UniformBuffer::new_default(device, Some("display settings"));
+        let (overdraw, overdraw_bg) = Self::create_overdraw_buffer(device, width, height);
+        let bloom = BloomChain::new(device, width, height);
+        let (view, expected_depth, bind_group) = Self::create_render_target(
+            device,
+            source_format,
+            width,
+            height,
+            &settings,
+            &overdraw,
+            bloom.view(),
+        );
         Self {
             pipeline,
             view,
+            expected_depth,
+            bloom,
+            msaa_view: create_multisampled_view(device, source_format, width, height, sample_count),
+            sample_count,
             format: source_format,
             bind_group,
             env_bg,
             has_env_map: false,
+            settings,
+            overdraw,
+            overdraw_bg,
+        }
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.settings.data().exposure
+    }
+
+    /// colors are multiplied with `exposure` before they are written to the surface
+    pub fn set_exposure(&mut self, queue: &wgpu::Queue, exposure: f32) {
+        self.settings.as_mut().exposure = exposure;
+        self.settings.sync(queue);
+    }
+
+    pub fn tonemapping(&self) -> Tonemapping {
+        Tonemapping::from_u32(self.settings.data().tonemapping)
+    }
+
+    /// tone mapping is applied after the exposure, only useful with an hdr render target
+    pub fn set_tonemapping(&mut self, queue: &wgpu::Queue, tonemapping: Tonemapping) {
+        self.settings.as_mut().tonemapping = tonemapping as u32;
+        self.settings.sync(queue);
+    }
+
+    pub fn output_color_space(&self) -> ColorSpace {
+        ColorSpace::from_u32(self.settings.data().output_color_space)
+    }
+
+    /// transfer function of the presented colors, independent of the color space the splats are blended in
+    /// srgb for regular displays, linear for compositing or captures that are processed further
+    pub fn set_output_color_space(&mut self, queue: &wgpu::Queue, color_space: ColorSpace) {
+        self.settings.as_mut().output_color_space = color_space as u32;
+        self.settings.sync(queue);
+    }
+
+    pub fn depth_of_field(&self) -> Option<DepthOfField> {
+        let settings = self.settings.data();
+        (settings.dof_aperture > 0.).then_some(DepthOfField {
+            focus_distance: settings.dof_focus_distance,
+            aperture: settings.dof_aperture,
+        })
+    }
+
+    /// the expected depth must be rendered into [Display::expected_depth_target] every frame
+    /// while the depth of field is enabled
+    pub fn set_depth_of_field(&mut self, queue: &wgpu::Queue, dof: Option<DepthOfField>) {
+        let settings = self.settings.as_mut();
+        let dof = dof.unwrap_or(DepthOfField {
+            aperture: 0.,
+            ..Default::default()
+        });
+        settings.dof_focus_distance = dof.focus_distance.max(f32::EPSILON);
+        settings.dof_aperture = dof.aperture.clamp(0., MAX_DOF_APERTURE);
+        self.settings.sync(queue);
+    }
+
+    pub fn bloom(&self) -> Option<Bloom> {
+        let intensity = self.settings.data().bloom_intensity;
+        (intensity > 0.).then_some(Bloom {
+            intensity,
+            threshold: self.bloom.threshold(),
+        })
+    }
+
+    /// the bloom must be recorded with [Display::record_bloom] every frame while it is enabled
+    pub fn set_bloom(&mut self, queue: &wgpu::Queue, bloom: Option<Bloom>) {
+        let bloom = bloom.unwrap_or(Bloom {
+            intensity: 0.,
+            ..Default::default()
+        });
+        self.settings.as_mut().bloom_intensity = bloom.intensity.max(0.);
+        self.settings.sync(queue);
+        self.bloom.set_threshold(queue, bloom.threshold);
+    }
+
+    /// blurs the bright parts of [Display::texture], the splats were blended in `color_space`
+    pub fn record_bloom(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        color_space: ColorSpace,
+    ) {
+        self.bloom
+            .record(device, queue, encoder, &self.view, color_space);
+    }
+
+    pub fn vignette(&self) -> f32 {
+        self.settings.data().vignette
+    }
+
+    /// darkens the image towards the corners, 0 is off and 1 darkens the corners to black
+    pub fn set_vignette(&mut self, queue: &wgpu::Queue, vignette: f32) {
+        self.settings.as_mut().vignette = vignette.clamp(0., 1.);
+        self.settings.sync(queue);
+    }
+
+    pub fn sharpening(&self) -> f32 {
+        self.settings.data().sharpening
+    }
+
+    /// contrast adaptive sharpening of the rendered image, 0 is off and 1 is the strongest
+    pub fn set_sharpening(&mut self, queue: &wgpu::Queue, sharpening: f32) {
+        self.settings.as_mut().sharpening = sharpening.clamp(0., 1.);
+        self.settings.sync(queue);
+    }
+
+    pub fn color_grading(&self) -> ColorGrading {
+        let settings = self.settings.data();
+        ColorGrading {
+            gamma: settings.gamma,
+            saturation: settings.saturation,
+            temperature: settings.temperature,
+            tint: settings.tint,
         }
     }
 
+    pub fn set_color_grading(&mut self, queue: &wgpu::Queue, grading: ColorGrading) {
+        let settings = self.settings.as_mut();
+        settings.gamma = grading.gamma.max(0.01);
+        settings.saturation = grading.saturation.max(0.);
+        settings.temperature = grading.temperature.clamp(-1., 1.);
+        settings.tint = grading.tint.clamp(-1., 1.);
+        self.settings.sync(queue);
+    }
+
     pub fn texture(&self) -> &wgpu::TextureView {
         &self.view
     }
 
+    /// target of [GaussianRenderer::render_expected_depth]
+    pub fn expected_depth_target(&self) -> &wgpu::TextureView {
+        &self.expected_depth
+    }
+
+    /// attachment for rendering the splats and its resolve target
+    /// the attachment is the multisampled target if msaa is enabled, otherwise [Display::texture]
+    pub fn render_target(&self) -> (&wgpu::TextureView, Option<&wgpu::TextureView>) {
+        match &self.msaa_view {
+            Some(msaa) => (msaa, Some(&self.view)),
+            None => (&self.view, None),
+        }
+    }
+
+    /// fragment counters for [GaussianRenderer::render_overdraw]
+    pub fn overdraw_bind_group(&self) -> &wgpu::BindGroup {
+        &self.overdraw_bg
+    }
+
+    /// resets the fragment counters, must be called before the splats are rendered in the overdraw mode
+    pub fn clear_overdraw(&self, encoder: &mut wgpu::CommandEncoder) {
+        // the first word holds the width
+        encoder.clear_buffer(&self.overdraw, 4, None);
+    }
+
+    fn create_overdraw_buffer(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("overdraw buffer"),
+            size: (1 + width as u64 * height as u64) * 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: true,
+        });
+        buffer
+            .slice(..4)
+            .get_mapped_range_mut()
+            .copy_from_slice(&width.to_le_bytes());
+        buffer.unmap();
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("overdraw bind group"),
+            layout: &GaussianRenderer::overdraw_bind_group_layout(device),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        (buffer, bind_group)
+    }
+
     fn env_map_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
         device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("env map bind group layout"),
@@ -564,7 +1640,10 @@ impl Display {
         format: wgpu::TextureFormat,
         width: u32,
         height: u32,
-    ) -> (wgpu::TextureView, wgpu::BindGroup) {
+        settings: &UniformBuffer<DisplayUniform>,
+        overdraw: &wgpu::Buffer,
+        bloom: &wgpu::TextureView,
+    ) -> (wgpu::TextureView, wgpu::TextureView, wgpu::BindGroup) {
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("display render image"),
             size: Extent3d {
@@ -580,6 +1659,23 @@ impl Display {
             view_formats: &[],
         });
         let texture_view = texture.create_view(&Default::default());
+        let expected_depth = device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("expected depth texture"),
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: EXPECTED_DEPTH_FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            })
+            .create_view(&Default::default());
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Linear,
@@ -597,9 +1693,25 @@ impl Display {
                     binding: 1,
                     resource: wgpu::BindingResource::Sampler(&sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: settings.buffer().as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: overdraw.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&expected_depth),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(bloom),
+                },
             ],
         });
-        return (texture_view, bind_group);
+        return (texture_view, expected_depth, bind_group);
     }
 
     pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
@@ -622,14 +1734,65 @@ impl Display {
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: UniformBuffer::<DisplayUniform>::binding_type(),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
             ],
         })
     }
 
     pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
-        let (view, bind_group) = Self::create_render_target(device, self.format, width, height);
+        let (overdraw, overdraw_bg) = Self::create_overdraw_buffer(device, width, height);
+        self.bloom.resize(device, width, height);
+        let (view, expected_depth, bind_group) = Self::create_render_target(
+            device,
+            self.format,
+            width,
+            height,
+            &self.settings,
+            &overdraw,
+            self.bloom.view(),
+        );
+        self.overdraw = overdraw;
+        self.overdraw_bg = overdraw_bg;
         self.bind_group = bind_group;
         self.view = view;
+        self.expected_depth = expected_depth;
+        self.msaa_view =
+            create_multisampled_view(device, self.format, width, height, self.sample_count);
     }
 
     pub fn render(
@@ -662,9 +1825,10 @@ impl Display {
     }
 }
 
+/// settings of one frame, see [GaussianRenderer::prepare]
 #[repr(C)]
 #[derive(Copy, Clone, Debug,PartialEq)]
-pub struct SplattingArgs {
+pub struct RenderSettings {
     pub camera: PerspectiveCamera,
     pub viewport: Vector2<u32>,
     pub gaussian_scaling: f32,
@@ -672,7 +1836,27 @@ pub struct SplattingArgs {
     pub show_env_map: bool,
     pub mip_splatting: Option<bool>,
     pub kernel_size: Option<f32>,
+    /// splats and fragments with a lower opacity are discarded (e.g. to remove transparent floaters)
+    pub opacity_threshold: f32,
+    /// color space in which the splats are blended
+    pub color_space: ColorSpace,
+    pub render_mode: RenderMode,
+    /// how overlapping splats are blended
+    pub transparency: Transparency,
+    /// splats with a smaller scale (geometric mean of the standard deviations) are skipped
+    /// in the ellipsoid render modes, 0 draws all splats
+    pub min_scale: f32,
+    /// blends distant splats towards the fog color
+    pub fog: Option<Fog>,
+    /// suppresses view dependent highlights of floaters
+    pub glare_filter: Option<GlareFilter>,
+    /// exposure of the displayed image, the glare filter compares exposed luminances
+    pub exposure: f32,
     pub clipping_box: Option<Aabb<f32>>,
+    /// planes (normal, distance) that cut away the splats with a negative distance
+    pub clip_planes: [Option<Vector4<f32>>; MAX_CLIP_PLANES],
+    /// oriented box, the splats outside of it are cut away
+    pub crop_box: Option<CropRegion>,
     pub walltime: Duration,
     pub scene_center: Option<Point3<f32>>,
     pub scene_extend: Option<f32>,
@@ -680,7 +1864,91 @@ pub struct SplattingArgs {
     pub resolution: Vector2<u32>,
 }
 
+/// maximum number of [RenderSettings::clip_planes]
+pub const MAX_CLIP_PLANES: usize = 6;
+
+/// multisampled render target with `sample_count` samples per pixel
+/// `None` if `sample_count` is 1 and the target can be rendered to directly
+pub fn create_multisampled_view(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    sample_count: u32,
+) -> Option<wgpu::TextureView> {
+    if sample_count <= 1 {
+        return None;
+    }
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("multisampled render target"),
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    Some(texture.create_view(&Default::default()))
+}
+
+/// depth attachment for [GaussianRenderer::set_depth_format]
+/// `sample_count` must match the color attachment of the render pass
+pub fn create_depth_view(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    sample_count: u32,
+) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("depth texture"),
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    texture.create_view(&Default::default())
+}
+
+/// highest sample count that is supported for `format` and not larger than `requested`
+/// without [wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES] only the guaranteed counts (1 and 4) can be used
+#[cfg(feature = "ui")]
+pub fn supported_sample_count(
+    adapter: &wgpu::Adapter,
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    requested: u32,
+) -> u32 {
+    let flags = if device
+        .features()
+        .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+    {
+        adapter.get_texture_format_features(format).flags
+    } else {
+        format.guaranteed_format_features(device.features()).flags
+    };
+    [8, 4, 2]
+        .into_iter()
+        .find(|&n| n <= requested && flags.sample_count_supported(n))
+        .unwrap_or(1)
+}
+
 pub const DEFAULT_KERNEL_SIZE: f32 = 0.3;
+/// range of the global splat scale multiplier in the user interface
+#[cfg(feature = "ui")]
+pub const GAUSSIAN_SCALING_RANGE: std::ops::RangeInclusive<f32> = 0.1..=2.;
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct SplattingArgsUniform {
@@ -694,14 +1962,58 @@ pub struct SplattingArgsUniform {
     kernel_size: f32,
     walltime: f32,
     scene_extend: f32,
-    _pad: u32,
+    /// number of [crate::io::LodRange]s including the total, 0 if all splats are processed
+    lod_ranges: u32,
+
+    scene_center: Point3<f32>,
+    opacity_threshold: f32,
 
-    scene_center: Vector4<f32>,
+    color_space: u32,
+    render_mode: u32,
+    min_scale: f32,
+    /// splat layout, see [PointCloud::layout]
+    half_precision: u32,
+    packed_covariance: u32,
+    log_scale_min: f32,
+    log_scale_max: f32,
+    /// 0: no fog, 1: linear, 2: exponential
+    fog_mode: u32,
+    /// premultiplied fog color in the blending color space
+    fog_color: Vector4<f32>,
+    /// recovers the view depth of a fragment from its depth, see `fs_oit` in gaussian.wgsl
+    proj_depth: Vector2<f32>,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    /// number of used `clip_planes`
+    num_clip_planes: u32,
+    /// 1 if the splats outside of `crop_box` are cut away
+    crop_box_enabled: u32,
+    _pad: u32,
+    clip_planes: [Vector4<f32>; MAX_CLIP_PLANES],
+    /// maps the crop box to [-1, 1]³
+    crop_box: Matrix4<f32>,
+    /// a ratio of 0 disables the glare filter
+    glare_ratio: f32,
+    /// minimum luminance of the glare filter divided by the exposure
+    glare_min_luminance: f32,
+    _pad2: [u32; 2],
 }
 
 impl SplattingArgsUniform {
     /// replaces values with default values for point cloud
-    pub fn from_args_and_pc(args: SplattingArgs, pc: &PointCloud) -> Self {
+    pub fn from_args_and_pc(args: RenderSettings, pc: &PointCloud) -> Self {
+        let (fog_mode, fog_start, fog_end, fog_density) = match args.fog.map(|f| f.falloff) {
+            None => (0, 0., 0., 0.),
+            Some(FogFalloff::Linear { start, end }) => (1, start, end, 0.),
+            Some(FogFalloff::Exponential { density }) => (2, 0., 0., density),
+        };
+        let mut clip_planes = [Vector4::zero(); MAX_CLIP_PLANES];
+        let mut num_clip_planes = 0;
+        for plane in args.clip_planes.iter().flatten() {
+            clip_planes[num_clip_planes] = *plane;
+            num_clip_planes += 1;
+        }
         Self {
             gaussian_scaling: args.gaussian_scaling,
             max_sh_deg: args.max_sh_deg,
@@ -724,7 +2036,39 @@ impl SplattingArgsUniform {
                 .to_vec()
                 .extend(0.),
             walltime: args.walltime.as_secs_f32(),
-            scene_center: pc.center().to_vec().extend(0.),
+            scene_center: pc.center(),
+            opacity_threshold: args.opacity_threshold,
+            color_space: args.color_space as u32,
+            render_mode: args.render_mode as u32,
+            min_scale: args.min_scale,
+            half_precision: pc.layout().half_precision as u32,
+            packed_covariance: pc.layout().packed_covariance.is_some() as u32,
+            log_scale_min: pc.layout().packed_covariance.map_or(0., |r| r.0),
+            log_scale_max: pc.layout().packed_covariance.map_or(0., |r| r.1),
+            fog_mode,
+            fog_color: args.fog.map_or(Vector4::zero(), |fog| {
+                let c = args.color_space.from_srgb(fog.color);
+                Vector4::new(c.r * c.a, c.g * c.a, c.b * c.a, c.a)
+                    .cast()
+                    .unwrap()
+            }),
+            proj_depth: {
+                let proj = args.camera.proj_matrix();
+                Vector2::new(proj[2][2], proj[3][2])
+            },
+            fog_start,
+            fog_end,
+            fog_density,
+            num_clip_planes: num_clip_planes as u32,
+            crop_box_enabled: args.crop_box.is_some() as u32,
+            clip_planes,
+            crop_box: args
+                .crop_box
+                .map_or(Matrix4::identity(), |crop| crop.world_to_unit()),
+            glare_ratio: args.glare_filter.map_or(0., |f| f.ratio),
+            glare_min_luminance: args
+                .glare_filter
+                .map_or(0., |f| f.min_luminance / args.exposure.max(1e-6)),
             scene_extend: args
                 .scene_extend
                 .unwrap_or(pc.bbox().radius())
@@ -750,9 +2094,31 @@ impl Default for SplattingArgsUniform {
                 0.,
             ),
             walltime: 0.,
-            scene_center: Vector4::new(0., 0., 0., 0.),
+            scene_center: Point3::origin(),
+            opacity_threshold: 0.,
             scene_extend: 1.,
+            lod_ranges: 0,
+            color_space: ColorSpace::Srgb as u32,
+            render_mode: RenderMode::Splats as u32,
+            min_scale: 0.,
+            half_precision: false as u32,
+            packed_covariance: false as u32,
+            log_scale_min: 0.,
+            log_scale_max: 0.,
+            fog_mode: 0,
+            fog_color: Vector4::zero(),
+            proj_depth: Vector2::new(0., 0.),
+            fog_start: 0.,
+            fog_end: 0.,
+            fog_density: 0.,
+            num_clip_planes: 0,
+            crop_box_enabled: 0,
             _pad: 0,
+            clip_planes: [Vector4::zero(); MAX_CLIP_PLANES],
+            crop_box: Matrix4::identity(),
+            glare_ratio: 0.,
+            glare_min_luminance: 0.,
+            _pad2: [0; 2],
         }
     }
 }
@@ -1,17 +1,26 @@
 use std::{
     collections::HashMap,
+    fs::File,
     hash::Hash,
-    io::{self, BufReader},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
 };
 
-use cgmath::{Matrix3, MetricSpace, Point3, SquareMatrix, Vector2};
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use cgmath::{Matrix, Matrix3, MetricSpace, Point3, Quaternion, SquareMatrix, Vector2, Vector3};
 use serde::{Deserialize, Serialize};
 
-use crate::camera::{focal2fov, fov2focal, PerspectiveCamera, PerspectiveProjection};
+use crate::{
+    camera::{focal2fov, fov2focal, PerspectiveCamera, PerspectiveProjection},
+    WebSplatError,
+};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SceneCamera {
+    #[serde(default)]
     pub id: usize,
+    #[serde(default)]
     pub img_name: String,
     pub width: u32,
     pub height: u32,
@@ -19,6 +28,11 @@ pub struct SceneCamera {
     pub rotation: [[f32; 3]; 3],
     pub fx: f32,
     pub fy: f32,
+    /// principal point in pixels, the image center if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cx: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cy: Option<f32>,
     #[serde(skip_deserializing, skip_serializing)]
     pub split: Split,
 }
@@ -31,6 +45,8 @@ impl std::hash::Hash for SceneCamera {
         bytemuck::cast_slice::<_, u8>(&self.position).hash(state);
         bytemuck::cast_slice::<_, u8>(&self.rotation).hash(state);
         bytemuck::cast_slice::<_, u8>(&[self.fx, self.fy]).hash(state);
+        self.cx.map(f32::to_bits).hash(state);
+        self.cy.map(f32::to_bits).hash(state);
         self.split.hash(state);
     }
 }
@@ -46,6 +62,10 @@ impl SceneCamera {
         let fx = fov2focal(cam.projection.fovx, viewport.x as f32);
         let fy = fov2focal(cam.projection.fovy, viewport.y as f32);
         let rot: Matrix3<f32> = cam.rotation.into();
+        // the principal point is only stored if it is not in the center
+        let offset = cam.projection.principal_offset;
+        let principal =
+            |offset: f32, size: u32| (offset != 0.).then_some((offset + 1.) * size as f32 / 2.);
         Self {
             id,
             img_name: name,
@@ -55,6 +75,8 @@ impl SceneCamera {
             rotation: rot.into(),
             fx,
             fy,
+            cx: principal(offset.x, viewport.x),
+            cy: principal(offset.y, viewport.y),
             split,
         }
     }
@@ -94,24 +116,38 @@ impl Into<PerspectiveCamera> for SceneCamera {
             rot.y[1] = -rot.y[1];
             rot.z[1] = -rot.z[1];
         }
+        let viewport = Vector2::new(self.width, self.height);
+        let center = viewport.cast::<f32>().unwrap() / 2.;
         PerspectiveCamera {
             position: self.position.into(),
             rotation: rot.into(),
-            projection: PerspectiveProjection::new(
-                Vector2::new(self.width, self.height),
-                Vector2::new(fovx, fovy),
-                0.01,
-                100.,
-            ),
+            projection: PerspectiveProjection::new(viewport, Vector2::new(fovx, fovy), 0.01, 100.)
+                .with_principal_point(
+                    viewport,
+                    Vector2::new(self.cx.unwrap_or(center.x), self.cy.unwrap_or(center.y)),
+                ),
         }
     }
 }
 
+/// file format a scene was loaded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SceneFormat {
+    /// cameras.json layout of Kerbl et al., written by [Scene::to_json]
+    #[default]
+    Cameras,
+    /// nerfstudio transforms.json
+    Nerfstudio,
+    /// colmap sparse reconstruction
+    Colmap,
+}
+
 #[derive(Debug)]
 pub struct Scene {
     cameras: HashMap<usize, SceneCamera>,
     /// maximum distance between two cameras
     extend: f32,
+    format: SceneFormat,
 }
 
 impl Scene {
@@ -130,23 +166,159 @@ impl Scene {
         Self {
             cameras: map,
             extend,
+            format: SceneFormat::Cameras,
         }
     }
 
-    pub fn from_json<R: io::Read>(file: R) -> Result<Self, anyhow::Error> {
-        let mut reader = BufReader::new(file);
-        let mut cameras: Vec<SceneCamera> = serde_json::from_reader(&mut reader)?;
-        for (i, c) in cameras.iter_mut().enumerate() {
-            // according to Kerbl et al "3D Gaussian Splatting for Real-Time Radiance Field Rendering"
-            // 7 out of 8 cameras are taken as training images
-            c.split = if i % 8 == 0 {
-                Split::Test
-            } else {
-                Split::Train
-            }
+    /// loads a scene file
+    /// supports the cameras.json layout of Kerbl et al. and nerfstudio's transforms.json
+    pub fn from_json<R: io::Read>(file: R) -> Result<Self, WebSplatError> {
+        Self::from_json_with_dataparser(file, None)
+    }
+
+    /// loads a scene from disk
+    /// `path` is either a json file (see [Scene::from_json]) or a colmap sparse reconstruction
+    /// (the directory with `cameras.bin` and `images.bin`, a dataset directory with `sparse/0` or one of the two files)
+    /// the poses of a nerfstudio transforms.json are aligned with the exported splats
+    /// if there is a `dataparser_transforms.json` next to it
+    pub fn open(path: &Path) -> Result<Self, WebSplatError> {
+        if let Some(sparse_dir) = colmap_dir(path) {
+            return Self::from_colmap(sparse_dir).map_err(WebSplatError::Scene);
         }
+        let dataparser_path = path.with_file_name("dataparser_transforms.json");
+        let dataparser = if dataparser_path.exists() {
+            let file = BufReader::new(File::open(&dataparser_path)?);
+            Some(serde_json::from_reader(file).map_err(|err| WebSplatError::Scene(err.into()))?)
+        } else {
+            None
+        };
+        Self::from_json_with_dataparser(File::open(path)?, dataparser.as_ref())
+    }
+
+    fn from_json_with_dataparser<R: io::Read>(
+        file: R,
+        dataparser: Option<&DataparserTransforms>,
+    ) -> Result<Self, WebSplatError> {
+        let mut data = Vec::new();
+        BufReader::new(file).read_to_end(&mut data)?;
+        let (mut cameras, format) = match serde_json::from_slice(&data) {
+            Ok(cameras) => (cameras, SceneFormat::Cameras),
+            Err(err) => match serde_json::from_slice::<NerfstudioTransforms>(&data) {
+                Ok(transforms) => (
+                    transforms
+                        .cameras(dataparser)
+                        .map_err(WebSplatError::Scene)?,
+                    SceneFormat::Nerfstudio,
+                ),
+                Err(_) => return Err(WebSplatError::Scene(err.into())),
+            },
+        };
+        assign_splits(&mut cameras);
         log::info!("loaded scene file with {} views", cameras.len());
-        Ok(Self::from_cameras(cameras))
+        Ok(Self {
+            format,
+            ..Self::from_cameras(cameras)
+        })
+    }
+
+    /// loads the cameras from a colmap sparse reconstruction (`cameras.bin` and `images.bin`)
+    pub fn from_colmap<P: AsRef<Path>>(sparse_dir: P) -> Result<Self, anyhow::Error> {
+        let sparse_dir = sparse_dir.as_ref();
+        let intrinsics = read_colmap_cameras(File::open(sparse_dir.join("cameras.bin"))?)?;
+        let mut images = read_colmap_images(File::open(sparse_dir.join("images.bin"))?)?;
+        // same order as used for training by Kerbl et al.
+        images.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut cameras = images
+            .into_iter()
+            .enumerate()
+            .map(|(id, img)| {
+                let cam = intrinsics.get(&img.camera_id).ok_or(anyhow::anyhow!(
+                    "image {} references unknown camera {}",
+                    img.name,
+                    img.camera_id
+                ))?;
+                // colmap stores world to camera transforms
+                let r = Matrix3::from(Quaternion::new(img.q[0], img.q[1], img.q[2], img.q[3]));
+                let t = Vector3::from(img.t);
+                let position = -(r.transpose() * t);
+                Ok(SceneCamera {
+                    id,
+                    img_name: img.name,
+                    width: cam.width,
+                    height: cam.height,
+                    position: position.into(),
+                    // rows of the camera to world rotation
+                    rotation: r.into(),
+                    fx: cam.fx,
+                    fy: cam.fy,
+                    cx: Some(cam.cx),
+                    cy: Some(cam.cy),
+                    split: Split::default(),
+                })
+            })
+            .collect::<Result<Vec<SceneCamera>, anyhow::Error>>()?;
+        assign_splits(&mut cameras);
+        log::info!("loaded colmap scene with {} views", cameras.len());
+        Ok(Self {
+            format: SceneFormat::Colmap,
+            ..Self::from_cameras(cameras)
+        })
+    }
+
+    /// writes all cameras (sorted by id) in the same format as read by [Scene::from_json]
+    pub fn to_json<W: io::Write>(&self, file: W) -> Result<(), anyhow::Error> {
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &self.cameras(None))?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn format(&self) -> SceneFormat {
+        self.format
+    }
+
+    /// file that [Scene::save] writes to for a scene loaded from `path`
+    /// scenes in other formats than cameras.json are written next to the original file instead of overwriting it
+    pub fn save_path(&self, path: &Path) -> PathBuf {
+        match self.format {
+            SceneFormat::Cameras => path.to_path_buf(),
+            SceneFormat::Nerfstudio => {
+                let stem = path
+                    .file_stem()
+                    .map_or("transforms".into(), |s| s.to_string_lossy());
+                path.with_file_name(format!("{stem}_cameras.json"))
+            }
+            SceneFormat::Colmap if path.is_dir() => path.join("cameras.json"),
+            SceneFormat::Colmap => path.with_file_name("cameras.json"),
+        }
+    }
+
+    /// writes the scene as cameras.json for a scene that was loaded from `path`, see [Scene::save_path]
+    /// returns the written file, later saves of this scene go to the same file
+    pub fn save(&mut self, path: &Path) -> Result<PathBuf, anyhow::Error> {
+        let save_path = self.save_path(path);
+        self.to_json(File::create(&save_path)?)?;
+        self.format = SceneFormat::Cameras;
+        Ok(save_path)
+    }
+
+    /// adds a camera to the scene
+    /// the camera id is replaced with the next free id which is returned
+    pub fn add_camera(&mut self, mut camera: SceneCamera) -> usize {
+        let id = self.cameras.keys().max().map_or(0, |m| m + 1);
+        camera.id = id;
+        if camera.img_name.is_empty() {
+            camera.img_name = id.to_string();
+        }
+        self.cameras.insert(id, camera);
+        self.extend = max_distance(
+            self.cameras
+                .values()
+                .map(|c| Point3::from(c.position))
+                .collect(),
+        );
+        id
     }
 
     pub fn camera(&self, i: usize) -> Option<SceneCamera> {
@@ -187,6 +359,229 @@ impl Scene {
     }
 }
 
+/// nerfstudio transforms.json
+/// intrinsics can either be shared or specified per frame
+#[derive(Debug, Deserialize)]
+struct NerfstudioTransforms {
+    fl_x: Option<f32>,
+    fl_y: Option<f32>,
+    cx: Option<f32>,
+    cy: Option<f32>,
+    w: Option<u32>,
+    h: Option<u32>,
+    frames: Vec<NerfstudioFrame>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NerfstudioFrame {
+    file_path: String,
+    /// camera to world matrix in OpenGL convention
+    transform_matrix: [[f32; 4]; 4],
+    fl_x: Option<f32>,
+    fl_y: Option<f32>,
+    cx: Option<f32>,
+    cy: Option<f32>,
+    w: Option<u32>,
+    h: Option<u32>,
+}
+
+/// dataparser_transforms.json written by nerfstudio next to the trained model
+/// the exported splats are in the transformed coordinate system, the poses in transforms.json are not
+#[derive(Debug, Deserialize)]
+struct DataparserTransforms {
+    /// 3x4 transform applied to the camera to world matrices
+    transform: [[f32; 4]; 3],
+    /// scale applied to the camera positions after the transform
+    scale: f32,
+}
+
+impl DataparserTransforms {
+    fn apply(&self, c2w: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+        let mut out = c2w;
+        for (i, row) in self.transform.iter().enumerate() {
+            for j in 0..4 {
+                out[i][j] = (0..4).map(|k| row[k] * c2w[k][j]).sum();
+            }
+            out[i][3] *= self.scale;
+        }
+        out
+    }
+}
+
+impl NerfstudioTransforms {
+    fn cameras(
+        &self,
+        dataparser: Option<&DataparserTransforms>,
+    ) -> Result<Vec<SceneCamera>, anyhow::Error> {
+        self.frames
+            .iter()
+            .enumerate()
+            .map(|(id, f)| {
+                let missing = |name| anyhow::anyhow!("frame {} has no {}", f.file_path, name);
+                let width = f.w.or(self.w).ok_or_else(|| missing("w"))?;
+                let height = f.h.or(self.h).ok_or_else(|| missing("h"))?;
+                let fx = f.fl_x.or(self.fl_x).ok_or_else(|| missing("fl_x"))?;
+                let fy = f.fl_y.or(self.fl_y).unwrap_or(fx);
+                let c2w = dataparser.map_or(f.transform_matrix, |d| d.apply(f.transform_matrix));
+                let (position, rotation) = opengl_to_scene_pose(c2w);
+                let img_name = std::path::Path::new(&f.file_path)
+                    .file_stem()
+                    .map_or(f.file_path.clone(), |s| s.to_string_lossy().to_string());
+                Ok(SceneCamera {
+                    id,
+                    img_name,
+                    width,
+                    height,
+                    position,
+                    rotation,
+                    fx,
+                    fy,
+                    cx: f.cx.or(self.cx),
+                    cy: f.cy.or(self.cy),
+                    split: Split::default(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// converts a camera to world matrix (row major, OpenGL convention) to the position and rotation used in scene files
+/// scene files use the colmap convention and store the rows of the camera to world rotation
+pub(crate) fn opengl_to_scene_pose(c2w: [[f32; 4]; 4]) -> ([f32; 3], [[f32; 3]; 3]) {
+    let position = [c2w[0][3], c2w[1][3], c2w[2][3]];
+    // flip y and z axis
+    let rotation = [0, 1, 2].map(|i| [c2w[i][0], -c2w[i][1], -c2w[i][2]]);
+    (position, rotation)
+}
+
+/// directory of the colmap sparse reconstruction that `path` points to
+fn colmap_dir(path: &Path) -> Option<PathBuf> {
+    let is_sparse_dir =
+        |dir: &Path| dir.join("cameras.bin").exists() && dir.join("images.bin").exists();
+    if path.is_dir() {
+        [path.to_path_buf(), path.join("sparse").join("0")]
+            .into_iter()
+            .find(|dir| is_sparse_dir(dir))
+    } else if path.extension().is_some_and(|ext| ext == "bin") {
+        path.parent()
+            .filter(|dir| is_sparse_dir(dir))
+            .map(Path::to_path_buf)
+    } else {
+        None
+    }
+}
+
+fn assign_splits(cameras: &mut [SceneCamera]) {
+    for (i, c) in cameras.iter_mut().enumerate() {
+        // according to Kerbl et al "3D Gaussian Splatting for Real-Time Radiance Field Rendering"
+        // 7 out of 8 cameras are taken as training images
+        c.split = if i % 8 == 0 {
+            Split::Test
+        } else {
+            Split::Train
+        }
+    }
+}
+
+struct ColmapCamera {
+    width: u32,
+    height: u32,
+    fx: f32,
+    fy: f32,
+    cx: f32,
+    cy: f32,
+}
+
+struct ColmapImage {
+    /// rotation quaternion (w,x,y,z)
+    q: [f32; 4],
+    t: [f32; 3],
+    camera_id: u32,
+    name: String,
+}
+
+/// the counts in a corrupt file are not trusted, at most this many entries are allocated up front
+const MAX_PREALLOCATED: u64 = 1 << 16;
+
+fn read_colmap_cameras<R: io::Read>(file: R) -> Result<HashMap<u32, ColmapCamera>, anyhow::Error> {
+    let mut r = BufReader::new(file);
+    let num_cameras = r.read_u64::<LittleEndian>()?;
+    let mut cameras = HashMap::with_capacity(num_cameras.min(MAX_PREALLOCATED) as usize);
+    for _ in 0..num_cameras {
+        let camera_id = r.read_i32::<LittleEndian>()? as u32;
+        let model_id = r.read_i32::<LittleEndian>()?;
+        let width = r.read_u64::<LittleEndian>()? as u32;
+        let height = r.read_u64::<LittleEndian>()? as u32;
+        // see https://github.com/colmap/colmap/blob/main/src/colmap/sensor/models.h
+        let (num_params, single_focal) = match model_id {
+            0 => (3, true),    // SIMPLE_PINHOLE
+            1 => (4, false),   // PINHOLE
+            2 => (4, true),    // SIMPLE_RADIAL
+            3 => (5, true),    // RADIAL
+            4 => (8, false),   // OPENCV
+            5 => (8, false),   // OPENCV_FISHEYE
+            6 => (12, false),  // FULL_OPENCV
+            7 => (5, false),   // FOV
+            8 => (4, true),    // SIMPLE_RADIAL_FISHEYE
+            9 => (5, true),    // RADIAL_FISHEYE
+            10 => (12, false), // THIN_PRISM_FISHEYE
+            _ => return Err(anyhow::anyhow!("unknown colmap camera model {}", model_id)),
+        };
+        let mut params = vec![0f64; num_params];
+        r.read_f64_into::<LittleEndian>(&mut params)?;
+        let fx = params[0] as f32;
+        let fy = if single_focal { fx } else { params[1] as f32 };
+        // the principal point follows the focal length(s) in all models
+        let c = if single_focal { 1 } else { 2 };
+        cameras.insert(
+            camera_id,
+            ColmapCamera {
+                width,
+                height,
+                fx,
+                fy,
+                cx: params[c] as f32,
+                cy: params[c + 1] as f32,
+            },
+        );
+    }
+    Ok(cameras)
+}
+
+fn read_colmap_images<R: io::Read>(file: R) -> Result<Vec<ColmapImage>, anyhow::Error> {
+    let mut r = BufReader::new(file);
+    let num_images = r.read_u64::<LittleEndian>()?;
+    let mut images = Vec::with_capacity(num_images.min(MAX_PREALLOCATED) as usize);
+    for _ in 0..num_images {
+        let _image_id = r.read_i32::<LittleEndian>()?;
+        let mut q = [0f64; 4];
+        r.read_f64_into::<LittleEndian>(&mut q)?;
+        let mut t = [0f64; 3];
+        r.read_f64_into::<LittleEndian>(&mut t)?;
+        let camera_id = r.read_i32::<LittleEndian>()? as u32;
+        let mut name = Vec::new();
+        loop {
+            match r.read_u8()? {
+                0 => break,
+                c => name.push(c),
+            }
+        }
+        // skip 2d points (x: f64, y: f64, point3d_id: i64)
+        let num_points2d = r.read_u64::<LittleEndian>()?;
+        io::copy(
+            &mut (&mut r).take(num_points2d.saturating_mul(24)),
+            &mut io::sink(),
+        )?;
+        images.push(ColmapImage {
+            q: q.map(|v| v as f32),
+            t: t.map(|v| v as f32),
+            camera_id,
+            name: String::from_utf8(name)?,
+        });
+    }
+    Ok(images)
+}
+
 /// calculate the maximum distance between any two points
 /// naive implementation with O(n^2)
 fn max_distance(points: Vec<Point3<f32>>) -> f32 {
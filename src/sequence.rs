@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    io::{GenericGaussianPointCloud, LoadOptions},
+    pointcloud::ShStorage,
+    time::Duration,
+    PointCloud,
+};
+
+/// sequence of point clouds for dynamic (4D) captures
+/// all frames are kept on the GPU and swapped with the rendered point cloud
+pub struct PointCloudSequence {
+    /// frames of the sequence
+    /// the slot of the current frame holds the point cloud that was swapped out last
+    frames: Vec<PointCloud>,
+    current: usize,
+    time: Duration,
+    pub fps: f32,
+    pub playing: bool,
+}
+
+impl PointCloudSequence {
+    /// lists all point cloud files in a directory sorted by name
+    pub fn list_files<P: AsRef<Path>>(dir: P) -> anyhow::Result<Vec<PathBuf>> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.extension()
+                    .is_some_and(|e| e == "ply" || (cfg!(feature = "npz") && e == "npz"))
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// loads all frames
+    /// the first frame is swapped into `pc`
+    pub fn load(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        files: &[PathBuf],
+        sh_storage: ShStorage,
+        options: &LoadOptions,
+        fps: f32,
+        pc: &mut PointCloud,
+    ) -> anyhow::Result<Self> {
+        let mut frames = Vec::with_capacity(files.len());
+        for file in files {
+            let pc_raw =
+                GenericGaussianPointCloud::load_with_options(std::fs::File::open(file)?, options)?;
+            let frame = PointCloud::new_with_sh_storage(device, queue, pc_raw, sh_storage)?;
+            if frame.sh_deg() != pc.sh_deg() || frame.compressed() != pc.compressed() {
+                return Err(anyhow::anyhow!(
+                    "frame {:?} uses a different sh degree or compression than the first frame",
+                    file
+                ));
+            }
+            frames.push(frame);
+        }
+        if frames.is_empty() {
+            return Err(anyhow::anyhow!("sequence has no frames"));
+        }
+        log::info!("loaded sequence with {} frames", frames.len());
+        std::mem::swap(pc, &mut frames[0]);
+        Ok(Self {
+            frames,
+            current: 0,
+            time: Duration::ZERO,
+            fps,
+            playing: true,
+        })
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn current_frame(&self) -> usize {
+        self.current
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f32(self.frames.len() as f32 / self.fps)
+    }
+
+    /// advances the time and swaps the next frame into `pc`
+    /// returns true if the frame changed
+    pub fn update(&mut self, dt: Duration, pc: &mut PointCloud) -> bool {
+        if self.playing {
+            self.time += dt;
+            let duration = self.duration();
+            if self.time >= duration {
+                self.time =
+                    Duration::from_secs_f32(self.time.as_secs_f32() % duration.as_secs_f32());
+            }
+        }
+        let frame = ((self.time.as_secs_f32() * self.fps) as usize).min(self.frames.len() - 1);
+        self.set_frame(frame, pc)
+    }
+
+    /// jumps to a frame
+    /// returns true if the frame changed
+    pub fn seek(&mut self, frame: usize, pc: &mut PointCloud) -> bool {
+        let frame = frame.min(self.frames.len() - 1);
+        self.time = Duration::from_secs_f32(frame as f32 / self.fps);
+        self.set_frame(frame, pc)
+    }
+
+    fn set_frame(&mut self, frame: usize, pc: &mut PointCloud) -> bool {
+        if frame == self.current {
+            return false;
+        }
+        // move the current frame back into its slot and take the new one
+        std::mem::swap(pc, &mut self.frames[self.current]);
+        std::mem::swap(pc, &mut self.frames[frame]);
+        self.current = frame;
+        true
+    }
+}
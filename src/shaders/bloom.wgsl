@@ -0,0 +1,85 @@
+struct BloomSettings {
+    // premultiplied linear colors with a lower maximum component do not glow
+    threshold: f32,
+    // color space the splats were blended in, 0: srgb, 1: linear
+    color_space: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+@group(0) @binding(0)
+var source_img: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> settings: BloomSettings;
+
+struct VertexOut {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) in_vertex_index: u32,
+) -> VertexOut {
+    // creates two triangles that cover the whole target
+    let xy = vec2<f32>(
+        f32(in_vertex_index % 2u == 0u),
+        f32(in_vertex_index < 2u)
+    );
+    return VertexOut(vec4<f32>(xy * 2. - (1.), 0., 1.), vec2<f32>(xy.x, 1. - xy.y));
+}
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    return select(pow((c + 0.055) / 1.055, vec3<f32>(2.4)), c / 12.92, c <= vec3<f32>(0.04045));
+}
+
+// premultiplied color of the source image in linear space
+fn source_linear(tex_coord: vec2<f32>) -> vec3<f32> {
+    let color = textureSampleLevel(source_img, source_sampler, tex_coord, 0.);
+    if settings.color_space == 1u || color.a <= 0. {
+        return color.rgb;
+    }
+    return srgb_to_linear(color.rgb / color.a) * color.a;
+}
+
+// four bilinear samples that cover 4x4 texels of the source
+@fragment
+fn fs_prefilter(vertex_in: VertexOut) -> @location(0) vec4<f32> {
+    let t = 1. / vec2<f32>(textureDimensions(source_img));
+    let tc = vertex_in.tex_coord;
+    let color = (source_linear(tc + vec2<f32>(-t.x, -t.y)) + source_linear(tc + vec2<f32>(t.x, -t.y))
+        + source_linear(tc + vec2<f32>(-t.x, t.y)) + source_linear(tc + vec2<f32>(t.x, t.y))) * 0.25;
+    let brightness = max(color.r, max(color.g, color.b));
+    let contribution = max(brightness - settings.threshold, 0.) / max(brightness, 1e-4);
+    return vec4<f32>(color * contribution, 1.);
+}
+
+@fragment
+fn fs_downsample(vertex_in: VertexOut) -> @location(0) vec4<f32> {
+    let t = 1. / vec2<f32>(textureDimensions(source_img));
+    let tc = vertex_in.tex_coord;
+    let color = textureSampleLevel(source_img, source_sampler, tc + vec2<f32>(-t.x, -t.y), 0.)
+        + textureSampleLevel(source_img, source_sampler, tc + vec2<f32>(t.x, -t.y), 0.)
+        + textureSampleLevel(source_img, source_sampler, tc + vec2<f32>(-t.x, t.y), 0.)
+        + textureSampleLevel(source_img, source_sampler, tc + vec2<f32>(t.x, t.y), 0.);
+    return vec4<f32>(color.rgb * 0.25, 1.);
+}
+
+// 3x3 tent filter of the coarser level, added to the finer level
+@fragment
+fn fs_upsample(vertex_in: VertexOut) -> @location(0) vec4<f32> {
+    let t = 1. / vec2<f32>(textureDimensions(source_img));
+    let tc = vertex_in.tex_coord;
+    var color = textureSampleLevel(source_img, source_sampler, tc, 0.).rgb * 4.;
+    color += (textureSampleLevel(source_img, source_sampler, tc + vec2<f32>(-t.x, 0.), 0.).rgb
+        + textureSampleLevel(source_img, source_sampler, tc + vec2<f32>(t.x, 0.), 0.).rgb
+        + textureSampleLevel(source_img, source_sampler, tc + vec2<f32>(0., -t.y), 0.).rgb
+        + textureSampleLevel(source_img, source_sampler, tc + vec2<f32>(0., t.y), 0.).rgb) * 2.;
+    color += textureSampleLevel(source_img, source_sampler, tc + vec2<f32>(-t.x, -t.y), 0.).rgb
+        + textureSampleLevel(source_img, source_sampler, tc + vec2<f32>(t.x, -t.y), 0.).rgb
+        + textureSampleLevel(source_img, source_sampler, tc + vec2<f32>(-t.x, t.y), 0.).rgb
+        + textureSampleLevel(source_img, source_sampler, tc + vec2<f32>(t.x, t.y), 0.).rgb;
+    return vec4<f32>(color / 16., 1.);
+}
@@ -0,0 +1,65 @@
+struct FeedSettings {
+    opacity: f32,
+    _pad0: f32,
+    _pad1: f32,
+    _pad2: f32,
+}
+
+@group(0) @binding(0)
+var feed_img : texture_2d<f32>;
+@group(0) @binding(1)
+var feed_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> settings: FeedSettings;
+
+// only bound for the depth pipeline
+@group(2) @binding(0)
+var feed_depth_img: texture_2d<f32>;
+// expected depth of the splats (depth premultiplied with the opacity in r, opacity in g)
+@group(2) @binding(1)
+var splat_depth_img: texture_2d<f32>;
+
+struct VertexOut {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) in_vertex_index: u32,
+) -> VertexOut {
+
+    // creates two vertices that cover the whole screen
+    let xy = vec2<f32>(
+        f32(in_vertex_index % 2u == 0u),
+        f32(in_vertex_index < 2u)
+    );
+    return VertexOut(vec4<f32>(xy * 2. - (1.), 0., 1.), vec2<f32>(xy.x, 1. - xy.y));
+}
+
+fn feed_color(tex_coord: vec2<f32>) -> vec4<f32> {
+    let color = textureSampleLevel(feed_img, feed_sampler, tex_coord, 0.);
+    // premultiplied alpha
+    let a = color.a * settings.opacity;
+    return vec4<f32>(color.rgb * a, a);
+}
+
+@fragment
+fn fs_main(vertex_in: VertexOut) -> @location(0) vec4<f32> {
+    return feed_color(vertex_in.tex_coord);
+}
+
+// draws the feed where it is closer than the splats
+@fragment
+fn fs_depth(vertex_in: VertexOut) -> @location(0) vec4<f32> {
+    let feed_size = textureDimensions(feed_depth_img);
+    let feed_pixel = min(vec2<u32>(vertex_in.tex_coord * vec2<f32>(feed_size)), feed_size - 1u);
+    let feed_depth = textureLoad(feed_depth_img, feed_pixel, 0).r;
+    let splat = textureLoad(splat_depth_img, vec2<u32>(vertex_in.pos.xy), 0).rg;
+    // pixels without a depth measurement are behind everything, pixels without splats in front of it
+    if feed_depth <= 0. || (splat.g > 1e-3 && splat.r / splat.g < feed_depth) {
+        discard;
+    }
+    return feed_color(vertex_in.tex_coord);
+}
@@ -1,5 +1,10 @@
 const PI:f32 = 3.1415926535897932384626433832795;
 const TWO_PI:f32 = 6.283185307179586476925286766559;
+// number of fragments per pixel at the top of the overdraw heatmap (log scale)
+const OVERDRAW_MAX:f32 = 1024.;
+const GOLDEN_ANGLE:f32 = 2.39996323;
+// distance between the samples of the depth of field in pixels
+const DOF_SAMPLE_SPACING:f32 = 1.;
 
 struct CameraUniforms {
     view: mat4x4<f32>,
@@ -12,16 +17,97 @@ struct CameraUniforms {
 };
 
 
+struct DisplaySettings {
+    exposure: f32,
+    // 0: none, 1: reinhard, 2: aces
+    tonemapping: u32,
+    // transfer function of the output, 0: srgb, 1: linear
+    output_color_space: u32,
+    // depth of field, an aperture of 0 disables the blur
+    dof_focus_distance: f32,
+    // blur radius in pixels at infinity
+    dof_aperture: f32,
+    // strength of the bloom, 0 disables it
+    bloom_intensity: f32,
+    // darkening of the corners, 0 disables it
+    vignette: f32,
+    // contrast adaptive sharpening, 0 disables it
+    sharpening: f32,
+    // color grading, the defaults (1, 1, 0, 0) leave the colors unchanged
+    gamma: f32,
+    saturation: f32,
+    // white balance, -1 to 1
+    temperature: f32,
+    tint: f32,
+}
+
 struct RenderSettings {
+    clipping_box_min: vec4<f32>,
+    clipping_box_max: vec4<f32>,
     gaussian_scaling: f32,
     max_sh_deg: u32,
     show_env_map: u32,
+    mip_spatting: u32,
+    kernel_size: f32,
+    walltime: f32,
+    scene_extend: f32,
+    lod_ranges: u32,
+    center: vec3<f32>,
+    opacity_threshold: f32,
+    // color space the splats were blended in, 0: srgb, 1: linear
+    color_space: u32,
+    // 0: splats, 1: wireframe, 2: ellipsoids, 3: points, 4: depth, 5: overdraw
+    render_mode: u32,
+    // splats with a smaller scale are skipped in the ellipsoid modes, 0 if all splats are drawn
+    min_scale: f32,
+    // 1 if the covariances and sh coefficients are stored as f16, f32 otherwise
+    half_precision: u32,
+    // 1 if the covariances are packed, the log scales are within [log_scale_min, log_scale_max]
+    packed_covariance: u32,
+    log_scale_min: f32,
+    log_scale_max: f32,
+    // 0: no fog, 1: linear, 2: exponential
+    fog_mode: u32,
+    // premultiplied fog color in the blending color space
+    fog_color: vec4<f32>,
+    // proj[2][2] and proj[3][2], recover the view depth of a fragment from its depth
+    proj_depth: vec2<f32>,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    // number of used clip planes
+    num_clip_planes: u32,
+    // 1 if the splats outside of the crop box are cut away
+    crop_box_enabled: u32,
+    // (normal, distance), splats with a negative distance to a plane are cut away
+    clip_planes: array<vec4<f32>, 6>,
+    // maps the crop box to [-1, 1]³
+    crop_box: mat4x4<f32>,
+    // splats brighter than glare_ratio times their view independent color are darkened, 0 disables the filter
+    glare_ratio: f32,
+    // luminance divided by the exposure, darker splats are never darkened
+    glare_min_luminance: f32,
 }
 
 @group(0) @binding(0)
 var source_img : texture_2d<f32>;
 @group(0) @binding(1)
 var texture_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> display_settings: DisplaySettings;
+// fragments per pixel of the last frame in the overdraw render mode, see gaussian.wgsl
+struct Overdraw {
+    width: u32,
+    counts: array<u32>,
+}
+@group(0) @binding(3)
+var<storage, read> overdraw: Overdraw;
+// view space depth premultiplied with the opacity (r) and the opacity (g), see gaussian.wgsl
+@group(0) @binding(4)
+var expected_depth_img: texture_2d<f32>;
+// blurred bright parts of the image (linear, premultiplied) at half resolution, see bloom.wgsl
+@group(0) @binding(5)
+var bloom_img: texture_2d<f32>;
 @group(1) @binding(0)
 var env_map : texture_2d<f32>;
 @group(1) @binding(1)
@@ -57,15 +143,221 @@ fn sample_env_map(dir: vec3<f32>) -> vec4<f32> {
     return textureSample(env_map, env_map_sampler, texcoord);
 }
 
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn expected_depth(tex_coord: vec2<f32>) -> f32 {
+    let d = textureSampleLevel(expected_depth_img, texture_sampler, tex_coord, 0.).rg;
+    if d.g <= 1e-3 {
+        // no splats, treated as infinitely far away
+        return 1e10;
+    }
+    return d.r / d.g;
+}
+
+// blur radius in pixels (thin lens model)
+fn circle_of_confusion(depth: f32) -> f32 {
+    let focus = display_settings.dof_focus_distance;
+    return display_settings.dof_aperture * abs(1. - focus / max(depth, 1e-6));
+}
+
+// gathers the samples on a spiral around the pixel
+// a sample contributes if its own blur radius reaches the pixel, samples behind the pixel are
+// limited to its blur radius so sharp foreground does not bleed into the background
+// see "Bokeh depth of field in a single pass" by Dennis Gustafsson
+fn depth_of_field(center: vec4<f32>, tex_coord: vec2<f32>) -> vec4<f32> {
+    let texel_size = 1. / vec2<f32>(textureDimensions(source_img));
+    let center_depth = expected_depth(tex_coord);
+    let center_size = circle_of_confusion(center_depth);
+    var color = center;
+    var total = 1.;
+    var radius = DOF_SAMPLE_SPACING;
+    var angle = 0.;
+    while radius < display_settings.dof_aperture {
+        let sample_coord = tex_coord + vec2<f32>(cos(angle), sin(angle)) * radius * texel_size;
+        let sample_color = textureSampleLevel(source_img, texture_sampler, sample_coord, 0.);
+        let sample_depth = expected_depth(sample_coord);
+        var sample_size = circle_of_confusion(sample_depth);
+        if sample_depth > center_depth {
+            sample_size = min(sample_size, center_size * 2.);
+        }
+        let m = smoothstep(radius - 0.5, radius + 0.5, sample_size);
+        color += mix(color / total, sample_color, m);
+        total += 1.;
+        radius += DOF_SAMPLE_SPACING / radius;
+        angle += GOLDEN_ANGLE;
+    }
+    return color / total;
+}
+
+// contrast adaptive sharpening, simplified from AMD FidelityFX CAS
+// the sharpening is reduced where the local contrast is already high to avoid ringing
+fn sharpen(center: vec4<f32>, tex_coord: vec2<f32>) -> vec4<f32> {
+    let t = 1. / vec2<f32>(textureDimensions(source_img));
+    let tc = tex_coord;
+    let n = textureSampleLevel(source_img, texture_sampler, tc + vec2<f32>(0., -t.y), 0.).rgb;
+    let w = textureSampleLevel(source_img, texture_sampler, tc + vec2<f32>(-t.x, 0.), 0.).rgb;
+    let e = textureSampleLevel(source_img, texture_sampler, tc + vec2<f32>(t.x, 0.), 0.).rgb;
+    let s = textureSampleLevel(source_img, texture_sampler, tc + vec2<f32>(0., t.y), 0.).rgb;
+    let mn = min(center.rgb, min(min(n, w), min(e, s)));
+    let mx = max(center.rgb, max(max(n, w), max(e, s)));
+    let amp = sqrt(saturate(min(mn, 1. - mx) / max(mx, vec3<f32>(1e-5))));
+    let weight = -amp / mix(8., 5., display_settings.sharpening);
+    let rgb = ((n + w + e + s) * weight + center.rgb) / (1. + 4. * weight);
+    return vec4<f32>(max(rgb, vec3<f32>(0.)), center.a);
+}
+
+// 1 at the center of the image, falls off towards the corners
+fn vignette(tex_coord: vec2<f32>) -> f32 {
+    let r = length(tex_coord * 2. - 1.) / sqrt(2.);
+    return 1. - display_settings.vignette * smoothstep(0.2, 1., r);
+}
+
+fn reinhard(color: vec3<f32>) -> vec3<f32> {
+    return color / (1. + luminance(color));
+}
+
+// ACES filmic curve fit by Krzysztof Narkowicz
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), vec3<f32>(0.), vec3<f32>(1.));
+}
+
+fn tonemap(rgb: vec3<f32>) -> vec3<f32> {
+    switch display_settings.tonemapping {
+        case 1u: {
+            return reinhard(rgb);
+        }
+        case 2u: {
+            return aces(rgb);
+        }
+        default: {
+            return rgb;
+        }
+    }
+}
+
+// gains of the white balance, normalized so the luminance of white does not change
+// the temperature shifts between blue and orange, the tint between green and magenta
+fn white_balance(rgb: vec3<f32>) -> vec3<f32> {
+    let t = display_settings.temperature;
+    let m = display_settings.tint;
+    let gains = vec3<f32>(1. + 0.25 * t + 0.125 * m, 1. - 0.25 * m, 1. - 0.25 * t + 0.125 * m);
+    return rgb * gains / luminance(gains);
+}
+
+fn color_grade(rgb: vec3<f32>) -> vec3<f32> {
+    let balanced = white_balance(rgb);
+    let graded = mix(vec3<f32>(luminance(balanced)), balanced, display_settings.saturation);
+    return max(graded, vec3<f32>(0.));
+}
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    return select(pow((c + 0.055) / 1.055, vec3<f32>(2.4)), c / 12.92, c <= vec3<f32>(0.04045));
+}
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let v = max(c, vec3<f32>(0.));
+    return select(1.055 * pow(v, vec3<f32>(1. / 2.4)) - 0.055, v * 12.92, v <= vec3<f32>(0.0031308));
+}
+
+// polynomial approximation of the turbo colormap (srgb) by Ruofei Du
+fn turbo(t: f32) -> vec3<f32> {
+    let r4 = vec4<f32>(0.13572138, 4.61539260, -42.66032258, 132.13108234);
+    let g4 = vec4<f32>(0.09140261, 2.19418839, 4.84296658, -14.18503333);
+    let b4 = vec4<f32>(0.10667330, 12.64194608, -60.58204836, 110.36276771);
+    let r2 = vec2<f32>(-152.94239396, 59.28637943);
+    let g2 = vec2<f32>(4.27729857, 2.82956604);
+    let b2 = vec2<f32>(-89.90310912, 27.34824973);
+    let x = saturate(t);
+    let v4 = vec4<f32>(1., x, x * x, x * x * x);
+    let v2 = v4.zw * v4.z;
+    return saturate(vec3<f32>(
+        dot(v4, r4) + dot(v2, r2),
+        dot(v4, g4) + dot(v2, g2),
+        dot(v4, b4) + dot(v2, b2)
+    ));
+}
+
+// exposure, color grading and tone mapping are applied in linear space
+// the colors are premultiplied, so they are converted without alpha
+// the bloom is added as premultiplied light, so it also glows over transparent pixels
+fn resolve(color: vec4<f32>, bloom: vec3<f32>, vignette: f32) -> vec4<f32> {
+    let alpha = saturate(color.a + max(bloom.r, max(bloom.g, bloom.b)));
+    if alpha <= 0. {
+        return color;
+    }
+    var rgb = vec3<f32>(0.);
+    if color.a > 0. {
+        rgb = color.rgb / color.a;
+    }
+    if render_settings.render_mode == 4u {
+        // expected depth of the pixel, near is red and far is blue
+        rgb = srgb_to_linear(turbo(1. - rgb.r));
+    } else {
+        if render_settings.color_space == 0u {
+            rgb = srgb_to_linear(rgb);
+        }
+        rgb = (rgb * color.a + bloom) / alpha;
+        rgb = tonemap(color_grade(rgb * display_settings.exposure) * vignette);
+        rgb = pow(max(rgb, vec3<f32>(0.)), vec3<f32>(1. / display_settings.gamma));
+    }
+    if display_settings.output_color_space == 0u {
+        rgb = linear_to_srgb(rgb);
+    }
+    return vec4<f32>(rgb * alpha, alpha);
+}
+
+fn overdraw_heatmap(tex_coord: vec2<f32>) -> vec4<f32> {
+    let size = textureDimensions(source_img);
+    let p = min(vec2<u32>(tex_coord * vec2<f32>(size)), size - 1u);
+    let count = f32(overdraw.counts[p.y * overdraw.width + p.x]);
+    let t = log2(1. + count) / log2(1. + OVERDRAW_MAX);
+    var rgb = srgb_to_linear(turbo(t));
+    if display_settings.output_color_space == 0u {
+        rgb = linear_to_srgb(rgb);
+    }
+    return vec4<f32>(rgb, 1.);
+}
+
 @fragment
 fn fs_main(vertex_in: VertexOut) -> @location(0) vec4<f32> {
-    let color = textureSample(source_img, texture_sampler, vertex_in.tex_coord);
-    if render_settings.show_env_map == 1u {
-        let local_pos = camera.proj_inv * vec4<f32>((vertex_in.tex_coord.xy * 2. - (1.)), 1., 1.);
-        let dir = camera.view_inv * vec4<f32>(local_pos.xyz, 0.);
-        let env_color = sample_env_map(normalize(dir.xyz));
-        return vec4<f32>(env_color.rgb * (1. - color.a) + color.rgb, 1.);
-    } else {
-        return color;
+    if render_settings.render_mode == 5u {
+        return overdraw_heatmap(vertex_in.tex_coord);
+    }
+    var sample = textureSample(source_img, texture_sampler, vertex_in.tex_coord);
+    var bloom = vec3<f32>(0.);
+    var vignette_factor = 1.;
+    if render_settings.render_mode != 4u {
+        if display_settings.sharpening > 0. {
+            sample = sharpen(sample, vertex_in.tex_coord);
+        }
+        if display_settings.dof_aperture > 0. {
+            sample = depth_of_field(sample, vertex_in.tex_coord);
+        }
+        if display_settings.bloom_intensity > 0. {
+            let b = textureSampleLevel(bloom_img, texture_sampler, vertex_in.tex_coord, 0.).rgb;
+            bloom = b * display_settings.bloom_intensity;
+        }
+        if display_settings.vignette > 0. {
+            vignette_factor = vignette(vertex_in.tex_coord);
+        }
+        if render_settings.show_env_map == 1u {
+            // the environment is blended behind the splats before the exposure and tone mapping
+            // the environment map is linear, the splats are blended in the render color space
+            let local_pos = camera.proj_inv * vec4<f32>((vertex_in.tex_coord.xy * 2. - (1.)), 1., 1.);
+            let dir = camera.view_inv * vec4<f32>(local_pos.xyz, 0.);
+            var env_color = sample_env_map(normalize(dir.xyz)).rgb;
+            if render_settings.color_space == 0u {
+                env_color = linear_to_srgb(env_color);
+            }
+            sample = vec4<f32>(env_color * (1. - sample.a) + sample.rgb, 1.);
+        }
     }
+    return resolve(sample, bloom, vignette_factor);
 }
\ No newline at end of file
@@ -1,10 +1,27 @@
 // we cutoff at 1/255 alpha value 
 const CUTOFF:f32 = 2.3539888583335364; // = sqrt(log(255))
+// the ellipsoid render modes draw the splats at two standard deviations
+// (a = r² / 2 in the fragment shader with r in standard deviations)
+const ELLIPSOID_A:f32 = 2.;
+// fragments with a lower opacity do not write depth, see fs_depth
+const DEPTH_OPACITY:f32 = 0.5;
+// range of the depth weights in the order independent transparency pass
+// small enough that the weighted sums do not overflow the f16 accumulation target
+const OIT_MIN_WEIGHT:f32 = 1e-2;
+const OIT_MAX_WEIGHT:f32 = 3e2;
+// golden ratio conjugate, shifts the blue noise every frame while keeping it blue in space
+const GOLDEN_RATIO:f32 = 0.6180339887;
+// selected splats are tinted towards this color
+const SELECTION_COLOR:vec3<f32> = vec3<f32>(1., 0.5, 0.);
+const SELECTION_TINT:f32 = 0.5;
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) screen_pos: vec2<f32>,
     @location(1) color: vec4<f32>,
+    // index of the splat, decorrelates the noise of overlapping splats
+    @location(2) @interpolate(flat) splat: u32,
+    @location(3) @interpolate(flat) selected: u32,
 };
 
 struct VertexInput {
@@ -20,6 +37,10 @@ struct Splat {
     pos: u32,
     // rgba packed as f16
     color_0: u32,color_1: u32,
+    // depth of the center in normalized device coordinates
+    depth: f32,
+    // bit 0: the splat is selected
+    flags: u32,
 };
 
 @group(0) @binding(2)
@@ -27,6 +48,98 @@ var<storage, read> points_2d : array<Splat>;
 @group(1) @binding(4)
 var<storage, read> indices : array<u32>;
 
+// same layout as in preprocess.wgsl, only opacity_threshold, render_mode and the fog are used
+struct RenderSettings {
+    clipping_box_min: vec4<f32>,
+    clipping_box_max: vec4<f32>,
+    gaussian_scaling: f32,
+    max_sh_deg: u32,
+    show_env_map: u32,
+    mip_spatting: u32,
+    kernel_size: f32,
+    walltime: f32,
+    scene_extend: f32,
+    lod_ranges: u32,
+    center: vec3<f32>,
+    opacity_threshold: f32,
+    // 0: srgb, 1: linear
+    color_space: u32,
+    // 0: splats, 1: wireframe, 2: ellipsoids, 3: points, 4: depth, 5: overdraw
+    render_mode: u32,
+    // splats with a smaller scale are skipped in the ellipsoid modes, 0 if all splats are drawn
+    min_scale: f32,
+    // 1 if the covariances and sh coefficients are stored as f16, f32 otherwise
+    half_precision: u32,
+    // 1 if the covariances are packed, the log scales are within [log_scale_min, log_scale_max]
+    packed_covariance: u32,
+    log_scale_min: f32,
+    log_scale_max: f32,
+    // 0: no fog, 1: linear, 2: exponential
+    fog_mode: u32,
+    // premultiplied fog color in the blending color space
+    fog_color: vec4<f32>,
+    // proj[2][2] and proj[3][2], recover the view depth of a fragment from its depth
+    proj_depth: vec2<f32>,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    // number of used clip planes
+    num_clip_planes: u32,
+    // 1 if the splats outside of the crop box are cut away
+    crop_box_enabled: u32,
+    // (normal, distance), splats with a negative distance to a plane are cut away
+    clip_planes: array<vec4<f32>, 6>,
+    // maps the crop box to [-1, 1]³
+    crop_box: mat4x4<f32>,
+    // splats brighter than glare_ratio times their view independent color are darkened, 0 disables the filter
+    glare_ratio: f32,
+    // luminance divided by the exposure, darker splats are never darkened
+    glare_min_luminance: f32,
+}
+
+@group(2) @binding(0)
+var<uniform> render_settings: RenderSettings;
+
+// fragments per pixel, only bound in the overdraw pipeline
+struct Overdraw {
+    // width of the render target
+    width: u32,
+    counts: array<atomic<u32>>,
+}
+@group(3) @binding(0)
+var<storage, read_write> overdraw: Overdraw;
+
+// same layout as in preprocess.wgsl, only bound in the order independent transparency pipeline
+struct CameraUniforms {
+    view: mat4x4<f32>,
+    view_inv: mat4x4<f32>,
+    proj: mat4x4<f32>,
+    proj_inv: mat4x4<f32>,
+    viewport: vec2<f32>,
+    focal: vec2<f32>
+};
+@group(3) @binding(0)
+var<uniform> camera: CameraUniforms;
+
+// only bound in the stochastic transparency pipeline
+struct Noise {
+    // offset of the noise mask in pixels
+    offset: vec2<u32>,
+    frame: u32,
+    _pad: u32,
+}
+@group(3) @binding(1)
+var blue_noise: texture_2d<f32>;
+@group(3) @binding(2)
+var<uniform> noise: Noise;
+
+// uniform random number in [0,1) (pcg hash)
+fn hash(v: u32) -> f32 {
+    let h = v * 747796405u + 2891336453u;
+    let w = ((h >> ((h >> 28u) + 4u)) ^ h) * 277803737u;
+    return f32((w >> 22u) ^ w) / 4294967296.;
+}
+
 @vertex
 fn vs_main(
     @builtin(vertex_index) in_vertex_index: u32,
@@ -50,18 +163,152 @@ fn vs_main(
     let position = vec2<f32>(x, y) * CUTOFF;
 
     let offset = 2. * mat2x2<f32>(v1, v2) * position;
-    out.position = vec4<f32>(v_center + offset, 0., 1.);
+    out.position = vec4<f32>(v_center + offset, vertex.depth, 1.);
     out.screen_pos = position;
     out.color = vec4<f32>(unpack2x16float(vertex.color_0), unpack2x16float(vertex.color_1));
+    out.splat = indices[in_instance_index];
+    out.selected = vertex.flags & 1u;
     return out;
 }
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+// part of the fragment covered by the fog
+fn fog(depth: f32) -> f32 {
+    switch render_settings.fog_mode {
+        case 1u: {
+            let range = max(render_settings.fog_end - render_settings.fog_start, 1e-6);
+            return saturate((depth - render_settings.fog_start) / range);
+        }
+        case 2u: {
+            return 1. - exp(-render_settings.fog_density * max(depth, 0.));
+        }
+        default: {
+            return 0.;
+        }
+    }
+}
+
+// premultiplied color of the fragment in the current render mode
+// the fog is blended in premultiplied, so a transparent fog color fades the splat out
+fn shade(in: VertexOutput) -> vec4<f32> {
+    var color = shade_unfogged(in);
+    if in.selected != 0u && render_settings.render_mode != 4u {
+        color = vec4<f32>(mix(color.rgb, SELECTION_COLOR * color.a, SELECTION_TINT), color.a);
+    }
+    if render_settings.fog_mode == 0u || render_settings.render_mode == 4u {
+        return color;
+    }
+    let z = render_settings.proj_depth.y / (in.position.z - render_settings.proj_depth.x);
+    return mix(color, render_settings.fog_color * color.a, fog(z));
+}
+
+fn shade_unfogged(in: VertexOutput) -> vec4<f32> {
     let a = dot(in.screen_pos, in.screen_pos);
+    // derivatives are taken before any discard
+    let da = fwidth(a);
+    let dpos = fwidth(in.screen_pos);
     if a > 2. * CUTOFF {
         discard;
     }
+    if render_settings.render_mode == 1u {
+        // outline and principal axes, about one pixel wide
+        let outline = abs(a - ELLIPSOID_A) < da;
+        let axes = a < ELLIPSOID_A && any(abs(in.screen_pos) < dpos);
+        if !(outline || axes) {
+            discard;
+        }
+        return vec4<f32>(in.color.rgb, 1.);
+    }
+    if render_settings.render_mode == 3u {
+        // the quad of a point spans POINT_RADIUS pixels for screen_pos = 1 (see preprocess.wgsl)
+        if a > 1. {
+            discard;
+        }
+        return vec4<f32>(in.color.rgb, 1.);
+    }
+    if render_settings.render_mode == 2u {
+        if a > ELLIPSOID_A {
+            discard;
+        }
+        // the view facing component of the ellipsoid normal, lit from the camera
+        let n_z = sqrt(1. - a / ELLIPSOID_A);
+        return vec4<f32>(in.color.rgb * (0.3 + 0.7 * n_z), 1.);
+    }
     let b = min(0.99, exp(-a) * in.color.a);
+    if b < render_settings.opacity_threshold {
+        discard;
+    }
     return vec4<f32>(in.color.rgb, 1.) * b;
-}
\ No newline at end of file
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in);
+}
+
+struct OitOutput {
+    @location(0) accum: vec4<f32>,
+    @location(1) revealage: vec4<f32>,
+}
+
+// weighted blended order independent transparency (McGuire and Bavoil 2013, equation 10)
+// splats close to the camera get a higher weight, the result is resolved in oit.wgsl
+@fragment
+fn fs_oit(in: VertexOutput) -> OitOutput {
+    let color = shade(in);
+    // linear depth between the near and far plane
+    let znear = -camera.proj[3][2] / camera.proj[2][2];
+    let zfar = -camera.proj[3][2] / (camera.proj[2][2] - (1.));
+    let z = camera.proj[3][2] / (in.position.z - camera.proj[2][2]);
+    let t = saturate((z - znear) / (zfar - znear));
+    let w = clamp(OIT_MAX_WEIGHT * pow(1. - t, 3.), OIT_MIN_WEIGHT, OIT_MAX_WEIGHT);
+    return OitOutput(color * w, vec4<f32>(color.a));
+}
+
+// view space depth premultiplied with the opacity
+// blending the sorted splats yields the expected depth
+// used for the depth of field in display.wgsl
+@fragment
+fn fs_expected_depth(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = shade(in);
+    let z = camera.proj[3][2] / (in.position.z - camera.proj[2][2]);
+    return vec4<f32>(z * color.a, color.a, 0., color.a);
+}
+
+// writes the depth of the splats that are mostly opaque at the fragment
+// the colors are left unchanged
+@fragment
+fn fs_depth(in: VertexOutput) -> @location(0) vec4<f32> {
+    let a = dot(in.screen_pos, in.screen_pos);
+    if a > 2. * CUTOFF || min(0.99, exp(-a) * in.color.a) < DEPTH_OPACITY {
+        discard;
+    }
+    return vec4<f32>(0.);
+}
+
+// stochastic transparency: the fragment is opaque with the probability of its opacity
+// the nearest opaque fragment wins the depth test
+// the average over the frames is resolved in stochastic.wgsl
+@fragment
+fn fs_stochastic(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = shade(in);
+    let p = (vec2<u32>(in.position.xy) + noise.offset) % textureDimensions(blue_noise);
+    let threshold = fract(
+        textureLoad(blue_noise, p, 0).r + f32(noise.frame) * GOLDEN_RATIO + hash(in.splat)
+    );
+    if color.a <= threshold {
+        discard;
+    }
+    return vec4<f32>(color.rgb / color.a, 1.);
+}
+
+// counts the fragments that are blended per pixel, the colors are left unchanged
+@fragment
+fn fs_overdraw(in: VertexOutput) -> @location(0) vec4<f32> {
+    let a = dot(in.screen_pos, in.screen_pos);
+    if a > 2. * CUTOFF || min(0.99, exp(-a) * in.color.a) < render_settings.opacity_threshold {
+        discard;
+    }
+    let p = vec2<u32>(in.position.xy);
+    atomicAdd(&overdraw.counts[p.y * overdraw.width + p.x], 1u);
+    return vec4<f32>(0.);
+}
@@ -0,0 +1,45 @@
+// histogram of the log2 luminance of the rendered image, used for auto exposure
+// bin 0 counts (almost) black pixels, bins 1 to 255 evenly divide the log2 luminance range
+
+const NUM_BINS: u32 = 256u;
+
+struct HistogramSettings {
+    min_log_lum: f32,
+    inv_log_lum_range: f32,
+}
+
+@group(0) @binding(0)
+var source_img: texture_2d<f32>;
+@group(0) @binding(1)
+var<storage, read_write> histogram: array<atomic<u32>, NUM_BINS>;
+@group(0) @binding(2)
+var<uniform> settings: HistogramSettings;
+
+var<workgroup> local_histogram: array<atomic<u32>, NUM_BINS>;
+
+fn luminance_bin(color: vec3<f32>) -> u32 {
+    let lum = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    if lum < 1e-5 {
+        return 0u;
+    }
+    let t = clamp((log2(lum) - settings.min_log_lum) * settings.inv_log_lum_range, 0., 1.);
+    return u32(t * f32(NUM_BINS - 2u) + 1.);
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main(
+    @builtin(global_invocation_id) gid: vec3<u32>,
+    @builtin(local_invocation_index) local_idx: u32,
+) {
+    atomicStore(&local_histogram[local_idx], 0u);
+    workgroupBarrier();
+
+    let size = textureDimensions(source_img);
+    if gid.x < size.x && gid.y < size.y {
+        let color = textureLoad(source_img, vec2<i32>(gid.xy), 0);
+        atomicAdd(&local_histogram[luminance_bin(color.rgb)], 1u);
+    }
+    workgroupBarrier();
+
+    atomicAdd(&histogram[local_idx], atomicLoad(&local_histogram[local_idx]));
+}
@@ -0,0 +1,33 @@
+// resolves the weighted blended order independent transparency targets (see gaussian.wgsl fs_oit)
+
+@group(0) @binding(0)
+var accum: texture_2d<f32>;
+@group(0) @binding(1)
+var revealage: texture_2d<f32>;
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) in_vertex_index: u32,
+) -> @builtin(position) vec4<f32> {
+
+    // creates two vertices that cover the whole screen
+    let xy = vec2<f32>(
+        f32(in_vertex_index % 2u == 0u),
+        f32(in_vertex_index < 2u)
+    );
+    return vec4<f32>(xy * 2. - (1.), 0., 1.);
+}
+
+@fragment
+fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let p = vec2<i32>(pos.xy);
+    let alpha = 1. - textureLoad(revealage, p, 0).r;
+    if alpha <= 0. {
+        discard;
+    }
+    let sum = textureLoad(accum, p, 0);
+    // weighted average of the premultiplied colors
+    let color = sum.rgb / max(sum.a, 1e-5);
+    // premultiplied alpha
+    return vec4<f32>(color * alpha, alpha);
+}
@@ -1,6 +1,12 @@
 const KERNEL_SIZE:f32 = 0.3;
 //const MAX_SH_DEG:u32 = <injected>u;
 
+// splats that are more transparent than this everywhere are not drawn (same cutoff as gaussian.wgsl)
+const MIN_OPACITY:f32 = 0.00392156862; // = 1/255
+
+// radius in pixels of the splat centers in the points render mode
+const POINT_RADIUS:f32 = 1.5;
+
 const SH_C0:f32 = 0.28209479177387814;
 
 const SH_C1 = 0.4886025119029199;
@@ -33,18 +39,17 @@ struct CameraUniforms {
     focal: vec2<f32>
 };
 
-struct Gaussian {
-    pos_opacity: array<u32,2>,
-    cov: array<u32,3>
-}
-
 struct Splat {
      // 4x f16 packed as u32
     v_0: u32, v_1: u32,
     // 2x f16 packed as u32
     pos: u32,
     // rgba packed as f16
-    color_0: u32,color_1: u32
+    color_0: u32,color_1: u32,
+    // depth of the center in normalized device coordinates
+    depth: f32,
+    // bit 0: the splat is selected
+    flags: u32,
 };
 
 struct DrawIndirect {
@@ -83,19 +88,71 @@ struct RenderSettings {
     kernel_size: f32,
     walltime: f32,
     scene_extend: f32,
+    // number of entries in lod_ranges, 0 if all splats are drawn
+    lod_ranges: u32,
     center: vec3<f32>,
+    opacity_threshold: f32,
+    // 0: srgb, 1: linear
+    color_space: u32,
+    // 0: splats, 1: wireframe, 2: ellipsoids, 3: points, 4: depth, 5: overdraw
+    render_mode: u32,
+    // splats with a smaller scale are skipped in the ellipsoid modes, 0 if all splats are drawn
+    min_scale: f32,
+    // 1 if the covariances and sh coefficients are stored as f16, f32 otherwise
+    half_precision: u32,
+    // 1 if the covariances are packed, the log scales are within [log_scale_min, log_scale_max]
+    packed_covariance: u32,
+    log_scale_min: f32,
+    log_scale_max: f32,
+    // 0: no fog, 1: linear, 2: exponential
+    fog_mode: u32,
+    // premultiplied fog color in the blending color space
+    fog_color: vec4<f32>,
+    // proj[2][2] and proj[3][2], recover the view depth of a fragment from its depth
+    proj_depth: vec2<f32>,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    // number of used clip planes
+    num_clip_planes: u32,
+    // 1 if the splats outside of the crop box are cut away
+    crop_box_enabled: u32,
+    // (normal, distance), splats with a negative distance to a plane are cut away
+    clip_planes: array<vec4<f32>, 6>,
+    // maps the crop box to [-1, 1]³
+    crop_box: mat4x4<f32>,
+    // splats brighter than glare_ratio times their view independent color are darkened, 0 disables the filter
+    glare_ratio: f32,
+    // luminance divided by the exposure, darker splats are never darkened
+    glare_min_luminance: f32,
+}
+
+// the splats selected from the level of detail hierarchy
+// thread offset + i processes splat start + i, the last entry holds the total number of selected splats
+struct LodRange {
+    offset: u32,
+    start: u32,
 }
 
 @group(0) @binding(0)
 var<uniform> camera: CameraUniforms;
 
 @group(1) @binding(0) 
-var<storage,read> gaussians : array<Gaussian>;
-@group(1) @binding(1) 
-var<storage,read> sh_coefs : array<array<u32,24>>;
+var<storage,read> gaussians : array<u32>;
+// @group(1) @binding(1) holds the sh coefficients
+// it is declared together with sh_word in sh_buffer.wgsl or sh_texture.wgsl
 
 @group(1) @binding(2) 
 var<storage,read_write> points_2d : array<Splat>;
+// one bit per splat, see PointCloud::set_hidden
+@group(1) @binding(7)
+var<storage,read> hidden : array<u32>;
+
+@group(1) @binding(5)
+var<storage,read> lod_ranges : array<LodRange>;
+// one bit per splat, see PointCloud::set_selection
+@group(1) @binding(6)
+var<storage,read> selection : array<u32>;
 
 @group(2) @binding(0)
 var<storage, read_write> sort_infos: SortInfos;
@@ -111,15 +168,56 @@ var<uniform> render_settings: RenderSettings;
 
 
 /// reads the ith sh coef from the vertex buffer
+/// number of u32 words of the sh coefficients of a splat (16 rgb coefficients as f16 or f32)
+fn sh_words() -> u32 {
+    if render_settings.half_precision != 0u {
+        return 24u;
+    }
+    return 48u;
+}
+
 fn sh_coef(splat_idx: u32, c_idx: u32) -> vec3<f32> {
-    let a = unpack2x16float(sh_coefs[splat_idx][(c_idx * 3u + 0u) / 2u])[(c_idx * 3u + 0u) % 2u];
-    let b = unpack2x16float(sh_coefs[splat_idx][(c_idx * 3u + 1u) / 2u])[(c_idx * 3u + 1u) % 2u];
-    let c = unpack2x16float(sh_coefs[splat_idx][(c_idx * 3u + 2u) / 2u])[(c_idx * 3u + 2u) % 2u];
+    if render_settings.half_precision == 0u {
+        return vec3<f32>(
+            bitcast<f32>(sh_word(splat_idx, c_idx * 3u + 0u)),
+            bitcast<f32>(sh_word(splat_idx, c_idx * 3u + 1u)),
+            bitcast<f32>(sh_word(splat_idx, c_idx * 3u + 2u)),
+        );
+    }
+    let a = unpack2x16float(sh_word(splat_idx, (c_idx * 3u + 0u) / 2u))[(c_idx * 3u + 0u) % 2u];
+    let b = unpack2x16float(sh_word(splat_idx, (c_idx * 3u + 1u) / 2u))[(c_idx * 3u + 1u) % 2u];
+    let c = unpack2x16float(sh_word(splat_idx, (c_idx * 3u + 2u) / 2u))[(c_idx * 3u + 2u) % 2u];
     return vec3<f32>(
         a, b, c
     );
 }
 
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    return select(pow((c + 0.055) / 1.055, vec3<f32>(2.4)), c / 12.92, c <= vec3<f32>(0.04045));
+}
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    return select(1.055 * pow(c, vec3<f32>(1. / 2.4)) - 0.055, c * 12.92, c <= vec3<f32>(0.0031308));
+}
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// darkens splats whose view dependent color is much brighter than their view independent color
+// floaters in front of light sources only light up from some directions,
+// small light sources are bright from all directions and are kept
+fn suppress_glare(rgb: vec3<f32>, v_idx: u32) -> vec3<f32> {
+    let color = srgb_to_linear(rgb);
+    let lum = luminance(color);
+    let base = luminance(srgb_to_linear(max(vec3<f32>(0.), SH_C0 * sh_coef(v_idx, 0u) + 0.5)));
+    let limit = max(base * render_settings.glare_ratio, render_settings.glare_min_luminance);
+    if lum > limit {
+        return linear_to_srgb(color * (limit / lum));
+    }
+    return rgb;
+}
+
 // spherical harmonics evaluation with Condon–Shortley phase
 fn evaluate_sh(dir: vec3<f32>, v_idx: u32, sh_deg: u32) -> vec3<f32> {
     var result = SH_C0 * sh_coef(v_idx, 0u);
@@ -153,38 +251,178 @@ fn evaluate_sh(dir: vec3<f32>, v_idx: u32, sh_deg: u32) -> vec3<f32> {
     return result;
 }
 
+/// number of u32 words for the position and opacity of a splat (4x f32)
+const POS_OPACITY_WORDS: u32 = 4u;
+
+/// number of u32 words per splat
+/// the covariance is either stored as 6x f16, 6x f32 or packed into a rotation and a scale
+fn gaussian_stride() -> u32 {
+    if render_settings.packed_covariance != 0u {
+        return POS_OPACITY_WORDS + 2u;
+    }
+    if render_settings.half_precision != 0u {
+        return POS_OPACITY_WORDS + 3u;
+    }
+    return POS_OPACITY_WORDS + 6u;
+}
+
+fn num_gaussians() -> u32 {
+    return arrayLength(&gaussians) / gaussian_stride();
+}
+
+/// position (xyz) and opacity (w) of a splat
+fn pos_opacity(v_idx: u32) -> vec4<f32> {
+    let offset = v_idx * gaussian_stride();
+    return vec4<f32>(
+        bitcast<f32>(gaussians[offset + 0u]),
+        bitcast<f32>(gaussians[offset + 1u]),
+        bitcast<f32>(gaussians[offset + 2u]),
+        bitcast<f32>(gaussians[offset + 3u]),
+    );
+}
+
+/// decodes a covariance that is packed into a rotation and a log scale with 10 bits per component
+/// the rotation stores the index of the largest quaternion component and the other three
+/// components (see io/quantize.rs)
+fn unpack_covariance(packed_rotation: u32, packed_scale: u32) -> array<f32,6> {
+    let smallest = (vec3<f32>(
+        f32((packed_rotation >> 20u) & 1023u),
+        f32((packed_rotation >> 10u) & 1023u),
+        f32(packed_rotation & 1023u)
+    ) / 1023. * 2. - 1.) / sqrt(2.);
+    let largest = sqrt(max(0., 1. - dot(smallest, smallest)));
+    // xyzw
+    var q: vec4<f32>;
+    switch packed_rotation >> 30u {
+        case 0u: { q = vec4<f32>(largest, smallest); }
+        case 1u: { q = vec4<f32>(smallest.x, largest, smallest.yz); }
+        case 2u: { q = vec4<f32>(smallest.xy, largest, smallest.z); }
+        default: { q = vec4<f32>(smallest, largest); }
+    }
+    let log_scale = mix(
+        vec3<f32>(render_settings.log_scale_min),
+        vec3<f32>(render_settings.log_scale_max),
+        vec3<f32>(
+            f32(packed_scale & 1023u),
+            f32((packed_scale >> 10u) & 1023u),
+            f32((packed_scale >> 20u) & 1023u)
+        ) / 1023.
+    );
+    let x = q.x;
+    let y = q.y;
+    let z = q.z;
+    let w = q.w;
+    let rotation = mat3x3<f32>(
+        1. - 2. * (y * y + z * z), 2. * (x * y + w * z), 2. * (x * z - w * y),
+        2. * (x * y - w * z), 1. - 2. * (x * x + z * z), 2. * (y * z + w * x),
+        2. * (x * z + w * y), 2. * (y * z - w * x), 1. - 2. * (x * x + y * y)
+    );
+    let scale = exp(log_scale);
+    let m = rotation * mat3x3<f32>(
+        scale.x, 0., 0.,
+        0., scale.y, 0.,
+        0., 0., scale.z
+    );
+    let cov = m * transpose(m);
+    return array<f32,6>(cov[0][0], cov[0][1], cov[0][2], cov[1][1], cov[1][2], cov[2][2]);
+}
+
 fn cov_coefs(v_idx: u32) -> array<f32,6> {
-    let a = unpack2x16float(gaussians[v_idx].cov[0]);
-    let b = unpack2x16float(gaussians[v_idx].cov[1]);
-    let c = unpack2x16float(gaussians[v_idx].cov[2]);
+    let offset = v_idx * gaussian_stride() + POS_OPACITY_WORDS;
+    if render_settings.packed_covariance != 0u {
+        return unpack_covariance(gaussians[offset], gaussians[offset + 1u]);
+    }
+    if render_settings.half_precision == 0u {
+        return array<f32,6>(
+            bitcast<f32>(gaussians[offset + 0u]),
+            bitcast<f32>(gaussians[offset + 1u]),
+            bitcast<f32>(gaussians[offset + 2u]),
+            bitcast<f32>(gaussians[offset + 3u]),
+            bitcast<f32>(gaussians[offset + 4u]),
+            bitcast<f32>(gaussians[offset + 5u]),
+        );
+    }
+    let a = unpack2x16float(gaussians[offset + 0u]);
+    let b = unpack2x16float(gaussians[offset + 1u]);
+    let c = unpack2x16float(gaussians[offset + 2u]);
     return array<f32,6>(a.x, a.y, b.x, b.y, c.x, c.y);
 }
 
+/// the scale of a splat is the geometric mean of its standard deviations
+fn min_scale_culled(cov3d: mat3x3<f32>) -> bool {
+    let ellipsoids = render_settings.render_mode == 1u || render_settings.render_mode == 2u;
+    return ellipsoids && render_settings.min_scale > 0.
+        && pow(max(determinant(cov3d), 0.), 1. / 6.) < render_settings.min_scale;
+}
+
+/// maps the thread index to the index of the selected splat
+/// returns an index out of bounds if the thread has no splat
+fn lod_splat_index(thread_idx: u32) -> u32 {
+    let n = render_settings.lod_ranges;
+    if thread_idx >= lod_ranges[n - 1u].offset {
+        return num_gaussians();
+    }
+    // binary search for the last range that starts at or before the thread
+    var lo = 0u;
+    var hi = n - 1u;
+    while lo + 1u < hi {
+        let mid = (lo + hi) / 2u;
+        if lod_ranges[mid].offset <= thread_idx {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    let range = lod_ranges[lo];
+    return range.start + thread_idx - range.offset;
+}
+
+// true if the point is cut away by a clip plane or lies outside of the crop box
+fn clipped(p: vec3<f32>) -> bool {
+    for (var i = 0u; i < render_settings.num_clip_planes; i++) {
+        if dot(render_settings.clip_planes[i], vec4<f32>(p, 1.)) < 0. {
+            return true;
+        }
+    }
+    if render_settings.crop_box_enabled == 1u {
+        let local = render_settings.crop_box * vec4<f32>(p, 1.);
+        return any(abs(local.xyz) > vec3<f32>(1.));
+    }
+    return false;
+}
+
 @compute @workgroup_size(256,1,1)
 fn preprocess(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) wgs: vec3<u32>) {
-    let idx = gid.x;
-    if idx >= arrayLength(&gaussians) {
+    var idx = gid.x;
+    if render_settings.lod_ranges > 0u {
+        idx = lod_splat_index(gid.x);
+    }
+    if idx >= num_gaussians() {
+        return;
+    }
+    if ((hidden[idx / 32u] >> (idx % 32u)) & 1u) != 0u {
         return;
     }
 
     let focal = camera.focal;
     let viewport = camera.viewport;
-    let vertex = gaussians[idx];
-    let a = unpack2x16float(vertex.pos_opacity[0]);
-    let b = unpack2x16float(vertex.pos_opacity[1]);
-    let xyz = vec3<f32>(a.x, a.y, b.x);
-    var opacity = b.y;
+    let vertex = pos_opacity(idx);
+    let xyz = vertex.xyz;
+    var opacity = vertex.w;
 
     if any(xyz < render_settings.clipping_box_min.xyz) || any(xyz > render_settings.clipping_box_max.xyz) {
         return;
     }
+    if clipped(xyz) {
+        return;
+    }
 
     var camspace = camera.view * vec4<f32>(xyz, 1.);
     let pos2d = camera.proj * camspace;
     let bounds = 1.2 * pos2d.w;
     let z = pos2d.z / pos2d.w;
 
-    if idx == 0u {
+    if gid.x == 0u {
         atomicAdd(&sort_dispatch.dispatch_x, 1u);   // safety addition to always have an unfull block at the end of the buffer
     }
     // frustum culling hack
@@ -201,12 +439,17 @@ fn preprocess(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgr
         scale_mod = smoothstep(0., 1., (walltime - dd));
     }
 
-    let scaling = render_settings.gaussian_scaling * scale_mod;
-    let Vrk = mat3x3<f32>(
+    let cov3d = mat3x3<f32>(
         cov_sparse[0], cov_sparse[1], cov_sparse[2],
         cov_sparse[1], cov_sparse[3], cov_sparse[4],
         cov_sparse[2], cov_sparse[4], cov_sparse[5]
-    ) * scaling * scaling;
+    );
+    if min_scale_culled(cov3d) {
+        return;
+    }
+
+    let scaling = render_settings.gaussian_scaling * scale_mod;
+    let Vrk = cov3d * scaling * scaling;
     let J = mat3x3<f32>(
         focal.x / camspace.z,
         0.,
@@ -235,6 +478,9 @@ fn preprocess(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgr
         }
         opacity *= coef;
     }
+    if opacity < max(MIN_OPACITY, render_settings.opacity_threshold) {
+        return;
+    }
 
     let diagonal1 = cov[0][0] + kernel_size;
     let offDiagonal = cov[0][1];
@@ -248,17 +494,34 @@ fn preprocess(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgr
 
     let diagonalVector = normalize(vec2<f32>(offDiagonal, lambda1 - diagonal1));
     // scaled eigenvectors in screen space 
-    let v1 = sqrt(2.0 * lambda1) * diagonalVector;
-    let v2 = sqrt(2.0 * lambda2) * vec2<f32>(diagonalVector.y, -diagonalVector.x);
+    var v1 = sqrt(2.0 * lambda1) * diagonalVector;
+    var v2 = sqrt(2.0 * lambda2) * vec2<f32>(diagonalVector.y, -diagonalVector.x);
+    let points = render_settings.render_mode == 3u;
+    if points {
+        // fixed size point at the center
+        v1 = vec2<f32>(POINT_RADIUS, 0.);
+        v2 = vec2<f32>(0., POINT_RADIUS);
+    }
 
     let v_center = pos2d.xyzw / pos2d.w;
 
+    let znear = -camera.proj[3][2] / camera.proj[2][2];
+    let zfar = -camera.proj[3][2] / (camera.proj[2][2] - (1.));
+
     let camera_pos = camera.view_inv[3].xyz;
     let dir = normalize(xyz - camera_pos);
-    let color = vec4<f32>(
-        max(vec3<f32>(0.), evaluate_sh(dir, idx, render_settings.max_sh_deg)),
-        opacity
-    );
+    var rgb = max(vec3<f32>(0.), evaluate_sh(dir, idx, select(render_settings.max_sh_deg, 0u, points)));
+    if render_settings.glare_ratio > 0. && !points {
+        rgb = suppress_glare(rgb, idx);
+    }
+    if render_settings.color_space == 1u {
+        rgb = srgb_to_linear(rgb);
+    }
+    if render_settings.render_mode == 4u {
+        // linear view depth, the blending averages it and display.wgsl maps it to a colormap
+        rgb = vec3<f32>(saturate((camspace.z - znear) / (zfar - znear)));
+    }
+    let color = vec4<f32>(rgb, opacity);
 
     let store_idx = atomicAdd(&sort_infos.keys_size, 1u);
     let v = vec4<f32>(v1 / viewport, v2 / viewport);
@@ -266,10 +529,10 @@ fn preprocess(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgr
         pack2x16float(v.xy), pack2x16float(v.zw),
         pack2x16float(v_center.xy),
         pack2x16float(color.rg), pack2x16float(color.ba),
+        v_center.z,
+        (selection[idx / 32u] >> (idx % 32u)) & 1u,
     );
     // filling the sorting buffers and the indirect sort dispatch buffer
-    let znear = -camera.proj[3][2] / camera.proj[2][2];
-    let zfar = -camera.proj[3][2] / (camera.proj[2][2] - (1.));
     // filling the sorting buffers and the indirect sort dispatch buffer
     sort_depths[store_idx] = bitcast<u32>(zfar - pos2d.z) ;//u32(f32(0xffffffu) - pos2d.z / zfar * f32(0xffffffu));
     sort_indices[store_idx] = store_idx;
@@ -0,0 +1,7 @@
+@group(1) @binding(1) 
+var<storage,read> sh_coefs : array<u32>;
+
+/// reads the ith u32 (2x f16 or f32, see sh_words) of the sh coefficients of a splat
+fn sh_word(splat_idx: u32, word_idx: u32) -> u32 {
+    return sh_coefs[splat_idx * sh_words() + word_idx];
+}
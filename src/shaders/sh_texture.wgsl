@@ -0,0 +1,15 @@
+// the coefficients of one splat are stored in consecutive Rgba32Uint texels (6 for f16, 12 for f32)
+// the splats are stored row by row, layer by layer
+@group(1) @binding(1) 
+var sh_texture : texture_2d_array<u32>;
+
+/// reads the ith u32 (2x f16 or f32, see sh_words) of the sh coefficients of a splat
+fn sh_word(splat_idx: u32, word_idx: u32) -> u32 {
+    let texels = sh_words() / 4u;
+    let size = textureDimensions(sh_texture);
+    let splats_per_row = size.x / texels;
+    let row = splat_idx / splats_per_row;
+    let x = (splat_idx % splats_per_row) * texels + word_idx / 4u;
+    let texel = textureLoad(sh_texture, vec2<u32>(x, row % size.y), row / size.y, 0);
+    return texel[word_idx % 4u];
+}
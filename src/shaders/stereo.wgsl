@@ -0,0 +1,51 @@
+struct StereoSettings {
+    // 0: side by side, 1: half side by side, 2: anaglyph
+    mode: u32,
+    _pad0: u32,
+    _pad1: u32,
+    _pad2: u32,
+}
+
+// displayed images of the eyes, already in the output color space
+@group(0) @binding(0)
+var left_img: texture_2d<f32>;
+@group(0) @binding(1)
+var right_img: texture_2d<f32>;
+@group(0) @binding(2)
+var eye_sampler: sampler;
+@group(0) @binding(3)
+var<uniform> settings: StereoSettings;
+
+struct VertexOut {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) in_vertex_index: u32,
+) -> VertexOut {
+    // creates two triangles that cover the whole target
+    let xy = vec2<f32>(
+        f32(in_vertex_index % 2u == 0u),
+        f32(in_vertex_index < 2u)
+    );
+    return VertexOut(vec4<f32>(xy * 2. - (1.), 0., 1.), vec2<f32>(xy.x, 1. - xy.y));
+}
+
+@fragment
+fn fs_main(vertex_in: VertexOut) -> @location(0) vec4<f32> {
+    let tc = vertex_in.tex_coord;
+    if settings.mode == 2u {
+        let left = textureSampleLevel(left_img, eye_sampler, tc, 0.);
+        let right = textureSampleLevel(right_img, eye_sampler, tc, 0.);
+        // half color anaglyph, the left eye is shown as gray in the red channel
+        // which reduces the rivalry between the eyes on red and cyan surfaces
+        let red = dot(left.rgb, vec3<f32>(0.299, 0.587, 0.114));
+        return vec4<f32>(red, right.g, right.b, max(left.a, right.a));
+    }
+    if tc.x < 0.5 {
+        return textureSampleLevel(left_img, eye_sampler, vec2<f32>(tc.x * 2., tc.y), 0.);
+    }
+    return textureSampleLevel(right_img, eye_sampler, vec2<f32>(tc.x * 2. - 1., tc.y), 0.);
+}
@@ -0,0 +1,80 @@
+// accumulates the stochastic transparency frames (see gaussian.wgsl fs_stochastic) over time
+// and composites the result over the render target
+
+struct ResolveSettings {
+    // maps the clip space position of the current frame to the last frame
+    reproject: mat4x4<f32>,
+    // weight of the current frame
+    blend: f32,
+    // clamp the history to the colors around the pixel
+    clamp_history: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+// the accumulated history in the composite pipeline
+@group(0) @binding(0)
+var current: texture_2d<f32>;
+@group(0) @binding(1)
+var depth: texture_depth_2d;
+@group(0) @binding(2)
+var history: texture_2d<f32>;
+@group(0) @binding(3)
+var history_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> settings: ResolveSettings;
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) in_vertex_index: u32,
+) -> @builtin(position) vec4<f32> {
+
+    // creates two vertices that cover the whole screen
+    let xy = vec2<f32>(
+        f32(in_vertex_index % 2u == 0u),
+        f32(in_vertex_index < 2u)
+    );
+    return vec4<f32>(xy * 2. - (1.), 0., 1.);
+}
+
+@fragment
+fn fs_resolve(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(current));
+    let p = vec2<i32>(pos.xy);
+    let color = textureLoad(current, p, 0);
+    if settings.blend >= 1. {
+        return color;
+    }
+
+    // position of the pixel in the last frame
+    let uv = pos.xy / vec2<f32>(size);
+    let ndc = vec2<f32>(uv.x * 2. - 1., 1. - uv.y * 2.);
+    let last = settings.reproject * vec4<f32>(ndc, textureLoad(depth, p, 0), 1.);
+    let last_ndc = last.xy / last.w;
+    let last_uv = vec2<f32>(last_ndc.x * 0.5 + 0.5, 0.5 - last_ndc.y * 0.5);
+    if any(last_uv < vec2<f32>(0.)) || any(last_uv > vec2<f32>(1.)) {
+        return color;
+    }
+    var last_color = textureSampleLevel(history, history_sampler, last_uv, 0.);
+
+    if settings.clamp_history != 0u {
+        var lo = color;
+        var hi = color;
+        for (var y = -1; y <= 1; y++) {
+            for (var x = -1; x <= 1; x++) {
+                let c = textureLoad(current, clamp(p + vec2<i32>(x, y), vec2<i32>(0), size - 1), 0);
+                lo = min(lo, c);
+                hi = max(hi, c);
+            }
+        }
+        last_color = clamp(last_color, lo, hi);
+    }
+    return mix(last_color, color, settings.blend);
+}
+
+@fragment
+fn fs_composite(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    // the opaque fragments are averaged over the frames, so the result is premultiplied
+    return textureLoad(current, vec2<i32>(pos.xy), 0);
+}
@@ -0,0 +1,236 @@
+//! experimental peer-to-peer scene sharing between two browser sessions
+//!
+//! there is no signaling server, the session descriptions are exchanged by hand (e.g. pasted into a chat):
+//! 1. the host calls [SharePeer::host] with the loaded files and sends [SharePeer::signal] (the offer) to the other peer
+//! 2. the other peer calls [SharePeer::join] with the offer and sends its [SharePeer::signal] (the answer) back
+//! 3. the host calls [SharePeer::accept] with the answer
+//!
+//! the scene and point cloud are then sent as codec frames over a data channel
+//! and the receiving peer opens the viewer once all chunks arrived
+
+use std::{cell::RefCell, rc::Rc};
+
+use js_sys::{Array, Promise, Reflect, Uint8Array};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    MessageEvent, RtcConfiguration, RtcDataChannel, RtcDataChannelEvent, RtcDataChannelInit,
+    RtcDataChannelState, RtcDataChannelType, RtcIceGatheringState, RtcIceServer, RtcPeerConnection,
+    RtcSdpType, RtcSessionDescriptionInit,
+};
+
+use crate::io::codec::{Frame, FrameKind};
+
+/// payload size of a single data channel message
+/// larger messages are not supported by all browsers
+const CHUNK_SIZE: usize = 1 << 16;
+/// sending pauses while more than this many bytes are queued in the data channel
+const MAX_BUFFERED: u32 = 1 << 22;
+const STUN_SERVER: &str = "stun:stun.l.google.com:19302";
+const CHANNEL_LABEL: &str = "web-splat";
+
+#[derive(Default)]
+struct Received {
+    pc: Vec<u8>,
+    scene: Option<Vec<u8>>,
+    bytes: usize,
+    done: bool,
+}
+
+#[wasm_bindgen]
+pub struct SharePeer {
+    connection: RtcPeerConnection,
+    received: Rc<RefCell<Received>>,
+}
+
+#[wasm_bindgen]
+impl SharePeer {
+    /// starts sharing the given files
+    /// the transfer begins as soon as the answer of the other peer is accepted
+    pub async fn host(pc: Vec<u8>, scene: Option<Vec<u8>>) -> Result<SharePeer, JsValue> {
+        let connection = new_connection()?;
+        let mut init = RtcDataChannelInit::new();
+        init.ordered(true);
+        let channel = connection.create_data_channel_with_data_channel_dict(CHANNEL_LABEL, &init);
+        channel.set_binary_type(RtcDataChannelType::Arraybuffer);
+
+        let offer = JsFuture::from(connection.create_offer()).await?;
+        set_description(&connection, RtcSdpType::Offer, &sdp(&offer)?, true).await?;
+        gather_candidates(&connection).await?;
+
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = send_files(&channel, pc, scene).await {
+                log::error!("failed to share scene: {:?}", err);
+            }
+        });
+        Ok(SharePeer {
+            connection,
+            received: Default::default(),
+        })
+    }
+
+    /// connects to a host with its offer
+    /// the viewer is opened once the whole point cloud was received
+    pub async fn join(offer: String) -> Result<SharePeer, JsValue> {
+        let connection = new_connection()?;
+        let received: Rc<RefCell<Received>> = Default::default();
+
+        let state = received.clone();
+        let ondatachannel =
+            Closure::<dyn FnMut(RtcDataChannelEvent)>::new(move |event: RtcDataChannelEvent| {
+                let channel = event.channel();
+                channel.set_binary_type(RtcDataChannelType::Arraybuffer);
+                let state = state.clone();
+                let onmessage =
+                    Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                        let data = Uint8Array::new(&event.data()).to_vec();
+                        if let Err(err) = receive_frame(&state, &data) {
+                            log::error!("invalid frame from peer: {:?}", err);
+                        }
+                    });
+                channel.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+                onmessage.forget();
+            });
+        connection.set_ondatachannel(Some(ondatachannel.as_ref().unchecked_ref()));
+        ondatachannel.forget();
+
+        set_description(&connection, RtcSdpType::Offer, &offer, false).await?;
+        let answer = JsFuture::from(connection.create_answer()).await?;
+        set_description(&connection, RtcSdpType::Answer, &sdp(&answer)?, true).await?;
+        gather_candidates(&connection).await?;
+
+        Ok(SharePeer {
+            connection,
+            received,
+        })
+    }
+
+    /// completes the connection with the answer of the other peer (host only)
+    pub async fn accept(&self, answer: String) -> Result<(), JsValue> {
+        set_description(&self.connection, RtcSdpType::Answer, &answer, false).await
+    }
+
+    /// session description that has to be sent to the other peer
+    pub fn signal(&self) -> Option<String> {
+        self.connection.local_description().map(|d| d.sdp())
+    }
+
+    /// number of bytes received so far
+    pub fn received_bytes(&self) -> usize {
+        self.received.borrow().bytes
+    }
+
+    pub fn close(&self) {
+        self.connection.close();
+    }
+}
+
+fn new_connection() -> Result<RtcPeerConnection, JsValue> {
+    let mut server = RtcIceServer::new();
+    server.urls(&JsValue::from_str(STUN_SERVER));
+    let mut config = RtcConfiguration::new();
+    config.ice_servers(&Array::of1(&server));
+    RtcPeerConnection::new_with_configuration(&config)
+}
+
+fn sdp(description: &JsValue) -> Result<String, JsValue> {
+    Reflect::get(description, &JsValue::from_str("sdp"))?
+        .as_string()
+        .ok_or_else(|| "session description without sdp".into())
+}
+
+async fn set_description(
+    connection: &RtcPeerConnection,
+    kind: RtcSdpType,
+    sdp: &str,
+    local: bool,
+) -> Result<(), JsValue> {
+    let mut description = RtcSessionDescriptionInit::new(kind);
+    description.sdp(sdp);
+    let promise = if local {
+        connection.set_local_description(&description)
+    } else {
+        connection.set_remote_description(&description)
+    };
+    JsFuture::from(promise).await?;
+    Ok(())
+}
+
+/// waits until all ice candidates are part of the local description
+/// so that a single message is enough for the signaling
+async fn gather_candidates(connection: &RtcPeerConnection) -> Result<(), JsValue> {
+    if connection.ice_gathering_state() == RtcIceGatheringState::Complete {
+        return Ok(());
+    }
+    let promise = Promise::new(&mut |resolve, _| {
+        let c = connection.clone();
+        let onchange = Closure::<dyn FnMut()>::new(move || {
+            if c.ice_gathering_state() == RtcIceGatheringState::Complete {
+                resolve.call0(&JsValue::NULL).ok();
+            }
+        });
+        connection.set_onicegatheringstatechange(Some(onchange.as_ref().unchecked_ref()));
+        onchange.forget();
+    });
+    JsFuture::from(promise).await?;
+    connection.set_onicegatheringstatechange(None);
+    Ok(())
+}
+
+async fn send_files(
+    channel: &RtcDataChannel,
+    pc: Vec<u8>,
+    scene: Option<Vec<u8>>,
+) -> Result<(), JsValue> {
+    if channel.ready_state() != RtcDataChannelState::Open {
+        let promise = Promise::new(&mut |resolve, _| channel.set_onopen(Some(&resolve)));
+        JsFuture::from(promise).await?;
+        channel.set_onopen(None);
+    }
+    channel.set_buffered_amount_low_threshold(MAX_BUFFERED / 2);
+
+    let frames = scene
+        .map(|s| Frame::new(FrameKind::Scene, s))
+        .into_iter()
+        .chain(
+            pc.chunks(CHUNK_SIZE)
+                .map(|c| Frame::new(FrameKind::Chunk, c.to_vec())),
+        )
+        .chain(std::iter::once(Frame::new(FrameKind::End, Vec::new())));
+    for frame in frames {
+        if channel.buffered_amount() > MAX_BUFFERED {
+            let promise =
+                Promise::new(&mut |resolve, _| channel.set_onbufferedamountlow(Some(&resolve)));
+            JsFuture::from(promise).await?;
+            channel.set_onbufferedamountlow(None);
+        }
+        let data = frame
+            .to_bytes(true)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        channel.send_with_u8_array(&data)?;
+    }
+    log::info!("shared {} bytes", pc.len());
+    Ok(())
+}
+
+fn receive_frame(state: &Rc<RefCell<Received>>, data: &[u8]) -> anyhow::Result<()> {
+    let (frame, _) = Frame::from_bytes(data)?;
+    let mut state = state.borrow_mut();
+    if state.done {
+        return Ok(());
+    }
+    state.bytes += data.len();
+    match frame.kind {
+        FrameKind::Scene => state.scene = Some(frame.payload),
+        FrameKind::Chunk => state.pc.extend(frame.payload),
+        FrameKind::End => {
+            state.done = true;
+            let pc = std::mem::take(&mut state.pc);
+            let scene = state.scene.take();
+            log::info!("received shared scene ({} bytes)", pc.len());
+            wasm_bindgen_futures::spawn_local(crate::run_wasm(pc, scene, None, None));
+        }
+        kind => log::debug!("skipping {:?} frame from peer", kind),
+    }
+    Ok(())
+}
@@ -0,0 +1,326 @@
+use std::str::FromStr;
+
+use cgmath::{Rotation, Vector2, Vector3};
+
+use crate::{camera::PerspectiveCamera, uniform::UniformBuffer};
+
+/// how the images of the two eyes are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StereoMode {
+    /// left eye on the left half of the window and right eye on the right half
+    /// every eye has the aspect ratio of its half (for cross-eyed or parallel viewing)
+    #[default]
+    SideBySide = 0,
+    /// like [StereoMode::SideBySide] but every eye is squeezed to half its width
+    /// 3d tvs stretch the halves back to the full screen
+    HalfSideBySide = 1,
+    /// red-cyan glasses, the left eye is shown in the red channel
+    Anaglyph = 2,
+}
+
+impl StereoMode {
+    pub const ALL: [StereoMode; 3] = [
+        StereoMode::SideBySide,
+        StereoMode::HalfSideBySide,
+        StereoMode::Anaglyph,
+    ];
+}
+
+impl std::fmt::Display for StereoMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            StereoMode::SideBySide => "Side by Side",
+            StereoMode::HalfSideBySide => "Half Side by Side",
+            StereoMode::Anaglyph => "Anaglyph",
+        })
+    }
+}
+
+impl FromStr for StereoMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sbs" | "side-by-side" => Ok(Self::SideBySide),
+            "half-sbs" | "half-side-by-side" => Ok(Self::HalfSideBySide),
+            "anaglyph" => Ok(Self::Anaglyph),
+            _ => Err(anyhow::anyhow!(
+                "unknown stereo mode '{}', expected sbs, half-sbs or anaglyph",
+                s
+            )),
+        }
+    }
+}
+
+/// renders the scene for a left and a right eye camera
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stereo {
+    pub mode: StereoMode,
+    /// distance between the eyes in scene units
+    pub ipd: f32,
+}
+
+impl Default for Stereo {
+    fn default() -> Self {
+        Self {
+            mode: StereoMode::default(),
+            ipd: 0.065,
+        }
+    }
+}
+
+impl Stereo {
+    /// camera of one eye, moved by half the eye distance along the right axis of `camera`
+    /// the eyes look in parallel, so objects at infinity are shown at the same position for both eyes
+    pub fn eye_camera(
+        &self,
+        mut camera: PerspectiveCamera,
+        viewport: Vector2<u32>,
+        right: bool,
+    ) -> PerspectiveCamera {
+        let offset = camera.rotation.invert() * Vector3::unit_x() * (self.ipd * 0.5);
+        camera.position += if right { offset } else { -offset };
+        if self.mode == StereoMode::SideBySide {
+            camera
+                .projection
+                .resize((viewport.x / 2).max(1), viewport.y.max(1));
+        }
+        camera
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct StereoUniform {
+    mode: u32,
+    _pad: [u32; 3],
+}
+
+impl Default for StereoUniform {
+    fn default() -> Self {
+        Self {
+            mode: StereoMode::default() as u32,
+            _pad: [0; 3],
+        }
+    }
+}
+
+/// combines the displayed images of the two eyes in the window
+pub(crate) struct StereoCompositor {
+    stereo: Stereo,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    settings: UniformBuffer<StereoUniform>,
+    format: wgpu::TextureFormat,
+    /// displayed images of the left and right eye
+    eyes: [wgpu::TextureView; 2],
+    bind_group: wgpu::BindGroup,
+}
+
+impl StereoCompositor {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        stereo: Stereo,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("stereo bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: UniformBuffer::<StereoUniform>::binding_type(),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("stereo pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/stereo.wgsl"));
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("stereo pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            multiview: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("stereo sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let settings = UniformBuffer::new_default(device, Some("stereo settings"));
+        let eyes = Self::create_eyes(device, format, width, height);
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, &eyes, &sampler, &settings);
+        let mut compositor = Self {
+            stereo,
+            pipeline,
+            bind_group_layout,
+            sampler,
+            settings,
+            format,
+            eyes,
+            bind_group,
+        };
+        compositor.set_stereo(queue, stereo);
+        compositor
+    }
+
+    fn create_eyes(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> [wgpu::TextureView; 2] {
+        ["left eye texture", "right eye texture"].map(|label| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width: width.max(1),
+                        height: height.max(1),
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&Default::default())
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        eyes: &[wgpu::TextureView; 2],
+        sampler: &wgpu::Sampler,
+        settings: &UniformBuffer<StereoUniform>,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("stereo bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&eyes[0]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&eyes[1]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: settings.buffer().as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// the eye textures have the size of the window
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.eyes = Self::create_eyes(device, self.format, width, height);
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.eyes,
+            &self.sampler,
+            &self.settings,
+        );
+    }
+
+    pub fn stereo(&self) -> Stereo {
+        self.stereo
+    }
+
+    pub fn set_stereo(&mut self, queue: &wgpu::Queue, stereo: Stereo) {
+        self.stereo = stereo;
+        self.settings.as_mut().mode = stereo.mode as u32;
+        self.settings.sync(queue);
+    }
+
+    /// target for the displayed image of one eye
+    pub fn eye_view(&self, right: bool) -> &wgpu::TextureView {
+        &self.eyes[right as usize]
+    }
+
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("stereo render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..4, 0..1);
+    }
+}
@@ -0,0 +1,619 @@
+//! stochastic transparency (Enderton et al. 2010)
+//! every fragment is opaque with the probability of its opacity, thresholded with a blue noise mask
+//! the nearest surviving fragment wins the depth test and the noisy frames are accumulated
+//! over time with reprojection (like temporal anti-aliasing)
+//! the splats are not sorted, so they never pop when the sort order changes
+
+use cgmath::{Matrix4, SquareMatrix, Vector2};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use wgpu::util::DeviceExt;
+
+use crate::{
+    gpu_rs::GPURSSorter,
+    pointcloud::PointCloud,
+    renderer::{CameraUniform, RenderSettings, SplattingArgsUniform},
+    uniform::UniformBuffer,
+};
+
+/// width and height of the tiled blue noise mask
+const NOISE_SIZE: usize = 64;
+const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+/// the accumulation of a still view stops after this many frames
+const MAX_FRAMES: u32 = 64;
+/// weight of the current frame while the camera moves
+const MOTION_BLEND: f32 = 0.2;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct NoiseUniform {
+    /// offset of the noise mask in pixels
+    offset: [u32; 2],
+    frame: u32,
+    _pad: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct ResolveUniform {
+    /// maps the clip space position of the current frame to the last frame
+    reproject: Matrix4<f32>,
+    /// weight of the current frame
+    blend: f32,
+    /// clamp the history to the colors around the pixel to avoid ghosting while moving
+    clamp_history: u32,
+    _pad: [u32; 2],
+}
+
+impl Default for ResolveUniform {
+    fn default() -> Self {
+        Self {
+            reproject: Matrix4::identity(),
+            blend: 1.,
+            clamp_history: 0,
+            _pad: [0; 2],
+        }
+    }
+}
+
+struct Targets {
+    color: wgpu::TextureView,
+    depth: wgpu::TextureView,
+    /// resolve bind groups writing into history 0 and 1
+    resolve: [wgpu::BindGroup; 2],
+    /// render target views of the history
+    history: [wgpu::TextureView; 2],
+    composite: [wgpu::BindGroup; 2],
+}
+
+pub(crate) struct StochasticTransparency {
+    pipeline: wgpu::RenderPipeline,
+    resolve_pipeline: wgpu::RenderPipeline,
+    composite_pipeline: wgpu::RenderPipeline,
+    noise: UniformBuffer<NoiseUniform>,
+    noise_bind_group: wgpu::BindGroup,
+    resolve_settings: UniformBuffer<ResolveUniform>,
+    sampler: wgpu::Sampler,
+    targets: Targets,
+    size: (u32, u32),
+    /// history that was written last
+    current: usize,
+    /// frames accumulated since the view stopped changing
+    frames: u32,
+    /// view projection and settings of the last accumulated frame
+    last_frame: Option<(Matrix4<f32>, RenderSettings)>,
+}
+
+impl StochasticTransparency {
+    /// `color_format` and `sample_count` of the render pass in which the result is composited
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        color_format: wgpu::TextureFormat,
+        sample_count: u32,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let noise_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("stochastic noise bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: UniformBuffer::<NoiseUniform>::binding_type(),
+                    count: None,
+                },
+            ],
+        });
+        let noise = UniformBuffer::new_default(device, Some("stochastic noise uniform buffer"));
+        let noise_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("blue noise texture"),
+                size: wgpu::Extent3d {
+                    width: NOISE_SIZE as u32,
+                    height: NOISE_SIZE as u32,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &blue_noise(NOISE_SIZE),
+        );
+        let noise_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("stochastic noise bind group"),
+            layout: &noise_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        &noise_texture.create_view(&Default::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: noise.buffer().as_entire_binding(),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/gaussian.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("stochastic pipeline layout"),
+            bind_group_layouts: &[
+                &PointCloud::bind_group_layout_render(device),
+                &GPURSSorter::bind_group_layout_rendering(device),
+                &UniformBuffer::<SplattingArgsUniform>::bind_group_layout(device),
+                &noise_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("stochastic pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_stochastic",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: COLOR_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: Default::default(),
+                bias: Default::default(),
+            }),
+            multisample: Default::default(),
+            multiview: None,
+        });
+
+        let resolve_shader =
+            device.create_shader_module(wgpu::include_wgsl!("shaders/stochastic.wgsl"));
+        let resolve_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("stochastic resolve pipeline layout"),
+            bind_group_layouts: &[
+                &Self::resolve_bind_group_layout(device),
+                &UniformBuffer::<ResolveUniform>::bind_group_layout(device),
+            ],
+            push_constant_ranges: &[],
+        });
+        let composite_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("stochastic composite pipeline layout"),
+            bind_group_layouts: &[&Self::composite_bind_group_layout(device)],
+            push_constant_ranges: &[],
+        });
+        let create_pipeline = |label: &str,
+                               layout: &wgpu::PipelineLayout,
+                               entry_point: &str,
+                               target: wgpu::ColorTargetState,
+                               sample_count: u32| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: &resolve_shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &resolve_shader,
+                    entry_point,
+                    targets: &[Some(target)],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    ..Default::default()
+                },
+                multiview: None,
+            })
+        };
+        let resolve_pipeline = create_pipeline(
+            "stochastic resolve pipeline",
+            &resolve_layout,
+            "fs_resolve",
+            COLOR_FORMAT.into(),
+            1,
+        );
+        // drawn in the same render pass as the splats
+        let composite_pipeline = create_pipeline(
+            "stochastic composite pipeline",
+            &composite_layout,
+            "fs_composite",
+            wgpu::ColorTargetState {
+                format: color_format,
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+            sample_count,
+        );
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("stochastic history sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let targets = Self::create_targets(device, &sampler, width, height);
+        Self {
+            pipeline,
+            resolve_pipeline,
+            composite_pipeline,
+            noise,
+            noise_bind_group,
+            resolve_settings: UniformBuffer::new_default(
+                device,
+                Some("stochastic resolve uniform buffer"),
+            ),
+            sampler,
+            targets,
+            size: (width, height),
+            current: 0,
+            frames: 0,
+            last_frame: None,
+        }
+    }
+
+    fn resolve_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        let texture_entry = |binding, sample_type| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("stochastic resolve bind group layout"),
+            entries: &[
+                texture_entry(0, wgpu::TextureSampleType::Float { filterable: false }),
+                texture_entry(1, wgpu::TextureSampleType::Depth),
+                texture_entry(2, wgpu::TextureSampleType::Float { filterable: true }),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    fn composite_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("stochastic composite bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    fn create_targets(
+        device: &wgpu::Device,
+        sampler: &wgpu::Sampler,
+        width: u32,
+        height: u32,
+    ) -> Targets {
+        let create_view = |label, format| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&Default::default())
+        };
+        let color = create_view("stochastic color texture", COLOR_FORMAT);
+        let depth = create_view("stochastic depth texture", DEPTH_FORMAT);
+        let history = [
+            create_view("stochastic history texture 0", COLOR_FORMAT),
+            create_view("stochastic history texture 1", COLOR_FORMAT),
+        ];
+        let resolve_layout = Self::resolve_bind_group_layout(device);
+        let composite_layout = Self::composite_bind_group_layout(device);
+        let resolve = |i: usize| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("stochastic resolve bind group"),
+                layout: &resolve_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&color),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&depth),
+                    },
+                    // reads the other history
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(&history[1 - i]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+            })
+        };
+        let composite = |i: usize| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("stochastic composite bind group"),
+                layout: &composite_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&history[i]),
+                }],
+            })
+        };
+        Targets {
+            resolve: [resolve(0), resolve(1)],
+            composite: [composite(0), composite(1)],
+            color,
+            depth,
+            history,
+        }
+    }
+
+    /// the targets must have the size of the render target in which the result is composited
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if self.size != (width, height) {
+            self.targets = Self::create_targets(device, &self.sampler, width, height);
+            self.size = (width, height);
+            self.last_frame = None;
+        }
+    }
+
+    /// shifts the noise mask in the next frames
+    /// a random offset every frame decorrelates the frames
+    pub fn set_noise_offset(&mut self, offset: Vector2<u32>) {
+        self.noise.as_mut().offset = offset.into();
+    }
+
+    /// a still view is accumulated over [MAX_FRAMES] frames, until then new frames must be rendered
+    pub fn is_converged(&self) -> bool {
+        self.last_frame.is_some() && self.frames >= MAX_FRAMES
+    }
+
+    /// renders one frame with stochastic coverage and accumulates it into the history
+    /// `bind_groups` are the point cloud, sorter and render settings bind groups
+    pub fn accumulate(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        queue: &wgpu::Queue,
+        bind_groups: [&wgpu::BindGroup; 3],
+        draw_indirect_buffer: &wgpu::Buffer,
+        camera: &CameraUniform,
+        render_settings: RenderSettings,
+    ) {
+        let view_proj = camera.proj_matrix * camera.view_matrix;
+        let settings = self.resolve_settings.as_mut();
+        settings.reproject = Matrix4::identity();
+        settings.blend = 1.;
+        settings.clamp_history = 0;
+        match self.last_frame {
+            // only the camera moved, the history is reprojected and clamped
+            Some((last_view_proj, last_args)) if same_scene(&last_args, &render_settings) => {
+                if last_view_proj == view_proj {
+                    self.frames = (self.frames + 1).min(MAX_FRAMES);
+                    settings.blend = 1. / (self.frames + 1) as f32;
+                } else {
+                    self.frames = 0;
+                    settings.reproject =
+                        last_view_proj * view_proj.invert().unwrap_or(Matrix4::identity());
+                    settings.blend = MOTION_BLEND;
+                    settings.clamp_history = 1;
+                }
+            }
+            // everything else invalidates the history
+            _ => self.frames = 0,
+        }
+        self.last_frame = Some((view_proj, render_settings));
+        self.resolve_settings.sync(queue);
+
+        let noise = self.noise.as_mut();
+        noise.frame = noise.frame.wrapping_add(1);
+        self.noise.sync(queue);
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("stochastic render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.targets.color,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.targets.depth,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                ..Default::default()
+            });
+            for (i, bind_group) in bind_groups.into_iter().enumerate() {
+                render_pass.set_bind_group(i as u32, bind_group, &[]);
+            }
+            render_pass.set_bind_group(3, &self.noise_bind_group, &[]);
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.draw_indirect(draw_indirect_buffer, 0);
+        }
+
+        self.current = 1 - self.current;
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("stochastic resolve render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.targets.history[self.current],
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+        render_pass.set_bind_group(0, &self.targets.resolve[self.current], &[]);
+        render_pass.set_bind_group(1, self.resolve_settings.bind_group(), &[]);
+        render_pass.set_pipeline(&self.resolve_pipeline);
+        render_pass.draw(0..4, 0..1);
+    }
+
+    /// blends the accumulated frames over the render target
+    pub fn composite<'rpass>(&'rpass self, render_pass: &mut wgpu::RenderPass<'rpass>) {
+        render_pass.set_bind_group(0, &self.targets.composite[self.current], &[]);
+        render_pass.set_pipeline(&self.composite_pipeline);
+        render_pass.draw(0..4, 0..1);
+    }
+}
+
+/// the settings only differ in the camera, so the last frame can be reprojected
+fn same_scene(a: &RenderSettings, b: &RenderSettings) -> bool {
+    RenderSettings {
+        camera: b.camera,
+        walltime: b.walltime,
+        resolution: b.resolution,
+        ..*a
+    } == *b
+}
+
+/// blue noise threshold mask (void and cluster, Ulichney 1993) with the ranks scaled to 0..=255
+fn blue_noise(size: usize) -> Vec<u8> {
+    let n = size * size;
+    // toroidal gaussian energy of a single pixel
+    let sigma = 1.5f32;
+    let kernel: Vec<f32> = (0..n)
+        .map(|i| {
+            let (x, y) = (i % size, i / size);
+            let dx = x.min(size - x) as f32;
+            let dy = y.min(size - y) as f32;
+            (-(dx * dx + dy * dy) / (2. * sigma * sigma)).exp()
+        })
+        .collect();
+    let update = |energy: &mut [f32], i: usize, sign: f32| {
+        let (px, py) = (i % size, i / size);
+        for (j, e) in energy.iter_mut().enumerate() {
+            let dx = (j % size + size - px) % size;
+            let dy = (j / size + size - py) % size;
+            *e += sign * kernel[dy * size + dx];
+        }
+    };
+    // set pixel with the highest energy
+    let tightest_cluster = |energy: &[f32], set: &[bool]| {
+        (0..n)
+            .filter(|i| set[*i])
+            .max_by(|a, b| energy[*a].total_cmp(&energy[*b]))
+            .unwrap()
+    };
+    // unset pixel with the lowest energy
+    let largest_void = |energy: &[f32], set: &[bool]| {
+        (0..n)
+            .filter(|i| !set[*i])
+            .min_by(|a, b| energy[*a].total_cmp(&energy[*b]))
+            .unwrap()
+    };
+
+    // initial pattern with a tenth of the pixels set, seeded so the mask is always the same
+    let mut rng = StdRng::seed_from_u64(0);
+    let initial = n / 10;
+    let mut energy = vec![0f32; n];
+    let mut set = vec![false; n];
+    let mut num_set = 0;
+    while num_set < initial {
+        let i = rng.gen_range(0..n);
+        if !set[i] {
+            set[i] = true;
+            update(&mut energy, i, 1.);
+            num_set += 1;
+        }
+    }
+    // move pixels from the tightest cluster to the largest void until the pattern is stable
+    for _ in 0..n {
+        let cluster = tightest_cluster(&energy, &set);
+        set[cluster] = false;
+        update(&mut energy, cluster, -1.);
+        let void = largest_void(&energy, &set);
+        set[void] = true;
+        update(&mut energy, void, 1.);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut rank = vec![0; n];
+    // the initial pixels are ranked by removing the tightest clusters
+    let (mut removed_energy, mut removed_set) = (energy.clone(), set.clone());
+    for r in (0..initial).rev() {
+        let cluster = tightest_cluster(&removed_energy, &removed_set);
+        removed_set[cluster] = false;
+        update(&mut removed_energy, cluster, -1.);
+        rank[cluster] = r;
+    }
+    // the remaining pixels are ranked by filling the largest voids
+    for r in initial..n {
+        let void = largest_void(&energy, &set);
+        set[void] = true;
+        update(&mut energy, void, 1.);
+        rank[void] = r;
+    }
+    rank.iter().map(|r| (r * 256 / n) as u8).collect()
+}
@@ -0,0 +1,254 @@
+use cgmath::{EuclideanSpace, InnerSpace, Point3, Quaternion};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::PerspectiveCamera,
+    math::{quaternion_from_array, quaternion_to_array, QuaternionOrder},
+    time::{Duration, Instant},
+};
+
+/// minimum time between two camera updates sent by the presenter
+const SEND_INTERVAL: Duration = Duration::from_millis(33);
+/// the camera is sent again after this time even if it did not move
+/// so that clients that joined later get the current view
+const RESEND_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncConfig {
+    /// websocket url of the room, e.g. `ws://localhost:9001/review`
+    /// all clients connected to the same url are in the same room
+    pub url: String,
+    /// drive the camera of all other clients in the room
+    pub presenter: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SyncMessage {
+    Camera {
+        position: [f32; 3],
+        /// scalar last
+        rotation: [f32; 4],
+    },
+}
+
+/// camera sync with other viewers (native or web) over a websocket room, see `sync_server`
+/// the presenter sends its camera to the room and all other clients follow it
+pub struct SyncSession {
+    connection: Connection,
+    presenter: bool,
+    /// follower stopped following the presenter
+    detached: bool,
+    last_sent: Option<(Instant, Point3<f32>, Quaternion<f32>)>,
+    /// latest camera of the presenter and if it was applied already
+    remote: Option<(Point3<f32>, Quaternion<f32>, bool)>,
+}
+
+impl SyncSession {
+    pub fn connect(config: &SyncConfig) -> anyhow::Result<Self> {
+        log::info!("joining sync room {}", config.url);
+        Ok(Self {
+            connection: Connection::open(&config.url)?,
+            presenter: config.presenter,
+            detached: false,
+            last_sent: None,
+            remote: None,
+        })
+    }
+
+    pub fn presenter(&self) -> bool {
+        self.presenter
+    }
+
+    pub fn detached(&self) -> bool {
+        self.detached
+    }
+
+    /// stop or resume following the presenter
+    /// when resuming, the camera jumps to the latest presenter camera
+    pub fn set_detached(&mut self, detached: bool) {
+        self.detached = detached;
+        if let Some(remote) = &mut self.remote {
+            remote.2 = false;
+        }
+    }
+
+    /// sends or applies the camera, called once per frame
+    /// returns true if the camera was changed
+    pub fn update(&mut self, camera: &mut PerspectiveCamera) -> bool {
+        for msg in self.connection.receive() {
+            match serde_json::from_str(&msg) {
+                Ok(SyncMessage::Camera { position, rotation }) => {
+                    let rotation = quaternion_from_array(rotation, QuaternionOrder::Xyzw);
+                    // the camera of any room member is applied, so broken values must not reach it
+                    let length = rotation.magnitude();
+                    if !position.iter().all(|v| v.is_finite())
+                        || !length.is_finite()
+                        || length < 1e-6
+                    {
+                        log::warn!("ignoring sync camera with invalid pose");
+                        continue;
+                    }
+                    self.remote = Some((Point3::from(position), rotation / length, false));
+                }
+                Err(err) => log::warn!("invalid sync message: {}", err),
+            }
+        }
+
+        if self.presenter {
+            let changed = match self.last_sent {
+                Some((time, position, rotation)) => {
+                    time.elapsed() >= SEND_INTERVAL
+                        && (position != camera.position
+                            || rotation != camera.rotation
+                            || time.elapsed() >= RESEND_INTERVAL)
+                }
+                None => true,
+            };
+            if changed {
+                self.last_sent = Some((Instant::now(), camera.position, camera.rotation));
+                let msg = SyncMessage::Camera {
+                    position: camera.position.to_vec().into(),
+                    rotation: quaternion_to_array(camera.rotation, QuaternionOrder::Xyzw),
+                };
+                match serde_json::to_string(&msg) {
+                    Ok(msg) => self.connection.send(msg),
+                    Err(err) => log::error!("cannot encode sync message: {}", err),
+                }
+            }
+            false
+        } else if self.detached {
+            false
+        } else if let Some((position, rotation, applied)) = &mut self.remote {
+            if *applied {
+                return false;
+            }
+            *applied = true;
+            camera.position = *position;
+            camera.rotation = *rotation;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Connection {
+    outgoing: std::sync::mpsc::Sender<String>,
+    incoming: std::sync::mpsc::Receiver<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Connection {
+    fn open(url: &str) -> anyhow::Result<Self> {
+        use std::sync::mpsc;
+        use tungstenite::{stream::MaybeTlsStream, Message};
+
+        let (mut socket, _) = tungstenite::connect(url)?;
+        // reads time out so that the thread can send the outgoing messages in between
+        let stream = match socket.get_mut() {
+            MaybeTlsStream::Plain(stream) => stream,
+            MaybeTlsStream::Rustls(stream) => stream.get_mut(),
+            _ => anyhow::bail!("unsupported websocket stream for {}", url),
+        };
+        stream.set_read_timeout(Some(std::time::Duration::from_millis(10)))?;
+        let (outgoing, outgoing_rx) = mpsc::channel::<String>();
+        let (incoming_tx, incoming) = mpsc::channel();
+        let url = url.to_string();
+        std::thread::spawn(move || loop {
+            loop {
+                match outgoing_rx.try_recv() {
+                    Ok(msg) => {
+                        if let Err(err) = socket.send(Message::Text(msg)) {
+                            log::error!("sync connection to {} lost: {}", url, err);
+                            return;
+                        }
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        // session was dropped
+                        socket.close(None).ok();
+                        return;
+                    }
+                }
+            }
+            match socket.read() {
+                Ok(Message::Text(msg)) => {
+                    if incoming_tx.send(msg).is_err() {
+                        // session was dropped
+                        socket.close(None).ok();
+                        return;
+                    }
+                }
+                Ok(Message::Close(_)) => {
+                    log::info!("sync room {} closed", url);
+                    return;
+                }
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(err))
+                    if matches!(
+                        err.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) => {}
+                Err(err) => {
+                    log::error!("sync connection to {} lost: {}", url, err);
+                    return;
+                }
+            }
+        });
+        Ok(Self { outgoing, incoming })
+    }
+
+    fn send(&self, msg: String) {
+        self.outgoing.send(msg).ok();
+    }
+
+    fn receive(&self) -> Vec<String> {
+        self.incoming.try_iter().collect()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+struct Connection {
+    socket: web_sys::WebSocket,
+    incoming: std::rc::Rc<std::cell::RefCell<Vec<String>>>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Connection {
+    fn open(url: &str) -> anyhow::Result<Self> {
+        use wasm_bindgen::{closure::Closure, JsCast};
+
+        let socket = web_sys::WebSocket::new(url)
+            .map_err(|err| anyhow::anyhow!("cannot connect to {}: {:?}", url, err))?;
+        let incoming: std::rc::Rc<std::cell::RefCell<Vec<String>>> = Default::default();
+        let queue = incoming.clone();
+        let onmessage =
+            Closure::<dyn FnMut(web_sys::MessageEvent)>::new(move |e: web_sys::MessageEvent| {
+                if let Some(msg) = e.data().as_string() {
+                    queue.borrow_mut().push(msg);
+                }
+            });
+        socket.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+        onmessage.forget();
+        Ok(Self { socket, incoming })
+    }
+
+    fn send(&self, msg: String) {
+        if self.socket.ready_state() == web_sys::WebSocket::OPEN {
+            self.socket.send_with_str(&msg).ok();
+        }
+    }
+
+    fn receive(&self) -> Vec<String> {
+        std::mem::take(&mut *self.incoming.borrow_mut())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.socket.close().ok();
+    }
+}
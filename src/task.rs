@@ -0,0 +1,175 @@
+//! background tasks that can be cancelled
+//! native tasks run on their own thread, on the web they are futures on the browser event loop
+
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+};
+
+/// error returned by operations that were cancelled
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("operation was cancelled")]
+pub struct Cancelled;
+
+/// shared flag to stop a long running operation
+/// operations check the token at safe points and return [Cancelled]
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// returns an error if the token was cancelled
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// reader that fails once the token is cancelled
+/// point cloud loaders and downloads stop at their next read
+pub struct CancellableReader<R> {
+    inner: R,
+    token: CancellationToken,
+}
+
+impl<R> CancellableReader<R> {
+    pub fn new(inner: R, token: CancellationToken) -> Self {
+        Self { inner, token }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for CancellableReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.token.check().map_err(io::Error::other)?;
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for CancellableReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+/// handle of a background task
+/// dropping the handle cancels the task, the result of a cancelled task is discarded
+pub struct Task<T> {
+    name: String,
+    token: CancellationToken,
+    rx: mpsc::Receiver<anyhow::Result<T>>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    /// runs `f` on a new thread
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn<F>(name: &str, f: F) -> Self
+    where
+        F: FnOnce(CancellationToken) -> anyhow::Result<T> + Send + 'static,
+    {
+        let token = CancellationToken::new();
+        let (tx, rx) = mpsc::channel();
+        let task_token = token.clone();
+        let span = tracing::info_span!("task", name);
+        std::thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                let _span = span.entered();
+                let result = f(task_token.clone());
+                tx.send(result.map_err(|err| cancelled_error(&task_token, err)))
+                    .ok();
+            })
+            .expect("failed to spawn task thread");
+        Self {
+            name: name.to_string(),
+            token,
+            rx,
+        }
+    }
+}
+
+impl<T: 'static> Task<T> {
+    /// runs the future returned by `f` on the browser event loop
+    #[cfg(target_arch = "wasm32")]
+    pub fn spawn_local<F, Fut>(name: &str, f: F) -> Self
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<T>> + 'static,
+    {
+        let token = CancellationToken::new();
+        let (tx, rx) = mpsc::channel();
+        let task_token = token.clone();
+        let future = f(token.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = future.await;
+            tx.send(result.map_err(|err| cancelled_error(&task_token, err)))
+                .ok();
+        });
+        Self {
+            name: name.to_string(),
+            token,
+            rx,
+        }
+    }
+}
+
+impl<T> Task<T> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// returns the result once the task finished
+    pub fn poll(&self) -> Option<anyhow::Result<T>> {
+        match self.rx.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Some(Err(anyhow::anyhow!("task {} panicked", self.name)))
+            }
+        }
+    }
+}
+
+impl<T> Drop for Task<T> {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+/// errors of cancelled tasks are often io errors of a [CancellableReader] or wrapped by the loaders
+/// they are replaced with [Cancelled] so that callers can detect them with [is_cancelled]
+fn cancelled_error(token: &CancellationToken, err: anyhow::Error) -> anyhow::Error {
+    if token.is_cancelled() {
+        Cancelled.into()
+    } else {
+        err
+    }
+}
+
+pub fn is_cancelled(err: &anyhow::Error) -> bool {
+    err.is::<Cancelled>()
+}
@@ -0,0 +1,8 @@
+//! platform independent time types
+//! native targets use `std::time`, on the web `Instant` is based on `performance.now()`
+//! so update loops, animations and benchmarks behave the same on all targets
+
+#[cfg(target_arch = "wasm32")]
+pub use instant::{Duration, Instant};
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::{Duration, Instant};
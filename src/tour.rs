@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+
+use cgmath::{Deg, EuclideanSpace, Point3, Quaternion, Rotation3};
+use serde::{Deserialize, Serialize};
+
+use crate::math::{quaternion_to_array, QuaternionOrder};
+
+/// cube faces in the order used by most panorama viewers (e.g. pannellum)
+pub const CUBE_FACES: [&str; 6] = ["front", "right", "back", "left", "up", "down"];
+
+/// rotation from the station camera to the camera of a cube face
+/// cameras look along +z with y pointing down, so the up face is a rotation of -90° around x
+pub fn face_rotation(face: usize) -> Quaternion<f32> {
+    match face {
+        0 => Quaternion::from_angle_y(Deg(0.)),
+        1 => Quaternion::from_angle_y(Deg(-90.)),
+        2 => Quaternion::from_angle_y(Deg(180.)),
+        3 => Quaternion::from_angle_y(Deg(90.)),
+        4 => Quaternion::from_angle_x(Deg(-90.)),
+        _ => Quaternion::from_angle_x(Deg(90.)),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Station {
+    pub name: String,
+    pub position: [f32; 3],
+    /// world to camera rotation of the front face (scalar last)
+    pub rotation: [f32; 4],
+    /// image files relative to the tour directory, same order as [CUBE_FACES]
+    pub faces: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    stations: Vec<Station>,
+}
+
+/// a virtual tour: a set of cubemap panoramas ("stations") with their poses
+/// stored as `tour.json` and one directory with the face images per station
+pub struct Tour {
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+impl Tour {
+    pub fn default_dir() -> PathBuf {
+        PathBuf::from("tour")
+    }
+
+    /// opens the tour in `dir`, new stations are appended to existing ones
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        let manifest_file = dir.join("tour.json");
+        let manifest = if manifest_file.exists() {
+            serde_json::from_reader(std::fs::File::open(manifest_file)?)?
+        } else {
+            Manifest::default()
+        };
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            manifest,
+        })
+    }
+
+    pub fn stations(&self) -> &[Station] {
+        &self.manifest.stations
+    }
+
+    /// saves the face images (same order as [CUBE_FACES]) and updates `tour.json`
+    /// returns the directory of the new station
+    pub fn add_station(
+        &mut self,
+        position: Point3<f32>,
+        rotation: Quaternion<f32>,
+        faces: &[image::RgbaImage],
+    ) -> anyhow::Result<PathBuf> {
+        let name = format!("station-{}", self.manifest.stations.len());
+        let station_dir = self.dir.join(&name);
+        std::fs::create_dir_all(&station_dir)?;
+        let mut files = Vec::with_capacity(faces.len());
+        for (img, face) in faces.iter().zip(CUBE_FACES) {
+            let file = PathBuf::from(&name).join(format!("{face}.png"));
+            img.save(self.dir.join(&file))?;
+            files.push(file);
+        }
+        self.manifest.stations.push(Station {
+            name,
+            position: position.to_vec().into(),
+            rotation: quaternion_to_array(rotation, QuaternionOrder::Xyzw),
+            faces: files,
+        });
+        let manifest_file = std::fs::File::create(self.dir.join("tour.json"))?;
+        serde_json::to_writer_pretty(manifest_file, &self.manifest)?;
+        Ok(station_dir)
+    }
+}
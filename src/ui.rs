@@ -1,13 +1,19 @@
-#[cfg(target_arch = "wasm32")]
-use instant::Duration;
-use std::ops::RangeInclusive;
-#[cfg(not(target_arch = "wasm32"))]
-use std::time::Duration;
+use std::{f32::consts::FRAC_PI_2, ops::RangeInclusive};
 
+use crate::renderer::{
+    ColorGrading, ColorSpace, Fog, FogFalloff, GlareFilter, RenderMode, RenderSettings,
+    Tonemapping, Transparency, DEFAULT_KERNEL_SIZE, GAUSSIAN_SCALING_RANGE, MAX_DOF_APERTURE,
+};
 #[cfg(not(target_arch = "wasm32"))]
-use crate::renderer::DEFAULT_KERNEL_SIZE;
-use crate::{SceneCamera, Split, WindowContext};
-use cgmath::{Euler, Matrix3, Quaternion};
+use crate::PresentMode;
+use crate::{
+    camera::Camera, io::CropRegion, keymap, pointcloud::Aabb, time::Duration, Action,
+    DynamicResolution, FeedMode, SceneCamera, Split, Stereo, StereoMode, WindowContext,
+    MAX_GRID_VIEWS, RENDER_SCALE_RANGE,
+};
+use cgmath::{
+    Angle, EuclideanSpace, Euler, InnerSpace, Matrix, Matrix3, Quaternion, Rad, Vector2, Vector3,
+};
 #[cfg(not(target_arch = "wasm32"))]
 use egui::Vec2b;
 
@@ -20,19 +26,135 @@ use egui::{emath::Numeric, Color32, RichText};
 #[cfg(not(target_arch = "wasm32"))]
 use egui_plot::{Legend, PlotPoints};
 
-pub(crate) fn ui(state: &mut WindowContext) -> bool {
-    let ctx = state.ui_renderer.winit.egui_ctx();
-    #[cfg(not(target_arch = "wasm32"))]
+/// reads the gpu timings of the last frame into the history
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn take_measurements(state: &mut WindowContext) {
     if let Some(stopwatch) = state.stopwatch.as_mut() {
         let durations = pollster::block_on(
             stopwatch.take_measurements(&state.wgpu_context.device, &state.wgpu_context.queue),
         );
-        state.history.push((
+        let timings = (
             *durations.get("preprocess").unwrap_or(&Duration::ZERO),
             *durations.get("sorting").unwrap_or(&Duration::ZERO),
             *durations.get("rasterization").unwrap_or(&Duration::ZERO),
-        ));
+        );
+        tracing::trace!(
+            preprocess_ms = timings.0.as_secs_f32() * 1000.,
+            sort_ms = timings.1.as_secs_f32() * 1000.,
+            rasterize_ms = timings.2.as_secs_f32() * 1000.,
+            "gpu timings"
+        );
+        state.history.push(timings);
     }
+}
+
+/// lists all key bindings, generated from the key map
+pub(crate) fn help(state: &mut WindowContext) {
+    let ctx = state.ui_renderer.winit.egui_ctx();
+    let mut open = true;
+    egui::Window::new("⌨ Keyboard Shortcuts")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0., 0.])
+        .show(ctx, |ui| {
+            egui::Grid::new("key bindings")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (action, _) in state.keys.bindings() {
+                        if let Some(shortcut) = state.keys.shortcut(action) {
+                            ui.strong(shortcut);
+                            ui.label(action.description());
+                            ui.end_row();
+                        }
+                    }
+                    ui.strong(keymap::key_name(keymap::HELP_KEY));
+                    ui.label(Action::ToggleHelp.description());
+                    ui.end_row();
+                    for (keys, description) in keymap::FIXED_BINDINGS {
+                        ui.strong(*keys);
+                        ui.label(*description);
+                        ui.end_row();
+                    }
+                });
+        });
+    if !open {
+        state.help_visible = false;
+    }
+}
+
+/// small overlay with frame rate, frame times and splat count
+pub(crate) fn hud(state: &mut WindowContext) {
+    let ctx = state.ui_renderer.winit.egui_ctx();
+    egui::Area::new(egui::Id::new("hud"))
+        .anchor(egui::Align2::RIGHT_TOP, [-10., 10.])
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                egui::Grid::new("hud_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("FPS");
+                    ui.label(format!("{:.0}", state.fps));
+                    ui.end_row();
+                    ui.label("CPU");
+                    ui.label(format!("{:.2} ms", state.cpu_frame_time));
+                    ui.end_row();
+                    // gpu timings are only available if the adapter supports timestamp queries
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some((pre, sort, rast)) = state
+                        .stopwatch
+                        .is_some()
+                        .then(|| state.history.last())
+                        .flatten()
+                    {
+                        for (label, d) in
+                            [("Preprocess", pre), ("Sorting", sort), ("Rasterize", rast)]
+                        {
+                            ui.label(label);
+                            ui.label(format!("{:.2} ms", d.as_secs_f32() * 1000.));
+                            ui.end_row();
+                        }
+                    }
+                    ui.label("Splats");
+                    ui.label(format_thousands(state.pc.num_points()));
+                    ui.end_row();
+                    let arena = state.frame_arena_stats();
+                    ui.label("Frame Arena");
+                    ui.label(format!(
+                        "{} / {}",
+                        format_bytes(arena.used as u64),
+                        format_bytes(arena.capacity as u64)
+                    ))
+                    .on_hover_text(format!(
+                        "peak {}, {} overflow allocations in the last frame",
+                        format_bytes(arena.peak as u64),
+                        arena.overflows
+                    ));
+                    ui.end_row();
+                });
+            });
+        });
+}
+
+/// caption of the playing camera path at the bottom of the window
+pub(crate) fn captions(state: &mut WindowContext) {
+    let Some(text) = state.current_caption() else {
+        return;
+    };
+    let ctx = state.ui_renderer.winit.egui_ctx();
+    egui::Area::new(egui::Id::new("caption"))
+        .anchor(egui::Align2::CENTER_BOTTOM, [0., -40.])
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.set_max_width(ctx.screen_rect().width() * 0.6);
+                ui.label(RichText::new(text).size(20.));
+            });
+        });
+}
+
+pub(crate) fn ui(state: &mut WindowContext) -> bool {
+    let ctx = state.ui_renderer.winit.egui_ctx();
 
     #[cfg(not(target_arch = "wasm32"))]
     let num_drawn = pollster::block_on(
@@ -58,10 +180,11 @@ pub(crate) fn ui(state: &mut WindowContext) -> bool {
                     (num_drawn as f32 / state.pc.num_points() as f32) * 100.
                 ));
             });
-            let history = state.history.to_vec();
-            let pre: Vec<f32> = history.iter().map(|v| v.0.as_secs_f32() * 1000.).collect();
-            let sort: Vec<f32> = history.iter().map(|v| v.1.as_secs_f32() * 1000.).collect();
-            let rast: Vec<f32> = history.iter().map(|v| v.2.as_secs_f32() * 1000.).collect();
+            let arena = &state.frame_arena;
+            let history = &state.history;
+            let pre = arena.alloc_from_iter(history.iter().map(|v| v.0.as_secs_f32() * 1000.));
+            let sort = arena.alloc_from_iter(history.iter().map(|v| v.1.as_secs_f32() * 1000.));
+            let rast = arena.alloc_from_iter(history.iter().map(|v| v.2.as_secs_f32() * 1000.));
 
             ui.label("Frame times (ms):");
             egui_plot::Plot::new("frame times")
@@ -81,39 +204,180 @@ pub(crate) fn ui(state: &mut WindowContext) -> bool {
                 )
                 .show(ui, |ui| {
                     let line =
-                        egui_plot::Line::new(PlotPoints::from_ys_f32(&pre)).name("preprocess");
+                        egui_plot::Line::new(PlotPoints::from_ys_f32(pre)).name("preprocess");
                     ui.line(line);
-                    let line = egui_plot::Line::new(PlotPoints::from_ys_f32(&sort)).name("sorting");
+                    let line = egui_plot::Line::new(PlotPoints::from_ys_f32(sort)).name("sorting");
                     ui.line(line);
                     let line =
-                        egui_plot::Line::new(PlotPoints::from_ys_f32(&rast)).name("rasterize");
+                        egui_plot::Line::new(PlotPoints::from_ys_f32(rast)).name("rasterize");
                     ui.line(line);
                 });
         });
 
+    let mut apply_hint = None;
+    let hints = if state.hints_dismissed {
+        Vec::new()
+    } else {
+        state.applicable_performance_hints()
+    };
+    if !hints.is_empty() {
+        let num_splats = state.num_drawn_splats();
+        egui::Window::new("⚠ Performance")
+            .default_width(300.)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} splats may render slowly on this GPU. Suggested settings:",
+                    format_thousands(num_splats)
+                ));
+                for hint in &hints {
+                    ui.horizontal(|ui| {
+                        let button = ui.button("Apply");
+                        let button = if hint.needs_reload() {
+                            button.on_hover_text("Reloads the point cloud")
+                        } else {
+                            button
+                        };
+                        if button.clicked() {
+                            apply_hint = Some(*hint);
+                        }
+                        ui.label(hint.description());
+                    });
+                }
+                if ui.button("Dismiss").clicked() {
+                    state.hints_dismissed = true;
+                }
+            });
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut present_mode = state.present_mode;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut save_config = false;
+    let mut transparent = state.transparent;
+    let mut render_scale = state.render_scale;
+    let mut dynamic_resolution = state.dynamic_resolution();
+    let mut stereo_changed = None;
+    #[cfg(all(feature = "openxr", target_arch = "wasm32"))]
+    let mut toggle_xr = false;
+    let mut grid_cameras = None;
     egui::Window::new("⚙ Render Settings").show(ctx, |ui| {
         egui::Grid::new("render_settings")
             .num_columns(2)
             .striped(true)
             .show(ui, |ui| {
-                ui.label("Gaussian Scaling");
+                ui.label("Gaussian Scaling").on_hover_text(
+                    "Scales all splats, smaller splats show the structure, larger splats fill holes",
+                );
                 ui.add(
-                    egui::DragValue::new(&mut state.splatting_args.gaussian_scaling)
-                        .range((1e-4)..=1.)
-                        .clamp_to_range(true)
-                        .speed(1e-2),
+                    egui::Slider::new(
+                        &mut state.splatting_args.gaussian_scaling,
+                        GAUSSIAN_SCALING_RANGE,
+                    )
+                    .logarithmic(true)
+                    .suffix("×"),
+                );
+                ui.end_row();
+                ui.label("Render Scale").on_hover_text(
+                    "Resolution relative to the window, values above 100% supersample the image",
+                );
+                ui.add(
+                    egui::Slider::new(&mut render_scale, RENDER_SCALE_RANGE)
+                        .custom_formatter(|v, _| format!("{:.0}%", v * 100.)),
+                );
+                ui.end_row();
+                ui.label("Dynamic Resolution").on_hover_text(
+                    "Lower the render scale while the camera moves fast, \
+                     the full resolution is restored when the camera stops",
                 );
+                ui.horizontal(|ui| {
+                    let mut enabled = dynamic_resolution.is_some();
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        dynamic_resolution = enabled.then(DynamicResolution::default);
+                    }
+                    if let Some(settings) = &mut dynamic_resolution {
+                        ui.add(
+                            egui::Slider::new(&mut settings.motion_scale, 0.25..=1.0)
+                                .custom_formatter(|v, _| format!("{:.0}%", v * 100.)),
+                        );
+                    }
+                });
+                ui.end_row();
+                ui.label("Opacity Threshold").on_hover_text(
+                    "Hide splats and splat fragments that are more transparent (e.g. floaters)",
+                );
+                ui.add(egui::Slider::new(
+                    &mut state.splatting_args.opacity_threshold,
+                    0.0..=1.0,
+                ));
+                ui.end_row();
+                ui.label("Render Mode").on_hover_text(
+                    "Inspect the shape of the splats, the depth or the overdraw of the scene",
+                );
+                egui::ComboBox::from_id_source("render_mode")
+                    .selected_text(state.splatting_args.render_mode.to_string())
+                    .show_ui(ui, |ui| {
+                        for m in RenderMode::ALL {
+                            ui.selectable_value(
+                                &mut state.splatting_args.render_mode,
+                                m,
+                                m.to_string(),
+                            );
+                        }
+                    });
+                ui.end_row();
+                ui.label("Transparency").on_hover_text(
+                    "Weighted blended and stochastic skip the sorting of the splats, \
+                     stochastic converges to the sorted result when the camera stops",
+                );
+                egui::ComboBox::from_id_source("transparency")
+                    .selected_text(state.splatting_args.transparency.to_string())
+                    .show_ui(ui, |ui| {
+                        for t in Transparency::ALL {
+                            ui.selectable_value(
+                                &mut state.splatting_args.transparency,
+                                t,
+                                t.to_string(),
+                            );
+                        }
+                    });
                 ui.end_row();
-                ui.label("Directional Color");
-                let mut dir_color = state.splatting_args.max_sh_deg > 0;
+                if let Some(stats) = state
+                    .pc_stats
+                    .as_ref()
+                    .filter(|_| state.splatting_args.render_mode.is_ellipsoid())
+                {
+                    let max = stats.largest_scales.len().max(1);
+                    ui.label("Largest Splats")
+                        .on_hover_text("Only draw this many of the largest splats");
+                    ui.horizontal(|ui| {
+                        let mut enabled = state.largest_splats.is_some();
+                        ui.checkbox(&mut enabled, "");
+                        let mut n = state.largest_splats.unwrap_or(max.min(1000));
+                        ui.add_enabled(
+                            enabled,
+                            egui::Slider::new(&mut n, 1..=max).logarithmic(true),
+                        );
+                        state.largest_splats = enabled.then_some(n);
+                    });
+                    ui.end_row();
+                }
+                ui.label("SH Degree").on_hover_text(
+                    "Highest spherical harmonics degree that is evaluated, 0 disables view dependent colors",
+                );
                 ui.add_enabled(
                     state.pc.sh_deg() > 0,
-                    egui::Checkbox::new(&mut dir_color, ""),
+                    egui::Slider::new(
+                        &mut state.splatting_args.max_sh_deg,
+                        0..=state.pc.sh_deg(),
+                    ),
                 );
-                state.splatting_args.max_sh_deg = if dir_color { state.pc.sh_deg() } else { 0 };
 
                 ui.end_row();
-                let enable_bg = !state.splatting_args.show_env_map && !state.display.has_env_map();
+                if state.display.has_env_map() {
+                    ui.label("Skybox");
+                    ui.checkbox(&mut state.splatting_args.show_env_map, "");
+                    ui.end_row();
+                }
+                let enable_bg = !state.splatting_args.show_env_map;
                 ui.add_enabled(enable_bg, egui::Label::new("Background Color"));
                 let mut color = egui::Color32::from_rgba_premultiplied(
                     (state.splatting_args.background_color.r*255.) as u8,
@@ -121,12 +385,16 @@ pub(crate) fn ui(state: &mut WindowContext) -> bool {
                     (state.splatting_args.background_color.b*255.) as u8,
                     (state.splatting_args.background_color.a*255.) as u8,
                 );
-                ui.add_enabled_ui(enable_bg, |ui| {
-                    egui::color_picker::color_edit_button_srgba(
-                        ui,
-                        &mut color,
-                        egui::color_picker::Alpha::BlendOrAdditive,
-                    )
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(enable_bg && !state.transparent, |ui| {
+                        egui::color_picker::color_edit_button_srgba(
+                            ui,
+                            &mut color,
+                            egui::color_picker::Alpha::BlendOrAdditive,
+                        )
+                    });
+                    ui.checkbox(&mut transparent, "Transparent")
+                        .on_hover_text("Alpha zero background for compositing over other windows or web pages");
                 });
 
                 let color32 = color.to_normalized_gamma_f32();
@@ -136,36 +404,471 @@ pub(crate) fn ui(state: &mut WindowContext) -> bool {
                 state.splatting_args.background_color.a = color32[3] as f64;
 
                 ui.end_row();
-                #[cfg(not(target_arch = "wasm32"))]
-                {
-                    ui.label("Dilation Kernel Size");
-                    optional_drag(
+                ui.label("Fog").on_hover_text("Blend distant splats towards the fog color");
+                let max_distance = (state.pc.bbox().radius() * 4.).max(1.);
+                let mut enabled = state.splatting_args.fog.is_some();
+                if ui.checkbox(&mut enabled, "").changed() {
+                    state.splatting_args.fog = enabled.then(|| {
+                        Fog::linear(
+                            state.splatting_args.background_color,
+                            max_distance / 4.,
+                            max_distance / 2.,
+                        )
+                    });
+                }
+                ui.end_row();
+                if let Some(fog) = &mut state.splatting_args.fog {
+                    let exponential = matches!(fog.falloff, FogFalloff::Exponential { .. });
+                    ui.label("Fog Falloff");
+                    egui::ComboBox::from_id_source("fog_falloff")
+                        .selected_text(if exponential { "Exponential" } else { "Linear" })
+                        .show_ui(ui, |ui| {
+                            if ui.selectable_label(!exponential, "Linear").clicked()
+                                && exponential
+                            {
+                                fog.falloff = FogFalloff::Linear {
+                                    start: max_distance / 4.,
+                                    end: max_distance / 2.,
+                                };
+                            }
+                            if ui.selectable_label(exponential, "Exponential").clicked()
+                                && !exponential
+                            {
+                                fog.falloff = FogFalloff::Exponential {
+                                    density: 4. / max_distance,
+                                };
+                            }
+                        });
+                    ui.end_row();
+                    match &mut fog.falloff {
+                        FogFalloff::Linear { start, end } => {
+                            ui.label("Fog Start");
+                            ui.add(egui::Slider::new(start, 0.0..=max_distance));
+                            ui.end_row();
+                            ui.label("Fog End");
+                            ui.add(egui::Slider::new(end, 0.0..=max_distance));
+                            ui.end_row();
+                        }
+                        FogFalloff::Exponential { density } => {
+                            ui.label("Fog Density");
+                            ui.add(egui::Slider::new(density, 1e-3..=10.0).logarithmic(true));
+                            ui.end_row();
+                        }
+                    }
+                    ui.label("Fog Color")
+                        .on_hover_text("A transparent fog color fades the splats out");
+                    let c = fog.color;
+                    let mut color = egui::Color32::from_rgba_unmultiplied(
+                        (c.r * 255.) as u8,
+                        (c.g * 255.) as u8,
+                        (c.b * 255.) as u8,
+                        (c.a * 255.) as u8,
+                    );
+                    if egui::color_picker::color_edit_button_srgba(
                         ui,
-                        &mut state.splatting_args.kernel_size,
-                        Some(0.0..=10.0),
-                        Some(0.1),
-                        Some(
-                            state
-                                .pc
-                                .dilation_kernel_size()
-                                .unwrap_or(DEFAULT_KERNEL_SIZE),
-                        ),
+                        &mut color,
+                        egui::color_picker::Alpha::OnlyBlend,
+                    )
+                    .changed()
+                    {
+                        let [r, g, b, a] = color.to_srgba_unmultiplied().map(|v| v as f64 / 255.);
+                        fog.color = wgpu::Color { r, g, b, a };
+                    }
+                    ui.end_row();
+                }
+                ui.label("Dilation Kernel Size")
+                    .on_hover_text("Size of the 2D screen space filter in pixels");
+                optional_drag(
+                    ui,
+                    &mut state.splatting_args.kernel_size,
+                    Some(0.0..=10.0),
+                    Some(0.1),
+                    Some(
+                        state
+                            .pc
+                            .dilation_kernel_size()
+                            .unwrap_or(DEFAULT_KERNEL_SIZE),
+                    ),
+                );
+                ui.end_row();
+                ui.label("Mip Splatting").on_hover_text(
+                    "Use the 2D mip filter instead of the dilation to avoid aliasing in zoomed out views",
+                );
+                optional_checkbox(
+                    ui,
+                    &mut state.splatting_args.mip_splatting,
+                    state.pc.mip_splatting().unwrap_or(false),
+                );
+                ui.end_row();
+                if let Some(feed) = &mut state.feed {
+                    ui.label("Feed Opacity");
+                    let mut opacity = feed.opacity();
+                    let opacity_changed =
+                        ui.add(egui::Slider::new(&mut opacity, 0.0..=1.0)).changed();
+                    ui.end_row();
+                    ui.label("Feed Mode");
+                    let mut mode = feed.mode;
+                    egui::ComboBox::from_id_source("feed_mode")
+                        .selected_text(format!("{mode:?}"))
+                        .show_ui(ui, |ui| {
+                            for m in [FeedMode::Behind, FeedMode::Front, FeedMode::Depth] {
+                                ui.selectable_value(&mut mode, m, format!("{m:?}"));
+                            }
+                        });
+                    ui.end_row();
+                    if opacity_changed || mode != feed.mode {
+                        feed.set_opacity(&state.wgpu_context.queue, opacity);
+                        feed.mode = mode;
+                        // force a redraw of the scene
+                        state.splatting_args.resolution = Vector2::new(0, 0);
+                    }
+                }
+                ui.label("Tone Mapping");
+                let mut tonemapping = state.display.tonemapping();
+                egui::ComboBox::from_id_source("tonemapping")
+                    .selected_text(tonemapping.to_string())
+                    .show_ui(ui, |ui| {
+                        for t in Tonemapping::ALL {
+                            ui.selectable_value(&mut tonemapping, t, t.to_string());
+                        }
+                    });
+                ui.end_row();
+                if tonemapping != state.display.tonemapping() {
+                    state
+                        .display
+                        .set_tonemapping(&state.wgpu_context.queue, tonemapping);
+                }
+                ui.label("Blending").on_hover_text("Color space in which the splats are blended");
+                let mut color_space = state.splatting_args.color_space;
+                color_space_combo(ui, "blending", &mut color_space);
+                ui.end_row();
+                if color_space != state.splatting_args.color_space {
+                    state.splatting_args.color_space = color_space;
+                    // force a redraw of the scene
+                    state.splatting_args.resolution = Vector2::new(0, 0);
+                }
+                ui.label("Output").on_hover_text("Transfer function of the presented colors");
+                let mut output = state.display.output_color_space();
+                color_space_combo(ui, "output", &mut output);
+                ui.end_row();
+                if output != state.display.output_color_space() {
+                    state
+                        .display
+                        .set_output_color_space(&state.wgpu_context.queue, output);
+                }
+                if state.exposure.is_none() {
+                    ui.label("Exposure");
+                    let mut ev = state.display.exposure().log2();
+                    if ui
+                        .add(egui::Slider::new(&mut ev, -8.0..=8.0).suffix(" EV"))
+                        .changed()
+                    {
+                        state
+                            .display
+                            .set_exposure(&state.wgpu_context.queue, ev.exp2());
+                    }
+                    ui.end_row();
+                }
+                if let Some(exposure) = &mut state.exposure {
+                    ui.label("Exposure");
+                    ui.label(format!("{:+.2} EV", exposure.exposure().log2()));
+                    ui.end_row();
+                    let settings = &mut exposure.settings;
+                    ui.label("Exposure Compensation");
+                    ui.add(egui::Slider::new(&mut settings.compensation, -4.0..=4.0).suffix(" EV"));
+                    ui.end_row();
+                    ui.label("Adaption Speed");
+                    ui.add(egui::Slider::new(&mut settings.speed, 0.1..=10.0).logarithmic(true));
+                    ui.end_row();
+                    ui.label("Min Exposure");
+                    ui.add(
+                        egui::Slider::new(&mut settings.min_ev, -16.0..=settings.max_ev)
+                            .suffix(" EV"),
                     );
                     ui.end_row();
-                    ui.label("Mip Splatting");
-                    optional_checkbox(
-                        ui,
-                        &mut state.splatting_args.mip_splatting,
-                        state.pc.mip_splatting().unwrap_or(false),
+                    ui.label("Max Exposure");
+                    ui.add(
+                        egui::Slider::new(&mut settings.max_ev, settings.min_ev..=16.0)
+                            .suffix(" EV"),
                     );
                     ui.end_row();
                 }
+                ui.label("Color Grading").on_hover_text(
+                    "Correct models that are too dark or have a color cast",
+                );
+                let mut grading = state.display.color_grading();
+                if ui.button("Reset").clicked() {
+                    grading = ColorGrading::default();
+                }
+                ui.end_row();
+                ui.label("Gamma");
+                ui.add(egui::Slider::new(&mut grading.gamma, 0.2..=5.0).logarithmic(true));
+                ui.end_row();
+                ui.label("Saturation");
+                ui.add(egui::Slider::new(&mut grading.saturation, 0.0..=2.0));
+                ui.end_row();
+                ui.label("Temperature")
+                    .on_hover_text("White balance, cooler (blue) to warmer (orange)");
+                ui.add(egui::Slider::new(&mut grading.temperature, -1.0..=1.0));
+                ui.end_row();
+                ui.label("Tint")
+                    .on_hover_text("White balance, green to magenta");
+                ui.add(egui::Slider::new(&mut grading.tint, -1.0..=1.0));
+                ui.end_row();
+                if grading != state.display.color_grading() {
+                    state
+                        .display
+                        .set_color_grading(&state.wgpu_context.queue, grading);
+                }
+                ui.label("Glare Filter").on_hover_text(
+                    "Darken splats that are much brighter from this direction than from others",
+                );
+                let mut enabled = state.splatting_args.glare_filter.is_some();
+                if ui.checkbox(&mut enabled, "").changed() {
+                    state.splatting_args.glare_filter = enabled.then(GlareFilter::default);
+                }
+                ui.end_row();
+                if let Some(filter) = &mut state.splatting_args.glare_filter {
+                    ui.label("Glare Ratio");
+                    ui.add(egui::Slider::new(&mut filter.ratio, 1.0..=20.0).logarithmic(true));
+                    ui.end_row();
+                    ui.label("Glare Min Luminance");
+                    ui.add(egui::Slider::new(&mut filter.min_luminance, 0.0..=4.0));
+                    ui.end_row();
+                }
+                ui.label("Depth of Field")
+                    .on_hover_text("Blur the parts of the scene that are not in focus");
+                let mut dof = state.display.depth_of_field();
+                let mut enabled = dof.is_some();
+                let toggled = ui.checkbox(&mut enabled, "").changed();
+                ui.end_row();
+                let mut changed = toggled;
+                if let Some(dof) = &mut dof {
+                    let max_distance = (state.pc.bbox().radius() * 4.).max(1.);
+                    ui.label("Focus Distance");
+                    changed |= ui
+                        .add(
+                            egui::Slider::new(&mut dof.focus_distance, 0.01..=max_distance)
+                                .logarithmic(true),
+                        )
+                        .changed();
+                    ui.end_row();
+                    ui.label("Aperture").on_hover_text("Blur radius of distant objects");
+                    changed |= ui
+                        .add(
+                            egui::Slider::new(&mut dof.aperture, 1.0..=MAX_DOF_APERTURE)
+                                .suffix(" px"),
+                        )
+                        .changed();
+                    ui.end_row();
+                }
+                if changed {
+                    let dof = enabled.then(|| dof.unwrap_or_default());
+                    state
+                        .display
+                        .set_depth_of_field(&state.wgpu_context.queue, dof);
+                }
+                if toggled {
+                    // the expected depth is rendered with the scene
+                    state.splatting_args.resolution = Vector2::new(0, 0);
+                }
+                ui.label("Bloom")
+                    .on_hover_text("Glow around bright regions");
+                let mut bloom = state.display.bloom();
+                let mut enabled = bloom.is_some();
+                let mut changed = ui.checkbox(&mut enabled, "").changed();
+                ui.end_row();
+                if let Some(bloom) = &mut bloom {
+                    ui.label("Bloom Intensity");
+                    changed |= ui
+                        .add(egui::Slider::new(&mut bloom.intensity, 0.01..=1.0).logarithmic(true))
+                        .changed();
+                    ui.end_row();
+                    ui.label("Bloom Threshold")
+                        .on_hover_text("Colors darker than the threshold do not glow");
+                    changed |= ui
+                        .add(egui::Slider::new(&mut bloom.threshold, 0.0..=4.0))
+                        .changed();
+                    ui.end_row();
+                }
+                if changed {
+                    let bloom = enabled.then(|| bloom.unwrap_or_default());
+                    state.display.set_bloom(&state.wgpu_context.queue, bloom);
+                    // the bloom is recorded with the scene
+                    state.splatting_args.resolution = Vector2::new(0, 0);
+                }
+                ui.label("Vignette")
+                    .on_hover_text("Darken the image towards the corners");
+                let mut vignette = state.display.vignette();
+                if ui
+                    .add(egui::Slider::new(&mut vignette, 0.0..=1.0))
+                    .changed()
+                {
+                    state
+                        .display
+                        .set_vignette(&state.wgpu_context.queue, vignette);
+                }
+                ui.end_row();
+                ui.label("Sharpening")
+                    .on_hover_text("Contrast adaptive sharpening of the rendered image");
+                let mut sharpening = state.display.sharpening();
+                if ui
+                    .add(egui::Slider::new(&mut sharpening, 0.0..=1.0))
+                    .changed()
+                {
+                    state
+                        .display
+                        .set_sharpening(&state.wgpu_context.queue, sharpening);
+                }
+                ui.end_row();
+                ui.label("Stereo")
+                    .on_hover_text("Render a left and a right eye view");
+                let mut enabled = state.stereo.is_some();
+                if ui.checkbox(&mut enabled, "").changed() {
+                    stereo_changed = Some(enabled.then(Stereo::default));
+                }
+                ui.end_row();
+                if let Some(mut stereo) = state.stereo.as_ref().map(|s| s.stereo()) {
+                    ui.label("Stereo Mode");
+                    egui::ComboBox::from_id_source("stereo_mode")
+                        .selected_text(stereo.mode.to_string())
+                        .show_ui(ui, |ui| {
+                            for mode in StereoMode::ALL {
+                                ui.selectable_value(&mut stereo.mode, mode, mode.to_string());
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Eye Distance")
+                        .on_hover_text("Distance between the eyes in scene units");
+                    let max_ipd = (state.pc.bbox().radius() * 0.1).max(0.1);
+                    ui.add(egui::Slider::new(&mut stereo.ipd, 0.0..=max_ipd));
+                    ui.end_row();
+                    #[cfg(all(feature = "openxr", target_arch = "wasm32"))]
+                    {
+                        ui.label("VR")
+                            .on_hover_text("Show the stereo view in a VR headset (WebXR)");
+                        let label = if state.xr.is_some() { "Exit" } else { "Enter" };
+                        toggle_xr = ui.button(label).clicked();
+                        ui.end_row();
+                    }
+                    if Some(stereo) != state.stereo.as_ref().map(|s| s.stereo()) {
+                        stereo_changed = Some(Some(stereo));
+                    }
+                }
+                let num_cameras = state.scene.as_ref().map_or(0, |scene| scene.num_cameras());
+                if num_cameras > 0 {
+                    ui.label("Viewport Grid")
+                        .on_hover_text("Show the current view next to the views of scene cameras");
+                    let mut enabled = state.grid.is_some();
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        // the scene cameras after the current one
+                        let first = state.current_view.map_or(0, |i| i + 1);
+                        let cameras = (0..num_cameras.min(MAX_GRID_VIEWS - 1))
+                            .map(|i| (first + i) % num_cameras)
+                            .filter(|_| enabled)
+                            .collect();
+                        grid_cameras = Some(cameras);
+                    }
+                    ui.end_row();
+                }
+                if let Some(mut cameras) = state.grid.as_ref().map(|grid| grid.cameras().to_vec()) {
+                    ui.label("Grid Views");
+                    let mut num_views = cameras.len() + 1;
+                    ui.add(egui::Slider::new(&mut num_views, 2..=MAX_GRID_VIEWS));
+                    ui.end_row();
+                    cameras.truncate(num_views - 1);
+                    while cameras.len() + 1 < num_views {
+                        let next = cameras.last().map_or(0, |c| (c + 1) % num_cameras.max(1));
+                        cameras.push(next);
+                    }
+                    for (i, camera) in cameras.iter_mut().enumerate() {
+                        ui.label(format!("View {} Camera", i + 2));
+                        ui.add(
+                            egui::DragValue::new(camera)
+                                .range(0..=num_cameras.saturating_sub(1))
+                                .clamp_to_range(true),
+                        );
+                        ui.end_row();
+                    }
+                    if state.grid.as_ref().is_some_and(|grid| grid.cameras() != cameras) {
+                        grid_cameras = Some(cameras);
+                    }
+                }
+                if let Some(lod) = state.pc.lod_mut() {
+                    ui.label("Level of Detail").on_hover_text(
+                        "Draw distant parts of the scene with fewer, merged splats",
+                    );
+                    let mut changed = ui.checkbox(&mut lod.enabled, "").changed();
+                    ui.end_row();
+                    if lod.enabled {
+                        ui.label("LOD Threshold").on_hover_text(
+                            "Octree nodes smaller than this on screen are drawn with their merged splats",
+                        );
+                        changed |= ui
+                            .add(
+                                egui::Slider::new(&mut lod.threshold, 1.0..=1024.0)
+                                    .logarithmic(true)
+                                    .suffix(" px"),
+                            )
+                            .changed();
+                        ui.end_row();
+                    }
+                    if changed {
+                        state.splatting_args.resolution = Vector2::new(0, 0);
+                    }
+                }
+                if state.density_grid.is_some() {
+                    ui.label("Zoom Collision").on_hover_text(
+                        "Stop zooming in before the camera passes through a surface",
+                    );
+                    ui.checkbox(&mut state.controller.zoom_collision, "");
+                    ui.end_row();
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    ui.label("Present Mode").on_hover_text(
+                        "Without v-sync the frame rate is not limited to the refresh rate \
+                         (mailbox and immediate render as fast as possible)",
+                    );
+                    egui::ComboBox::from_id_source("present_mode")
+                        .selected_text(present_mode.to_string())
+                        .show_ui(ui, |ui| {
+                            for m in PresentMode::ALL {
+                                ui.selectable_value(&mut present_mode, m, m.to_string());
+                            }
+                        });
+                    ui.end_row();
+                }
+                ui.label("Frame Rate Cap")
+                    .on_hover_text("Limit the frame rate, e.g. to save battery");
+                ui.horizontal(|ui| {
+                    let mut enabled = state.max_fps.is_some();
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        state.max_fps = enabled.then_some(30.);
+                    }
+                    if let Some(max_fps) = &mut state.max_fps {
+                        ui.add(egui::Slider::new(max_fps, 10.0..=240.0).suffix(" fps"));
+                    }
+                });
+                ui.end_row();
             });
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui
+            .button("Save as Default")
+            .on_hover_text(
+                "Store background color, window size, zoom collision and frame rate settings \
+                 in the config file",
+            )
+            .clicked()
+        {
+            save_config = true;
+        }
     });
 
     let mut new_camera: Option<SetCamera> = None;
     #[allow(unused_mut)]
     let mut toggle_tracking_shot = false;
+    let mut suggested_view = None;
     egui::Window::new("ℹ Scene")
         .default_width(200.)
         .resizable(true)
@@ -178,6 +881,14 @@ pub(crate) fn ui(state: &mut WindowContext) -> bool {
                     ui.strong("Gaussians:");
                     ui.label(format_thousands(state.pc.num_points()));
                     ui.end_row();
+                    if state.pc.num_loaded() < state.pc.num_points() {
+                        ui.strong("Loaded:");
+                        ui.label(format!(
+                            "{:.0}%",
+                            state.pc.num_loaded() as f32 / state.pc.num_points() as f32 * 100.
+                        ));
+                        ui.end_row();
+                    }
                     ui.strong("SH Degree:");
                     ui.label(state.pc.sh_deg().to_string());
                     ui.end_row();
@@ -202,6 +913,25 @@ pub(crate) fn ui(state: &mut WindowContext) -> bool {
                             .unwrap_or("-".to_string()),
                     );
                     ui.end_row();
+                    if let Some(metadata) = &state.metadata {
+                        for (label, value) in [
+                            ("Title:", &metadata.title),
+                            ("Author:", &metadata.author),
+                            ("License:", &metadata.license),
+                            ("Source:", &metadata.source),
+                        ] {
+                            if let Some(value) = value {
+                                ui.strong(label);
+                                ui.add(egui::Label::new(value).truncate());
+                                ui.end_row();
+                            }
+                        }
+                        if let Some(description) = &metadata.description {
+                            ui.strong("Description:");
+                            ui.add(egui::Label::new(description).wrap());
+                            ui.end_row();
+                        }
+                    }
                     if let Some(path) = &state.pointcloud_file_path {
                         ui.strong("File:");
                         let text = path.to_string_lossy().to_string();
@@ -215,6 +945,30 @@ pub(crate) fn ui(state: &mut WindowContext) -> bool {
                     ui.end_row();
                 });
 
+            if !state.suggested_views.is_empty() || state.suggesting_views.is_some() {
+                ui.separator();
+                ui.collapsing("Suggested Views", |ui| {
+                    if state.suggesting_views.is_some() {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("finding good views...");
+                        });
+                    }
+                    for (i, view) in state.suggested_views.iter().enumerate() {
+                        if ui
+                            .button(format!(
+                                "View {} ({:.0}% coverage)",
+                                i + 1,
+                                view.coverage * 100.
+                            ))
+                            .clicked()
+                        {
+                            suggested_view = Some(i);
+                        }
+                    }
+                });
+            }
+
             if let Some(scene) = &state.scene {
                 let nearest = scene.nearest_camera(state.splatting_args.camera.position, None);
                 ui.separator();
@@ -256,61 +1010,6 @@ pub(crate) fn ui(state: &mut WindowContext) -> bool {
                             }
                         });
 
-                    egui::ScrollArea::vertical()
-                        .max_height(300.)
-                        .show(ui, |ui| {
-                            let cameras = scene.cameras(None);
-                            let cameras2 = cameras.clone();
-                            let curr_view = state.current_view;
-                            egui::Grid::new("scene views grid")
-                                .num_columns(4)
-                                .striped(true)
-                                .with_row_color(move |idx, _| {
-                                    if let Some(view_id) = curr_view {
-                                        if idx < cameras.len() && (&cameras)[idx].id == view_id {
-                                            return Some(Color32::from_gray(64));
-                                        }
-                                    }
-                                    return None;
-                                })
-                                .min_col_width(50.)
-                                .show(ui, |ui| {
-                                    let style = ui.style().clone();
-                                    for c in cameras2 {
-                                        ui.colored_label(
-                                            style.visuals.strong_text_color(),
-                                            c.id.to_string(),
-                                        );
-                                        ui.colored_label(
-                                            match c.split {
-                                                Split::Train => Color32::DARK_GREEN,
-                                                Split::Test => Color32::LIGHT_GREEN,
-                                            },
-                                            c.split.to_string(),
-                                        )
-                                        .on_hover_text(
-                                            RichText::new(format!(
-                                                "{:#?}",
-                                                Euler::from(Quaternion::from(Matrix3::from(
-                                                    c.rotation
-                                                )))
-                                            )),
-                                        );
-
-                                        let resp =
-                                            ui.add(egui::Label::new(c.img_name.clone()).truncate());
-                                        if let Some(view_id) = curr_view {
-                                            if c.id == view_id {
-                                                resp.scroll_to_me(None);
-                                            }
-                                        }
-                                        if ui.button("🎥").clicked() {
-                                            new_camera = Some(SetCamera::ID(c.id));
-                                        }
-                                        ui.end_row();
-                                    }
-                                });
-                        });
                     if let Some(nearest) = nearest {
                         ui.separator();
                         if ui.button(format!("Snap to closest ({nearest})")).clicked() {
@@ -321,6 +1020,139 @@ pub(crate) fn ui(state: &mut WindowContext) -> bool {
             }
         });
 
+    if let Some(stats) = &state.pc_stats {
+        egui::Window::new("📊 Statistics")
+            .default_open(false)
+            .resizable(true)
+            .show(ctx, |ui| {
+                egui::Grid::new("point cloud stats")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Gaussians:");
+                        ui.label(format_thousands(stats.num_points as u32));
+                        ui.end_row();
+                        ui.strong("SH Degree:");
+                        ui.label(stats.sh_deg.to_string());
+                        ui.end_row();
+                        ui.strong("Compressed:");
+                        ui.label(stats.compressed.to_string());
+                        ui.end_row();
+                        ui.strong("CPU Memory:");
+                        ui.label(format_bytes(stats.cpu_memory as u64));
+                        ui.end_row();
+                        ui.strong("GPU Memory:");
+                        ui.label(format_bytes(state.pc.gpu_memory()));
+                        ui.end_row();
+                        let size = stats.aabb.size();
+                        ui.strong("Extent:");
+                        ui.label(format!("{:.2} × {:.2} × {:.2}", size.x, size.y, size.z));
+                        ui.end_row();
+                        ui.strong("Bounds:");
+                        ui.label(format!(
+                            "({:.2}, {:.2}, {:.2}) - ({:.2}, {:.2}, {:.2})",
+                            stats.aabb.min.x,
+                            stats.aabb.min.y,
+                            stats.aabb.min.z,
+                            stats.aabb.max.x,
+                            stats.aabb.max.y,
+                            stats.aabb.max.z
+                        ));
+                        ui.end_row();
+                        ui.strong("Median Scale:");
+                        ui.label(format!("{:.2e}", stats.median_scale));
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                ui.label("Opacity");
+                let bin_width = 1. / stats.opacity_histogram.len() as f64;
+                let bars = stats
+                    .opacity_histogram
+                    .iter()
+                    .enumerate()
+                    .map(|(i, n)| {
+                        egui_plot::Bar::new((i as f64 + 0.5) * bin_width, *n as f64)
+                            .width(bin_width)
+                    })
+                    .collect();
+                histogram_plot(ui, "opacity histogram", egui_plot::BarChart::new(bars));
+
+                ui.label("Scale");
+                let bars = stats
+                    .scale_histogram
+                    .iter()
+                    .map(|(lower, n)| {
+                        egui_plot::Bar::new(lower.log10() as f64 + 0.5, *n as f64)
+                            .width(1.)
+                            .name(format!("≥ {:.0e}", lower))
+                    })
+                    .collect();
+                histogram_plot(ui, "scale histogram", egui_plot::BarChart::new(bars));
+            });
+    }
+
+    if let Some(scene) = &state.scene {
+        egui::Window::new("🎥 Cameras")
+            .default_width(300.)
+            .resizable(true)
+            .show(ctx, |ui| {
+                let filter_id = egui::Id::new("camera filter");
+                let mut filter =
+                    ctx.data_mut(|d| d.get_temp::<String>(filter_id).unwrap_or_default());
+                ui.horizontal(|ui| {
+                    ui.label("🔍");
+                    ui.text_edit_singleline(&mut filter);
+                });
+                let cameras: Vec<SceneCamera> = scene
+                    .cameras(None)
+                    .into_iter()
+                    .filter(|c| {
+                        filter.is_empty()
+                            || c.img_name.contains(filter.as_str())
+                            || c.id.to_string() == filter
+                    })
+                    .collect();
+                ctx.data_mut(|d| d.insert_temp(filter_id, filter));
+                ui.separator();
+
+                // only the visible rows are laid out, so scenes with thousands of images stay fast
+                let row_height =
+                    ui.text_style_height(&egui::TextStyle::Body) + ui.spacing().item_spacing.y;
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, true])
+                    .max_height(400.)
+                    .show_rows(ui, row_height, cameras.len(), |ui, rows| {
+                        for c in &cameras[rows] {
+                            ui.horizontal(|ui| {
+                                ui.add_sized(
+                                    [40., row_height],
+                                    egui::Label::new(RichText::new(c.id.to_string()).strong()),
+                                );
+                                let selected = state.current_view == Some(c.id);
+                                let resp = ui
+                                    .selectable_label(selected, &c.img_name)
+                                    .on_hover_text(format!(
+                                        "{} split\n{:#?}",
+                                        c.split.to_string(),
+                                        Euler::from(Quaternion::from(Matrix3::from(c.rotation)))
+                                    ));
+                                if resp.clicked() {
+                                    new_camera = Some(SetCamera::ID(c.id));
+                                }
+                                ui.colored_label(
+                                    match c.split {
+                                        Split::Train => Color32::DARK_GREEN,
+                                        Split::Test => Color32::LIGHT_GREEN,
+                                    },
+                                    format!("{}×{}", c.width, c.height),
+                                );
+                            });
+                        }
+                    });
+            });
+    }
+
     #[cfg(target_arch = "wasm32")]
     egui::Window::new("🎮")
         .default_width(200.)
@@ -376,9 +1208,225 @@ pub(crate) fn ui(state: &mut WindowContext) -> bool {
                     ui.label("Start/Pause Tracking shot");
                     ui.label("T");
                     ui.end_row();
+                    ui.label("Play/Pause Camera Path");
+                    ui.label("P");
+                    ui.end_row();
+                    ui.label("Seek Camera Path");
+                    ui.label(", / .");
+                    ui.end_row();
+                });
+        });
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut menu_action: Option<Action> = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let native_menu = state.has_native_menu();
+    #[cfg(not(target_arch = "wasm32"))]
+    egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+        let keys = &state.keys;
+        // menu entry that shows the key bound to the action
+        let mut item = |ui: &mut egui::Ui, label: &str, action: Action| {
+            let mut button = egui::Button::new(label);
+            if let Some(shortcut) = keys.shortcut(action) {
+                button = button.shortcut_text(shortcut);
+            }
+            let response = ui.add(button);
+            if response.clicked() {
+                menu_action = Some(action);
+                ui.close_menu();
+            }
+            response
+        };
+        egui::menu::bar(ui, |ui| {
+            // the menus are shown by the native menu bar if there is one
+            if !native_menu {
+                ui.menu_button("File", |ui| {
+                    item(ui, "Open…", Action::Open);
+                    item(ui, "Reload", Action::Reload);
+                    item(ui, "Gallery", Action::ToggleGallery);
+                });
+                ui.menu_button("View", |ui| {
+                    ui.checkbox(&mut state.ui_visible, "User Interface");
+                    ui.checkbox(&mut state.hud_visible, "HUD");
+                    ui.checkbox(&mut state.help_visible, "Keyboard Shortcuts");
+                    ui.separator();
+                    item(ui, "Rear View", Action::ToggleInset);
+                    item(ui, "Fullscreen", Action::ToggleFullscreen);
+                    ui.separator();
+                    item(ui, "Next Camera", Action::NextCamera);
+                    item(ui, "Previous Camera", Action::PreviousCamera);
+                    item(ui, "Nearest Camera", Action::NearestCamera);
+                    ui.separator();
+                    item(ui, "Tracking Shot", Action::ToggleTrackingShot);
+                    item(ui, "Camera Path", Action::ToggleCameraPath);
+                });
+                ui.menu_button("Export", |ui| {
+                    item(ui, "Screenshot", Action::Screenshot);
+                    item(ui, "Capture Panorama", Action::CapturePanorama).on_hover_text(
+                        "adds a cubemap at the camera position to the virtual tour in ./tour",
+                    );
+                    ui.separator();
+                    item(ui, "Save View", Action::SaveView);
+                    item(ui, "Save View to Scene", Action::SaveViewToScene);
+                });
+            }
+            if let Some(task) = &state.loading {
+                ui.separator();
+                ui.spinner();
+                ui.label(task.name());
+                if ui.small_button("Cancel").clicked() {
+                    task.cancel();
+                }
+            }
+        });
+    });
+
+    let mut open_entry: Option<crate::GalleryEntry> = None;
+    if state.gallery_visible {
+        let mut open = true;
+        egui::Window::new("🖼 Gallery")
+            .open(&mut open)
+            .default_width(560.)
+            .show(ctx, |ui| {
+                let entries = state.gallery.entries().to_vec();
+                if entries.is_empty() {
+                    ui.label("No recently opened scenes");
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(400.)
+                    .show(ui, |ui| {
+                        ui.horizontal_wrapped(|ui| {
+                            for entry in entries {
+                                ui.vertical(|ui| {
+                                    ui.set_width(128.);
+                                    let clicked = match state.gallery.thumbnail_texture(ctx, &entry)
+                                    {
+                                        Some(texture) => {
+                                            let size = texture.size_vec2()
+                                                * (128. / texture.size_vec2().x);
+                                            ui.add(egui::ImageButton::new(
+                                                egui::load::SizedTexture::new(texture.id(), size),
+                                            ))
+                                            .clicked()
+                                        }
+                                        None => ui
+                                            .add_sized(
+                                                egui::Vec2::new(128., 72.),
+                                                egui::Button::new("no preview"),
+                                            )
+                                            .clicked(),
+                                    };
+                                    ui.add(egui::Label::new(entry.name()).truncate())
+                                        .on_hover_text(entry.pointcloud.to_string_lossy());
+                                    if clicked {
+                                        open_entry = Some(entry.clone());
+                                    }
+                                });
+                            }
+                        });
+                    });
+            });
+        state.gallery_visible = open;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut seek_frame: Option<usize> = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(sequence) = &mut state.sequence {
+        egui::Window::new("⏯ Sequence")
+            .default_width(400.)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let label = if sequence.playing { "⏸" } else { "▶" };
+                    if ui.button(label).clicked() {
+                        sequence.playing = !sequence.playing;
+                    }
+                    let mut frame = sequence.current_frame();
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut frame, 0..=sequence.num_frames() - 1)
+                                .text("frame"),
+                        )
+                        .changed()
+                    {
+                        sequence.playing = false;
+                        seek_frame = Some(frame);
+                    }
                 });
+                ui.add(
+                    egui::DragValue::new(&mut sequence.fps)
+                        .range(1. ..=120.)
+                        .suffix(" fps"),
+                );
+            });
+    }
+
+    if let Some(sync) = &mut state.sync {
+        egui::Window::new("👥 Sync").show(ctx, |ui| {
+            if sync.presenter() {
+                ui.label("presenting");
+            } else {
+                let mut follow = !sync.detached();
+                if ui.checkbox(&mut follow, "follow presenter").changed() {
+                    sync.set_detached(!follow);
+                }
+            }
+        });
+    }
+
+    let mut partition_visible: Option<Vec<bool>> = None;
+    if let Some(partitions) = state.partitions.as_ref().filter(|p| p.parts.len() > 1) {
+        egui::Window::new("🧩 Partitions")
+            .default_open(false)
+            .show(ctx, |ui| {
+                let mut visible = state.partition_visible.clone();
+                egui::Grid::new("partitions")
+                    .striped(true)
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        for (i, part) in partitions.parts.iter().enumerate() {
+                            let size = part.aabb.size();
+                            ui.checkbox(&mut visible[i], format!("Part {}", i + 1))
+                                .on_hover_text(format!(
+                                    "{:.1} × {:.1} × {:.1}",
+                                    size.x, size.y, size.z
+                                ));
+                            ui.label(format!("{} splats", part.num_splats));
+                            if ui.button("Solo").clicked() {
+                                visible = (0..visible.len()).map(|j| j == i).collect();
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if ui.button("Show All").clicked() {
+                    visible.fill(true);
+                }
+                if visible != state.partition_visible {
+                    partition_visible = Some(visible);
+                }
+            });
+    }
+
+    egui::Window::new("✂ Clipping")
+        .default_open(false)
+        .show(ctx, |ui| {
+            clipping(ui, &mut state.splatting_args, *state.pc.bbox())
         });
 
+    let mut inset_open = true;
+    if let Some((inset, id)) = &state.inset {
+        let size = inset.size();
+        egui::Window::new("🔙 Rear View")
+            .open(&mut inset_open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.image(egui::load::SizedTexture::new(
+                    *id,
+                    egui::Vec2::new(size.x as f32, size.y as f32) / ctx.pixels_per_point(),
+                ));
+            });
+    }
+
     let requested_repaint = ctx.has_requested_repaint();
 
     if let Some(c) = new_camera {
@@ -387,6 +1435,66 @@ pub(crate) fn ui(state: &mut WindowContext) -> bool {
             SetCamera::Camera(c) => state.set_camera(c, Duration::from_millis(200)),
         }
     }
+    #[cfg(not(target_arch = "wasm32"))]
+    if save_config {
+        if let Err(err) = state.save_user_config() {
+            log::error!("failed to save config: {:?}", err);
+        }
+    }
+    if let Some(i) = suggested_view {
+        state.set_suggested_view(i);
+    }
+    if let Some(hint) = apply_hint {
+        if let Err(err) = state.apply_performance_hint(hint) {
+            log::error!("failed to apply {:?}: {:?}", hint, err);
+        }
+    }
+    if transparent != state.transparent {
+        state.set_transparent(transparent);
+    }
+    if render_scale != state.render_scale {
+        state.set_render_scale(render_scale);
+    }
+    if let Some(stereo) = stereo_changed {
+        state.set_stereo(stereo);
+    }
+    #[cfg(all(feature = "openxr", target_arch = "wasm32"))]
+    if toggle_xr {
+        // dropping the viewer ends the session
+        if state.xr.take().is_none() {
+            state.enter_xr();
+        }
+    }
+    if let Some(cameras) = grid_cameras {
+        state.set_grid(cameras);
+    }
+    if dynamic_resolution != state.dynamic_resolution() {
+        state.set_dynamic_resolution(dynamic_resolution);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if present_mode != state.present_mode {
+        state.set_present_mode(present_mode);
+    }
+    if !inset_open {
+        state.set_inset_visible(false);
+    }
+    if let Some(visible) = partition_visible {
+        state.set_partition_visibility(visible);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(frame) = seek_frame {
+        state.seek_sequence(frame);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(action) = menu_action {
+        state.run_action(action);
+    }
+    if let Some(entry) = open_entry {
+        #[cfg(not(target_arch = "wasm32"))]
+        state.open_pointcloud(entry.pointcloud, entry.scene, false);
+        #[cfg(target_arch = "wasm32")]
+        crate::open_page(&entry.pointcloud, entry.scene.as_deref());
+    }
     if toggle_tracking_shot {
         if let Some((_animation, playing)) = &mut state.animation {
             *playing = !*playing;
@@ -404,6 +1512,110 @@ enum SetCamera {
 }
 
 /// 212312321 -> 212.312.321
+/// clip planes and crop box that cut away parts of the scene, e.g. walls in front of an interior
+fn clipping(ui: &mut egui::Ui, args: &mut RenderSettings, bbox: Aabb<f32>) {
+    let center = bbox.center().to_vec();
+    let radius = bbox.radius().max(1e-3);
+    egui::Grid::new("clipping")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            for (i, slot) in args.clip_planes.iter_mut().enumerate() {
+                let Some(plane) = slot else {
+                    continue;
+                };
+                let normal = plane.truncate();
+                // direction of the normal and position of the plane relative to the scene center
+                let mut yaw = Rad::atan2(normal.z, normal.x);
+                let mut pitch = Rad::asin(normal.y.clamp(-1., 1.));
+                let mut offset = -(plane.w + normal.dot(center));
+                ui.label(format!("Plane {}", i + 1));
+                let mut remove = false;
+                let changed = ui
+                    .horizontal(|ui| {
+                        let mut changed = ui
+                            .drag_angle(&mut yaw.0)
+                            .on_hover_text("Azimuth of the normal")
+                            .changed();
+                        changed |= ui
+                            .drag_angle(&mut pitch.0)
+                            .on_hover_text("Elevation of the normal")
+                            .changed();
+                        changed |= ui
+                            .add(egui::Slider::new(&mut offset, -radius..=radius))
+                            .changed();
+                        if ui.button("⇄").on_hover_text("Flip the plane").clicked() {
+                            yaw += Rad::turn_div_2();
+                            pitch = -pitch;
+                            offset = -offset;
+                            changed = true;
+                        }
+                        remove = ui.button("🗑").clicked();
+                        changed
+                    })
+                    .inner;
+                ui.end_row();
+                if changed {
+                    let pitch = Rad(pitch.0.clamp(-FRAC_PI_2, FRAC_PI_2));
+                    let n = Vector3::new(
+                        pitch.cos() * yaw.cos(),
+                        pitch.sin(),
+                        pitch.cos() * yaw.sin(),
+                    );
+                    *plane = n.extend(-(n.dot(center) + offset));
+                }
+                if remove {
+                    *slot = None;
+                }
+            }
+        });
+    let free_slot = args.clip_planes.iter().position(|p| p.is_none());
+    if ui
+        .add_enabled(free_slot.is_some(), egui::Button::new("Add Plane"))
+        .on_hover_text("Cut away everything in front of a plane through the scene center")
+        .clicked()
+    {
+        // faces away from the camera and cuts away the splats in front of the center
+        let view = args.camera.view_matrix();
+        let forward = view.row(2).truncate().normalize();
+        args.clip_planes[free_slot.unwrap()] = Some(forward.extend(-forward.dot(center)));
+    }
+    ui.separator();
+    let mut enabled = args.crop_box.is_some();
+    if ui.checkbox(&mut enabled, "Crop Box").changed() {
+        args.crop_box = enabled.then(|| CropRegion::from_aabb(bbox.min, bbox.max));
+    }
+    if let Some(crop) = &mut args.crop_box {
+        egui::Grid::new("crop box").num_columns(2).show(ui, |ui| {
+            ui.label("Center");
+            ui.horizontal(|ui| {
+                for v in [&mut crop.center.x, &mut crop.center.y, &mut crop.center.z] {
+                    ui.add(egui::DragValue::new(v).speed(radius * 0.01));
+                }
+            });
+            ui.end_row();
+            ui.label("Size");
+            ui.horizontal(|ui| {
+                let half_size = &mut crop.half_size;
+                for v in [&mut half_size.x, &mut half_size.y, &mut half_size.z] {
+                    let mut size = *v * 2.;
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut size)
+                                .speed(radius * 0.01)
+                                .range(1e-3..=f32::INFINITY),
+                        )
+                        .changed()
+                    {
+                        *v = size / 2.;
+                    }
+                }
+            });
+            ui.end_row();
+        });
+    }
+}
+
 fn format_thousands(n: u32) -> String {
     let mut n = n;
     let mut result = String::new();
@@ -419,6 +1631,34 @@ fn format_thousands(n: u32) -> String {
     result
 }
 
+/// 1536 -> 1.50 KiB
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024. && unit < UNITS.len() - 1 {
+        size /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.2} {}", size, UNITS[unit])
+    }
+}
+
+/// small non interactive bar chart, the x axis of the scale histogram is log10
+fn histogram_plot(ui: &mut egui::Ui, id: &str, chart: egui_plot::BarChart) {
+    egui_plot::Plot::new(id)
+        .height(100.)
+        .allow_drag(false)
+        .allow_boxed_zoom(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .show_y(false)
+        .show(ui, |plot_ui| plot_ui.bar_chart(chart));
+}
+
 #[allow(unused)]
 fn optional_drag<T: Numeric>(
     ui: &mut egui::Ui,
@@ -478,3 +1718,13 @@ fn optional_checkbox(ui: &mut egui::Ui, opt: &mut Option<bool>, default: bool) {
         *opt = Some(val);
     }
 }
+
+fn color_space_combo(ui: &mut egui::Ui, id: &str, color_space: &mut ColorSpace) {
+    egui::ComboBox::from_id_source(id)
+        .selected_text(color_space.to_string())
+        .show_ui(ui, |ui| {
+            for c in ColorSpace::ALL {
+                ui.selectable_value(color_space, c, c.to_string());
+            }
+        });
+}
@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::keymap::KeyMap;
+
+/// surface present mode, see [wgpu::PresentMode]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresentMode {
+    #[default]
+    AutoVsync,
+    AutoNoVsync,
+    Fifo,
+    Mailbox,
+    Immediate,
+}
+
+impl PresentMode {
+    pub const ALL: [PresentMode; 5] = [
+        PresentMode::AutoVsync,
+        PresentMode::AutoNoVsync,
+        PresentMode::Fifo,
+        PresentMode::Mailbox,
+        PresentMode::Immediate,
+    ];
+
+    /// frames are presented at the vertical blank, so the frame rate is limited to the refresh rate
+    pub fn is_vsync(self) -> bool {
+        matches!(self, PresentMode::AutoVsync | PresentMode::Fifo)
+    }
+}
+
+impl std::fmt::Display for PresentMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PresentMode::AutoVsync => "V-Sync",
+            PresentMode::AutoNoVsync => "No V-Sync",
+            PresentMode::Fifo => "Fifo",
+            PresentMode::Mailbox => "Mailbox",
+            PresentMode::Immediate => "Immediate",
+        })
+    }
+}
+
+impl std::str::FromStr for PresentMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "vsync" | "auto-vsync" => Ok(Self::AutoVsync),
+            "no-vsync" | "auto-no-vsync" => Ok(Self::AutoNoVsync),
+            "fifo" => Ok(Self::Fifo),
+            "mailbox" => Ok(Self::Mailbox),
+            "immediate" => Ok(Self::Immediate),
+            _ => Err(anyhow::anyhow!(
+                "unknown present mode '{}', expected vsync, no-vsync, fifo, mailbox or immediate",
+                s
+            )),
+        }
+    }
+}
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(mode: PresentMode) -> Self {
+        match mode {
+            PresentMode::AutoVsync => wgpu::PresentMode::AutoVsync,
+            PresentMode::AutoNoVsync => wgpu::PresentMode::AutoNoVsync,
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+        }
+    }
+}
+
+/// persistent user settings stored in `~/.config/web-splat/config.toml`
+/// command line arguments take precedence over the values in the file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserConfig {
+    /// camera movement speed
+    pub controller_speed: f32,
+    /// mouse and keyboard rotation sensitivity
+    pub controller_sensitivity: f32,
+    /// stop zooming in before the camera passes through dense regions of the point cloud
+    pub zoom_collision: bool,
+    /// initial window size in pixels
+    pub window_size: Option<[u32; 2]>,
+    pub present_mode: PresentMode,
+    /// frame rate cap in frames per second, e.g. to save battery, no cap if not set
+    pub max_fps: Option<f32>,
+    /// rgba clear color
+    pub background_color: Option<[f64; 4]>,
+    pub keys: KeyMap,
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            controller_speed: 0.1,
+            controller_sensitivity: 0.05,
+            zoom_collision: true,
+            window_size: None,
+            present_mode: PresentMode::default(),
+            max_fps: None,
+            background_color: None,
+            keys: KeyMap::default(),
+        }
+    }
+}
+
+impl UserConfig {
+    pub fn default_path() -> PathBuf {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+            .unwrap_or_else(std::env::temp_dir)
+            .join("web-splat")
+            .join("config.toml")
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// loads the config file or writes the default config if there is none yet
+    pub fn load_or_create(path: &Path) -> Self {
+        if path.exists() {
+            match Self::load(path) {
+                Ok(config) => return config,
+                Err(err) => log::error!("failed to load config {:?}: {:?}", path, err),
+            }
+            return Self::default();
+        }
+        let config = Self::default();
+        if let Err(err) = config.save(path) {
+            log::warn!("failed to write default config {:?}: {:?}", path, err);
+        }
+        config
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn background_color(&self) -> Option<wgpu::Color> {
+        self.background_color
+            .map(|[r, g, b, a]| wgpu::Color { r, g, b, a })
+    }
+}
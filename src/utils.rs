@@ -1,12 +1,17 @@
+use crate::time::Duration;
 use cgmath::{BaseFloat, Matrix, Matrix3, Quaternion, SquareMatrix, Vector3};
-use std::time::Duration;
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
 use std::{fmt::Debug, mem::MaybeUninit};
+#[cfg(feature = "ui")]
 use winit::keyboard::KeyCode;
 
 use std::{collections::HashMap, mem::size_of};
 
+pub mod math;
+pub mod simd;
+
+#[cfg(feature = "ui")]
 pub fn key_to_num(key: KeyCode) -> Option<u32> {
     match key {
         KeyCode::Digit0 => Some(0),
@@ -133,7 +138,7 @@ impl GPUStopwatch {
     }
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
 #[derive(Debug)]
 pub struct RingBuffer<T: Copy> {
     index: usize,
@@ -141,7 +146,7 @@ pub struct RingBuffer<T: Copy> {
     container: Box<[MaybeUninit<T>]>,
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
 impl<T> RingBuffer<T>
 where
     T: Copy + Debug,
@@ -160,22 +165,22 @@ where
         self.size = (self.size + 1).clamp(0, self.container.len());
     }
 
-    pub fn to_vec(&self) -> Vec<T> {
+    /// items from oldest to newest
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = T> + '_ {
         let start = self
             .index
             .checked_sub(self.size)
             .unwrap_or(self.container.len() - (self.size - self.index));
-        self.container
-            .iter()
-            .cycle()
-            .skip(start)
-            .take(self.size)
-            .map(|c| unsafe { c.assume_init() })
-            .collect()
+        (0..self.size).map(move |i| unsafe {
+            self.container[(start + i) % self.container.len()].assume_init()
+        })
+    }
+
+    pub fn last(&self) -> Option<T> {
+        self.iter().last()
     }
 }
 
-#[cfg(feature = "npz")]
 pub fn sh_num_coefficients(sh_deg: u32) -> u32 {
     (sh_deg + 1) * (sh_deg + 1)
 }
@@ -210,3 +215,106 @@ pub fn sigmoid(x: f32) -> f32 {
         x.exp() / (1. + x.exp())
     }
 }
+
+/// converts premultiplied colors (as produced by the splat blending) to straight alpha
+/// the result is a matte that can be composited in other tools
+pub fn unpremultiply(img: &mut image::RgbaImage) {
+    for p in img.pixels_mut() {
+        let a = p[3] as f32 / 255.;
+        if a > 0. {
+            for c in 0..3 {
+                p[c] = (p[c] as f32 / a).round().min(255.) as u8;
+            }
+        }
+    }
+}
+
+/// copies a texture to the cpu and converts it to an 8 bit rgba image
+/// supports Rgba8Unorm(Srgb) and Rgba16Float textures
+pub async fn download_texture(
+    texture: &wgpu::Texture,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> anyhow::Result<image::RgbaImage> {
+    let texture_format = texture.format();
+    let texel_size: u32 = texture_format.block_copy_size(None).ok_or(anyhow::anyhow!(
+        "unsupported texture format {:?}",
+        texture_format
+    ))?;
+    let fb_size = texture.size();
+    let align: u32 = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT - 1;
+    let bytes_per_row = (texel_size * fb_size.width) + align & !align;
+
+    let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("texture download buffer"),
+        size: (bytes_per_row * fb_size.height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("download texture encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &staging_buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(fb_size.height),
+            },
+        },
+        fb_size,
+    );
+    let sub_idx = queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = staging_buffer.slice(..);
+    let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| tx.send(result).unwrap());
+    device.poll(wgpu::Maintain::WaitForSubmissionIndex(sub_idx));
+    rx.receive()
+        .await
+        .ok_or(anyhow::anyhow!("texture download canceled"))??;
+
+    let pixels: Vec<u8> = {
+        let data = slice.get_mapped_range();
+        let rows = data.chunks(bytes_per_row as usize);
+        match texture_format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => rows
+                .flat_map(|row| row[..(fb_size.width * texel_size) as usize].to_vec())
+                .collect(),
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => rows
+                .flat_map(|row| {
+                    row[..(fb_size.width * texel_size) as usize]
+                        .chunks(4)
+                        .flat_map(|c| [c[2], c[1], c[0], c[3]])
+                        .collect::<Vec<u8>>()
+                })
+                .collect(),
+            wgpu::TextureFormat::Rgba16Float => rows
+                .flat_map(|row| {
+                    row[..(fb_size.width * texel_size) as usize]
+                        .chunks(2)
+                        .map(|c| {
+                            (half::f16::from_le_bytes([c[0], c[1]])
+                                .to_f32()
+                                .clamp(0., 1.)
+                                * 255.) as u8
+                        })
+                        .collect::<Vec<u8>>()
+                })
+                .collect(),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "unsupported texture format {:?}",
+                    texture_format
+                ))
+            }
+        }
+    };
+    staging_buffer.unmap();
+
+    image::RgbaImage::from_raw(fb_size.width, fb_size.height, pixels)
+        .ok_or(anyhow::anyhow!("invalid image size"))
+}
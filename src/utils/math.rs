@@ -0,0 +1,226 @@
+//! math helpers shared by culling, alignment and import code
+
+use cgmath::{
+    BaseFloat, InnerSpace, Matrix, Matrix3, Matrix4, Point3, Quaternion, SquareMatrix, Vector3,
+    Vector4,
+};
+
+use crate::pointcloud::Aabb;
+
+/// component order of quaternions stored as arrays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuaternionOrder {
+    /// scalar first (used by the 3D gaussian splatting ply files and colmap)
+    Wxyz,
+    /// scalar last (used by glTF, three.js and scipy)
+    Xyzw,
+}
+
+pub fn quaternion_from_array<T: BaseFloat>(q: [T; 4], order: QuaternionOrder) -> Quaternion<T> {
+    match order {
+        QuaternionOrder::Wxyz => Quaternion::new(q[0], q[1], q[2], q[3]),
+        QuaternionOrder::Xyzw => Quaternion::new(q[3], q[0], q[1], q[2]),
+    }
+}
+
+pub fn quaternion_to_array<T: BaseFloat>(q: Quaternion<T>, order: QuaternionOrder) -> [T; 4] {
+    match order {
+        QuaternionOrder::Wxyz => [q.s, q.v.x, q.v.y, q.v.z],
+        QuaternionOrder::Xyzw => [q.v.x, q.v.y, q.v.z, q.s],
+    }
+}
+
+/// converts a rotation matrix given as rows (row major, as in json files and numpy arrays)
+pub fn matrix_from_rows<T: BaseFloat>(rows: [[T; 3]; 3]) -> Matrix3<T> {
+    // Matrix3::from takes columns
+    Matrix3::from(rows).transpose()
+}
+
+/// rotation of a matrix that may contain scaling, the result is normalized
+pub fn quaternion_from_matrix<T: BaseFloat>(m: Matrix3<T>) -> Quaternion<T> {
+    Quaternion::from(Matrix3::from_cols(
+        m.x.normalize(),
+        m.y.normalize(),
+        m.z.normalize(),
+    ))
+    .normalize()
+}
+
+/// eigen decomposition of a covariance matrix (upper right half, see [crate::utils::build_cov])
+/// returns the rotation and the scale (square roots of the eigenvalues)
+pub fn decompose_cov(cov: [f32; 6]) -> (Quaternion<f32>, Vector3<f32>) {
+    let c = cov.map(|v| v as f64);
+    let mut a = [[c[0], c[1], c[2]], [c[1], c[3], c[4]], [c[2], c[4], c[5]]];
+    // eigenvectors (columns)
+    let mut v = [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+    // cyclic jacobi rotations, converges in a few sweeps for 3x3 matrices
+    for _ in 0..16 {
+        let off_diagonal = a[0][1].powi(2) + a[0][2].powi(2) + a[1][2].powi(2);
+        let diagonal = a[0][0].powi(2) + a[1][1].powi(2) + a[2][2].powi(2);
+        if off_diagonal <= 1e-24 * diagonal {
+            break;
+        }
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q] == 0. {
+                continue;
+            }
+            let theta = (a[q][q] - a[p][p]) / (2. * a[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.).sqrt());
+            let c = 1. / (t * t + 1.).sqrt();
+            let s = t * c;
+            for k in 0..3 {
+                let (akp, akq) = (a[k][p], a[k][q]);
+                a[k][p] = c * akp - s * akq;
+                a[k][q] = s * akp + c * akq;
+            }
+            for k in 0..3 {
+                let (apk, aqk) = (a[p][k], a[q][k]);
+                a[p][k] = c * apk - s * aqk;
+                a[q][k] = s * apk + c * aqk;
+            }
+            for row in v.iter_mut() {
+                let (vp, vq) = (row[p], row[q]);
+                row[p] = c * vp - s * vq;
+                row[q] = s * vp + c * vq;
+            }
+        }
+    }
+    let mut rotation = matrix_from_rows(v.map(|row| row.map(|x| x as f32)));
+    // the eigenvectors may form a reflection
+    if rotation.determinant() < 0. {
+        rotation.z = -rotation.z;
+    }
+    let scale = Vector3::new(a[0][0], a[1][1], a[2][2]).map(|l| l.max(0.).sqrt() as f32);
+    (Quaternion::from(rotation).normalize(), scale)
+}
+
+/// translation, rotation and scale of an affine transformation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trs<T: BaseFloat> {
+    pub translation: Vector3<T>,
+    pub rotation: Quaternion<T>,
+    pub scale: Vector3<T>,
+}
+
+impl<T: BaseFloat> Trs<T> {
+    /// decomposes a matrix without shear
+    /// a negative determinant (mirroring) is moved into the x scale
+    pub fn decompose(m: Matrix4<T>) -> Self {
+        let translation = m.w.truncate();
+        let mut scale = Vector3::new(
+            m.x.truncate().magnitude(),
+            m.y.truncate().magnitude(),
+            m.z.truncate().magnitude(),
+        );
+        let mut rot = Matrix3::from_cols(
+            m.x.truncate() / scale.x,
+            m.y.truncate() / scale.y,
+            m.z.truncate() / scale.z,
+        );
+        if rot.determinant() < T::zero() {
+            scale.x = -scale.x;
+            rot.x = -rot.x;
+        }
+        Self {
+            translation,
+            rotation: Quaternion::from(rot).normalize(),
+            scale,
+        }
+    }
+
+    pub fn to_matrix(&self) -> Matrix4<T> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+/// planes of a view frustum, each plane is stored as (normal, distance)
+/// points inside the frustum have a positive distance to all planes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    pub planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    /// extracts the planes from a projection * view matrix with a [0, 1] depth range (like wgpu)
+    pub fn from_matrix(m: Matrix4<f32>) -> Self {
+        let planes = [
+            m.row(3) + m.row(0), // left
+            m.row(3) - m.row(0), // right
+            m.row(3) + m.row(1), // bottom
+            m.row(3) - m.row(1), // top
+            m.row(2),            // near
+            m.row(3) - m.row(2), // far
+        ]
+        .map(|p| p / p.truncate().magnitude());
+        Self { planes }
+    }
+
+    pub fn contains_point(&self, p: Point3<f32>) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.dot(p.to_homogeneous()) >= 0.)
+    }
+
+    pub fn intersects_sphere(&self, center: Point3<f32>, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.dot(center.to_homogeneous()) >= -radius)
+    }
+
+    /// conservative test, may return true for boxes close to the frustum corners
+    pub fn intersects_aabb(&self, aabb: &Aabb<f32>) -> bool {
+        self.planes.iter().all(|plane| {
+            // corner that is furthest in the direction of the plane normal
+            let p = Point3::new(
+                if plane.x >= 0. {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if plane.y >= 0. {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if plane.z >= 0. {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+            plane.dot(p.to_homogeneous()) >= 0.
+        })
+    }
+}
+
+pub fn aabb_contains_point(aabb: &Aabb<f32>, p: Point3<f32>) -> bool {
+    p.x >= aabb.min.x
+        && p.y >= aabb.min.y
+        && p.z >= aabb.min.z
+        && p.x <= aabb.max.x
+        && p.y <= aabb.max.y
+        && p.z <= aabb.max.z
+}
+
+pub fn aabb_intersects_sphere(aabb: &Aabb<f32>, center: Point3<f32>, radius: f32) -> bool {
+    let closest = Point3::new(
+        center.x.clamp(aabb.min.x, aabb.max.x),
+        center.y.clamp(aabb.min.y, aabb.max.y),
+        center.z.clamp(aabb.min.z, aabb.max.z),
+    );
+    (closest - center).magnitude2() <= radius * radius
+}
+
+/// transforms the corners of the box and returns their bounding box
+pub fn transform_aabb(aabb: &Aabb<f32>, m: Matrix4<f32>) -> Aabb<f32> {
+    let corners = aabb
+        .corners()
+        .map(|c| Point3::from_homogeneous(m * c.to_homogeneous()));
+    let mut result = Aabb::new(corners[0], corners[0]);
+    for c in &corners[1..] {
+        result.grow(c);
+    }
+    result
+}
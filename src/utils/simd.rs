@@ -0,0 +1,94 @@
+//! vectorized cpu loops used while loading and ranking point clouds
+//! the loops use the portable 8 lane vectors of the `wide` crate (avx / sse / neon / simd128 depending on the target),
+//! float conversions use the f16c / fp16 instructions of the half crate if the cpu supports them
+//! see `benches/simd.rs` for a comparison with the scalar loops
+
+use cgmath::{Matrix4, Point3};
+use half::{f16, slice::HalfFloatSliceExt};
+use wide::{f32x8, i32x8, u32x8};
+
+use crate::pointcloud::Aabb;
+
+/// number of points processed at once
+const LANES: usize = 8;
+
+/// converts half precision points to single precision
+pub fn points_to_f32(points: impl Iterator<Item = Point3<f16>>) -> Vec<Point3<f32>> {
+    let half: Vec<Point3<f16>> = points.collect();
+    let mut result = vec![Point3::new(0f32, 0., 0.); half.len()];
+    bytemuck::cast_slice::<_, f16>(&half)
+        .convert_to_f32_slice(bytemuck::cast_slice_mut(&mut result));
+    result
+}
+
+/// converts single precision values to half precision
+pub fn f32_to_f16<const N: usize>(values: &[f32; N]) -> [f16; N] {
+    let mut result = [f16::ZERO; N];
+    result.convert_from_f32_slice(values);
+    result
+}
+
+/// x, y and z coordinates of 8 points
+fn transpose(chunk: &[Point3<f32>]) -> [f32x8; 3] {
+    let axis = |i: usize| f32x8::new(std::array::from_fn(|j| chunk[j][i]));
+    [axis(0), axis(1), axis(2)]
+}
+
+/// bounding box of the points or None if there are no points
+pub fn bounding_box(points: &[Point3<f32>]) -> Option<Aabb<f32>> {
+    let first = *points.first()?;
+    let mut min = [
+        f32x8::splat(first.x),
+        f32x8::splat(first.y),
+        f32x8::splat(first.z),
+    ];
+    let mut max = min;
+
+    let chunks = points.chunks_exact(LANES);
+    let rest = chunks.remainder();
+    for chunk in chunks {
+        for ((min, max), v) in min.iter_mut().zip(&mut max).zip(transpose(chunk)) {
+            *min = min.fast_min(v);
+            *max = max.fast_max(v);
+        }
+    }
+
+    let reduce = |lanes: [f32x8; 3], f: fn(f32, f32) -> f32| {
+        Point3::from(lanes.map(|axis| axis.to_array().into_iter().reduce(f).unwrap()))
+    };
+    let mut aabb = Aabb::new(reduce(min, f32::min), reduce(max, f32::max));
+    for p in rest {
+        aabb.grow(p);
+    }
+    Some(aabb)
+}
+
+/// sort keys for the view space depth of the points, sorting the keys ascending sorts the points front to back
+/// the keys are the bits of the depth with the order of negative floats flipped, so they compare like the floats
+pub fn depth_keys(points: &[Point3<f32>], view: &Matrix4<f32>) -> Vec<u32> {
+    // third row of the view matrix
+    let row = [view.x.z, view.y.z, view.z.z, view.w.z].map(f32x8::splat);
+    let depth =
+        |[x, y, z]: [f32x8; 3]| x.mul_add(row[0], y.mul_add(row[1], z.mul_add(row[2], row[3])));
+
+    let mut keys = Vec::with_capacity(points.len());
+    let chunks = points.chunks_exact(LANES);
+    let rest = chunks.remainder();
+    for chunk in chunks {
+        let bits: u32x8 = bytemuck::cast(depth(transpose(chunk)));
+        // all ones for negative depths, only the sign bit for positive ones
+        let sign: i32x8 = bytemuck::cast::<_, i32x8>(bits) >> 31;
+        let mask = bytemuck::cast::<_, u32x8>(sign) | u32x8::splat(1 << 31);
+        keys.extend_from_slice(&(bits ^ mask).to_array());
+    }
+    for p in rest {
+        let depth = p.x.mul_add(
+            view.x.z,
+            p.y.mul_add(view.y.z, p.z.mul_add(view.z.z, view.w.z)),
+        );
+        let bits = depth.to_bits();
+        let mask = ((bits as i32 >> 31) as u32) | (1 << 31);
+        keys.push(bits ^ mask);
+    }
+    keys
+}
@@ -0,0 +1,122 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+    time::{Duration, Instant},
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// time without changes after which a file is considered completely written
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// watches a single file for changes
+/// the parent directory is watched so that files replaced by a rename (as done by most exporters) are detected too
+#[cfg(feature = "ui")]
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher,
+    rx: Receiver<()>,
+    last_change: Option<Instant>,
+}
+
+#[cfg(feature = "ui")]
+impl FileWatcher {
+    pub fn new(path: &Path) -> anyhow::Result<Self> {
+        let file = path.canonicalize()?;
+        let dir = file
+            .parent()
+            .ok_or(anyhow::anyhow!("{:?} has no parent directory", file))?
+            .to_path_buf();
+        let (tx, rx) = channel();
+        let watched: PathBuf = file.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+                Ok(event) => {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                        && event.paths.iter().any(|p| p == &watched)
+                    {
+                        tx.send(()).ok();
+                    }
+                }
+                Err(err) => log::warn!("file watcher error: {err}"),
+            })?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        log::info!("watching {:?} for changes", file);
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+            last_change: None,
+        })
+    }
+
+    /// returns true if the file changed and no further changes occured for a short time
+    pub fn poll(&mut self) -> bool {
+        while self.rx.try_recv().is_ok() {
+            self.last_change = Some(Instant::now());
+        }
+        match self.last_change {
+            Some(t) if t.elapsed() > DEBOUNCE => {
+                self.last_change = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// watches a directory for new or changed files with one of the given extensions
+pub struct FolderWatcher {
+    _watcher: RecommendedWatcher,
+    rx: Receiver<PathBuf>,
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl FolderWatcher {
+    pub fn new(dir: &Path, extensions: &[&str]) -> anyhow::Result<Self> {
+        let dir = dir.canonicalize()?;
+        let extensions: Vec<String> = extensions.iter().map(|e| e.to_lowercase()).collect();
+        let (tx, rx) = channel();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+                Ok(event) => {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        for path in event.paths {
+                            let matches = path
+                                .extension()
+                                .map(|e| e.to_string_lossy().to_lowercase())
+                                .is_some_and(|e| extensions.contains(&e));
+                            if matches {
+                                tx.send(path).ok();
+                            }
+                        }
+                    }
+                }
+                Err(err) => log::warn!("folder watcher error: {err}"),
+            })?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        log::info!("watching {:?} for new files", dir);
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+            pending: HashMap::new(),
+        })
+    }
+
+    /// returns the files that changed and were not modified for a short time
+    /// large files are written over several seconds, so every change restarts the timer of the file
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        while let Ok(path) = self.rx.try_recv() {
+            self.pending.insert(path, Instant::now());
+        }
+        let ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, t)| t.elapsed() > DEBOUNCE)
+            .map(|(p, _)| p.clone())
+            .collect();
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        ready.into_iter().filter(|p| p.is_file()).collect()
+    }
+}
@@ -0,0 +1,99 @@
+use std::{path::Path, str::FromStr};
+
+use image::{imageops::FilterType, ImageBuffer, Pixel, RgbaImage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+}
+
+impl FromStr for WatermarkPosition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['-', '_'], "").as_str() {
+            "topleft" => Ok(Self::TopLeft),
+            "topright" => Ok(Self::TopRight),
+            "bottomleft" => Ok(Self::BottomLeft),
+            "bottomright" => Ok(Self::BottomRight),
+            "center" => Ok(Self::Center),
+            _ => Err(anyhow::anyhow!("unknown watermark position '{}'", s)),
+        }
+    }
+}
+
+/// logo that is composited onto exported images and video frames
+pub struct Watermark {
+    image: RgbaImage,
+    pub position: WatermarkPosition,
+    /// opacity in [0,1]
+    pub opacity: f32,
+    /// width of the watermark relative to the image width
+    pub scale: f32,
+    /// distance to the image border in pixels
+    pub margin: u32,
+}
+
+impl Watermark {
+    pub fn new(image: RgbaImage, position: WatermarkPosition, opacity: f32) -> Self {
+        Self {
+            image,
+            position,
+            opacity: opacity.clamp(0., 1.),
+            scale: 0.15,
+            margin: 16,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        position: WatermarkPosition,
+        opacity: f32,
+    ) -> anyhow::Result<Self> {
+        let image = image::open(path)?.to_rgba8();
+        Ok(Self::new(image, position, opacity))
+    }
+
+    /// blends the watermark over the image
+    /// works for rgb and rgba images, the alpha channel of rgba images is combined with the watermark alpha
+    pub fn apply<P: Pixel<Subpixel = u8>>(&self, img: &mut ImageBuffer<P, Vec<u8>>) {
+        let width = ((img.width() as f32 * self.scale) as u32).clamp(1, img.width());
+        let height = ((self.image.height() as f32 * width as f32 / self.image.width() as f32)
+            as u32)
+            .clamp(1, img.height());
+        let logo = image::imageops::resize(&self.image, width, height, FilterType::Triangle);
+
+        let max_x = img.width() - width;
+        let max_y = img.height() - height;
+        let (x0, y0) = match self.position {
+            WatermarkPosition::TopLeft => (self.margin, self.margin),
+            WatermarkPosition::TopRight => (max_x.saturating_sub(self.margin), self.margin),
+            WatermarkPosition::BottomLeft => (self.margin, max_y.saturating_sub(self.margin)),
+            WatermarkPosition::BottomRight => (
+                max_x.saturating_sub(self.margin),
+                max_y.saturating_sub(self.margin),
+            ),
+            WatermarkPosition::Center => (max_x / 2, max_y / 2),
+        };
+        let (x0, y0) = (x0.min(max_x), y0.min(max_y));
+
+        for (x, y, src) in logo.enumerate_pixels() {
+            let a = src[3] as f32 / 255. * self.opacity;
+            if a <= 0. {
+                continue;
+            }
+            let dst = img.get_pixel_mut(x0 + x, y0 + y).channels_mut();
+            for (d, s) in dst.iter_mut().zip(src.0).take(3) {
+                *d = (s as f32 * a + *d as f32 * (1. - a)).round() as u8;
+            }
+            if dst.len() == 4 {
+                dst[3] = (255. * a + dst[3] as f32 * (1. - a)).round() as u8;
+            }
+        }
+    }
+}
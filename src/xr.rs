@@ -0,0 +1,279 @@
+//! WebXR immersive mode of the web viewer
+//!
+//! wgpu cannot present into the framebuffer of an XR session, so the two eyes are rendered
+//! side by side into the window canvas (see [crate::StereoMode::SideBySide]) with the head tracked eye cameras
+//! and the canvas is copied into the framebuffer of an XRWebGLLayer every XR frame
+//!
+//! every eye has its own renderer, so the splats are sorted per eye
+//! the copied image is one frame behind the head pose
+//! browsers that pause the animation frames of the page during an immersive session are not supported
+
+use std::{cell::RefCell, rc::Rc};
+
+use cgmath::{Matrix, Matrix3, Quaternion, Rad, SquareMatrix, Vector2, Vector3};
+use js_sys::{Object, Reflect};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    HtmlCanvasElement, WebGl2RenderingContext as Gl, WebGlFramebuffer, WebGlTexture, XrEye,
+    XrFrame, XrReferenceSpace, XrReferenceSpaceType, XrRenderStateInit, XrSession, XrSessionMode,
+    XrView, XrWebGlLayer, XrWebGlLayerInit,
+};
+
+use crate::{camera::PerspectiveCamera, stereo::Stereo};
+
+/// pose and projection of one eye in the reference space of the session
+#[derive(Debug, Clone, Copy)]
+struct EyePose {
+    position: Vector3<f32>,
+    orientation: Quaternion<f32>,
+    /// column major opengl projection matrix
+    projection: [f32; 16],
+}
+
+#[derive(Default)]
+struct XrState {
+    /// left and right eye of the last XR frame
+    eyes: Option<[EyePose; 2]>,
+    /// number of XR frames so far
+    frame: u64,
+    ended: bool,
+}
+
+/// immersive VR session that shows the eyes rendered by the viewer
+pub(crate) struct XrViewer {
+    state: Rc<RefCell<XrState>>,
+    session: Rc<RefCell<Option<XrSession>>>,
+    /// camera of the viewer when the session was started, placed at the origin of the reference space
+    origin: PerspectiveCamera,
+    /// scene units per meter
+    scale: f32,
+    last_frame: u64,
+}
+
+impl XrViewer {
+    /// requests an immersive VR session that shows `canvas`
+    /// must be called while handling a user gesture (e.g. a click)
+    /// the eye distance of `stereo` sets the scale of the scene (the default is one scene unit per meter)
+    pub fn start(canvas: HtmlCanvasElement, origin: PerspectiveCamera, stereo: Stereo) -> Self {
+        let state: Rc<RefCell<XrState>> = Default::default();
+        let session: Rc<RefCell<Option<XrSession>>> = Default::default();
+        {
+            let state = state.clone();
+            let session = session.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match start_session(canvas, state.clone()).await {
+                    Ok(s) => *session.borrow_mut() = Some(s),
+                    Err(err) => {
+                        log::error!("cannot start XR session: {:?}", err);
+                        state.borrow_mut().ended = true;
+                    }
+                }
+            });
+        }
+        Self {
+            state,
+            session,
+            origin,
+            scale: stereo.ipd / Stereo::default().ipd,
+            last_frame: 0,
+        }
+    }
+
+    /// true if the session ended or could not be started
+    pub fn ended(&self) -> bool {
+        self.state.borrow().ended
+    }
+
+    pub fn end(&self) {
+        if let Some(session) = self.session.borrow().as_ref() {
+            let _ = session.end();
+        }
+    }
+
+    /// returns true if the head pose changed since the last call
+    pub fn update(&mut self) -> bool {
+        let frame = self.state.borrow().frame;
+        let changed = frame != self.last_frame;
+        self.last_frame = frame;
+        changed
+    }
+
+    /// head tracked camera of one eye in scene space, `None` before the first XR frame
+    pub fn eye_camera(&self, right: bool) -> Option<PerspectiveCamera> {
+        let eye = self.state.borrow().eyes?[right as usize];
+        // webxr cameras look along -z with y up, the cameras of the viewer along z with y down
+        let flip = Matrix3::from_diagonal(Vector3::new(1., -1., -1.));
+        // the rotation of the viewer cameras transforms from world to camera space
+        let origin = Matrix3::from(self.origin.rotation);
+        let eye_rotation = Matrix3::from(eye.orientation);
+
+        let mut camera = self.origin;
+        camera.rotation = Quaternion::from(flip * eye_rotation.transpose() * flip * origin);
+        camera.position += origin.transpose() * flip * eye.position * self.scale;
+        // field of view and principal point of the opengl projection
+        let p = eye.projection;
+        camera.projection.fovx = Rad(2. * (1. / p[0]).atan());
+        camera.projection.fovy = Rad(2. * (1. / p[5]).atan());
+        camera.projection.principal_offset = Vector2::new(-p[8], p[9]);
+        Some(camera)
+    }
+}
+
+impl Drop for XrViewer {
+    fn drop(&mut self) {
+        self.end();
+    }
+}
+
+async fn start_session(
+    canvas: HtmlCanvasElement,
+    state: Rc<RefCell<XrState>>,
+) -> Result<XrSession, JsValue> {
+    let window = web_sys::window().ok_or("no window")?;
+    let session: XrSession = JsFuture::from(
+        window
+            .navigator()
+            .xr()
+            .request_session(XrSessionMode::ImmersiveVr),
+    )
+    .await?
+    .dyn_into()?;
+
+    // the webgl context only copies the canvas into the framebuffer of the session
+    let gl_canvas: HtmlCanvasElement = window
+        .document()
+        .ok_or("no document")?
+        .create_element("canvas")?
+        .dyn_into()?;
+    let options = Object::new();
+    Reflect::set(&options, &"xrCompatible".into(), &true.into())?;
+    let gl: Gl = gl_canvas
+        .get_context_with_context_options("webgl2", &options)?
+        .ok_or("webgl2 is not supported")?
+        .dyn_into()?;
+    // blitting into a multisampled framebuffer is not allowed
+    let mut layer_init = XrWebGlLayerInit::new();
+    layer_init.antialias(false).depth(false);
+    let layer = XrWebGlLayer::new_with_web_gl2_rendering_context_and_layer_init(
+        &session,
+        &gl,
+        &layer_init,
+    )?;
+    let mut render_state = XrRenderStateInit::new();
+    render_state.base_layer(Some(&layer));
+    session.update_render_state_with_state(&render_state);
+    let space: XrReferenceSpace =
+        JsFuture::from(session.request_reference_space(XrReferenceSpaceType::Local))
+            .await?
+            .dyn_into()?;
+
+    let texture = gl.create_texture().ok_or("cannot create texture")?;
+    let framebuffer = gl.create_framebuffer().ok_or("cannot create framebuffer")?;
+
+    let ended = state.clone();
+    let onend = Closure::<dyn FnMut()>::new(move || ended.borrow_mut().ended = true);
+    session.set_onend(Some(onend.as_ref().unchecked_ref()));
+    onend.forget();
+
+    // the frame callback requests the next frame with itself
+    let callback: Rc<RefCell<Option<Closure<dyn FnMut(f64, XrFrame)>>>> = Default::default();
+    let next = callback.clone();
+    *callback.borrow_mut() = Some(Closure::new(move |_time: f64, frame: XrFrame| {
+        let session = frame.session();
+        if let Some(pose) = frame.get_viewer_pose(&space) {
+            let views: Vec<XrView> = pose.views().iter().map(|v| v.unchecked_into()).collect();
+            if let Err(err) = copy_canvas(&gl, &texture, &framebuffer, &canvas, &layer, &views) {
+                log::error!("cannot copy canvas into XR framebuffer: {:?}", err);
+            }
+            let mut state = state.borrow_mut();
+            state.eyes = eye_poses(&views);
+            state.frame += 1;
+        }
+        if !state.borrow().ended {
+            if let Some(callback) = next.borrow().as_ref() {
+                session.request_animation_frame(callback.as_ref().unchecked_ref());
+            }
+        }
+    }));
+    if let Some(callback) = callback.borrow().as_ref() {
+        session.request_animation_frame(callback.as_ref().unchecked_ref());
+    }
+    Ok(session)
+}
+
+/// copies the left and right half of the canvas into the viewports of the eyes
+/// the rows of the canvas start at the top, so the image is flipped while copying
+fn copy_canvas(
+    gl: &Gl,
+    texture: &WebGlTexture,
+    framebuffer: &WebGlFramebuffer,
+    canvas: &HtmlCanvasElement,
+    layer: &XrWebGlLayer,
+    views: &[XrView],
+) -> Result<(), JsValue> {
+    gl.bind_texture(Gl::TEXTURE_2D, Some(texture));
+    gl.tex_image_2d_with_u32_and_u32_and_html_canvas_element(
+        Gl::TEXTURE_2D,
+        0,
+        Gl::RGBA as i32,
+        Gl::RGBA,
+        Gl::UNSIGNED_BYTE,
+        canvas,
+    )?;
+    gl.bind_framebuffer(Gl::READ_FRAMEBUFFER, Some(framebuffer));
+    gl.framebuffer_texture_2d(
+        Gl::READ_FRAMEBUFFER,
+        Gl::COLOR_ATTACHMENT0,
+        Gl::TEXTURE_2D,
+        Some(texture),
+        0,
+    );
+    gl.bind_framebuffer(Gl::DRAW_FRAMEBUFFER, layer.framebuffer().as_ref());
+    let half_width = canvas.width() as i32 / 2;
+    let height = canvas.height() as i32;
+    for view in views {
+        let Some(viewport) = layer.get_viewport(view) else {
+            continue;
+        };
+        let x = if view.eye() == XrEye::Right {
+            half_width
+        } else {
+            0
+        };
+        gl.blit_framebuffer(
+            x,
+            height,
+            x + half_width,
+            0,
+            viewport.x(),
+            viewport.y(),
+            viewport.x() + viewport.width(),
+            viewport.y() + viewport.height(),
+            Gl::COLOR_BUFFER_BIT,
+            Gl::LINEAR,
+        );
+    }
+    Ok(())
+}
+
+/// poses of the left and right eye, `None` for mono views
+fn eye_poses(views: &[XrView]) -> Option<[EyePose; 2]> {
+    let pose = |eye: XrEye| {
+        let view = views.iter().find(|v| v.eye() == eye)?;
+        let transform = view.transform();
+        let position = transform.position();
+        let orientation = transform.orientation();
+        Some(EyePose {
+            position: Vector3::new(position.x(), position.y(), position.z()).cast()?,
+            orientation: Quaternion::new(
+                orientation.w() as f32,
+                orientation.x() as f32,
+                orientation.y() as f32,
+                orientation.z() as f32,
+            ),
+            projection: view.projection_matrix().try_into().ok()?,
+        })
+    };
+    Some([pose(XrEye::Left)?, pose(XrEye::Right)?])
+}